@@ -5,6 +5,10 @@ pub enum ErrorDeComunicacion {
     Utf8Error(Utf8Error),
     IoError(io::Error),
     ErrorRepositorioNoExiste(String),
+    /// Error fatal que mando el servidor, por ejemplo por el canal 3 de side-band.
+    ErrorDelServidor(String),
+    /// El servidor mando algo que no respeta el protocolo.
+    ErrorDeProtocolo(String),
 }
 
 impl fmt::Display for ErrorDeComunicacion {
@@ -15,6 +19,8 @@ impl fmt::Display for ErrorDeComunicacion {
             ErrorDeComunicacion::ErrorRepositorioNoExiste(e) => {
                 writeln!(f, "ERR El repositorio {} no existe", e)
             }
+            ErrorDeComunicacion::ErrorDelServidor(e) => write!(f, "Error del servidor: {}", e),
+            ErrorDeComunicacion::ErrorDeProtocolo(e) => write!(f, "Error de protocolo: {}", e),
         }
     }
 }
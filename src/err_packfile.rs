@@ -0,0 +1,85 @@
+use std::{error::Error, fmt};
+
+/// Errores encontrados al validar un packfile recibido, antes de guardar cualquier objeto.
+#[derive(Debug, PartialEq)]
+pub enum ErrorPackfile {
+    /// El packfile es mas corto de lo que indica su contenido.
+    Truncado,
+    /// Los primeros 4 bytes no son `PACK`.
+    FirmaInvalida,
+    /// Solo se soportan las versiones 2 y 3.
+    VersionNoSoportada(u32),
+    /// El sha1 de los bytes del packfile no coincide con los ultimos 20 bytes.
+    ChecksumInvalido { esperado: String, calculado: String },
+    /// La entrada que empieza en el offset no se pudo descomprimir o tiene un tipo desconocido.
+    ObjetoCorrupto { offset: usize, motivo: String },
+    /// La entrada que empieza en el offset no se descomprime al tamanio declarado en su header.
+    TamanioIncorrecto {
+        offset: usize,
+        declarado: usize,
+        obtenido: usize,
+    },
+    /// Las instrucciones del delta que empieza en el offset no son validas para su base.
+    DeltaInvalido { offset: usize, motivo: String },
+    /// No se encontro la base de algun REF_DELTA, ni en el packfile ni en el repositorio.
+    BaseFaltante(Vec<String>),
+    /// Quedaron bytes entre el ultimo objeto y el checksum.
+    BytesSobrantes(usize),
+}
+
+impl fmt::Display for ErrorPackfile {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            ErrorPackfile::Truncado => write!(f, "Packfile invalido: esta truncado"),
+            ErrorPackfile::FirmaInvalida => write!(f, "Packfile invalido: firma invalida"),
+            ErrorPackfile::VersionNoSoportada(version) => {
+                write!(f, "Packfile invalido: version {} no soportada", version)
+            }
+            ErrorPackfile::ChecksumInvalido {
+                esperado,
+                calculado,
+            } => write!(
+                f,
+                "Packfile invalido: checksum incorrecto, se esperaba {} y se calculo {}",
+                esperado, calculado
+            ),
+            ErrorPackfile::ObjetoCorrupto { offset, motivo } => write!(
+                f,
+                "Packfile invalido: objeto corrupto en el offset {}: {}",
+                offset, motivo
+            ),
+            ErrorPackfile::TamanioIncorrecto {
+                offset,
+                declarado,
+                obtenido,
+            } => write!(
+                f,
+                "Packfile invalido: el objeto en el offset {} declara {} bytes pero tiene {}",
+                offset, declarado, obtenido
+            ),
+            ErrorPackfile::DeltaInvalido { offset, motivo } => write!(
+                f,
+                "Packfile invalido: delta invalido en el offset {}: {}",
+                offset, motivo
+            ),
+            ErrorPackfile::BaseFaltante(bases) => write!(
+                f,
+                "Packfile invalido: no se encontraron las bases {}",
+                bases.join(", ")
+            ),
+            ErrorPackfile::BytesSobrantes(cantidad) => write!(
+                f,
+                "Packfile invalido: sobran {} bytes antes del checksum",
+                cantidad
+            ),
+        }
+    }
+}
+
+impl Error for ErrorPackfile {}
+
+impl From<ErrorPackfile> for String {
+    fn from(error: ErrorPackfile) -> Self {
+        error.to_string()
+    }
+}
@@ -44,10 +44,12 @@ pub fn render(builder: &gtk::Builder, logger: Arc<Logger>) {
             None => return,
         };
 
-        log_list::render(&builder_clone, active.as_str(), logger.clone());
-        log_seleccionado::render(&builder_clone, None);
-
+        // si el checkout falla (por ejemplo por un hook) se sigue mostrando la rama actual
         Checkout::from(vec![active.to_string()], logger.clone()).ejecutar_gui();
+        let rama_actual = ramas::obtener_rama_actual().unwrap_or_else(|_| active.to_string());
+
+        log_list::render(&builder_clone, &rama_actual, logger.clone());
+        log_seleccionado::render(&builder_clone, None);
 
         log_list::refresh(&builder_clone);
         staging_area::refresh(&builder_clone);
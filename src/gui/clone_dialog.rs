@@ -4,7 +4,7 @@ use gtk::prelude::*;
 
 use crate::tipos_de_dato::{comandos::clone::Clone, logger::Logger};
 
-use super::comando_gui::ComandoGui;
+use super::{comando_gui::ComandoGui, info_dialog};
 
 fn run_dialog(builder: &gtk::Builder) {
     let dialog: gtk::MessageDialog = builder.object("clone").unwrap();
@@ -19,9 +19,13 @@ fn clonar_dialog(builder: &gtk::Builder, logger: Arc<Logger>) {
     dialog.set_position(gtk::WindowPosition::Center);
 
     confirm.connect_clicked(move |_| {
-        Clone::from(&mut vec![input.text().to_string()], logger.clone(), true).ejecutar_gui();
+        let resultado =
+            Clone::from(&mut vec![input.text().to_string()], logger.clone(), true).ejecutar_gui();
         input.set_text("");
         dialog.hide();
+        if let Some(resultado) = resultado {
+            info_dialog::mostrar_resultado(&resultado);
+        }
     });
 }
 
@@ -1,5 +1,7 @@
 use gtk::prelude::*;
 
+use crate::utils::hooks::PREFIJO_ERROR_HOOK;
+
 pub fn setup(build: &gtk::Builder) {
     let dialog: gtk::MessageDialog = build.object("error-dialog").unwrap();
     let aceptar_button: gtk::Button = build.object("error-close").unwrap();
@@ -30,6 +32,19 @@ pub fn mostrar_mensaje(titulo: &str, error: &str) {
     dialog.run();
 }
 
+/// Muestra el resultado de un comando. Los comandos que hablan con el servidor devuelven primero
+/// los mensajes de progreso del remoto y en la ultima linea el resultado, que va como titulo.
+pub fn mostrar_resultado(resultado: &str) {
+    let (mensajes_del_remoto, titulo) = resultado.rsplit_once('\n').unwrap_or(("", resultado));
+    mostrar_mensaje(titulo, mensajes_del_remoto)
+}
+
+/// Muestra el error de un comando. Si el comando lo corto un hook, el titulo dice que hook fallo
+/// y abajo va lo que escribio el hook.
 pub fn mostrar_error(error: &str) {
+    if error.starts_with(PREFIJO_ERROR_HOOK) {
+        let (titulo, salida_hook) = error.split_once('\n').unwrap_or((error, ""));
+        return mostrar_mensaje(titulo, salida_hook);
+    }
     mostrar_mensaje("Error", error)
 }
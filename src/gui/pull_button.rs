@@ -18,7 +18,7 @@ pub fn render(
         let resultado = Pull::from(Vec::new(), logger.clone()).ejecutar_gui();
 
         if let Some(resultado) = resultado {
-            info_dialog::mostrar_mensaje(&resultado, "");
+            info_dialog::mostrar_resultado(&resultado);
             log_list::render(&builder_clone, &branch_actual, logger.clone());
         }
     });
@@ -1,20 +1,48 @@
 pub mod err_comunicacion;
+pub mod err_packfile;
 pub mod servidor {
+    pub mod comparacion;
+    pub mod config_servidor;
+    pub mod control_acceso;
+    pub mod explorador_repositorio;
+    pub mod flujo_servidor;
     pub mod gir_server;
     pub mod http_server;
+    pub mod limite_conexiones;
     pub mod pull_request;
+    pub mod ramas_protegidas;
     pub mod receive_pack;
     pub mod repos_almacen;
+    pub mod repositorio;
+    pub mod shallow;
     pub mod upload_pack;
+    pub mod upload_pack_v2;
     pub mod vector_threads;
     pub mod rutas {
         pub mod actualizar_pull_request;
+        pub mod actualizar_repositorio;
+        pub mod aprobar_pull_request;
+        pub mod comparar_referencias;
         pub mod crear_pull_request;
+        pub mod crear_repositorio;
+        pub mod eliminar_repositorio;
+        pub mod listar_commits;
         pub mod listar_pull_request;
+        pub mod listar_ramas;
+        pub mod listar_repositorios;
+        pub mod listar_tags;
         pub mod mensaje_servidor;
         pub mod mergear_pull_request;
+        pub mod obtener_archivos_pull_request;
+        pub mod obtener_commit;
         pub mod obtener_commits_pull_request;
+        pub mod obtener_contenido;
         pub mod obtener_pull_request;
+        pub mod obtener_repositorio;
+        pub mod obtener_tree;
+        pub mod proteccion_de_ramas;
+        pub mod smart_http;
+        pub mod verificar_repositorio;
     }
 }
 pub mod tipos_de_dato {
@@ -24,12 +52,16 @@ pub mod tipos_de_dato {
     pub mod conflicto;
     pub mod date;
     pub mod diffgrid;
+    pub mod flujo;
+    pub mod indice_packfile;
     pub mod info_ramas;
     pub mod lado_conflicto;
     pub mod logger;
     pub mod mensajes_log;
+    pub mod negociacion;
     pub mod objeto;
     pub mod packfile;
+    pub mod permiso;
     pub mod referencia;
     pub mod referencia_commit;
     pub mod region;
@@ -41,6 +73,7 @@ pub mod tipos_de_dato {
     pub mod objetos {
         pub mod blob;
         pub mod commit;
+        pub mod tag;
         pub mod tree;
     }
 
@@ -49,6 +82,7 @@ pub mod tipos_de_dato {
         pub mod endpoint_handler;
         pub mod error;
         pub mod estado;
+        pub mod flujo_http;
         pub mod metodos;
         pub mod request;
         pub mod response;
@@ -63,6 +97,8 @@ pub mod tipos_de_dato {
         pub mod clone;
         pub mod commit;
         pub mod fetch;
+        pub mod fsck;
+        pub mod gc;
         pub mod hash_object;
         pub mod init;
         pub mod log;
@@ -88,12 +124,14 @@ pub mod utils {
     pub mod compresion;
     pub mod fase_descubrimiento;
     pub mod gir_config;
+    pub mod hooks;
     pub mod index;
     pub mod io;
     pub mod objects;
     pub mod path_buf;
     pub mod ramas;
     pub mod referencia;
+    pub mod shallow;
     pub mod strings;
     pub mod tags;
     pub mod testing;
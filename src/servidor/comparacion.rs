@@ -0,0 +1,343 @@
+use std::{
+    collections::{HashMap, HashSet},
+    path::{Path, PathBuf},
+    sync::Arc,
+};
+
+use serde::Serialize;
+
+use crate::{
+    servidor::explorador_repositorio,
+    tipos_de_dato::{
+        comandos::{cat_file, log::Log, merge::Merge},
+        http::error::ErrorHttp,
+        logger::Logger,
+        objeto::Objeto,
+        objetos::{commit::CommitObj, tree::Tree},
+        tipo_diff::TipoDiff,
+    },
+};
+
+/// Lineas sin cambios que se muestran antes y despues de cada cambio del patch.
+const LINEAS_DE_CONTEXTO: usize = 3;
+
+#[derive(Serialize, Debug, PartialEq, Clone, Copy)]
+#[serde(rename_all = "lowercase")]
+pub enum EstadoArchivo {
+    Agregado,
+    Eliminado,
+    Modificado,
+}
+
+/// Cambios de un archivo entre dos commits, como se muestran por la api.
+#[derive(Serialize, Debug, PartialEq)]
+pub struct ArchivoModificado {
+    pub path: String,
+    pub estado: EstadoArchivo,
+    pub adiciones: usize,
+    pub eliminaciones: usize,
+    /// Diff unificado del archivo. Si el archivo es binario esta vacio.
+    pub patch: String,
+}
+
+/// Lo que cambia `head` respecto de `base`: los commits de `head` que no estan en `base` y los
+/// archivos que cambiaron desde el commit base entre los dos.
+#[derive(Serialize, Debug)]
+pub struct Comparacion {
+    pub base: String,
+    pub head: String,
+    pub commit_base: String,
+    pub commits: Vec<CommitObj>,
+    pub archivos: Vec<ArchivoModificado>,
+}
+
+impl Comparacion {
+    /// Compara las ramas, tags o commits `base` y `head` del repositorio ubicado en
+    /// `dir_repositorio`.
+    ///
+    /// ## Errores
+    /// - Si alguna de las referencias no existe
+    /// - Si no tienen un commit base en comun
+    pub fn entre(
+        dir_repositorio: &Path,
+        base: &str,
+        head: &str,
+        logger: Arc<Logger>,
+    ) -> Result<Comparacion, ErrorHttp> {
+        let dir_objetos = dir_repositorio
+            .join(".gir/objects/")
+            .to_string_lossy()
+            .to_string();
+        let hash_base = explorador_repositorio::resolver_commit(dir_repositorio, base)?;
+        let hash_head = explorador_repositorio::resolver_commit(dir_repositorio, head)?;
+
+        let historia_base = obtener_historia(dir_repositorio, &hash_base, logger.clone())?;
+        let historia_head = obtener_historia(dir_repositorio, &hash_head, logger.clone())?;
+        let commits_head: HashSet<&String> =
+            historia_head.iter().map(|commit| &commit.hash).collect();
+        // igual que en el merge, el commit base es el mas nuevo de base que tambien esta en head
+        let commit_base = historia_base
+            .iter()
+            .find(|commit| commits_head.contains(&commit.hash))
+            .map(|commit| commit.hash.clone())
+            .ok_or_else(|| {
+                ErrorHttp::ValidationFailed(format!(
+                    "No se encontro un commit base entre las dos ramas: {} y {}",
+                    base, head
+                ))
+            })?;
+
+        let commits_base: HashSet<&String> =
+            historia_base.iter().map(|commit| &commit.hash).collect();
+        let commits = historia_head
+            .iter()
+            .filter(|commit| !commits_base.contains(&commit.hash))
+            .cloned()
+            .collect();
+
+        let tree_base = obtener_tree_de_commit(&commit_base, &dir_objetos, logger.clone())?;
+        let tree_head = obtener_tree_de_commit(&hash_head, &dir_objetos, logger)?;
+        let archivos = obtener_archivos_modificados(&tree_base, &tree_head, &dir_objetos)
+            .map_err(ErrorHttp::InternalServerError)?;
+
+        Ok(Comparacion {
+            base: hash_base,
+            head: hash_head,
+            commit_base,
+            commits,
+            archivos,
+        })
+    }
+}
+
+/// Devuelve los archivos que cambian de `tree_base` a `tree_head`, ordenados por path. El
+/// contenido de los archivos se lee de `dir_objetos`.
+///
+/// El diff de los archivos modificados es el de `Tree::deep_changes`, que solo compara los
+/// archivos que estan en los dos trees, asi que el de los agregados y eliminados se arma aca
+/// contra un contenido vacio. No se usa `Merge::obtener_diffs_entre_dos_archivos` porque separa
+/// por `\n` y el salto de linea del final cuenta como una linea vacia mas, que apareceria en el
+/// patch.
+pub fn obtener_archivos_modificados(
+    tree_base: &Tree,
+    tree_head: &Tree,
+    dir_objetos: &str,
+) -> Result<Vec<ArchivoModificado>, String> {
+    let mut diffs = tree_base.deep_changes_de(tree_head, dir_objetos)?;
+    let blobs_base = obtener_blobs(tree_base);
+    let blobs_head = obtener_blobs(tree_head);
+
+    let mut paths: Vec<&String> = blobs_base.keys().chain(blobs_head.keys()).collect();
+    paths.sort();
+    paths.dedup();
+
+    let mut archivos = Vec::new();
+    for path in paths {
+        let (estado, contenido_base, contenido_head) =
+            match (blobs_base.get(path), blobs_head.get(path)) {
+                (Some(base), Some(head)) if base == head => continue,
+                (Some(base), Some(head)) => (
+                    EstadoArchivo::Modificado,
+                    obtener_contenido(base, dir_objetos)?,
+                    obtener_contenido(head, dir_objetos)?,
+                ),
+                (None, Some(head)) => (
+                    EstadoArchivo::Agregado,
+                    String::new(),
+                    obtener_contenido(head, dir_objetos)?,
+                ),
+                (Some(base), None) => (
+                    EstadoArchivo::Eliminado,
+                    obtener_contenido(base, dir_objetos)?,
+                    String::new(),
+                ),
+                (None, None) => continue,
+            };
+
+        if contenido_base.contains('\0') || contenido_head.contains('\0') {
+            archivos.push(ArchivoModificado {
+                path: path.to_string(),
+                estado,
+                adiciones: 0,
+                eliminaciones: 0,
+                patch: String::new(),
+            });
+            continue;
+        }
+
+        let lineas_base: Vec<&str> = contenido_base.lines().collect();
+        let lineas_head: Vec<&str> = contenido_head.lines().collect();
+        let diff = diffs
+            .remove(path.as_str())
+            .unwrap_or_else(|| Merge::obtener_diff(lineas_base.clone(), lineas_head.clone()));
+        archivos.push(armar_archivo_modificado(
+            path,
+            estado,
+            &diff,
+            &lineas_base,
+            &lineas_head,
+        ));
+    }
+    Ok(archivos)
+}
+
+fn obtener_historia(
+    dir_repositorio: &Path,
+    hash: &str,
+    logger: Arc<Logger>,
+) -> Result<Vec<CommitObj>, ErrorHttp> {
+    let commit = explorador_repositorio::obtener_commit(dir_repositorio, hash, logger.clone())?;
+    Log::obtener_listas_de_commits_de(commit, dir_repositorio, logger)
+        .map_err(ErrorHttp::InternalServerError)
+}
+
+fn obtener_tree_de_commit(
+    hash: &str,
+    dir_objetos: &str,
+    logger: Arc<Logger>,
+) -> Result<Tree, ErrorHttp> {
+    let commit = CommitObj::from_hash_de(hash.to_string(), dir_objetos, logger.clone())
+        .map_err(ErrorHttp::InternalServerError)?;
+    Tree::from_hash_de(&commit.hash_tree, PathBuf::from("."), dir_objetos, logger)
+        .map_err(ErrorHttp::InternalServerError)
+}
+
+// Hash de cada archivo del tree, por path.
+fn obtener_blobs(tree: &Tree) -> HashMap<String, String> {
+    tree.obtener_objetos_hoja()
+        .into_iter()
+        .filter_map(|objeto| match objeto {
+            Objeto::Blob(blob) => Some((blob.ubicacion.to_string_lossy().to_string(), blob.hash)),
+            Objeto::Tree(_) => None,
+        })
+        .collect()
+}
+
+fn obtener_contenido(hash: &str, dir_objetos: &str) -> Result<String, String> {
+    Ok(cat_file::obtener_contenido_objeto_de(hash, dir_objetos)?.1)
+}
+
+// El diff del merge tiene las lineas sin los espacios de los extremos, asi que para el patch
+// se toman las lineas originales siguiendo el orden de los cambios.
+fn armar_archivo_modificado(
+    path: &str,
+    estado: EstadoArchivo,
+    diff: &[(usize, TipoDiff)],
+    lineas_base: &[&str],
+    lineas_head: &[&str],
+) -> ArchivoModificado {
+    let mut lineas = Vec::new();
+    let (mut indice_base, mut indice_head) = (0, 0);
+    for (_, tipo_diff) in diff {
+        let linea = match tipo_diff {
+            TipoDiff::Unchanged(_) => {
+                indice_base += 1;
+                indice_head += 1;
+                (' ', lineas_base[indice_base - 1], indice_base, indice_head)
+            }
+            TipoDiff::Removed(_) => {
+                indice_base += 1;
+                ('-', lineas_base[indice_base - 1], indice_base, indice_head)
+            }
+            TipoDiff::Added(_) => {
+                indice_head += 1;
+                ('+', lineas_head[indice_head - 1], indice_base, indice_head)
+            }
+        };
+        lineas.push(linea);
+    }
+
+    ArchivoModificado {
+        path: path.to_string(),
+        estado,
+        adiciones: lineas.iter().filter(|(tipo, ..)| *tipo == '+').count(),
+        eliminaciones: lineas.iter().filter(|(tipo, ..)| *tipo == '-').count(),
+        patch: armar_patch(&lineas),
+    }
+}
+
+// Arma los bloques `@@ -inicio,cantidad +inicio,cantidad @@` del diff unificado. Cada linea
+// tiene su tipo (' ', '-' o '+') y los numeros de linea en base y head hasta ella inclusive.
+fn armar_patch(lineas: &[(char, &str, usize, usize)]) -> String {
+    let cambios: Vec<usize> = lineas
+        .iter()
+        .enumerate()
+        .filter(|(_, (tipo, ..))| *tipo != ' ')
+        .map(|(i, _)| i)
+        .collect();
+
+    // rangos de lineas de cada bloque, juntando los cambios cuyos contextos se tocan
+    let mut bloques: Vec<(usize, usize)> = Vec::new();
+    for cambio in cambios {
+        let inicio = cambio.saturating_sub(LINEAS_DE_CONTEXTO);
+        let fin = (cambio + LINEAS_DE_CONTEXTO + 1).min(lineas.len());
+        match bloques.last_mut() {
+            Some((_, fin_anterior)) if inicio <= *fin_anterior => *fin_anterior = fin,
+            _ => bloques.push((inicio, fin)),
+        }
+    }
+
+    let mut patch = Vec::new();
+    for (inicio, fin) in bloques {
+        let bloque = &lineas[inicio..fin];
+        let cantidad_base = bloque.iter().filter(|(tipo, ..)| *tipo != '+').count();
+        let cantidad_head = bloque.iter().filter(|(tipo, ..)| *tipo != '-').count();
+        // los numeros de linea de la primera linea del bloque, sin contarla
+        let (tipo, _, linea_base, linea_head) = bloque[0];
+        let inicio_base = linea_base - usize::from(tipo != '+');
+        let inicio_head = linea_head - usize::from(tipo != '-');
+        // si un lado no tiene lineas, el bloque empieza despues de la linea anterior
+        patch.push(format!(
+            "@@ -{},{} +{},{} @@",
+            inicio_base + usize::from(cantidad_base > 0),
+            cantidad_base,
+            inicio_head + usize::from(cantidad_head > 0),
+            cantidad_head
+        ));
+        for (tipo, linea, ..) in bloque {
+            patch.push(format!("{}{}", tipo, linea));
+        }
+    }
+    patch.join("\n")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn archivo(base: &str, head: &str) -> ArchivoModificado {
+        let lineas_base: Vec<&str> = base.lines().collect();
+        let lineas_head: Vec<&str> = head.lines().collect();
+        let diff = Merge::obtener_diff(lineas_base.clone(), lineas_head.clone());
+        armar_archivo_modificado(
+            "archivo",
+            EstadoArchivo::Modificado,
+            &diff,
+            &lineas_base,
+            &lineas_head,
+        )
+    }
+
+    #[test]
+    fn test01_el_patch_tiene_los_cambios_con_su_contexto_y_las_lineas_originales() {
+        let base = "1\n2\n3\n4\n5\n6\n7\n8\n9\n10\n11\n12\n";
+        let head = "1\n  dos\n3\n4\n5\n6\n7\n8\n9\n10\n11\n12\n13\n";
+
+        let archivo = archivo(base, head);
+
+        assert_eq!(archivo.adiciones, 2);
+        assert_eq!(archivo.eliminaciones, 1);
+        assert_eq!(
+            archivo.patch,
+            "@@ -1,5 +1,5 @@\n 1\n-2\n+  dos\n 3\n 4\n 5\n@@ -10,3 +10,4 @@\n 10\n 11\n 12\n+13"
+        );
+    }
+
+    #[test]
+    fn test02_un_archivo_nuevo_es_un_solo_bloque_de_lineas_agregadas() {
+        let archivo = archivo("", "a\nb\n");
+
+        assert_eq!((archivo.adiciones, archivo.eliminaciones), (2, 0));
+        assert_eq!(archivo.patch, "@@ -0,0 +1,2 @@\n+a\n+b");
+    }
+}
@@ -0,0 +1,362 @@
+use std::{
+    collections::HashMap,
+    env,
+    path::{Path, PathBuf},
+    sync::OnceLock,
+};
+
+use native_tls::{Identity, TlsAcceptor};
+
+use crate::utils::{gir_config, io};
+
+use super::control_acceso::TODOS_LOS_REPOSITORIOS;
+
+/// Ip en la que escuchan los servidores si no se configura otra direccion.
+const IP_POR_DEFECTO: &str = "127.0.0.1";
+/// Puerto del servidor gir si no se configura otro, el mismo que usa git daemon.
+const PUERTO_GIR_POR_DEFECTO: &str = "9418";
+/// Directorio de los repositorios si no se configura otro, relativo al directorio desde el que
+/// se lanza el servidor.
+const DIR_REPOSITORIOS_POR_DEFECTO: &str = "srv";
+const MAX_CONEXIONES_POR_DEFECTO: usize = 100;
+
+const CLAVE_GIR: &str = "gir";
+const CLAVE_HTTP: &str = "http";
+const CLAVE_REPOSITORIOS: &str = "repositorios";
+const CLAVE_MAX_CONEXIONES: &str = "max_conexiones";
+const CLAVE_CERTIFICADO: &str = "certificado";
+const CLAVE_CLAVE: &str = "clave";
+const CLAVE_USUARIOS: &str = "usuarios";
+const CLAVES: [&str; 7] = [
+    CLAVE_GIR,
+    CLAVE_HTTP,
+    CLAVE_REPOSITORIOS,
+    CLAVE_MAX_CONEXIONES,
+    CLAVE_CERTIFICADO,
+    CLAVE_CLAVE,
+    CLAVE_USUARIOS,
+];
+/// Flag con el que se pasa el archivo de configuracion.
+const FLAG_CONFIG: &str = "config";
+
+/// Directorio de los repositorios que usan las rutas del servidor http.
+static DIR_REPOSITORIOS: OnceLock<PathBuf> = OnceLock::new();
+
+/// Configuracion con la que se levantan los servidores gir y http.
+///
+/// Se arma con [`ConfigServidor::from_args`] a partir de los flags del servidor y, si se pasa
+/// `--config <archivo>`, de un archivo con una entrada `clave = valor` por linea. Las claves del
+/// archivo son las mismas que los flags, con `_` en vez de `-`:
+///
+/// ```text
+/// # direcciones (ip:puerto) en las que escucha cada servidor
+/// gir = 0.0.0.0:9418
+/// http = 0.0.0.0:8443
+/// repositorios = /var/lib/gir
+/// max_conexiones = 50
+/// # si se indican los dos, ambos servidores atienden solo por TLS
+/// certificado = /etc/gir/cert.pem
+/// clave = /etc/gir/clave.pem
+/// # sin archivo de cuentas no se pide autenticacion
+/// usuarios = /etc/gir/usuarios.json
+/// ```
+#[derive(Debug, Clone, PartialEq)]
+pub struct ConfigServidor {
+    /// Direccion (ip:puerto) en la que escucha el servidor gir.
+    pub direccion_gir: String,
+    /// Direccion (ip:puerto) en la que escucha el servidor http.
+    pub direccion_http: String,
+    /// Directorio donde estan los repositorios, siempre absoluto.
+    pub dir_repositorios: PathBuf,
+    /// Cantidad de clientes que cada servidor atiende a la vez.
+    pub max_conexiones: usize,
+    /// Certificado y clave para atender por TLS, si se configuraron.
+    pub tls: Option<ConfigTls>,
+    /// Archivo con las cuentas de los usuarios y sus permisos, si se configuro. Siempre absoluto.
+    pub usuarios: Option<PathBuf>,
+}
+
+/// Archivos PEM con los que los servidores atienden por TLS, con rutas absolutas.
+#[derive(Debug, Clone, PartialEq)]
+pub struct ConfigTls {
+    /// Certificado (o cadena de certificados) del servidor.
+    pub certificado: PathBuf,
+    /// Clave privada del certificado, en formato PKCS#8.
+    pub clave: PathBuf,
+}
+
+impl ConfigServidor {
+    /// Arma la configuracion a partir de los argumentos del servidor, sin el nombre del programa.
+    /// Los flags (`--gir`, `--http`, `--repositorios`, `--max-conexiones`, `--certificado`,
+    /// `--clave` y `--usuarios`) pisan lo que diga el archivo de `--config`. Por compatibilidad, un argumento
+    /// suelto es el puerto del servidor gir.
+    pub fn from_args(args: Vec<String>) -> Result<ConfigServidor, String> {
+        let mut flags = HashMap::new();
+        let mut archivo_config = None;
+        let mut args = args.into_iter();
+        while let Some(arg) = args.next() {
+            let Some(flag) = arg.strip_prefix("--") else {
+                flags.insert(CLAVE_GIR.to_string(), format!("{}:{}", IP_POR_DEFECTO, arg));
+                continue;
+            };
+            let valor = args
+                .next()
+                .ok_or_else(|| format!("Falta el valor de --{}", flag))?;
+            if flag == FLAG_CONFIG {
+                archivo_config = Some(valor);
+                continue;
+            }
+            let clave = flag.replace('-', "_");
+            if !CLAVES.contains(&clave.as_str()) {
+                return Err(format!("Opcion desconocida: --{}", flag));
+            }
+            flags.insert(clave, valor);
+        }
+
+        let mut valores = match archivo_config {
+            Some(archivo) => Self::leer_archivo(Path::new(&archivo))?,
+            None => HashMap::new(),
+        };
+        valores.extend(flags);
+        Self::from_valores(valores)
+    }
+
+    fn leer_archivo(archivo: &Path) -> Result<HashMap<String, String>, String> {
+        let contenido = io::leer_a_string(archivo)?;
+        let mut valores = HashMap::new();
+        for linea in contenido.lines().map(str::trim) {
+            if linea.is_empty() || linea.starts_with('#') {
+                continue;
+            }
+            let (clave, valor) = linea
+                .split_once('=')
+                .ok_or_else(|| format!("Linea invalida en {}: {}", archivo.display(), linea))?;
+            let clave = clave.trim();
+            if !CLAVES.contains(&clave) {
+                return Err(format!(
+                    "Clave desconocida en {}: {}",
+                    archivo.display(),
+                    clave
+                ));
+            }
+            valores.insert(clave.to_string(), valor.trim().to_string());
+        }
+        Ok(valores)
+    }
+
+    fn from_valores(mut valores: HashMap<String, String>) -> Result<ConfigServidor, String> {
+        let direccion_gir = valores
+            .remove(CLAVE_GIR)
+            .unwrap_or_else(|| format!("{}:{}", IP_POR_DEFECTO, PUERTO_GIR_POR_DEFECTO));
+        // sin direccion http configurada se sigue usando el puerto del ~/.girconfig
+        let direccion_http = match valores.remove(CLAVE_HTTP) {
+            Some(direccion) => direccion,
+            None => {
+                let puerto = gir_config::conseguir_puerto_http().ok_or(
+                    "No se pudo conseguir el puerto http, revise el archivo config o use --http",
+                )?;
+                format!("{}:{}", IP_POR_DEFECTO, puerto)
+            }
+        };
+
+        let dir_repositorios = Self::ruta_absoluta(
+            valores
+                .remove(CLAVE_REPOSITORIOS)
+                .unwrap_or(DIR_REPOSITORIOS_POR_DEFECTO.to_string()),
+        )?;
+
+        let max_conexiones = match valores.remove(CLAVE_MAX_CONEXIONES) {
+            Some(max) => match max.parse::<usize>() {
+                Ok(max) if max > 0 => max,
+                _ => return Err(format!("Cantidad maxima de conexiones invalida: {}", max)),
+            },
+            None => MAX_CONEXIONES_POR_DEFECTO,
+        };
+
+        let tls = match (
+            valores.remove(CLAVE_CERTIFICADO),
+            valores.remove(CLAVE_CLAVE),
+        ) {
+            (Some(certificado), Some(clave)) => Some(ConfigTls {
+                certificado: Self::ruta_absoluta(certificado)?,
+                clave: Self::ruta_absoluta(clave)?,
+            }),
+            (None, None) => None,
+            _ => return Err("Para usar TLS hay que indicar el certificado y la clave".to_string()),
+        };
+
+        let usuarios = valores
+            .remove(CLAVE_USUARIOS)
+            .map(Self::ruta_absoluta)
+            .transpose()?;
+
+        Ok(ConfigServidor {
+            direccion_gir,
+            direccion_http,
+            dir_repositorios,
+            max_conexiones,
+            tls,
+            usuarios,
+        })
+    }
+
+    // Las rutas relativas de la configuracion son relativas al directorio desde el que se lanza
+    // el servidor, asi que se resuelven al cargarla para que no dependan del directorio actual.
+    fn ruta_absoluta(ruta: String) -> Result<PathBuf, String> {
+        Ok(env::current_dir()
+            .map_err(|e| format!("No se pudo obtener el directorio actual: {}", e))?
+            .join(ruta))
+    }
+
+    /// Devuelve el acceptor con el que los servidores hacen el handshake TLS con cada cliente,
+    /// o None si no se configuro TLS.
+    pub fn crear_acceptor_tls(&self) -> Result<Option<TlsAcceptor>, String> {
+        let Some(tls) = &self.tls else {
+            return Ok(None);
+        };
+        let certificado = io::leer_bytes(&tls.certificado)?;
+        let clave = io::leer_bytes(&tls.clave)?;
+        let identidad = Identity::from_pkcs8(&certificado, &clave)
+            .map_err(|e| format!("Certificado o clave TLS invalidos: {}", e))?;
+        let acceptor =
+            TlsAcceptor::new(identidad).map_err(|e| format!("No se pudo configurar TLS: {}", e))?;
+        Ok(Some(acceptor))
+    }
+}
+
+/// Fija el directorio de los repositorios para las rutas del servidor http. Solo se puede
+/// fijar una vez; los llamados siguientes no cambian nada.
+pub fn fijar_dir_repositorios(dir: PathBuf) {
+    let _ = DIR_REPOSITORIOS.set(dir);
+}
+
+/// Devuelve el directorio de los repositorios del servidor http. Si no se fijo ninguno es `srv`,
+/// relativo al directorio actual.
+pub fn dir_repositorios() -> PathBuf {
+    DIR_REPOSITORIOS
+        .get()
+        .cloned()
+        .unwrap_or_else(|| PathBuf::from(DIR_REPOSITORIOS_POR_DEFECTO))
+}
+
+/// Devuelve el directorio del repositorio `repo` dentro del directorio de los repositorios.
+pub fn dir_repositorio(repo: &str) -> PathBuf {
+    dir_repositorios().join(repo)
+}
+
+/// Verifica que `repo` se pueda usar como nombre de un repositorio del directorio de los
+/// repositorios: tiene que ser un solo componente de la ruta, para que no se pueda salir del
+/// directorio (con `..`, `/`, etc), y no puede ser el comodin de los permisos de las cuentas.
+pub fn validar_nombre_repositorio(repo: &str) -> Result<(), String> {
+    let es_valido = !repo.is_empty()
+        && repo != "."
+        && repo != ".."
+        && repo != TODOS_LOS_REPOSITORIOS
+        && !repo.contains(['/', '\\', '\0']);
+    if !es_valido {
+        return Err(format!("El nombre de repositorio {} no es valido", repo));
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn args(args: &[&str]) -> Vec<String> {
+        args.iter().map(|arg| arg.to_string()).collect()
+    }
+
+    #[test]
+    fn test01_los_flags_pisan_al_archivo_de_configuracion() {
+        let archivo = "tmp/config_servidor_test01";
+        io::escribir_bytes(
+            archivo,
+            "# configuracion\ngir = 0.0.0.0:9000\nhttp = 0.0.0.0:9001\nmax_conexiones = 5\n",
+        )
+        .unwrap();
+
+        let config = ConfigServidor::from_args(args(&[
+            "--config",
+            archivo,
+            "--http",
+            "0.0.0.0:9002",
+            "--repositorios",
+            "/var/lib/gir",
+        ]))
+        .unwrap();
+
+        assert_eq!(config.direccion_gir, "0.0.0.0:9000");
+        assert_eq!(config.direccion_http, "0.0.0.0:9002");
+        assert_eq!(config.dir_repositorios, PathBuf::from("/var/lib/gir"));
+        assert_eq!(config.max_conexiones, 5);
+        assert!(config.tls.is_none());
+        assert!(config.usuarios.is_none());
+    }
+
+    #[test]
+    fn test02_un_argumento_suelto_es_el_puerto_del_servidor_gir() {
+        let config =
+            ConfigServidor::from_args(args(&["9418", "--http", "127.0.0.1:8080"])).unwrap();
+
+        assert_eq!(config.direccion_gir, "127.0.0.1:9418");
+        assert_eq!(
+            config.dir_repositorios,
+            env::current_dir().unwrap().join("srv")
+        );
+        assert_eq!(config.max_conexiones, MAX_CONEXIONES_POR_DEFECTO);
+    }
+
+    #[test]
+    fn test03_tls_necesita_certificado_y_clave() {
+        let solo_certificado = ConfigServidor::from_args(args(&[
+            "--http",
+            "127.0.0.1:8080",
+            "--certificado",
+            "cert.pem",
+        ]));
+        let con_los_dos = ConfigServidor::from_args(args(&[
+            "--http",
+            "127.0.0.1:8080",
+            "--certificado",
+            "cert.pem",
+            "--clave",
+            "clave.pem",
+        ]))
+        .unwrap();
+
+        assert!(solo_certificado.is_err());
+        let dir_actual = env::current_dir().unwrap();
+        assert_eq!(
+            con_los_dos.tls,
+            Some(ConfigTls {
+                certificado: dir_actual.join("cert.pem"),
+                clave: dir_actual.join("clave.pem"),
+            })
+        );
+    }
+
+    #[test]
+    fn test04_opciones_invalidas_dan_error() {
+        let desconocida = ConfigServidor::from_args(args(&["--puerto", "9418"]));
+        let sin_valor = ConfigServidor::from_args(args(&["--http"]));
+        let max_invalido =
+            ConfigServidor::from_args(args(&["--http", "127.0.0.1:8080", "--max-conexiones", "0"]));
+
+        assert!(desconocida.is_err());
+        assert!(sin_valor.is_err());
+        assert!(max_invalido.is_err());
+    }
+
+    #[test]
+    fn test05_el_nombre_de_repositorio_no_puede_salir_del_directorio_de_repositorios() {
+        for invalido in ["", ".", "..", "../otro", "a/b", "a\\b", "*"] {
+            assert!(
+                validar_nombre_repositorio(invalido).is_err(),
+                "{}",
+                invalido
+            );
+        }
+        assert!(validar_nombre_repositorio("repo.v2").is_ok());
+    }
+}
@@ -0,0 +1,526 @@
+use std::{
+    collections::HashMap,
+    path::{Path, PathBuf},
+    sync::{PoisonError, RwLock},
+};
+
+use hmac::Hmac;
+use serde::Deserialize;
+use sha1::Sha1;
+use sha2::{Digest, Sha256};
+
+use crate::{
+    tipos_de_dato::{http::error::ErrorHttp, objetos::tree::Tree, permiso::Permiso},
+    utils::{io, strings},
+};
+
+use super::config_servidor::ConfigServidor;
+
+/// Repositorio comodin en los permisos de una cuenta: vale para todos los repositorios que no
+/// tienen un permiso propio.
+pub const TODOS_LOS_REPOSITORIOS: &str = "*";
+/// Parametro extra del pedido del protocolo gir con el que el cliente manda sus credenciales,
+/// codificadas igual que en el header `Authorization: Basic`.
+pub const PARAMETRO_CREDENCIALES: &str = "credenciales=";
+
+/// Iteraciones de PBKDF2 con las que se derivan los secretos de una cuenta que no indica otra
+/// cantidad.
+const ITERACIONES_POR_DEFECTO: u32 = 100_000;
+/// Tamanio en bytes de la contrasenia derivada, el de un hash sha1.
+const TAMANIO_DERIVADO: usize = 20;
+
+/// Cuenta de un usuario del servidor. La contrasenia se guarda derivada con PBKDF2-HMAC-SHA1
+/// usando la sal de la cuenta, en hexa. Se puede generar con openssl:
+///
+/// ```text
+/// openssl kdf -keylen 20 -kdfopt digest:SHA1 -kdfopt pass:<contrasenia> \
+///     -kdfopt hexsalt:<sal> -kdfopt iter:100000 PBKDF2
+/// ```
+///
+/// Los tokens son aleatorios y largos, asi que alcanza con guardar su sha256 en hexa
+/// (`printf %s <token> | sha256sum`), que se puede buscar directamente sin probar cada cuenta.
+#[derive(Debug, Deserialize)]
+struct Cuenta {
+    nombre: String,
+    /// Sal de la cuenta en hexa, distinta para cada cuenta.
+    sal: String,
+    #[serde(default = "iteraciones_por_defecto")]
+    iteraciones: u32,
+    contrasenia: String,
+    /// Sha256 de cada token de la cuenta, en hexa.
+    #[serde(default)]
+    tokens: Vec<String>,
+    /// Permiso por repositorio. La entrada `*` vale para los repositorios que no aparecen.
+    #[serde(default)]
+    permisos: HashMap<String, Permiso>,
+}
+
+impl Cuenta {
+    // Indica si el secreto es uno de los tokens de la cuenta o su contrasenia. Los tokens se
+    // prueban primero porque no hace falta derivarlos.
+    fn verificar(&self, secreto: &str) -> bool {
+        let digesto = digerir_token(secreto);
+        if self.tokens.iter().any(|token| iguales(token, &digesto)) {
+            return true;
+        }
+        match derivar_contrasenia(secreto, &self.sal, self.iteraciones) {
+            Some(derivada) => iguales(&self.contrasenia, &derivada),
+            None => false,
+        }
+    }
+}
+
+fn iteraciones_por_defecto() -> u32 {
+    ITERACIONES_POR_DEFECTO
+}
+
+#[derive(Debug, Deserialize)]
+struct ArchivoCuentas {
+    cuentas: Vec<Cuenta>,
+}
+
+/// Credenciales con las que se identifica un cliente.
+#[derive(Debug, PartialEq)]
+pub enum Credenciales {
+    /// Usuario y contrasenia. En lugar de la contrasenia se puede usar uno de los tokens de la
+    /// cuenta, como con git.
+    Basica {
+        usuario: String,
+        contrasenia: String,
+    },
+    /// Solo un token, que identifica a la cuenta que lo tiene.
+    Token(String),
+}
+
+impl Credenciales {
+    /// Obtiene las credenciales del valor del header `Authorization`: `Basic <base64 de
+    /// usuario:contrasenia>`, `Bearer <token>` o `token <token>`.
+    pub fn desde_header_http(valor: &str) -> Option<Credenciales> {
+        let (esquema, credenciales) = valor.trim().split_once(' ')?;
+        match esquema.to_lowercase().as_str() {
+            "basic" => Self::desde_base64(credenciales.trim()),
+            "bearer" | "token" => Some(Credenciales::Token(credenciales.trim().to_string())),
+            _ => None,
+        }
+    }
+
+    /// Obtiene las credenciales de `usuario:contrasenia` codificado en base64.
+    pub fn desde_base64(codificadas: &str) -> Option<Credenciales> {
+        let decodificadas = String::from_utf8(strings::decodificar_base64(codificadas)?).ok()?;
+        let (usuario, contrasenia) = decodificadas.split_once(':')?;
+        Some(Credenciales::Basica {
+            usuario: usuario.to_string(),
+            contrasenia: contrasenia.to_string(),
+        })
+    }
+}
+
+/// Motivo por el que se rechaza un pedido.
+#[derive(Debug, PartialEq)]
+pub enum ErrorAcceso {
+    /// El cliente no mando credenciales o no corresponden a ninguna cuenta.
+    NoAutenticado,
+    /// La cuenta no tiene el permiso necesario sobre el repositorio.
+    SinPermiso {
+        usuario: String,
+        repositorio: String,
+        permiso: Permiso,
+    },
+    /// El cliente mando credenciales por una conexion sin cifrar.
+    SinTls,
+}
+
+impl ErrorAcceso {
+    pub fn mensaje(&self) -> String {
+        match self {
+            ErrorAcceso::NoAutenticado => "Se necesitan credenciales validas".to_string(),
+            ErrorAcceso::SinPermiso {
+                usuario,
+                repositorio,
+                permiso,
+            } => format!(
+                "El usuario {} no tiene permiso de {} sobre el repositorio {}",
+                usuario, permiso, repositorio
+            ),
+            ErrorAcceso::SinTls => {
+                "No se aceptan credenciales sin TLS, configure un certificado en el servidor"
+                    .to_string()
+            }
+        }
+    }
+}
+
+impl From<ErrorAcceso> for ErrorHttp {
+    fn from(error: ErrorAcceso) -> Self {
+        match error {
+            ErrorAcceso::NoAutenticado => ErrorHttp::Unauthorized(error.mensaje()),
+            ErrorAcceso::SinPermiso { .. } | ErrorAcceso::SinTls => {
+                ErrorHttp::Forbidden(error.mensaje())
+            }
+        }
+    }
+}
+
+/// Cuentas del servidor y sus permisos sobre cada repositorio.
+///
+/// Si el servidor no tiene archivo de cuentas no se pide autenticacion y cualquiera puede hacer
+/// todo, como antes de que existieran las cuentas.
+pub struct ControlAcceso {
+    cuentas: Option<RwLock<Vec<Cuenta>>>,
+    /// Indice de la cuenta duenia de cada token, por el sha256 del token.
+    tokens: HashMap<String, usize>,
+    /// Archivo del que se cargaron las cuentas, donde se guardan los cambios de permisos.
+    archivo: Option<PathBuf>,
+}
+
+impl ControlAcceso {
+    pub fn sin_restricciones() -> ControlAcceso {
+        ControlAcceso {
+            cuentas: None,
+            tokens: HashMap::new(),
+            archivo: None,
+        }
+    }
+
+    /// Carga las cuentas de un archivo json con el formato:
+    ///
+    /// ```text
+    /// {"cuentas": [{"nombre": "juan", "sal": "<hexa>", "contrasenia": "<pbkdf2>",
+    ///               "tokens": ["<sha256>"], "permisos": {"repo": "escritura", "*": "lectura"}}]}
+    /// ```
+    ///
+    /// `iteraciones` es opcional y por defecto es 100000.
+    pub fn desde_archivo(archivo: &Path) -> Result<ControlAcceso, String> {
+        let contenido = io::leer_a_string(archivo)?;
+        let archivo_cuentas: ArchivoCuentas = serde_json::from_str(&contenido).map_err(|e| {
+            format!(
+                "No se pudo leer el archivo de cuentas {}: {}",
+                archivo.display(),
+                e
+            )
+        })?;
+        let mut tokens = HashMap::new();
+        for (indice, cuenta) in archivo_cuentas.cuentas.iter().enumerate() {
+            for token in &cuenta.tokens {
+                if tokens.insert(token.to_lowercase(), indice).is_some() {
+                    return Err(format!(
+                        "El token {} aparece en mas de una cuenta del archivo {}",
+                        token,
+                        archivo.display()
+                    ));
+                }
+            }
+        }
+        Ok(ControlAcceso {
+            cuentas: Some(RwLock::new(archivo_cuentas.cuentas)),
+            tokens,
+            archivo: Some(archivo.to_path_buf()),
+        })
+    }
+
+    /// Arma el control de acceso con el archivo de cuentas de la configuracion, si tiene.
+    pub fn desde_config(config: &ConfigServidor) -> Result<ControlAcceso, String> {
+        match &config.usuarios {
+            Some(archivo) => Self::desde_archivo(archivo),
+            None => Ok(Self::sin_restricciones()),
+        }
+    }
+
+    /// Verifica que las credenciales correspondan a una cuenta con al menos `permiso` sobre el
+    /// repositorio. Devuelve el nombre de la cuenta y el permiso que tiene, o None y Admin si el
+    /// servidor no tiene cuentas.
+    ///
+    /// Las credenciales solo se aceptan si la conexion es por TLS (`usa_tls`), sea cual sea el
+    /// protocolo, porque si no viajan sin cifrar.
+    pub fn autorizar(
+        &self,
+        credenciales: Option<&Credenciales>,
+        repositorio: &str,
+        permiso: Permiso,
+        usa_tls: bool,
+    ) -> Result<(Option<String>, Permiso), ErrorAcceso> {
+        if credenciales.is_some() && !usa_tls {
+            return Err(ErrorAcceso::SinTls);
+        }
+        let Some(cuentas) = &self.cuentas else {
+            return Ok((None, Permiso::Admin));
+        };
+        let cuentas = cuentas.read().unwrap_or_else(PoisonError::into_inner);
+        let cuenta = credenciales
+            .and_then(|credenciales| self.autenticar(&cuentas, credenciales))
+            .ok_or(ErrorAcceso::NoAutenticado)?;
+
+        let repositorio = repositorio.trim_matches('/');
+        let permiso_cuenta = cuenta
+            .permisos
+            .get(repositorio)
+            .or_else(|| cuenta.permisos.get(TODOS_LOS_REPOSITORIOS));
+        match permiso_cuenta {
+            Some(permiso_cuenta) if *permiso_cuenta >= permiso => {
+                Ok((Some(cuenta.nombre.clone()), *permiso_cuenta))
+            }
+            _ => Err(ErrorAcceso::SinPermiso {
+                usuario: cuenta.nombre.clone(),
+                repositorio: repositorio.to_string(),
+                permiso,
+            }),
+        }
+    }
+
+    /// Pasa los permisos que las cuentas tienen sobre el repositorio `viejo` al repositorio
+    /// `nuevo`, en memoria y en el archivo de cuentas, para que renombrar un repositorio no
+    /// cambie quien puede usarlo.
+    pub fn renombrar_repositorio(&self, viejo: &str, nuevo: &str) -> Result<(), String> {
+        let (Some(cuentas), Some(archivo)) = (&self.cuentas, &self.archivo) else {
+            return Ok(());
+        };
+        let mut cuentas = cuentas.write().unwrap_or_else(PoisonError::into_inner);
+
+        // el archivo se edita como json generico para no perder nada de lo que tenga
+        let error_archivo = |e: serde_json::Error| {
+            format!(
+                "No se pudo actualizar el archivo de cuentas {}: {}",
+                archivo.display(),
+                e
+            )
+        };
+        let mut contenido: serde_json::Value =
+            serde_json::from_str(&io::leer_a_string(archivo)?).map_err(error_archivo)?;
+        if let Some(cuentas_archivo) = contenido["cuentas"].as_array_mut() {
+            for cuenta in cuentas_archivo {
+                if let Some(permisos) = cuenta["permisos"].as_object_mut() {
+                    if let Some(permiso) = permisos.remove(viejo) {
+                        permisos.insert(nuevo.to_string(), permiso);
+                    }
+                }
+            }
+        }
+        let contenido = serde_json::to_string_pretty(&contenido).map_err(error_archivo)?;
+        io::escribir_bytes(archivo, contenido)?;
+
+        for cuenta in cuentas.iter_mut() {
+            if let Some(permiso) = cuenta.permisos.remove(viejo) {
+                cuenta.permisos.insert(nuevo.to_string(), permiso);
+            }
+        }
+        Ok(())
+    }
+
+    fn autenticar<'a>(
+        &self,
+        cuentas: &'a [Cuenta],
+        credenciales: &Credenciales,
+    ) -> Option<&'a Cuenta> {
+        match credenciales {
+            Credenciales::Basica {
+                usuario,
+                contrasenia,
+            } => cuentas
+                .iter()
+                .find(|cuenta| cuenta.nombre == *usuario)
+                .filter(|cuenta| cuenta.verificar(contrasenia)),
+            Credenciales::Token(token) => self
+                .tokens
+                .get(&digerir_token(token))
+                .and_then(|indice| cuentas.get(*indice)),
+        }
+    }
+}
+
+/// Deriva la contrasenia con PBKDF2-HMAC-SHA1 y la devuelve en hexa. Devuelve None si la sal
+/// no esta en hexa.
+fn derivar_contrasenia(contrasenia: &str, sal: &str, iteraciones: u32) -> Option<String> {
+    let sal = Tree::decode_hex(sal).ok()?;
+    let mut derivada = [0u8; TAMANIO_DERIVADO];
+    pbkdf2::pbkdf2::<Hmac<Sha1>>(contrasenia.as_bytes(), &sal, iteraciones, &mut derivada).ok()?;
+    Some(Tree::encode_hex(&derivada))
+}
+
+/// Devuelve el sha256 del token en hexa, que es como se guardan los tokens de las cuentas.
+fn digerir_token(token: &str) -> String {
+    Tree::encode_hex(&Sha256::digest(token.as_bytes()))
+}
+
+// Compara los hashes sin cortar en la primera diferencia, para no dar pistas por el tiempo.
+fn iguales(guardado: &str, derivado: &str) -> bool {
+    let guardado = guardado.to_lowercase();
+    guardado.len() == derivado.len()
+        && guardado
+            .bytes()
+            .zip(derivado.bytes())
+            .fold(0, |diferencia, (a, b)| diferencia | (a ^ b))
+            == 0
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    // pbkdf2 de "contrasenia", con pocas iteraciones para que los tests sean rapidos, y sha256
+    // de "token"
+    const CUENTAS: &str = r#"{"cuentas": [
+        {
+            "nombre": "juan",
+            "sal": "a1b2c3d4e5f60718",
+            "iteraciones": 1000,
+            "contrasenia": "99e7230361bb75a06c360d0d6d03eff9323295ca",
+            "tokens": ["3c469e9d6c5875d37a43f353d4f88e61fcf812c66eee3457465a40b0da4153e0"],
+            "permisos": {"repo": "escritura", "*": "lectura"}
+        }
+    ]}"#;
+
+    fn control_acceso(archivo: &str) -> ControlAcceso {
+        io::escribir_bytes(archivo, CUENTAS).unwrap();
+        ControlAcceso::desde_archivo(Path::new(archivo)).unwrap()
+    }
+
+    #[test]
+    fn test01_se_autoriza_segun_el_permiso_del_repositorio_o_el_comodin() {
+        let control = control_acceso("tmp/control_acceso_test01.json");
+        let credenciales = Credenciales::Basica {
+            usuario: "juan".to_string(),
+            contrasenia: "contrasenia".to_string(),
+        };
+
+        let escritura_repo =
+            control.autorizar(Some(&credenciales), "/repo/", Permiso::Escritura, true);
+        let lectura_otro = control.autorizar(Some(&credenciales), "otro", Permiso::Lectura, true);
+        let escritura_otro =
+            control.autorizar(Some(&credenciales), "otro", Permiso::Escritura, true);
+
+        assert_eq!(
+            escritura_repo,
+            Ok((Some("juan".to_string()), Permiso::Escritura))
+        );
+        assert!(lectura_otro.is_ok());
+        assert!(matches!(
+            escritura_otro,
+            Err(ErrorAcceso::SinPermiso { .. })
+        ));
+    }
+
+    #[test]
+    fn test02_sin_credenciales_validas_no_se_autoriza() {
+        let control = control_acceso("tmp/control_acceso_test02.json");
+        let contrasenia_incorrecta = Credenciales::Basica {
+            usuario: "juan".to_string(),
+            contrasenia: "otra".to_string(),
+        };
+
+        assert_eq!(
+            control.autorizar(None, "repo", Permiso::Lectura, true),
+            Err(ErrorAcceso::NoAutenticado)
+        );
+        assert_eq!(
+            control.autorizar(
+                Some(&contrasenia_incorrecta),
+                "repo",
+                Permiso::Lectura,
+                true
+            ),
+            Err(ErrorAcceso::NoAutenticado)
+        );
+    }
+
+    #[test]
+    fn test03_se_aceptan_tokens_y_el_header_basic() {
+        let control = control_acceso("tmp/control_acceso_test03.json");
+        let basic = format!("Basic {}", strings::codificar_base64(b"juan:contrasenia"));
+        let basic_con_token = format!("Basic {}", strings::codificar_base64(b"juan:token"));
+
+        assert_eq!(basic, "Basic anVhbjpjb250cmFzZW5pYQ==");
+
+        for header in [basic.as_str(), basic_con_token.as_str(), "Bearer token"] {
+            let credenciales = Credenciales::desde_header_http(header).unwrap();
+            assert!(control
+                .autorizar(Some(&credenciales), "repo", Permiso::Escritura, true)
+                .is_ok());
+        }
+    }
+
+    #[test]
+    fn test04_sin_archivo_de_cuentas_se_permite_todo() {
+        let control = ControlAcceso::sin_restricciones();
+
+        assert_eq!(
+            control.autorizar(None, "repo", Permiso::Admin, true),
+            Ok((None, Permiso::Admin))
+        );
+    }
+
+    #[test]
+    fn test05_las_contrasenias_se_derivan_con_pbkdf2_hmac_sha1() {
+        // vectores de prueba del RFC 6070, con la sal "salt" en hexa
+        assert_eq!(
+            derivar_contrasenia("password", "73616c74", 1).unwrap(),
+            "0c60c80f961f0e71f3a9b524af6012062fe037a6"
+        );
+        assert_eq!(
+            derivar_contrasenia("password", "73616c74", 4096).unwrap(),
+            "4b007901b765489abead49d926f721d065a429c1"
+        );
+        assert!(derivar_contrasenia("password", "no es hexa", 1).is_none());
+    }
+
+    #[test]
+    fn test06_un_token_desconocido_no_autentica_a_ninguna_cuenta() {
+        let control = control_acceso("tmp/control_acceso_test06.json");
+
+        assert_eq!(
+            control.autorizar(
+                Some(&Credenciales::Token("otro".to_string())),
+                "repo",
+                Permiso::Lectura,
+                true
+            ),
+            Err(ErrorAcceso::NoAutenticado)
+        );
+    }
+
+    #[test]
+    fn test07_sin_tls_no_se_aceptan_credenciales_aunque_no_haya_cuentas() {
+        let control = control_acceso("tmp/control_acceso_test07.json");
+        let credenciales = Credenciales::Token("token".to_string());
+
+        assert_eq!(
+            control.autorizar(Some(&credenciales), "repo", Permiso::Lectura, false),
+            Err(ErrorAcceso::SinTls)
+        );
+        assert_eq!(
+            ControlAcceso::sin_restricciones().autorizar(
+                Some(&credenciales),
+                "repo",
+                Permiso::Lectura,
+                false
+            ),
+            Err(ErrorAcceso::SinTls)
+        );
+        assert_eq!(
+            control.autorizar(None, "repo", Permiso::Lectura, false),
+            Err(ErrorAcceso::NoAutenticado)
+        );
+    }
+
+    #[test]
+    fn test08_al_renombrar_un_repositorio_se_pasan_sus_permisos() {
+        let archivo = "tmp/control_acceso_test08.json";
+        let control = control_acceso(archivo);
+        let credenciales = Credenciales::Basica {
+            usuario: "juan".to_string(),
+            contrasenia: "contrasenia".to_string(),
+        };
+
+        control.renombrar_repositorio("repo", "nuevo").unwrap();
+        let recargado = ControlAcceso::desde_archivo(Path::new(archivo)).unwrap();
+
+        for control in [control, recargado] {
+            assert!(control
+                .autorizar(Some(&credenciales), "nuevo", Permiso::Escritura, true)
+                .is_ok());
+            assert!(matches!(
+                control.autorizar(Some(&credenciales), "repo", Permiso::Escritura, true),
+                Err(ErrorAcceso::SinPermiso { .. })
+            ));
+        }
+    }
+}
@@ -0,0 +1,332 @@
+use std::{
+    path::{Path, PathBuf},
+    sync::Arc,
+};
+
+use serde::Serialize;
+
+use crate::{
+    tipos_de_dato::{
+        comandos::{cat_file, log::Log},
+        http::error::ErrorHttp,
+        logger::Logger,
+        objeto::Objeto,
+        objetos::{commit::CommitObj, tag::TagObj, tree::Tree},
+    },
+    utils::{compresion, io, referencia, strings},
+};
+
+const DIR_OBJETOS: &str = ".gir/objects/";
+const MODO_TREE: &str = "40000";
+
+/// Rama o tag del repositorio, con el commit al que apunta.
+#[derive(Serialize, Debug, PartialEq)]
+pub struct Referencia {
+    pub nombre: String,
+    pub commit: String,
+}
+
+/// Entrada de un tree: un archivo (`blob`), un directorio (`tree`) o un submodulo (`commit`).
+#[derive(Serialize, Debug, PartialEq)]
+pub struct EntradaTree {
+    pub path: String,
+    pub modo: String,
+    pub tipo: String,
+    pub sha: String,
+}
+
+/// Contenido de un path del repositorio: el archivo con su contenido en base64, o las entradas
+/// del directorio.
+#[derive(Serialize, Debug, PartialEq)]
+#[serde(untagged)]
+pub enum Contenido {
+    Archivo {
+        path: String,
+        sha: String,
+        tamanio: usize,
+        contenido: String,
+    },
+    Directorio(Vec<EntradaTree>),
+}
+
+/// Devuelve la ruta de los objetos del repositorio ubicado en `dir_repositorio`, en el formato
+/// que esperan las funciones de lectura de objetos.
+fn dir_objetos(dir_repositorio: &Path) -> String {
+    dir_repositorio
+        .join(DIR_OBJETOS)
+        .to_string_lossy()
+        .to_string()
+}
+
+/// Devuelve las ramas o los tags del repositorio, ordenados por nombre. El commit de los tags
+/// anotados es al que apunta el tag.
+pub fn obtener_referencias(
+    dir_repositorio: &Path,
+    tags: bool,
+) -> Result<Vec<Referencia>, ErrorHttp> {
+    let prefijo = if tags { "refs/tags/" } else { "refs/heads/" };
+    let dir_gir = dir_repositorio.join(".gir");
+    let dir_objetos = dir_objetos(dir_repositorio);
+
+    let mut referencias = Vec::new();
+    for (referencia, hash) in referencia::obtener_refs_y_hashes(&dir_gir.join(prefijo), &dir_gir)
+        .map_err(ErrorHttp::InternalServerError)?
+    {
+        let commit = TagObj::pelar(&hash, &dir_objetos).map_err(ErrorHttp::InternalServerError)?;
+        referencias.push(Referencia {
+            nombre: referencia.trim_start_matches(prefijo).to_string(),
+            commit,
+        });
+    }
+    referencias.sort_by(|a, b| a.nombre.cmp(&b.nombre));
+    Ok(referencias)
+}
+
+/// Devuelve el hash del commit al que apunta la referencia, que puede ser una rama, un tag o
+/// el hash de un commit o de un tag.
+pub fn resolver_commit(dir_repositorio: &Path, referencia: &str) -> Result<String, ErrorHttp> {
+    if referencia.is_empty() || referencia.split('/').any(|parte| parte == "..") {
+        return Err(ErrorHttp::ValidationFailed(format!(
+            "La referencia {} no es valida",
+            referencia
+        )));
+    }
+    let dir_objetos = dir_objetos(dir_repositorio);
+    let candidatos = [
+        dir_repositorio.join(format!(".gir/refs/heads/{}", referencia)),
+        dir_repositorio.join(format!(".gir/refs/tags/{}", referencia)),
+    ];
+    let hash = match candidatos
+        .iter()
+        .find_map(|ruta| io::leer_a_string(ruta).ok())
+    {
+        Some(hash) => hash.trim().to_string(),
+        None if es_hash(referencia) && compresion::existe_objeto(referencia, &dir_objetos) => {
+            referencia.to_string()
+        }
+        None => {
+            return Err(ErrorHttp::NotFound(format!(
+                "No existe la referencia {}",
+                referencia
+            )))
+        }
+    };
+
+    let commit = TagObj::pelar(&hash, &dir_objetos).map_err(ErrorHttp::InternalServerError)?;
+    if tipo_de_objeto(&commit, &dir_objetos)? != "commit" {
+        return Err(ErrorHttp::ValidationFailed(format!(
+            "{} no es un commit",
+            referencia
+        )));
+    }
+    Ok(commit)
+}
+
+/// Devuelve los primeros `limite` commits alcanzables desde `desde`, del mas nuevo al mas
+/// viejo. Si se pasa un path solo se devuelven los commits que cambiaron ese archivo o
+/// directorio respecto de su primer padre.
+pub fn obtener_commits(
+    dir_repositorio: &Path,
+    desde: &str,
+    path: Option<&str>,
+    limite: usize,
+    logger: Arc<Logger>,
+) -> Result<Vec<CommitObj>, ErrorHttp> {
+    let dir_objetos = dir_objetos(dir_repositorio);
+    let commit = obtener_commit(dir_repositorio, desde, logger.clone())?;
+    let mut commits = Log::obtener_listas_de_commits_de(commit, dir_repositorio, logger.clone())
+        .map_err(ErrorHttp::InternalServerError)?;
+    let path = match path {
+        Some(path) if !path.trim_matches('/').is_empty() => Path::new(path.trim_matches('/')),
+        _ => {
+            commits.truncate(limite);
+            return Ok(commits);
+        }
+    };
+
+    // se busca el path en los trees a medida que hace falta, sin cargarlos enteros, y se
+    // deja de buscar cuando ya se tienen todos los commits pedidos
+    let mut filtrados = Vec::new();
+    for commit in commits {
+        if filtrados.len() == limite {
+            break;
+        }
+        let hash_actual = hash_en_tree(&commit.hash_tree, path, &dir_objetos)?;
+        let hash_padre = match commit.padres.first() {
+            Some(padre) => {
+                let padre = CommitObj::from_hash_de(padre.clone(), &dir_objetos, logger.clone())
+                    .map_err(ErrorHttp::InternalServerError)?;
+                hash_en_tree(&padre.hash_tree, path, &dir_objetos)?
+            }
+            None => None,
+        };
+        if hash_actual != hash_padre {
+            filtrados.push(commit);
+        }
+    }
+    Ok(filtrados)
+}
+
+/// Devuelve el commit al que apunta la referencia.
+pub fn obtener_commit(
+    dir_repositorio: &Path,
+    referencia: &str,
+    logger: Arc<Logger>,
+) -> Result<CommitObj, ErrorHttp> {
+    let hash = resolver_commit(dir_repositorio, referencia)?;
+    CommitObj::from_hash_de(hash, &dir_objetos(dir_repositorio), logger)
+        .map_err(ErrorHttp::InternalServerError)
+}
+
+/// Devuelve las entradas del tree con ese hash, o del tree del commit al que apunta la
+/// referencia. Si es recursivo incluye las entradas de los subdirectorios.
+pub fn obtener_tree(
+    dir_repositorio: &Path,
+    referencia: &str,
+    recursivo: bool,
+    logger: Arc<Logger>,
+) -> Result<Vec<EntradaTree>, ErrorHttp> {
+    let dir_objetos = dir_objetos(dir_repositorio);
+    let hash_tree = if es_hash(referencia)
+        && compresion::existe_objeto(referencia, &dir_objetos)
+        && tipo_de_objeto(referencia, &dir_objetos)? == "tree"
+    {
+        referencia.to_string()
+    } else {
+        obtener_commit(dir_repositorio, referencia, logger.clone())?.hash_tree
+    };
+    let tree = cargar_tree(&hash_tree, &dir_objetos, logger)?;
+
+    let mut entradas = Vec::new();
+    agregar_entradas(&tree, recursivo, &mut entradas);
+    Ok(entradas)
+}
+
+/// Devuelve el contenido del path en el commit al que apunta la referencia. Un path vacio es
+/// la raiz del repositorio.
+pub fn obtener_contenido(
+    dir_repositorio: &Path,
+    referencia: &str,
+    path: &str,
+    logger: Arc<Logger>,
+) -> Result<Contenido, ErrorHttp> {
+    let dir_objetos = dir_objetos(dir_repositorio);
+    let hash_tree = obtener_commit(dir_repositorio, referencia, logger.clone())?.hash_tree;
+    let tree = cargar_tree(&hash_tree, &dir_objetos, logger)?;
+    let path = path.trim_matches('/');
+
+    let objeto = if path.is_empty() {
+        Objeto::Tree(tree)
+    } else {
+        buscar_objeto(&tree, Path::new(path))
+            .ok_or_else(|| ErrorHttp::NotFound(format!("No existe {} en {}", path, referencia)))?
+    };
+
+    match objeto {
+        Objeto::Tree(tree) => {
+            let mut entradas = Vec::new();
+            agregar_entradas(&tree, false, &mut entradas);
+            Ok(Contenido::Directorio(entradas))
+        }
+        Objeto::Blob(blob) => {
+            // el contenido puede ser binario, asi que se lee como bytes
+            let objeto = compresion::obtener_objeto_descomprimido(&blob.hash, &dir_objetos)
+                .map_err(ErrorHttp::InternalServerError)?;
+            let contenido = match objeto.iter().position(|byte| *byte == 0) {
+                Some(fin_header) => &objeto[fin_header + 1..],
+                None => {
+                    return Err(ErrorHttp::InternalServerError(format!(
+                        "El objeto {} es invalido",
+                        blob.hash
+                    )))
+                }
+            };
+            Ok(Contenido::Archivo {
+                path: path.to_string(),
+                sha: blob.hash,
+                tamanio: contenido.len(),
+                contenido: strings::codificar_base64(contenido),
+            })
+        }
+    }
+}
+
+fn es_hash(referencia: &str) -> bool {
+    referencia.len() == 40 && referencia.chars().all(|c| c.is_ascii_hexdigit())
+}
+
+fn tipo_de_objeto(hash: &str, dir_objetos: &str) -> Result<String, ErrorHttp> {
+    cat_file::obtener_tipo_objeto_de(hash, dir_objetos).map_err(ErrorHttp::InternalServerError)
+}
+
+fn cargar_tree(hash_tree: &str, dir_objetos: &str, logger: Arc<Logger>) -> Result<Tree, ErrorHttp> {
+    Tree::from_hash_de(hash_tree, PathBuf::from("."), dir_objetos, logger)
+        .map_err(ErrorHttp::InternalServerError)
+}
+
+// Hash del archivo o directorio en el path dentro del tree del commit, si existe.
+// Devuelve el hash del objeto que esta en `path` dentro del tree, leyendo solo los trees del
+// camino hasta el.
+fn hash_en_tree(
+    hash_tree: &str,
+    path: &Path,
+    dir_objetos: &str,
+) -> Result<Option<String>, ErrorHttp> {
+    let mut hash = hash_tree.to_string();
+    for componente in path.components() {
+        let contenido = compresion::descomprimir_objeto(&hash, dir_objetos)
+            .map_err(ErrorHttp::InternalServerError)?;
+        if !contenido.starts_with("tree ") {
+            return Ok(None);
+        }
+        let hijo = Tree::obtener_datos_de_contenido(&contenido)
+            .map_err(ErrorHttp::InternalServerError)?
+            .into_iter()
+            .find(|(_, nombre, _)| componente.as_os_str() == nombre.as_str());
+        match hijo {
+            Some((_, _, hash_hijo)) => hash = hash_hijo,
+            None => return Ok(None),
+        }
+    }
+    Ok(Some(hash))
+}
+
+fn buscar_objeto(tree: &Tree, path: &Path) -> Option<Objeto> {
+    for objeto in &tree.objetos {
+        let path_objeto = objeto.obtener_path();
+        if path_objeto == path {
+            return Some(objeto.clone());
+        }
+        if let Objeto::Tree(subtree) = objeto {
+            if path.starts_with(&path_objeto) {
+                return buscar_objeto(subtree, path);
+            }
+        }
+    }
+    None
+}
+
+fn agregar_entradas(tree: &Tree, recursivo: bool, entradas: &mut Vec<EntradaTree>) {
+    for objeto in &tree.objetos {
+        let path = objeto.obtener_path().to_string_lossy().to_string();
+        match objeto {
+            Objeto::Tree(subtree) => {
+                entradas.push(EntradaTree {
+                    path,
+                    modo: MODO_TREE.to_string(),
+                    tipo: "tree".to_string(),
+                    sha: objeto.obtener_hash(),
+                });
+                if recursivo {
+                    agregar_entradas(subtree, recursivo, entradas);
+                }
+            }
+            Objeto::Blob(blob) => entradas.push(EntradaTree {
+                path,
+                modo: blob.modo.como_str().to_string(),
+                tipo: blob.modo.tipo_objeto().to_string(),
+                sha: blob.hash.clone(),
+            }),
+        }
+    }
+}
@@ -0,0 +1,99 @@
+use std::{
+    io::{self, Read, Write},
+    net::TcpStream,
+};
+
+use native_tls::{TlsAcceptor, TlsStream};
+
+use super::{
+    config_servidor::ConfigServidor,
+    limite_conexiones::{LimiteConexiones, PermisoConexion},
+};
+
+/// Conexion de un cliente con alguno de los servidores, segun si se configuro TLS.
+pub enum FlujoServidor {
+    /// Conexion sin cifrar.
+    Tcp(TcpStream),
+    /// Conexion por TLS, despues del handshake.
+    Tls(Box<TlsStream<TcpStream>>),
+}
+
+/// Lo que comparten los threads que aceptan clientes de un servidor: el limite de conexiones y,
+/// si se configuro, el acceptor TLS.
+#[derive(Clone)]
+pub struct AceptadorConexiones {
+    limite: LimiteConexiones,
+    tls: Option<TlsAcceptor>,
+}
+
+impl AceptadorConexiones {
+    pub fn new(max_conexiones: usize, tls: Option<TlsAcceptor>) -> AceptadorConexiones {
+        AceptadorConexiones {
+            limite: LimiteConexiones::new(max_conexiones),
+            tls,
+        }
+    }
+
+    /// Arma el aceptador con el maximo de conexiones y el TLS de la configuracion.
+    pub fn desde_config(config: &ConfigServidor) -> Result<AceptadorConexiones, String> {
+        Ok(Self::new(
+            config.max_conexiones,
+            config.crear_acceptor_tls()?,
+        ))
+    }
+
+    /// Ocupa un lugar para atender a un cliente nuevo. Devuelve None si el servidor ya esta
+    /// atendiendo el maximo de conexiones.
+    pub fn tomar_lugar(&self) -> Option<PermisoConexion> {
+        self.limite.tomar()
+    }
+
+    /// Arma el flujo de la conexion aceptada. Con TLS se hace el handshake, por lo que conviene
+    /// llamarlo desde el thread que atiende al cliente.
+    pub fn abrir_flujo(&self, stream: TcpStream) -> Result<FlujoServidor, String> {
+        match &self.tls {
+            Some(acceptor) => {
+                let stream = acceptor
+                    .accept(stream)
+                    .map_err(|e| format!("Fallo el handshake TLS: {}", e))?;
+                Ok(FlujoServidor::Tls(Box::new(stream)))
+            }
+            None => Ok(FlujoServidor::Tcp(stream)),
+        }
+    }
+}
+
+impl FlujoServidor {
+    /// Termina la conexion. Con TLS avisa al cliente que no hay mas datos (close_notify), para
+    /// que pueda distinguir el fin de la respuesta de una conexion cortada.
+    pub fn cerrar(&mut self) {
+        if let FlujoServidor::Tls(stream) = self {
+            let _ = stream.shutdown();
+        }
+    }
+}
+
+impl Read for FlujoServidor {
+    fn read(&mut self, buf: &mut [u8]) -> io::Result<usize> {
+        match self {
+            FlujoServidor::Tcp(stream) => stream.read(buf),
+            FlujoServidor::Tls(stream) => stream.read(buf),
+        }
+    }
+}
+
+impl Write for FlujoServidor {
+    fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+        match self {
+            FlujoServidor::Tcp(stream) => stream.write(buf),
+            FlujoServidor::Tls(stream) => stream.write(buf),
+        }
+    }
+
+    fn flush(&mut self) -> io::Result<()> {
+        match self {
+            FlujoServidor::Tcp(stream) => stream.flush(),
+            FlujoServidor::Tls(stream) => stream.flush(),
+        }
+    }
+}
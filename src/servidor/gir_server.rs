@@ -1,28 +1,37 @@
 use crate::err_comunicacion::ErrorDeComunicacion;
-use crate::servidor::{receive_pack::receive_pack, upload_pack::upload_pack};
+use crate::servidor::{
+    receive_pack::receive_pack, upload_pack::upload_pack, upload_pack_v2::upload_pack_v2,
+};
 use crate::tipos_de_dato::respuesta_pedido::RespuestaDePedido;
-use crate::tipos_de_dato::{comunicacion::Comunicacion, logger::Logger};
+use crate::tipos_de_dato::{comunicacion::Comunicacion, logger::Logger, permiso::Permiso};
 use crate::utils::{self, io as gir_io};
-use std::env::args;
+use std::io::{Read, Write};
 use std::sync::mpsc::Sender;
 
-use std::{
-    env,
-    net::{TcpListener, TcpStream},
-    path::PathBuf,
-    str,
-    sync::Arc,
-    thread,
-};
+use std::{net::TcpListener, path::PathBuf, str, sync::Arc, thread};
 
+use super::config_servidor::{self, ConfigServidor};
+use super::control_acceso::{ControlAcceso, Credenciales, PARAMETRO_CREDENCIALES};
+use super::flujo_servidor::{AceptadorConexiones, FlujoServidor};
 use super::repos_almacen::ReposAlmacen;
 use super::rutas::mensaje_servidor::MensajeServidor;
 use super::vector_threads::VectorThreads;
 
-const VERSION: &str = "version 1\n";
-const CAPABILITIES: &str = "ofs-delta symref=HEAD:refs/heads/master agent=git/2.17.1";
-const DIR: &str = "/srv"; // direccion relativa
-static SERVER_ARGS: usize = 2;
+pub const VERSION: &str = "version 1\n";
+pub const VERSION_2: &str = "version 2\n";
+/// Capacidades que se anuncian junto a las referencias en upload-pack.
+pub const CAPABILITIES: &str =
+    "multi_ack_detailed ofs-delta side-band-64k shallow deepen-since symref=HEAD:refs/heads/master agent=git/2.17.1";
+/// Capacidades que se anuncian junto a las referencias en receive-pack.
+pub const CAPACIDADES_RECEIVE_PACK: &str =
+    "report-status delete-refs atomic ofs-delta side-band-64k agent=git/2.17.1";
+/// Capacidades que se anuncian en el protocolo v2, una por linea despues de la version.
+const CAPACIDADES_V2: [&str; 4] = [
+    "agent=git/2.17.1",
+    "ls-refs",
+    "fetch=shallow",
+    "object-format=sha1",
+];
 
 ///
 pub struct ServidorGir {
@@ -40,29 +49,34 @@ pub struct ServidorGir {
     pub tx: Sender<MensajeServidor>,
 
     pub repos_almacen: ReposAlmacen,
+
+    /// Directorio donde estan los repositorios que sirve el servidor
+    pub dir_repositorios: PathBuf,
+
+    /// Limite de clientes atendidos a la vez y TLS, si se configuro
+    pub conexiones: AceptadorConexiones,
+
+    /// Cuentas y permisos con los que se autorizan los pedidos
+    pub control_acceso: Arc<ControlAcceso>,
 }
 
 impl ServidorGir {
     /// # Argumentos:
-    /// * `address` - Direccion en la que se va a escuchar las conexiones de los clientes
+    /// * `config` - Direccion en la que se escuchan las conexiones de los clientes, directorio de
+    ///   los repositorios, maximo de conexiones y TLS
     /// * `logger` - Logger para registrar los eventos del servidor
     pub fn new(
+        config: &ConfigServidor,
         logger: Arc<Logger>,
         threads: VectorThreads,
         tx: Sender<MensajeServidor>,
         repos_almacen: ReposAlmacen,
     ) -> Result<ServidorGir, String> {
-        let argv = args().collect::<Vec<String>>();
-        if argv.len() != SERVER_ARGS {
-            println!("Cantidad de argumentos inválido");
-            let app_name = &argv[0];
-            println!("Usage:\n{:?} <puerto>", app_name);
-            return Err("Cantidad de argumentos inválido".to_string());
-        }
+        let conexiones = AceptadorConexiones::desde_config(config)?;
+        let control_acceso = Arc::new(ControlAcceso::desde_config(config)?);
+        let address = &config.direccion_gir;
 
-        let address = "127.0.0.1:".to_owned() + &argv[1];
-
-        let listener = TcpListener::bind(&address).map_err(|e| e.to_string())?;
+        let listener = TcpListener::bind(address).map_err(|e| e.to_string())?;
         println!("Escuchando servidor gir en {}", address);
         logger.log("Servidor iniciado");
 
@@ -73,6 +87,9 @@ impl ServidorGir {
             main: None,
             tx,
             repos_almacen,
+            dir_repositorios: config.dir_repositorios.clone(),
+            conexiones,
+            control_acceso,
         })
     }
 
@@ -86,33 +103,40 @@ impl ServidorGir {
         listener: Arc<TcpListener>,
         threads: VectorThreads,
         logger: Arc<Logger>,
-        tx: Sender<MensajeServidor>,
         repos_almacen: ReposAlmacen,
+        dir_repositorios: PathBuf,
+        conexiones: AceptadorConexiones,
+        control_acceso: Arc<ControlAcceso>,
     ) {
         while let Ok((stream, socket)) = listener.accept() {
+            let Some(permiso) = conexiones.tomar_lugar() else {
+                logger.log(&format!(
+                    "Se rechazo la conexion de {}: se alcanzo el maximo de conexiones",
+                    socket
+                ));
+                continue;
+            };
             logger.log(&format!("Se conecto un cliente a gir desde {}", socket));
             let logger_clone = logger.clone();
-            let tx = tx.clone();
             let repos_almacen = repos_almacen.clone();
+            let dir_repositorios = dir_repositorios.clone();
+            let conexiones = conexiones.clone();
+            let control_acceso = control_acceso.clone();
             let handle = thread::spawn(move || -> Result<(), String> {
-                let stream_clonado = match stream.try_clone() {
-                    Ok(stream) => stream,
-                    Err(e) => {
-                        tx.send(MensajeServidor::HttpErrorFatal)
-                            .expect("Error al enviar mensaje de error fatal al servidor");
-                        logger_clone.log(&format!("Error al clonar el stream en http server: {e}"));
-                        return Err(format!("Error al clonar el stream en http server: {e}"));
-                    }
-                };
-                let mut comunicacion = Comunicacion::<TcpStream>::new_para_server(
-                    stream_clonado,
-                    logger_clone.clone(),
-                );
+                let _permiso = permiso;
+                let flujo = conexiones
+                    .abrir_flujo(stream)
+                    .inspect_err(|e| logger_clone.log(e))?;
+                let usa_tls = matches!(flujo, FlujoServidor::Tls(_));
+                let mut comunicacion =
+                    Comunicacion::new_para_server_sobre(flujo, logger_clone.clone());
                 Self::manejar_cliente(
                     &mut comunicacion,
-                    &(env!("CARGO_MANIFEST_DIR").to_string() + DIR),
+                    &dir_repositorios.to_string_lossy(),
                     logger_clone.clone(),
                     repos_almacen,
+                    &control_acceso,
+                    usa_tls,
                 )?;
                 Ok(())
             });
@@ -124,8 +148,6 @@ impl ServidorGir {
             }
         }
 
-        tx.send(MensajeServidor::GirErrorFatal)
-            .expect("Error al enviar mensaje de error fatal al servidor");
         logger.log("Se cerro el servidor");
     }
 
@@ -137,33 +159,58 @@ impl ServidorGir {
         let logger = self.logger.clone();
         let tx = self.tx.clone();
         let repos_almacen = self.repos_almacen.clone();
+        let dir_repositorios = self.dir_repositorios.clone();
+        let conexiones = self.conexiones.clone();
+        let control_acceso = self.control_acceso.clone();
         let handle = thread::spawn(move || {
-            Self::aceptar_conexiones(listener, threads, logger, tx, repos_almacen);
+            Self::aceptar_conexiones(
+                listener,
+                threads,
+                logger,
+                repos_almacen,
+                dir_repositorios,
+                conexiones,
+                control_acceso,
+            );
+            tx.send(MensajeServidor::GirErrorFatal)
+                .expect("Error al enviar mensaje de error fatal al servidor");
         });
         self.main = Some(handle);
         Ok(())
     }
 
     // Funcion para parsear el pedido del cliente y actuar segun corresponda, retorna en caso de que se haya enviado un
-    fn manejar_cliente(
-        comunicacion: &mut Comunicacion<TcpStream>,
+    fn manejar_cliente<T: Read + Write>(
+        comunicacion: &mut Comunicacion<T>,
         dir: &str,
         logger: Arc<Logger>,
         repos_almacen: ReposAlmacen,
+        control_acceso: &ControlAcceso,
+        usa_tls: bool,
     ) -> Result<(), String> {
         let pedido = match comunicacion.aceptar_pedido()? {
             RespuestaDePedido::Mensaje(mensaje) => mensaje,
             RespuestaDePedido::Terminate => return Ok(()),
         }; // acepto la primera linea
-        Self::procesar_pedido(&pedido, comunicacion, dir, logger, repos_almacen)?; // parse de la liena para ver que se pide
+        Self::procesar_pedido(
+            &pedido,
+            comunicacion,
+            dir,
+            logger,
+            repos_almacen,
+            control_acceso,
+            usa_tls,
+        )?; // parse de la liena para ver que se pide
         Ok(())
     }
 
-    // Facilita la primera parte de la funcion anterior
+    // Facilita la primera parte de la funcion anterior. Ademas del pedido, el repositorio y su
+    // directorio devuelve si el cliente pidio el protocolo v2 en los parametros extra
+    // (`\0\0version=2\0`)
     fn parsear_linea_pedido_y_responder_con_version(
         linea_pedido: &str,
         dir: &str,
-    ) -> Result<(String, String, String), String> {
+    ) -> Result<(String, String, String, bool), String> {
         let pedido: Vec<String> = linea_pedido
             .split_whitespace()
             .map(|s| s.to_string())
@@ -172,22 +219,85 @@ impl ServidorGir {
         let args: Vec<String> = pedido[1].split('\0').map(|s| s.to_string()).collect();
         let repositorio = args[0].clone();
         let dir_repositorio = dir.to_string() + &args[0] + "/.gir/";
+        let pide_version_2 = args.iter().skip(1).any(|arg| arg == "version=2");
         let pedido = &pedido[0];
-        Ok((pedido.to_owned(), repositorio, dir_repositorio))
+        Ok((
+            pedido.to_owned(),
+            repositorio,
+            dir_repositorio,
+            pide_version_2,
+        ))
+    }
+
+    // Verifica que el cliente tenga el permiso pedido sobre el repositorio, con las credenciales
+    // que mando en los parametros extra del pedido (`\0credenciales=...\0`). Si no lo tiene se
+    // le avisa con una linea de error.
+    fn autorizar_pedido<T: Read + Write>(
+        linea: &str,
+        repo: &str,
+        permiso: Permiso,
+        control_acceso: &ControlAcceso,
+        comunicacion: &mut Comunicacion<T>,
+        logger: &Logger,
+        usa_tls: bool,
+    ) -> Result<(), String> {
+        let credenciales = linea
+            .split('\0')
+            .find_map(|parametro| parametro.strip_prefix(PARAMETRO_CREDENCIALES))
+            .and_then(Credenciales::desde_base64);
+        let resultado = control_acceso
+            .autorizar(credenciales.as_ref(), repo, permiso, usa_tls)
+            .map_err(|error| error.mensaje());
+        if let Err(mensaje) = resultado {
+            comunicacion.enviar(&utils::strings::obtener_linea_con_largo_hex(&format!(
+                "ERR {}\n",
+                mensaje
+            )))?;
+            logger.log(&mensaje);
+            return Err(mensaje);
+        }
+        Ok(())
     }
 
     // Funcion para actuar segun si se recibe un upload-pack o un receive-pack, en caso de que sea un receive-pack y el repositorio no exista, se crea el mismo
-    fn procesar_pedido(
+    // si el cliente es admin
+    fn procesar_pedido<T: Read + Write>(
         linea: &str,
-        comunicacion: &mut Comunicacion<TcpStream>,
+        comunicacion: &mut Comunicacion<T>,
         dir: &str,
         logger: Arc<Logger>,
         repos_almacen: ReposAlmacen,
+        control_acceso: &ControlAcceso,
+        usa_tls: bool,
     ) -> Result<(), String> {
-        let (pedido, repo, dir_repo) =
+        let (pedido, repo, dir_repo, pide_version_2) =
             Self::parsear_linea_pedido_y_responder_con_version(linea, dir)?;
+        if let Err(error) = config_servidor::validar_nombre_repositorio(repo.trim_matches('/')) {
+            comunicacion.enviar(&utils::strings::obtener_linea_con_largo_hex(&format!(
+                "ERR {}\n",
+                error
+            )))?;
+            logger.log(&error);
+            return Err(error);
+        }
 
-        let mutex = repos_almacen.obtener_mutex_del_repo(&repo)?;
+        let permiso = match pedido.as_str() {
+            "git-receive-pack" if PathBuf::from(&dir_repo).exists() => Permiso::Escritura,
+            "git-receive-pack" => Permiso::Admin,
+            _ => Permiso::Lectura,
+        };
+        Self::autorizar_pedido(
+            linea,
+            &repo,
+            permiso,
+            control_acceso,
+            comunicacion,
+            &logger,
+            usa_tls,
+        )?;
+
+        // con el mismo nombre con el que lo bloquea el servidor http
+        let mutex = repos_almacen.obtener_mutex_del_repo(repo.trim_matches('/'))?;
 
         let _lock = mutex.lock().map_err(|e| e.to_string())?;
 
@@ -200,26 +310,26 @@ impl ServidorGir {
                     logger.log(&error);
                     return Err("No existe el repositorio".to_string());
                 }
-                comunicacion.enviar(&utils::strings::obtener_linea_con_largo_hex(VERSION))?;
-                println!("upload-pack recibido, ejecutando");
-                refs = server_utils::obtener_refs_de(PathBuf::from(&dir_repo))?;
-                comunicacion.responder(&refs)?;
-                upload_pack(dir_repo, comunicacion, &refs, logger.clone())
+                if pide_version_2 {
+                    logger.log("upload-pack v2 recibido, ejecutando");
+                    server_utils::anunciar_capacidades_v2(comunicacion)?;
+                    upload_pack_v2(dir_repo, comunicacion, logger.clone())
+                } else {
+                    comunicacion.enviar(&utils::strings::obtener_linea_con_largo_hex(VERSION))?;
+                    println!("upload-pack recibido, ejecutando");
+                    refs = server_utils::obtener_refs_de(PathBuf::from(&dir_repo), CAPABILITIES)?;
+                    comunicacion.responder(&refs)?;
+                    upload_pack(dir_repo, comunicacion, &refs, logger.clone())
+                }
             }
             "git-receive-pack" => {
                 println!("receive-pack recibido, ejecutando");
 
                 let path = PathBuf::from(&dir_repo);
-
-                if !path.exists() {
-                    gir_io::crear_directorio(path.join("refs/"))?;
-                    gir_io::crear_directorio(path.join("refs/heads/"))?;
-                    gir_io::crear_directorio(path.join("refs/tags/"))?;
-                    gir_io::crear_directorio(path.join("pulls"))?;
-                }
+                server_utils::crear_repositorio_si_no_existe(&path)?;
 
                 comunicacion.enviar(&utils::strings::obtener_linea_con_largo_hex(VERSION))?;
-                refs = server_utils::obtener_refs_de(path)?;
+                refs = server_utils::obtener_refs_de(path, CAPACIDADES_RECEIVE_PACK)?;
                 comunicacion.responder(&refs)?;
                 receive_pack(dir_repo.to_string(), comunicacion, logger.clone())
             }
@@ -241,11 +351,41 @@ impl ServidorGir {
 }
 
 // -------------- utils del server --------------
-mod server_utils {
+pub mod server_utils {
     use super::*;
+    use std::io::{Read, Write};
+    use std::path::Path;
+
+    /// Crea la estructura de un repositorio vacio en `dir` (el directorio .gir) si todavia no
+    /// existe, para poder recibir un push.
+    pub fn crear_repositorio_si_no_existe(dir: &Path) -> Result<(), String> {
+        if !dir.exists() {
+            gir_io::crear_directorio(dir.join("refs/"))?;
+            gir_io::crear_directorio(dir.join("refs/heads/"))?;
+            gir_io::crear_directorio(dir.join("refs/tags/"))?;
+            gir_io::crear_directorio(dir.join("pulls"))?;
+        }
+        Ok(())
+    }
 
-    /// Funcion que busca y devuelve las referencias de una direccion dada en formato pkt de un directorio con el formato de git
-    pub fn obtener_refs_de(dir: PathBuf) -> Result<Vec<String>, String> {
+    /// Anuncia las capacidades del protocolo v2: la linea de version, una capacidad por linea y
+    /// un flush-pkt. Las referencias se piden despues con el comando ls-refs.
+    pub fn anunciar_capacidades_v2<T: Read + Write>(
+        comunicacion: &mut Comunicacion<T>,
+    ) -> Result<(), String> {
+        comunicacion.enviar(&utils::strings::obtener_linea_con_largo_hex(VERSION_2))?;
+        for capacidad in CAPACIDADES_V2 {
+            comunicacion.enviar(&utils::strings::obtener_linea_con_largo_hex(&format!(
+                "{}\n",
+                capacidad
+            )))?;
+        }
+        comunicacion.enviar_flush_pkt()
+    }
+
+    /// Funcion que busca y devuelve las referencias de una direccion dada en formato pkt de un directorio con el formato de git.
+    /// A la primera referencia se le agregan las capacidades dadas
+    pub fn obtener_refs_de(dir: PathBuf, capacidades: &str) -> Result<Vec<String>, String> {
         let mut refs: Vec<String> = Vec::new();
         let head_ref = utils::referencia::obtener_ref_head(dir.join("HEAD"));
         if let Ok(head) = head_ref {
@@ -258,25 +398,24 @@ mod server_utils {
         utils::referencia::obtener_refs_con_largo_hex(&mut refs, dir.join("refs/heads/"), dir_str)?;
         utils::referencia::obtener_refs_con_largo_hex(&mut refs, dir.join("refs/tags/"), dir_str)?;
         if !refs.is_empty() {
-            let ref_con_cap = agregar_capacidades(refs[0].clone());
+            let ref_con_cap = agregar_capacidades(refs[0].clone(), capacidades);
             refs.remove(0);
             refs.insert(0, ref_con_cap);
         } else {
-            refs.push(agregar_capacidades("0".repeat(40)));
+            refs.push(agregar_capacidades("0".repeat(40), capacidades));
         }
         Ok(refs)
     }
 
-    /// Funcion que agrega las capacidades del servidor a una referencia dada en formato pkt
-    pub fn agregar_capacidades(referencia: String) -> String {
+    /// Funcion que agrega las capacidades dadas a una referencia dada en formato pkt
+    pub fn agregar_capacidades(referencia: String, capacidades: &str) -> String {
         let mut referencia_con_capacidades: String;
         if referencia.len() > 40 {
             referencia_con_capacidades = referencia.split_at(4).1.to_string() + "\0";
         } else {
             referencia_con_capacidades = referencia + "\0";
         }
-        let capacidades: Vec<&str> = CAPABILITIES.split_whitespace().collect();
-        for cap in capacidades.iter() {
+        for cap in capacidades.split_whitespace() {
             referencia_con_capacidades.push_str(&format!("{} ", cap));
         }
         let mut referencia_con_capacidades = referencia_con_capacidades.trim_end().to_string();
@@ -288,13 +427,16 @@ mod server_utils {
 #[cfg(test)]
 mod tests {
     use super::*;
+    use crate::tipos_de_dato::{comando::Ejecutar, comandos::tag::Tag};
+    use crate::utils::testing::MockTcpStream;
     use serial_test::serial;
 
     #[test]
     #[serial]
     fn test01_agregar_capacidades() {
         let referencia = "0".repeat(40);
-        let referencia_con_capacidades = server_utils::agregar_capacidades(referencia);
+        let referencia_con_capacidades =
+            server_utils::agregar_capacidades(referencia, CAPABILITIES);
         println!("{}", referencia_con_capacidades);
         assert_eq!(
             referencia_con_capacidades,
@@ -309,7 +451,7 @@ mod tests {
     fn test02_obtener_refs_con_ref_vacia_devuelve_ref_nula() {
         let dir =
             PathBuf::from(env!("CARGO_MANIFEST_DIR").to_string() + "/server_test_dir/test02/.gir/");
-        let refs = server_utils::obtener_refs_de(dir).unwrap();
+        let refs = server_utils::obtener_refs_de(dir, CAPABILITIES).unwrap();
         println!("{:?}", refs);
         assert_eq!(
             refs[0],
@@ -324,7 +466,7 @@ mod tests {
     fn test03_obtener_refs_con_ref_head_devuelve_ref_head() {
         let dir =
             PathBuf::from(env!("CARGO_MANIFEST_DIR").to_string() + "/server_test_dir/test03/.gir/");
-        let refs = server_utils::obtener_refs_de(dir).unwrap();
+        let refs = server_utils::obtener_refs_de(dir, CAPABILITIES).unwrap();
         println!("{:?}", refs);
         assert_eq!(
             refs[0],
@@ -336,4 +478,71 @@ mod tests {
             )
         );
     }
+
+    #[test]
+    #[serial]
+    fn test04_obtener_refs_anuncia_el_commit_de_los_tags_anotados() {
+        let logger = Arc::new(Logger::new(PathBuf::from("tmp/gir_server_test04")).unwrap());
+        utils::testing::limpiar_archivo_gir(logger.clone());
+        utils::io::escribir_bytes("tmp/gir_server_test04_archivo", "contenido").unwrap();
+        utils::testing::addear_archivos_y_comittear(
+            vec!["tmp/gir_server_test04_archivo".to_string()],
+            logger.clone(),
+        );
+        let commit = utils::ramas::obtener_hash_commit_asociado_rama_actual().unwrap();
+        let args = ["-a", "v1.0", "-m", "mensaje"].map(String::from).to_vec();
+        Tag::from(args, logger).unwrap().ejecutar().unwrap();
+        let hash_tag = utils::io::leer_a_string(".gir/refs/tags/v1.0").unwrap();
+
+        let refs = server_utils::obtener_refs_de(PathBuf::from("./.gir/"), CAPABILITIES).unwrap();
+
+        let posicion_tag = refs
+            .iter()
+            .position(|linea| {
+                *linea
+                    == utils::strings::obtener_linea_con_largo_hex(&format!(
+                        "{} refs/tags/v1.0",
+                        hash_tag
+                    ))
+            })
+            .unwrap();
+        assert_eq!(
+            refs[posicion_tag + 1],
+            utils::strings::obtener_linea_con_largo_hex(&format!("{} refs/tags/v1.0^{{}}", commit))
+        );
+    }
+
+    #[test]
+    fn test05_no_se_aceptan_credenciales_sin_tls() {
+        let logger = Arc::new(Logger::new(PathBuf::from("tmp/gir_server_test05")).unwrap());
+        let mock = MockTcpStream {
+            lectura_data: Vec::new(),
+            escritura_data: Vec::new(),
+        };
+        let mut comunicacion = Comunicacion::new_para_testing(mock, logger.clone());
+        let linea = "git-upload-pack /repo/\0host=gir.com\0\0version=1\0credenciales=anVhbjpjb250cmFzZW5pYQ==\0";
+        let control_acceso = ControlAcceso::sin_restricciones();
+
+        let sin_tls = ServidorGir::autorizar_pedido(
+            linea,
+            "/repo/",
+            Permiso::Lectura,
+            &control_acceso,
+            &mut comunicacion,
+            &logger,
+            false,
+        );
+        let con_tls = ServidorGir::autorizar_pedido(
+            linea,
+            "/repo/",
+            Permiso::Lectura,
+            &control_acceso,
+            &mut comunicacion,
+            &logger,
+            true,
+        );
+
+        assert!(sin_tls.is_err());
+        assert!(con_tls.is_ok());
+    }
 }
@@ -0,0 +1,65 @@
+use std::sync::{
+    atomic::{AtomicUsize, Ordering},
+    Arc,
+};
+
+/// Cuenta los clientes que esta atendiendo un servidor para no pasarse del maximo configurado.
+#[derive(Clone)]
+pub struct LimiteConexiones {
+    activas: Arc<AtomicUsize>,
+    maximo: usize,
+}
+
+/// Lugar ocupado por una conexion. Se libera cuando se dropea, al terminar de atender al
+/// cliente.
+pub struct PermisoConexion {
+    activas: Arc<AtomicUsize>,
+}
+
+impl LimiteConexiones {
+    pub fn new(maximo: usize) -> LimiteConexiones {
+        LimiteConexiones {
+            activas: Arc::new(AtomicUsize::new(0)),
+            maximo,
+        }
+    }
+
+    /// Ocupa un lugar para una conexion nueva. Devuelve None si ya se estan atendiendo `maximo`
+    /// conexiones.
+    pub fn tomar(&self) -> Option<PermisoConexion> {
+        self.activas
+            .fetch_update(Ordering::SeqCst, Ordering::SeqCst, |activas| {
+                (activas < self.maximo).then_some(activas + 1)
+            })
+            .ok()?;
+        Some(PermisoConexion {
+            activas: self.activas.clone(),
+        })
+    }
+}
+
+impl Drop for PermisoConexion {
+    fn drop(&mut self) {
+        self.activas.fetch_sub(1, Ordering::SeqCst);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test01_no_se_pasa_del_maximo_y_se_libera_al_dropear_el_permiso() {
+        let limite = LimiteConexiones::new(2);
+
+        let primero = limite.tomar();
+        let segundo = limite.tomar();
+        let tercero = limite.tomar();
+        drop(primero);
+        let cuarto = limite.tomar();
+
+        assert!(segundo.is_some());
+        assert!(tercero.is_none());
+        assert!(cuarto.is_some());
+    }
+}
@@ -1,4 +1,5 @@
 use std::{
+    env::args,
     path::PathBuf,
     sync::{
         mpsc::{channel, Receiver, Sender},
@@ -10,16 +11,20 @@ use std::{
 use gir::servidor::vector_threads::VectorThreads;
 use gir::{
     servidor::{
-        gir_server::ServidorGir, http_server::ServidorHttp, repos_almacen::ReposAlmacen,
-        rutas::mensaje_servidor::MensajeServidor,
+        config_servidor::ConfigServidor, gir_server::ServidorGir, http_server::ServidorHttp,
+        repos_almacen::ReposAlmacen, rutas::mensaje_servidor::MensajeServidor,
     },
     tipos_de_dato::logger::Logger,
 };
 
 const MAX_INTENTOS_REINICIO: u8 = 5;
 static MINIMO_TIEMPO_DE_FUNCIONAMIENTO: u64 = 60;
+const USO: &str = "Uso: server [puerto_gir] [--config <archivo>] [--gir <ip:puerto>] \
+[--http <ip:puerto>] [--repositorios <dir>] [--max-conexiones <n>] \
+[--certificado <archivo.pem> --clave <archivo.pem>] [--usuarios <archivo.json>]";
 
 fn correr_servidor(
+    config: &ConfigServidor,
     logger: Arc<Logger>,
     channel: (Sender<MensajeServidor>, Receiver<MensajeServidor>),
     threads: VectorThreads,
@@ -31,6 +36,7 @@ fn correr_servidor(
     let mut intentos_http = 0;
 
     let mut servidor_http = ServidorHttp::new(
+        config,
         logger.clone(),
         threads.clone(),
         tx.clone(),
@@ -39,6 +45,7 @@ fn correr_servidor(
     servidor_http.iniciar_servidor()?;
 
     let mut servidor_gir = ServidorGir::new(
+        config,
         logger.clone(),
         threads.clone(),
         tx.clone(),
@@ -76,12 +83,15 @@ fn correr_servidor(
 }
 
 fn main() -> Result<(), String> {
+    let config = ConfigServidor::from_args(args().skip(1).collect()).inspect_err(|_| {
+        println!("{}", USO);
+    })?;
     let logger = Arc::new(Logger::new(PathBuf::from("server_logger.txt"))?);
 
     let channel = channel::<MensajeServidor>();
     let threads = Arc::new(Mutex::new(Vec::new()));
 
-    correr_servidor(logger.clone(), channel, threads.clone())?;
+    correr_servidor(&config, logger.clone(), channel, threads.clone())?;
 
     if let Ok(mut threads) = threads.lock() {
         for handle in threads.drain(..) {
@@ -102,11 +112,14 @@ mod test {
     #[test]
     fn test01_correr_servidor_rompe_luego_de_3_intentos() {
         let logger = Arc::new(Logger::new(PathBuf::from("server_logger.txt")).unwrap());
+        let config =
+            ConfigServidor::from_args(vec!["--http".to_string(), "direccion invalida".to_string()])
+                .unwrap();
 
         let channel = channel::<MensajeServidor>();
         let threads = Arc::new(Mutex::new(Vec::new()));
 
-        let result = correr_servidor(logger.clone(), channel, threads.clone());
+        let result = correr_servidor(&config, logger.clone(), channel, threads.clone());
 
         assert!(result.is_err());
     }
@@ -1,4 +1,9 @@
 use crate::{
+    servidor::{
+        comparacion::{ArchivoModificado, Comparacion},
+        config_servidor,
+        ramas_protegidas::RamasProtegidas,
+    },
     tipos_de_dato::{
         comandos::{log::Log, merge::Merge},
         http::error::ErrorHttp,
@@ -10,7 +15,11 @@ use crate::{
 
 use chrono::{DateTime, Utc};
 use serde::{Deserialize, Serialize};
-use std::{collections::HashMap, path::PathBuf, sync::Arc};
+use std::{
+    collections::HashMap,
+    path::{Path, PathBuf},
+    sync::Arc,
+};
 
 const OPEN: &str = "open";
 const CLOSED: &str = "closed";
@@ -36,6 +45,12 @@ pub struct PullRequest {
     pub fecha_creacion: String,
     pub fecha_modificacion: String,
     pub repositorio: String,
+    /// Usuarios que aprobaron el pull request.
+    #[serde(skip_serializing_if = "Vec::is_empty", default)]
+    pub aprobaciones: Vec<String>,
+    /// Commit de `rama_head` que se aprobo. Si la rama se mueve las aprobaciones dejan de valer.
+    #[serde(skip_serializing_if = "Option::is_none", default)]
+    pub commit_aprobado: Option<String>,
 }
 
 fn default_valor_opcional() -> Option<String> {
@@ -47,10 +62,12 @@ impl PullRequest {
     ///
     /// ## Argumentos
     /// - repositorio: el repositorio para el cual se va a crear el pr.
-    ///                 Tiene que existir `./srv/{repositorio}`
+    ///                 Tiene que existir en el directorio de repositorios del servidor
     /// -body: el cuerpo de la request recibida. El body tiene que contener
     ///         los campos: `head` y `base`. Puede tener como opcionales:
     ///         `title` y `body`.
+    /// - usuario: el usuario autenticado, si el servidor tiene cuentas. Si esta es el autor
+    ///   del pr; si no, el autor es el que viene en `head` (`autor:rama`).
     ///
     /// ## Resultado
     /// - El pr creados con los campos obligatorios y opcionales. El numero del
@@ -64,6 +81,7 @@ impl PullRequest {
     pub fn crear_pr(
         repositorio: &str,
         body: HashMap<String, String>,
+        usuario: Option<String>,
     ) -> Result<PullRequest, ErrorHttp> {
         Self::verificar_repositorio(repositorio)?;
 
@@ -72,6 +90,7 @@ impl PullRequest {
         let descripcion = Self::obtener_descripcion(&body);
         let estado = OPEN.to_string();
         let (autor, rama_head) = Self::obtener_autor_y_rama_head(repositorio, &body)?;
+        let autor = usuario.unwrap_or(autor);
         let rama_base = Self::obtener_rama_base(repositorio, &body)?;
         let fecha_actual = Self::obtener_fecha_actual();
 
@@ -86,6 +105,8 @@ impl PullRequest {
             fecha_creacion: fecha_actual.clone(),
             fecha_modificacion: fecha_actual,
             repositorio: repositorio.to_string(),
+            aprobaciones: Vec::new(),
+            commit_aprobado: None,
         })
     }
 
@@ -163,6 +184,87 @@ impl PullRequest {
         Ok(se_actualizo_el_pull_request)
     }
 
+    ///Agrega la aprobacion del usuario al commit actual de la rama head del pull request. Cada
+    /// usuario cuenta una sola vez. Si la rama head se movio desde las aprobaciones anteriores,
+    /// estas se descartan.
+    ///
+    /// ## Errores
+    /// - Si el pr esta cerrado
+    /// - Si el usuario es el autor del pr
+    /// - Si no se puede leer la rama head
+    pub fn aprobar(&mut self, usuario: &str) -> Result<(), ErrorHttp> {
+        if self.estado == *CLOSED {
+            return Err(ErrorHttp::ValidationFailed(
+                "No se puede aprobar un pull request cerrado".to_string(),
+            ));
+        }
+        if self.autor == usuario {
+            return Err(ErrorHttp::ValidationFailed(
+                "El autor no puede aprobar su propio pull request".to_string(),
+            ));
+        }
+
+        let commit_head = self.obtener_commit_head()?;
+        if self.commit_aprobado.as_ref() != Some(&commit_head) {
+            self.aprobaciones.clear();
+            self.commit_aprobado = Some(commit_head);
+        }
+        if !self
+            .aprobaciones
+            .iter()
+            .any(|aprobacion| aprobacion == usuario)
+        {
+            self.aprobaciones.push(usuario.to_string());
+            self.fecha_modificacion = Self::obtener_fecha_actual();
+        }
+        Ok(())
+    }
+
+    ///Devuelve la cantidad de aprobaciones que valen para el commit actual de la rama head.
+    ///
+    /// ## Errores
+    /// - Si no se puede leer la rama head
+    pub fn aprobaciones_vigentes(&self) -> Result<u64, ErrorHttp> {
+        if self.commit_aprobado.as_ref() != Some(&self.obtener_commit_head()?) {
+            return Ok(0);
+        }
+        Ok(self.aprobaciones.len() as u64)
+    }
+
+    ///Verifica que el pr se pueda mergear en su rama base. Si la rama base esta protegida el pr
+    /// tiene que tener las aprobaciones que piden sus reglas, hechas sobre el commit actual de
+    /// la rama head.
+    ///
+    /// ## Errores
+    /// - Si no se pueden leer las ramas protegidas del repositorio
+    /// - Si faltan aprobaciones
+    pub fn verificar_proteccion_rama_base(&self) -> Result<(), ErrorHttp> {
+        let dir_gir = config_servidor::dir_repositorio(&self.repositorio).join(".gir");
+        let protegidas =
+            RamasProtegidas::cargar(&dir_gir).map_err(ErrorHttp::InternalServerError)?;
+
+        if let Some(proteccion) = protegidas.obtener(&self.rama_base) {
+            let aprobaciones = self.aprobaciones_vigentes()?;
+            if aprobaciones < proteccion.aprobaciones_requeridas {
+                return Err(ErrorHttp::Forbidden(format!(
+                    "La rama {} esta protegida: el pull request necesita {} aprobaciones y tiene {}",
+                    self.rama_base, proteccion.aprobaciones_requeridas, aprobaciones
+                )));
+            }
+        }
+        Ok(())
+    }
+
+    // Hash del commit al que apunta la rama head en el repositorio del pr.
+    fn obtener_commit_head(&self) -> Result<String, ErrorHttp> {
+        let ruta = config_servidor::dir_repositorio(&self.repositorio)
+            .join(".gir/refs/heads")
+            .join(&self.rama_head);
+        io::leer_a_string(ruta)
+            .map(|hash| hash.trim().to_string())
+            .map_err(ErrorHttp::InternalServerError)
+    }
+
     fn actualizar_rama_base(&mut self, body: &HashMap<String, String>) -> Result<bool, ErrorHttp> {
         if let Some(nueva_rama_base) = body.get("base") {
             Self::validar_rama(nueva_rama_base, &self.repositorio)?;
@@ -230,7 +332,7 @@ impl PullRequest {
     }
 
     fn verificar_repositorio(repositorio: &str) -> Result<(), ErrorHttp> {
-        let dir_repositorio = PathBuf::from(format!("./srv/{repositorio}"));
+        let dir_repositorio = config_servidor::dir_repositorio(repositorio);
 
         if dir_repositorio.exists() {
             Ok(())
@@ -246,8 +348,21 @@ impl PullRequest {
             .map_err(ErrorHttp::InternalServerError)
     }
 
+    /// Devuelve los archivos que cambia el pull request: los cambios de la rama head desde su
+    /// commit base con la rama base.
+    pub fn obtener_archivos_modificados(
+        &self,
+        logger: Arc<Logger>,
+    ) -> Result<Vec<ArchivoModificado>, ErrorHttp> {
+        let dir_repositorio = config_servidor::dir_repositorio(&self.repositorio);
+        let comparacion =
+            Comparacion::entre(&dir_repositorio, &self.rama_base, &self.rama_head, logger)?;
+        Ok(comparacion.archivos)
+    }
+
     fn _obtener_commits(&self, logger: Arc<Logger>) -> Result<Vec<CommitObj>, String> {
-        self.entrar_a_repositorio()
+        let directorio_previo = self
+            .entrar_a_repositorio()
             .map_err(|e| e.obtener_mensaje())?;
         let hash_ultimo_commit = Merge::obtener_commit_de_branch(&self.rama_head)?;
         let ultimo_commit = CommitObj::from_hash(hash_ultimo_commit, logger.clone())?;
@@ -257,7 +372,7 @@ impl PullRequest {
             &self.rama_head,
             logger.clone(),
         )?;
-        self.salir_del_repositorio()
+        self.salir_del_repositorio(&directorio_previo)
             .map_err(|e| e.obtener_mensaje())?;
 
         let commits_spliteados: Vec<&[CommitObj]> = commits
@@ -271,7 +386,7 @@ impl PullRequest {
     }
 
     fn obtener_numero(repositorio: &str) -> Result<u64, ErrorHttp> {
-        let direccion = PathBuf::from(format!("./srv/{repositorio}/pulls"));
+        let direccion = config_servidor::dir_repositorio(repositorio).join("pulls");
         if !direccion.exists() {
             return Ok(1);
         }
@@ -314,7 +429,9 @@ impl PullRequest {
 
     //Comprueba si existe en
     fn validar_rama(rama: &str, repositorio: &str) -> Result<(), ErrorHttp> {
-        let direccion = PathBuf::from(format!("./srv/{repositorio}/.gir/refs/heads/{rama}"));
+        let direccion = config_servidor::dir_repositorio(repositorio)
+            .join(".gir/refs/heads")
+            .join(rama);
         println!("direccion: {:?}", direccion);
         if !direccion.exists() {
             Err(ErrorHttp::ValidationFailed(format!(
@@ -343,14 +460,22 @@ impl PullRequest {
         body.get("body").map(|descripcion| descripcion.to_owned())
     }
 
-    pub fn entrar_a_repositorio(&self) -> Result<(), ErrorHttp> {
-        let direccion = format!("srv/{}", self.repositorio);
+    /// Se mueve al directorio del repositorio del pull request. Devuelve el directorio en el que
+    /// se estaba, para volver con `salir_del_repositorio`.
+    pub fn entrar_a_repositorio(&self) -> Result<PathBuf, ErrorHttp> {
+        let directorio_previo = std::env::current_dir().map_err(|e| {
+            ErrorHttp::InternalServerError(format!(
+                "No se pudo obtener el directorio actual: {}",
+                e
+            ))
+        })?;
+        let direccion = config_servidor::dir_repositorio(&self.repositorio);
         utils::io::cambiar_directorio(direccion).map_err(ErrorHttp::InternalServerError)?;
-        Ok(())
+        Ok(directorio_previo)
     }
 
-    pub fn salir_del_repositorio(&self) -> Result<(), ErrorHttp> {
-        utils::io::cambiar_directorio("../../").map_err(ErrorHttp::InternalServerError)?;
+    pub fn salir_del_repositorio(&self, directorio_previo: &Path) -> Result<(), ErrorHttp> {
+        utils::io::cambiar_directorio(directorio_previo).map_err(ErrorHttp::InternalServerError)?;
         Ok(())
     }
 
@@ -392,6 +517,7 @@ mod test {
 
     use crate::{
         servidor::{
+            control_acceso::ControlAcceso, flujo_servidor::AceptadorConexiones,
             gir_server::ServidorGir, repos_almacen::ReposAlmacen, vector_threads::VectorThreads,
         },
         tipos_de_dato::{
@@ -447,6 +573,8 @@ mod test {
                 fecha_modificacion,
                 autor,
                 repositorio: "test01_guardar_pr".to_string(),
+                aprobaciones: Vec::new(),
+                commit_aprobado: None,
             }
         };
         let direccion = PathBuf::from("tmp/test01.json");
@@ -485,6 +613,8 @@ mod test {
                 repositorio:
                     "test02_se_puede_guardar_y_cargar_un_pr_con_un_campo_que_no_se_seriliza"
                         .to_string(),
+                aprobaciones: Vec::new(),
+                commit_aprobado: None,
             }
         };
         let direccion = PathBuf::from("tmp/test02.json");
@@ -521,6 +651,8 @@ mod test {
                 fecha_modificacion,
                 autor,
                 repositorio: "test03_se_puede_actualizar_el_titulo".to_string(),
+                aprobaciones: Vec::new(),
+                commit_aprobado: None,
             }
         };
 
@@ -555,6 +687,8 @@ mod test {
                 fecha_modificacion,
                 autor,
                 repositorio: "test04_se_puede_actualizar_la_descripcion".to_string(),
+                aprobaciones: Vec::new(),
+                commit_aprobado: None,
             }
         };
 
@@ -589,6 +723,8 @@ mod test {
                 fecha_modificacion,
                 autor,
                 repositorio: "test05_se_puede_actualizar_el_estado".to_string(),
+                aprobaciones: Vec::new(),
+                commit_aprobado: None,
             }
         };
 
@@ -625,6 +761,8 @@ mod test {
                 autor,
                 repositorio: "test06_se_el_estado_no_puede_cambiar_a_algo_que_no_se_open_o_closed"
                     .to_string(),
+                aprobaciones: Vec::new(),
+                commit_aprobado: None,
             }
         };
 
@@ -657,6 +795,8 @@ mod test {
                 fecha_modificacion,
                 autor,
                 repositorio: "repo_test_07_pull_request".to_string(),
+                aprobaciones: Vec::new(),
+                commit_aprobado: None,
             }
         };
 
@@ -700,6 +840,8 @@ mod test {
                 autor,
                 repositorio: "test08_no_se_puede_actualizar_la_rama_base_con_una_rama_inexistente"
                     .to_string(),
+                aprobaciones: Vec::new(),
+                commit_aprobado: None,
             }
         };
 
@@ -740,6 +882,8 @@ mod test {
                 autor,
                 repositorio: "test09_no_se_puede_actualizar_la_rama_base_a_la_rama_head"
                     .to_string(),
+                aprobaciones: Vec::new(),
+                commit_aprobado: None,
             }
         };
 
@@ -778,6 +922,8 @@ mod test {
                 fecha_modificacion,
                 autor,
                 repositorio: "test_10_se_puede_filtrar_el_pr_acorde_a_su_estado".to_string(),
+                aprobaciones: Vec::new(),
+                commit_aprobado: None,
             }
         };
 
@@ -802,6 +948,8 @@ mod test {
                 fecha_modificacion,
                 autor,
                 repositorio: "test_10_se_puede_filtrar_el_pr_acorde_a_su_estado".to_string(),
+                aprobaciones: Vec::new(),
+                commit_aprobado: None,
             }
         };
 
@@ -840,6 +988,8 @@ mod test {
                 fecha_modificacion,
                 autor,
                 repositorio: "test_11_se_puede_filtrar_el_pr_acorde_a_su_rama_base".to_string(),
+                aprobaciones: Vec::new(),
+                commit_aprobado: None,
             }
         };
 
@@ -864,6 +1014,8 @@ mod test {
                 fecha_modificacion,
                 autor,
                 repositorio: "test_11_se_puede_filtrar_el_pr_acorde_a_su_rama_base".to_string(),
+                aprobaciones: Vec::new(),
+                commit_aprobado: None,
             }
         };
 
@@ -898,6 +1050,8 @@ mod test {
                 autor,
                 repositorio: "test_12_se_puede_filtrar_el_pr_acorde_a_su_autor_y_rama_head"
                     .to_string(),
+                aprobaciones: Vec::new(),
+                commit_aprobado: None,
             }
         };
 
@@ -923,6 +1077,8 @@ mod test {
                 autor,
                 repositorio: "test_12_se_puede_filtrar_el_pr_acorde_a_su_autor_y_rama_head"
                     .to_string(),
+                aprobaciones: Vec::new(),
+                commit_aprobado: None,
             }
         };
 
@@ -947,6 +1103,8 @@ mod test {
                 fecha_modificacion,
                 autor,
                 repositorio: "repo".to_string(),
+                aprobaciones: Vec::new(),
+                commit_aprobado: None,
             }
         };
 
@@ -972,6 +1130,8 @@ mod test {
                 autor,
                 repositorio: "test_12_se_puede_filtrar_el_pr_acorde_a_su_autor_y_rama_head"
                     .to_string(),
+                aprobaciones: Vec::new(),
+                commit_aprobado: None,
             }
         };
 
@@ -1007,6 +1167,8 @@ mod test {
                 fecha_modificacion,
                 autor,
                 repositorio: "test_13_se_puede_filtrar_el_pr_con_varios_filtros".to_string(),
+                aprobaciones: Vec::new(),
+                commit_aprobado: None,
             }
         };
 
@@ -1031,6 +1193,8 @@ mod test {
                 fecha_modificacion,
                 autor,
                 repositorio: "test_13_se_puede_filtrar_el_pr_con_varios_filtros".to_string(),
+                aprobaciones: Vec::new(),
+                commit_aprobado: None,
             }
         };
 
@@ -1055,6 +1219,8 @@ mod test {
                 fecha_modificacion,
                 autor,
                 repositorio: "test_13_se_puede_filtrar_el_pr_con_varios_filtros".to_string(),
+                aprobaciones: Vec::new(),
+                commit_aprobado: None,
             }
         };
 
@@ -1087,6 +1253,9 @@ mod test {
                 main: None,
                 tx,
                 repos_almacen,
+                dir_repositorios: PathBuf::from(env!("CARGO_MANIFEST_DIR")).join("srv"),
+                conexiones: AceptadorConexiones::new(10, None),
+                control_acceso: Arc::new(ControlAcceso::sin_restricciones()),
             };
             servidor_gir.iniciar_servidor().unwrap();
         });
@@ -1126,6 +1295,8 @@ mod test {
                 fecha_modificacion,
                 autor,
                 repositorio: "repo".to_string(),
+                aprobaciones: Vec::new(),
+                commit_aprobado: None,
             }
         };
 
@@ -1155,6 +1326,9 @@ mod test {
                 main: None,
                 tx,
                 repos_almacen: ReposAlmacen::new(),
+                dir_repositorios: PathBuf::from(env!("CARGO_MANIFEST_DIR")).join("srv"),
+                conexiones: AceptadorConexiones::new(10, None),
+                control_acceso: Arc::new(ControlAcceso::sin_restricciones()),
             };
             servidor_gir.iniciar_servidor().unwrap();
         });
@@ -1194,6 +1368,8 @@ mod test {
                 fecha_modificacion,
                 autor,
                 repositorio: "repo".to_string(),
+                aprobaciones: Vec::new(),
+                commit_aprobado: None,
             }
         };
 
@@ -1212,4 +1388,39 @@ mod test {
 
         io::rm_directorio("tmp/pr_test_15_dir").unwrap();
     }
+
+    #[test]
+    #[serial]
+    fn test_16_las_aprobaciones_dejan_de_valer_si_se_mueve_la_rama_head() {
+        let repositorio = "pr_test_16_repo";
+        let ruta_head =
+            config_servidor::dir_repositorio(repositorio).join(".gir/refs/heads/trabajo");
+        io::escribir_bytes(&ruta_head, "1".repeat(40)).unwrap();
+        let mut pr = PullRequest {
+            numero: 1,
+            titulo: None,
+            descripcion: None,
+            estado: String::from("open"),
+            autor: String::from("juan"),
+            rama_head: String::from("trabajo"),
+            rama_base: String::from("master"),
+            fecha_creacion: String::from("Fecha creacion"),
+            fecha_modificacion: String::from("Fecha modificacion"),
+            repositorio: repositorio.to_string(),
+            aprobaciones: Vec::new(),
+            commit_aprobado: None,
+        };
+
+        pr.aprobar("maria").unwrap();
+        let antes_de_mover = pr.aprobaciones_vigentes().unwrap();
+        io::escribir_bytes(&ruta_head, "2".repeat(40)).unwrap();
+        let despues_de_mover = pr.aprobaciones_vigentes().unwrap();
+        pr.aprobar("ana").unwrap();
+
+        assert_eq!(antes_de_mover, 1);
+        assert_eq!(despues_de_mover, 0);
+        assert_eq!(pr.aprobaciones, vec!["ana".to_string()]);
+        assert_eq!(pr.aprobaciones_vigentes().unwrap(), 1);
+        io::rm_directorio(config_servidor::dir_repositorio(repositorio)).unwrap();
+    }
 }
@@ -0,0 +1,115 @@
+use std::path::{Path, PathBuf};
+
+use serde::{Deserialize, Serialize};
+
+use crate::utils::io;
+
+/// Archivo del directorio .gir del repositorio del servidor donde se guardan las reglas.
+const ARCHIVO_PROTECCIONES: &str = "protecciones.json";
+const PREFIJO_RAMAS: &str = "refs/heads/";
+
+/// Reglas de una rama protegida. Una rama protegida nunca se puede borrar.
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
+pub struct ProteccionRama {
+    pub rama: String,
+    /// Si es true la rama no acepta pushes, solo cambia al mergear un pull request.
+    pub requiere_pull_request: bool,
+    /// Aprobaciones que necesita un pull request para poder mergearse en la rama.
+    pub aprobaciones_requeridas: u64,
+    /// Si es false la rama solo acepta pushes fast-forward. Las reglas guardadas antes de que
+    /// existiera esta opcion no aceptan force push.
+    #[serde(default)]
+    pub permite_force_push: bool,
+}
+
+/// Ramas protegidas de un repositorio del servidor.
+pub struct RamasProtegidas {
+    archivo: PathBuf,
+    reglas: Vec<ProteccionRama>,
+}
+
+impl RamasProtegidas {
+    /// Carga las reglas del repositorio cuyo directorio .gir es `dir_gir`. Si el repositorio no
+    /// tiene reglas guardadas no tiene ninguna rama protegida.
+    pub fn cargar(dir_gir: &Path) -> Result<RamasProtegidas, String> {
+        let archivo = dir_gir.join(ARCHIVO_PROTECCIONES);
+        let reglas = if archivo.exists() {
+            let contenido = io::leer_a_string(&archivo)?;
+            serde_json::from_str(&contenido).map_err(|e| {
+                format!(
+                    "No se pudieron leer las ramas protegidas de {}: {}",
+                    archivo.display(),
+                    e
+                )
+            })?
+        } else {
+            Vec::new()
+        };
+        Ok(RamasProtegidas { archivo, reglas })
+    }
+
+    pub fn guardar(&self) -> Result<(), String> {
+        let contenido = serde_json::to_string(&self.reglas)
+            .map_err(|e| format!("No se pudieron serializar las ramas protegidas: {}", e))?;
+        io::escribir_bytes(&self.archivo, contenido)
+    }
+
+    pub fn obtener(&self, rama: &str) -> Option<&ProteccionRama> {
+        self.reglas.iter().find(|regla| regla.rama == rama)
+    }
+
+    /// Obtiene las reglas de la rama a la que apunta la referencia (`refs/heads/'rama'`), si
+    /// esta protegida.
+    pub fn obtener_de_referencia(&self, referencia: &str) -> Option<&ProteccionRama> {
+        self.obtener(referencia.strip_prefix(PREFIJO_RAMAS)?)
+    }
+
+    /// Protege la rama de la regla, reemplazando las reglas que tuviera.
+    pub fn proteger(&mut self, proteccion: ProteccionRama) {
+        self.desproteger(&proteccion.rama);
+        self.reglas.push(proteccion);
+    }
+
+    /// Saca la proteccion de la rama. Devuelve si estaba protegida.
+    pub fn desproteger(&mut self, rama: &str) -> bool {
+        let cantidad = self.reglas.len();
+        self.reglas.retain(|regla| regla.rama != rama);
+        cantidad != self.reglas.len()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn proteccion(rama: &str, aprobaciones_requeridas: u64) -> ProteccionRama {
+        ProteccionRama {
+            rama: rama.to_string(),
+            requiere_pull_request: true,
+            aprobaciones_requeridas,
+            permite_force_push: false,
+        }
+    }
+
+    #[test]
+    fn test01_las_reglas_se_guardan_y_se_cargan() {
+        let dir_gir = Path::new("tmp/ramas_protegidas_test01");
+        let _ = io::rm_directorio(dir_gir);
+        let mut protegidas = RamasProtegidas::cargar(dir_gir).unwrap();
+        protegidas.proteger(proteccion("master", 1));
+        protegidas.proteger(proteccion("master", 2));
+        protegidas.proteger(proteccion("otra", 0));
+        protegidas.guardar().unwrap();
+
+        let mut cargadas = RamasProtegidas::cargar(dir_gir).unwrap();
+        let se_desprotegio = cargadas.desproteger("otra");
+
+        assert_eq!(
+            cargadas.obtener_de_referencia("refs/heads/master"),
+            Some(&proteccion("master", 2))
+        );
+        assert!(se_desprotegio);
+        assert!(cargadas.obtener("otra").is_none());
+        assert!(cargadas.obtener_de_referencia("refs/tags/master").is_none());
+    }
+}
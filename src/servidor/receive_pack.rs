@@ -1,16 +1,66 @@
+use super::ramas_protegidas::RamasProtegidas;
 use crate::tipos_de_dato::comunicacion::Comunicacion;
 use crate::tipos_de_dato::logger::Logger;
+use crate::tipos_de_dato::objetos::tree::Tree;
 use crate::tipos_de_dato::packfile::Packfile;
-use crate::utils::io;
+use crate::utils::{compresion, hooks, io, objects, strings};
+use std::collections::HashSet;
 use std::io::{Read, Write};
+use std::path::{Path, PathBuf};
 use std::sync::Arc;
 
+const REPORT_STATUS: &str = "report-status";
+const DELETE_REFS: &str = "delete-refs";
+const ATOMIC: &str = "atomic";
+const SIDE_BAND_64K: &str = "side-band-64k";
+const HOOK_PRE_RECEIVE: &str = "pre-receive";
+const HOOK_UPDATE: &str = "update";
+const HOOK_POST_RECEIVE: &str = "post-receive";
+
+/// Pedido del cliente de mover una referencia de `viejo` a `nuevo`. Si `nuevo` es el hash nulo
+/// se pide borrar la referencia y si `viejo` es el hash nulo se pide crearla.
+struct Actualizacion {
+    viejo: String,
+    nuevo: String,
+    referencia: String,
+}
+
+impl Actualizacion {
+    fn es_borrado(&self) -> bool {
+        self.nuevo == "0".repeat(40)
+    }
+
+    // Linea 'viejo' 'nuevo' 'referencia' que reciben los hooks pre-receive y post-receive.
+    fn linea_para_hook(&self) -> String {
+        format!("{} {} {}\n", self.viejo, self.nuevo, self.referencia)
+    }
+}
+
 /// Funcion que se encarga de recibir un packfile y actualizar las referencias siguiendo el git transfer protocol
 /// # Argumentos
 /// * `dir` - Direccion del repositorio
 /// * `comunicacion` - Comunicacion con el cliente
+///
+/// Cada referencia se actualiza solo si su valor actual en el servidor es el hash viejo que
+/// mando el cliente y si el objeto nuevo existe. Las ramas protegidas ademas no se pueden borrar,
+/// solo aceptan cambios fast-forward salvo que permitan force push y, si requieren pull request,
+/// solo se pueden crear: despues cambian unicamente al mergear un pull request.
+/// Si el cliente pide `atomic` y alguna actualizacion se rechaza no se actualiza ninguna, y si pide
+/// `report-status` se le responde `ok 'ref'` o `ng 'ref' 'motivo'` por cada una.
+///
+/// Si el repositorio tiene hooks ejecutables en `hooks/`, antes de actualizar se corre
+/// `pre-receive` con todas las actualizaciones validas en stdin (si falla no se actualiza
+/// ninguna) y `update` por cada referencia, con la referencia, el hash viejo y el nuevo como
+/// argumentos (si falla no se actualiza esa referencia). Despues de actualizar se corre
+/// `post-receive` con las actualizaciones aplicadas. Si el cliente pide `side-band-64k` lo que
+/// escriben los hooks en stdout se le manda como mensajes de progreso.
+///
+/// El packfile recibido se guarda en un directorio de cuarentena (`cuarentena-'checksum'/`) y
+/// pasa a `objects/` recien cuando se acepta alguna actualizacion, asi un push rechazado no deja
+/// objetos en el repositorio.
 /// # Errores
-/// Devuelve un error si no se puede leer el packfile o si no se puede escribir en el repositorio
+/// Devuelve un error si no se puede leer el packfile, si el packfile no es valido o si no se puede escribir en el repositorio.
+/// El packfile se verifica antes de escribir nada, asi que si es invalido no se guardan objetos ni se actualizan referencias
 pub fn receive_pack<T>(
     dir: String,
     comunicacion: &mut Comunicacion<T>,
@@ -20,28 +70,406 @@ where
     T: Read + Write,
 {
     logger.log("Iniciando receive pack");
-    let actualizaciones = comunicacion.obtener_lineas()?;
-    let packfile = comunicacion.obtener_packfile()?;
-
-    Packfile::leer_packfile_y_escribir(&packfile, dir.clone() + "objects/")?;
+    let (actualizaciones, capacidades) =
+        parsear_actualizaciones(comunicacion.obtener_lineas_v2()?)?;
+    if actualizaciones.is_empty() {
+        logger.log("El cliente no pidio actualizar ninguna referencia");
+        return Ok(());
+    }
+    let report_status = capacidades.contains(REPORT_STATUS);
+    let side_band = capacidades.contains(SIDE_BAND_64K);
 
-    for actualizacion in &actualizaciones {
-        let mut partes = actualizacion.split(' ');
-        let viejo_hash_ref = partes.next().unwrap_or("");
-        let nuevo_hash_ref = partes.next().unwrap_or("");
-        let referencia = partes.next().unwrap_or("").trim_end_matches('\n');
-        if nuevo_hash_ref != viejo_hash_ref {
-            io::escribir_bytes(dir.clone() + referencia, nuevo_hash_ref)?;
+    // si solo se borran referencias el cliente no manda packfile
+    let mut cuarentena = None;
+    if actualizaciones
+        .iter()
+        .any(|actualizacion| !actualizacion.es_borrado())
+    {
+        let packfile = comunicacion.obtener_packfile()?;
+        match recibir_packfile(&packfile, &dir) {
+            Ok(dir_cuarentena) => cuarentena = Some(dir_cuarentena),
+            Err(error) => {
+                logger.log(&format!("Se rechaza el packfile recibido: {}", error));
+                if report_status {
+                    let rechazos: Vec<(String, Result<(), String>)> = actualizaciones
+                        .iter()
+                        .map(|actualizacion| {
+                            (
+                                actualizacion.referencia.clone(),
+                                Err("error al desempaquetar".to_string()),
+                            )
+                        })
+                        .collect();
+                    enviar_reporte(
+                        comunicacion,
+                        &format!("unpack {}", error),
+                        &rechazos,
+                        side_band,
+                    )?;
+                }
+                return Err(error);
+            }
         }
     }
+
+    let resultado = actualizar_referencias(
+        &dir,
+        &actualizaciones,
+        &capacidades,
+        cuarentena.as_deref(),
+        comunicacion,
+        &logger,
+    );
+    if let Some(cuarentena) = cuarentena {
+        let _ = io::rm_directorio(cuarentena);
+    }
+    resultado?;
     logger.log("Receive pack ejecutado con exito");
     Ok(())
 }
+
+// Valida las actualizaciones, corre los hooks, aplica las que se aceptaron (pasando antes los
+// objetos de la cuarentena al repositorio) y le responde al cliente.
+fn actualizar_referencias<T: Read + Write>(
+    dir: &str,
+    actualizaciones: &[Actualizacion],
+    capacidades: &HashSet<String>,
+    cuarentena: Option<&str>,
+    comunicacion: &mut Comunicacion<T>,
+    logger: &Arc<Logger>,
+) -> Result<(), String> {
+    let report_status = capacidades.contains(REPORT_STATUS);
+    let side_band = capacidades.contains(SIDE_BAND_64K);
+    let dir_objetos = dir.to_string() + "objects/";
+    let mut dirs_objetos = vec![dir_objetos.as_str()];
+    dirs_objetos.extend(cuarentena);
+
+    let protegidas = RamasProtegidas::cargar(Path::new(dir))?;
+    let mut resultados: Vec<(String, Result<(), String>)> = actualizaciones
+        .iter()
+        .map(|actualizacion| {
+            (
+                actualizacion.referencia.clone(),
+                validar_actualizacion(dir, &dirs_objetos, actualizacion, capacidades, &protegidas),
+            )
+        })
+        .collect();
+    let mut mensajes_hooks = String::new();
+    correr_hooks_previos(
+        dir,
+        actualizaciones,
+        &mut resultados,
+        &mut mensajes_hooks,
+        logger,
+    );
+    if capacidades.contains(ATOMIC) && resultados.iter().any(|(_, resultado)| resultado.is_err()) {
+        for (_, resultado) in resultados
+            .iter_mut()
+            .filter(|(_, resultado)| resultado.is_ok())
+        {
+            *resultado = Err("fallo el push atomico".to_string());
+        }
+    }
+
+    if let Some(cuarentena) = cuarentena {
+        if resultados.iter().any(|(_, resultado)| resultado.is_ok()) {
+            mover_objetos_de_cuarentena(cuarentena, &dir_objetos)?;
+        }
+    }
+    for (actualizacion, (_, resultado)) in actualizaciones.iter().zip(&resultados) {
+        match resultado {
+            Ok(()) => aplicar_actualizacion(dir, actualizacion)?,
+            Err(motivo) => logger.log(&format!(
+                "Se rechaza la actualizacion de {}: {}",
+                actualizacion.referencia, motivo
+            )),
+        }
+    }
+    let aplicadas: String = actualizaciones
+        .iter()
+        .zip(&resultados)
+        .filter(|(_, (_, resultado))| resultado.is_ok())
+        .map(|(actualizacion, _)| actualizacion.linea_para_hook())
+        .collect();
+    if !aplicadas.is_empty() {
+        let (_, salida) = correr_hook(dir, HOOK_POST_RECEIVE, &[], &aplicadas, logger);
+        mensajes_hooks.push_str(&salida);
+    }
+
+    if side_band && !mensajes_hooks.is_empty() {
+        comunicacion.enviar_progreso(&mensajes_hooks)?;
+    }
+    if report_status {
+        enviar_reporte(comunicacion, "unpack ok", &resultados, side_band)?;
+    } else if side_band {
+        comunicacion.enviar_flush_pkt()?;
+    }
+    Ok(())
+}
+
+// Separa las lineas 'viejo' 'nuevo' 'referencia' que manda el cliente de las capacidades que pide,
+// que vienen en la primera linea despues de un '\0'.
+fn parsear_actualizaciones(
+    lineas: Vec<String>,
+) -> Result<(Vec<Actualizacion>, HashSet<String>), String> {
+    let mut actualizaciones = Vec::new();
+    let mut capacidades = HashSet::new();
+    for linea in lineas {
+        let linea = linea.trim_end_matches('\n');
+        let (actualizacion, pedidas) = linea.split_once('\0').unwrap_or((linea, ""));
+        capacidades.extend(pedidas.split_whitespace().map(String::from));
+
+        let partes: Vec<&str> = actualizacion.split(' ').collect();
+        if partes.len() != 3 {
+            return Err(format!(
+                "Linea de actualizacion invalida: {}",
+                actualizacion
+            ));
+        }
+        actualizaciones.push(Actualizacion {
+            viejo: partes[0].to_string(),
+            nuevo: partes[1].to_string(),
+            referencia: partes[2].to_string(),
+        });
+    }
+    Ok((actualizaciones, capacidades))
+}
+
+// Verifica el packfile y lo guarda en un directorio de cuarentena dentro de `dir`, que devuelve.
+// Las bases que le faltan si es thin se toman de los objetos del repositorio.
+fn recibir_packfile(packfile: &[u8], dir: &str) -> Result<String, String> {
+    let dir_objetos = dir.to_string() + "objects/";
+    let entradas =
+        Packfile::verificar_packfile(packfile, &dir_objetos).map_err(|error| error.to_string())?;
+    let cuarentena = format!(
+        "{}cuarentena-{}/",
+        dir,
+        Tree::encode_hex(&packfile[packfile.len() - 20..])
+    );
+    if let Err(error) = Packfile::guardar_packfile_en(packfile, entradas, &dir_objetos, &cuarentena)
+    {
+        let _ = io::rm_directorio(&cuarentena);
+        return Err(error);
+    }
+    Ok(cuarentena)
+}
+
+// Pasa los packfiles de la cuarentena a `{dir_objetos}pack/`.
+fn mover_objetos_de_cuarentena(cuarentena: &str, dir_objetos: &str) -> Result<(), String> {
+    let dir_packs = PathBuf::from(cuarentena).join("pack");
+    if !dir_packs.exists() {
+        return Ok(());
+    }
+    let destino = PathBuf::from(dir_objetos).join("pack");
+    io::crear_directorio(&destino)?;
+    for entrada in io::leer_directorio(&dir_packs)? {
+        let entrada = entrada.map_err(|e| e.to_string())?;
+        std::fs::rename(entrada.path(), destino.join(entrada.file_name())).map_err(|e| {
+            format!(
+                "No se pudo pasar {} al repositorio: {}",
+                entrada.path().display(),
+                e
+            )
+        })?;
+    }
+    Ok(())
+}
+
+// Devuelve el motivo por el que no se puede aplicar la actualizacion, si lo hay.
+fn validar_actualizacion(
+    dir: &str,
+    dirs_objetos: &[&str],
+    actualizacion: &Actualizacion,
+    capacidades: &HashSet<String>,
+    protegidas: &RamasProtegidas,
+) -> Result<(), String> {
+    let referencia = &actualizacion.referencia;
+    if !referencia.starts_with("refs/")
+        || referencia
+            .split('/')
+            .any(|parte| parte.is_empty() || parte == "." || parte == "..")
+    {
+        return Err("nombre de referencia invalido".to_string());
+    }
+    if obtener_valor_actual(dir, referencia) != actualizacion.viejo {
+        return Err("la referencia cambio en el servidor, haga fetch primero".to_string());
+    }
+
+    let proteccion = protegidas.obtener_de_referencia(referencia);
+    if actualizacion.es_borrado() {
+        if let Some(proteccion) = proteccion {
+            return Err(format!("la rama {} esta protegida", proteccion.rama));
+        }
+        if !capacidades.contains(DELETE_REFS) {
+            return Err("no se pidio delete-refs".to_string());
+        }
+        if io::leer_a_string(dir.to_string() + "HEAD")
+            .is_ok_and(|head| head.trim() == format!("ref: {}", referencia))
+        {
+            return Err("no se puede borrar la rama actual".to_string());
+        }
+        return Ok(());
+    }
+    if !dirs_objetos
+        .iter()
+        .any(|dir_objetos| compresion::existe_objeto(&actualizacion.nuevo, dir_objetos))
+    {
+        return Err(format!("falta el objeto {}", actualizacion.nuevo));
+    }
+    if actualizacion.viejo == "0".repeat(40) {
+        return Ok(());
+    }
+    if referencia.starts_with("refs/tags/") {
+        return Err("el tag ya existe".to_string());
+    }
+    let Some(proteccion) = proteccion else {
+        return Ok(());
+    };
+    if !proteccion.permite_force_push
+        && !objects::es_ancestro_en(&actualizacion.viejo, &actualizacion.nuevo, dirs_objetos)?
+    {
+        return Err(format!(
+            "la rama {} esta protegida y no acepta force push",
+            proteccion.rama
+        ));
+    }
+    if proteccion.requiere_pull_request {
+        return Err(format!(
+            "la rama {} esta protegida, solo se actualiza mergeando un pull request aprobado",
+            proteccion.rama
+        ));
+    }
+    Ok(())
+}
+
+// Corre pre-receive con las actualizaciones que pasaron la validacion y, si las acepta, update
+// por cada una. Las que rechaza algun hook se marcan como rechazadas en `resultados`. Lo que
+// escriben los hooks se agrega a `mensajes`.
+fn correr_hooks_previos(
+    dir: &str,
+    actualizaciones: &[Actualizacion],
+    resultados: &mut [(String, Result<(), String>)],
+    mensajes: &mut String,
+    logger: &Arc<Logger>,
+) {
+    let validas: String = actualizaciones
+        .iter()
+        .zip(resultados.iter())
+        .filter(|(_, (_, resultado))| resultado.is_ok())
+        .map(|(actualizacion, _)| actualizacion.linea_para_hook())
+        .collect();
+    if validas.is_empty() {
+        return;
+    }
+
+    let (exito, salida) = correr_hook(dir, HOOK_PRE_RECEIVE, &[], &validas, logger);
+    mensajes.push_str(&salida);
+    if !exito {
+        for (_, resultado) in resultados.iter_mut().filter(|(_, r)| r.is_ok()) {
+            *resultado = Err("rechazado por el hook pre-receive".to_string());
+        }
+        return;
+    }
+
+    for (actualizacion, (_, resultado)) in actualizaciones.iter().zip(resultados.iter_mut()) {
+        if resultado.is_err() {
+            continue;
+        }
+        let argumentos = [
+            actualizacion.referencia.as_str(),
+            actualizacion.viejo.as_str(),
+            actualizacion.nuevo.as_str(),
+        ];
+        let (exito, salida) = correr_hook(dir, HOOK_UPDATE, &argumentos, "", logger);
+        mensajes.push_str(&salida);
+        if !exito {
+            *resultado = Err("rechazado por el hook update".to_string());
+        }
+    }
+}
+
+// Corre el hook del repositorio y devuelve si lo acepto y lo que escribio en stdout y stderr,
+// para mandarselo al cliente por el canal de progreso. Si el hook no existe se acepta, y si no
+// se pudo ejecutar se rechaza.
+fn correr_hook(
+    dir: &str,
+    nombre: &str,
+    argumentos: &[&str],
+    entrada: &str,
+    logger: &Arc<Logger>,
+) -> (bool, String) {
+    let dir = Path::new(dir);
+    match hooks::ejecutar_hook(&dir.join("hooks"), nombre, argumentos, entrada, dir) {
+        Ok(Some(salida)) => {
+            logger.log(&format!(
+                "Hook {} termino {}",
+                nombre,
+                if salida.exito {
+                    "con exito"
+                } else {
+                    "con error"
+                }
+            ));
+            // como git, lo que el hook escribe en stdout y stderr le llega al cliente
+            (salida.exito, salida.salida + &salida.errores)
+        }
+        Ok(None) => (true, String::new()),
+        Err(error) => {
+            logger.log(&error);
+            (false, error + "\n")
+        }
+    }
+}
+
+// Hash al que apunta la referencia en el servidor, o el hash nulo si no existe.
+fn obtener_valor_actual(dir: &str, referencia: &str) -> String {
+    match io::leer_a_string(dir.to_string() + referencia) {
+        Ok(hash) if !hash.trim().is_empty() => hash.trim().to_string(),
+        _ => "0".repeat(40),
+    }
+}
+
+fn aplicar_actualizacion(dir: &str, actualizacion: &Actualizacion) -> Result<(), String> {
+    let ruta = PathBuf::from(dir.to_string() + &actualizacion.referencia);
+    if actualizacion.es_borrado() {
+        io::rm_directorio(ruta)
+    } else {
+        io::escribir_bytes(ruta, &actualizacion.nuevo)
+    }
+}
+
+// Responde el report-status: la linea unpack, una linea ok/ng por referencia y un flush-pkt.
+fn enviar_reporte<T: Read + Write>(
+    comunicacion: &mut Comunicacion<T>,
+    linea_unpack: &str,
+    resultados: &[(String, Result<(), String>)],
+    side_band: bool,
+) -> Result<(), String> {
+    let mut reporte = strings::obtener_linea_con_largo_hex(&format!("{}\n", linea_unpack));
+    for (referencia, resultado) in resultados {
+        let linea = match resultado {
+            Ok(()) => format!("ok {}\n", referencia),
+            Err(motivo) => format!("ng {} {}\n", referencia, motivo),
+        };
+        reporte.push_str(&strings::obtener_linea_con_largo_hex(&linea));
+    }
+    // con side-band el reporte entero (con su flush-pkt) va por el canal de datos
+    if side_band {
+        reporte.push_str("0000");
+        comunicacion.enviar_datos_multiplexados(reporte.as_bytes())?;
+    } else {
+        comunicacion.enviar(&reporte)?;
+    }
+    comunicacion.enviar_flush_pkt()
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
+    use crate::servidor::ramas_protegidas::ProteccionRama;
     use crate::tipos_de_dato::{comunicacion::Comunicacion, logger::Logger, packfile};
-    use crate::utils;
+    use crate::utils::testing::{self, escribir_hook};
+    use crate::utils::{self, ramas};
     use serial_test::serial;
     use std::io::{Read, Write};
     use std::path::PathBuf;
@@ -70,14 +498,97 @@ mod tests {
             self.lectura_data.flush()
         }
     }
+
+    // Manda las actualizaciones (con las capacidades en la primera linea) y el packfile, corre
+    // receive pack sobre `dir` y devuelve las lineas del report-status.
+    fn correr_receive_pack(
+        dir: &str,
+        actualizaciones: &[(&str, &str, &str)],
+        capacidades: &str,
+        packfile: Option<Vec<u8>>,
+        logger: Arc<Logger>,
+    ) -> Vec<String> {
+        correr_receive_pack_sin_leer_respuesta(dir, actualizaciones, capacidades, packfile, logger)
+            .obtener_lineas_v2()
+            .unwrap()
+    }
+
+    fn correr_receive_pack_sin_leer_respuesta(
+        dir: &str,
+        actualizaciones: &[(&str, &str, &str)],
+        capacidades: &str,
+        packfile: Option<Vec<u8>>,
+        logger: Arc<Logger>,
+    ) -> Comunicacion<MockTcpStream> {
+        let mock = MockTcpStream {
+            lectura_data: Vec::new(),
+        };
+        let mut comunicacion = Comunicacion::new_para_testing(mock, logger.clone());
+        for (i, (viejo, nuevo, referencia)) in actualizaciones.iter().enumerate() {
+            let mut linea = format!("{} {} {}", viejo, nuevo, referencia);
+            if i == 0 {
+                linea.push_str(&format!("\0{}", capacidades));
+            }
+            linea.push('\n');
+            comunicacion
+                .enviar(&utils::strings::obtener_linea_con_largo_hex(&linea))
+                .unwrap();
+        }
+        comunicacion.enviar_flush_pkt().unwrap();
+        if let Some(packfile) = packfile {
+            comunicacion.enviar_pack_file(packfile).unwrap();
+        }
+        receive_pack(dir.to_string(), &mut comunicacion, logger).unwrap();
+        comunicacion
+    }
+
+    // Crea un repositorio local con dos commits, el segundo hijo del primero, y un directorio de
+    // servidor vacio. Devuelve los hashes de los commits y el packfile con todos los objetos.
+    fn preparar_repos(dir_servidor: &str, logger: Arc<Logger>) -> (String, String, Vec<u8>) {
+        testing::limpiar_archivo_gir(logger.clone());
+        let _ = io::rm_directorio(dir_servidor);
+        let archivo = "tmp/receive_pack_archivo".to_string();
+        io::escribir_bytes(&archivo, "primero").unwrap();
+        testing::addear_archivos_y_comittear(vec![archivo.clone()], logger.clone());
+        let primero = ramas::obtener_hash_commit_asociado_rama_actual().unwrap();
+        io::escribir_bytes(&archivo, "segundo").unwrap();
+        testing::addear_archivos_y_comittear(vec![archivo], logger);
+        let segundo = ramas::obtener_hash_commit_asociado_rama_actual().unwrap();
+        let packfile = packfile::Packfile::obtener_pack_entero("./.gir/objects/").unwrap();
+        (primero, segundo, packfile)
+    }
+
     #[test]
     #[serial]
     fn test01_refs_se_actualizan_correctamente() {
         let test_dir = env!("CARGO_MANIFEST_DIR").to_string() + "/server_test_dir/test03/.gir/";
+        let commit = "4163eb28ec61fd1d0c17cf9b77f4c17e1e338b0b";
+        let logger = Arc::new(Logger::new(PathBuf::from("tmp/fetch_02.txt")).unwrap());
+        let packfile =
+            packfile::Packfile::obtener_pack_entero(&(test_dir.clone() + "objects/")).unwrap();
+        let nuevo_repo = "tmp/receive_pack_test01/";
+        let _ = io::rm_directorio(nuevo_repo);
+
+        let reporte = correr_receive_pack(
+            nuevo_repo,
+            &[(&"0".repeat(40), commit, "refs/heads/master")],
+            "report-status",
+            Some(packfile),
+            logger,
+        );
+
+        assert_eq!(reporte, vec!["unpack ok\n", "ok refs/heads/master\n"]);
+        let nueva_ref = io::leer_a_string(nuevo_repo.to_string() + "refs/heads/master").unwrap();
+        assert_eq!(nueva_ref, commit);
+    }
+
+    #[test]
+    #[serial]
+    fn test02_packfile_corrupto_no_actualiza_refs_ni_escribe_objetos() {
         let mock: MockTcpStream = MockTcpStream {
             lectura_data: Vec::new(),
         };
-        let logger = Arc::new(Logger::new(PathBuf::from("tmp/fetch_02.txt")).unwrap());
+        let logger = Arc::new(Logger::new(PathBuf::from("tmp/receive_pack_02.txt")).unwrap());
         let mut comunicacion = Comunicacion::new_para_testing(mock, logger.clone());
 
         let actualizaciones = utils::strings::obtener_linea_con_largo_hex(
@@ -85,14 +596,400 @@ mod tests {
         );
         comunicacion.enviar(&actualizaciones).unwrap();
         comunicacion.enviar("0000").unwrap();
-        let packfile =
-            packfile::Packfile::obtener_pack_con_archivos(vec![], &(test_dir.clone() + "objects/"))
-                .unwrap();
+        let mut packfile = packfile::Packfile::obtener_pack_con_archivos(
+            vec!["5122b1de1b7a07e36b01cd62bd622a0715f92478".to_string()],
+            &(env!("CARGO_MANIFEST_DIR").to_string() + "/packfile_test_dir/"),
+        )
+        .unwrap();
+        let ultimo = packfile.len() - 1;
+        packfile[ultimo] ^= 0xff;
         comunicacion.enviar_pack_file(packfile).unwrap();
-        let nuevo_repo = env!("CARGO_MANIFEST_DIR").to_string() + "/server_test_dir/test04/";
-        receive_pack(nuevo_repo.clone(), &mut comunicacion, logger.clone()).unwrap();
-        // let ref_nuevo_repo = io::leer_bytes(nuevo_repo + "refs/heads/master").unwrap();
-        let nueva_ref = io::leer_a_string(nuevo_repo + "refs/heads/master").unwrap();
-        assert_eq!(nueva_ref, "1".repeat(40));
+
+        let nuevo_repo = env!("CARGO_MANIFEST_DIR").to_string() + "/server_test_dir/test05/";
+        let _ = io::rm_directorio(&nuevo_repo);
+        assert!(receive_pack(nuevo_repo.clone(), &mut comunicacion, logger.clone()).is_err());
+        assert!(!PathBuf::from(nuevo_repo.clone() + "refs/heads/master").exists());
+        assert!(!PathBuf::from(nuevo_repo + "objects").exists());
+    }
+
+    #[test]
+    #[serial]
+    fn test03_no_se_actualiza_una_referencia_que_cambio_en_el_servidor() {
+        let logger = Arc::new(Logger::new(PathBuf::from("tmp/receive_pack_03.txt")).unwrap());
+        let dir = "tmp/receive_pack_test03/";
+        let (primero, segundo, packfile) = preparar_repos(dir, logger.clone());
+        let nulo = "0".repeat(40);
+        let master = "refs/heads/master";
+        correr_receive_pack(
+            dir,
+            &[(&nulo, &primero, master)],
+            "",
+            Some(packfile.clone()),
+            logger.clone(),
+        );
+
+        let reporte = correr_receive_pack(
+            dir,
+            &[(&nulo, &segundo, master)],
+            "report-status",
+            Some(packfile.clone()),
+            logger.clone(),
+        );
+        assert_eq!(
+            reporte[1],
+            "ng refs/heads/master la referencia cambio en el servidor, haga fetch primero\n"
+        );
+        assert_eq!(
+            io::leer_a_string(dir.to_string() + master).unwrap(),
+            primero
+        );
+
+        let reporte = correr_receive_pack(
+            dir,
+            &[(&primero, &segundo, master)],
+            "report-status",
+            Some(packfile),
+            logger,
+        );
+        assert_eq!(reporte[1], "ok refs/heads/master\n");
+        assert_eq!(
+            io::leer_a_string(dir.to_string() + master).unwrap(),
+            segundo
+        );
+    }
+
+    #[test]
+    #[serial]
+    fn test04_una_rama_sin_proteger_acepta_force_push() {
+        let logger = Arc::new(Logger::new(PathBuf::from("tmp/receive_pack_04.txt")).unwrap());
+        let dir = "tmp/receive_pack_test04/";
+        let (primero, segundo, packfile) = preparar_repos(dir, logger.clone());
+        let master = "refs/heads/master";
+        correr_receive_pack(
+            dir,
+            &[(&"0".repeat(40), &segundo, master)],
+            "",
+            Some(packfile.clone()),
+            logger.clone(),
+        );
+
+        let reporte = correr_receive_pack(
+            dir,
+            &[(&segundo, &primero, master)],
+            "report-status",
+            Some(packfile),
+            logger,
+        );
+
+        assert_eq!(reporte, vec!["unpack ok\n", "ok refs/heads/master\n"]);
+        assert_eq!(
+            io::leer_a_string(dir.to_string() + master).unwrap(),
+            primero
+        );
+    }
+
+    #[test]
+    #[serial]
+    fn test05_borrar_una_referencia_requiere_delete_refs() {
+        let logger = Arc::new(Logger::new(PathBuf::from("tmp/receive_pack_05.txt")).unwrap());
+        let dir = "tmp/receive_pack_test05/";
+        let (primero, _, packfile) = preparar_repos(dir, logger.clone());
+        let nulo = "0".repeat(40);
+        let rama = "refs/heads/rama";
+        correr_receive_pack(
+            dir,
+            &[(&nulo, &primero, rama)],
+            "",
+            Some(packfile),
+            logger.clone(),
+        );
+
+        let reporte = correr_receive_pack(
+            dir,
+            &[(&primero, &nulo, rama)],
+            "report-status",
+            None,
+            logger.clone(),
+        );
+        assert_eq!(reporte[1], "ng refs/heads/rama no se pidio delete-refs\n");
+        assert!(PathBuf::from(dir.to_string() + rama).exists());
+
+        let reporte = correr_receive_pack(
+            dir,
+            &[(&primero, &nulo, rama)],
+            "report-status delete-refs",
+            None,
+            logger,
+        );
+        assert_eq!(reporte, vec!["unpack ok\n", "ok refs/heads/rama\n"]);
+        assert!(!PathBuf::from(dir.to_string() + rama).exists());
+    }
+
+    #[test]
+    #[serial]
+    fn test06_push_atomico_no_actualiza_ninguna_referencia_si_se_rechaza_alguna() {
+        let logger = Arc::new(Logger::new(PathBuf::from("tmp/receive_pack_06.txt")).unwrap());
+        let dir = "tmp/receive_pack_test06/";
+        let (primero, segundo, packfile) = preparar_repos(dir, logger.clone());
+
+        let reporte = correr_receive_pack(
+            dir,
+            &[
+                (&"0".repeat(40), &primero, "refs/heads/master"),
+                (&"1".repeat(40), &segundo, "refs/heads/otra"),
+            ],
+            "report-status atomic",
+            Some(packfile),
+            logger,
+        );
+
+        assert_eq!(
+            reporte,
+            vec![
+                "unpack ok\n",
+                "ng refs/heads/master fallo el push atomico\n",
+                "ng refs/heads/otra la referencia cambio en el servidor, haga fetch primero\n",
+            ]
+        );
+        assert!(!PathBuf::from(dir.to_string() + "refs/heads/master").exists());
+        assert!(!PathBuf::from(dir.to_string() + "refs/heads/otra").exists());
+    }
+
+    #[test]
+    #[serial]
+    fn test07_si_falla_pre_receive_no_se_actualiza_nada_y_su_salida_llega_al_cliente() {
+        let logger = Arc::new(Logger::new(PathBuf::from("tmp/receive_pack_07.txt")).unwrap());
+        let dir = "tmp/receive_pack_test07/";
+        let (primero, _, packfile) = preparar_repos(dir, logger.clone());
+        let nulo = "0".repeat(40);
+        let dir_hooks = dir.to_string() + "hooks";
+        escribir_hook(
+            &dir_hooks,
+            "pre-receive",
+            "#!/bin/sh\necho no se aceptan pushes\ncat\nexit 1\n",
+        );
+
+        let mut comunicacion = correr_receive_pack_sin_leer_respuesta(
+            dir,
+            &[(&nulo, &primero, "refs/heads/master")],
+            "report-status side-band-64k",
+            Some(packfile),
+            logger,
+        );
+        let reporte = comunicacion.obtener_lineas_multiplexadas().unwrap();
+
+        assert_eq!(
+            reporte,
+            vec![
+                "unpack ok\n",
+                "ng refs/heads/master rechazado por el hook pre-receive\n"
+            ]
+        );
+        assert_eq!(
+            comunicacion.obtener_progreso(),
+            vec![
+                "no se aceptan pushes".to_string(),
+                format!("{} {} refs/heads/master", nulo, primero)
+            ]
+        );
+        assert!(!PathBuf::from(dir.to_string() + "refs/heads/master").exists());
+    }
+
+    #[test]
+    #[serial]
+    fn test08_update_rechaza_solo_su_referencia_y_post_receive_recibe_las_actualizadas() {
+        let logger = Arc::new(Logger::new(PathBuf::from("tmp/receive_pack_08.txt")).unwrap());
+        let dir = "tmp/receive_pack_test08/";
+        let (primero, segundo, packfile) = preparar_repos(dir, logger.clone());
+        let nulo = "0".repeat(40);
+        let dir_hooks = dir.to_string() + "hooks";
+        escribir_hook(
+            &dir_hooks,
+            "update",
+            "#!/bin/sh\n[ \"$1\" != refs/heads/protegida ]\n",
+        );
+        escribir_hook(
+            &dir_hooks,
+            "post-receive",
+            "#!/bin/sh\ncat > post-receive.txt\n",
+        );
+
+        let reporte = correr_receive_pack(
+            dir,
+            &[
+                (&nulo, &primero, "refs/heads/master"),
+                (&nulo, &segundo, "refs/heads/protegida"),
+            ],
+            "report-status",
+            Some(packfile),
+            logger,
+        );
+
+        assert_eq!(
+            reporte,
+            vec![
+                "unpack ok\n",
+                "ok refs/heads/master\n",
+                "ng refs/heads/protegida rechazado por el hook update\n",
+            ]
+        );
+        assert_eq!(
+            io::leer_a_string(dir.to_string() + "post-receive.txt").unwrap(),
+            format!("{} {} refs/heads/master\n", nulo, primero)
+        );
+        assert!(!PathBuf::from(dir.to_string() + "refs/heads/protegida").exists());
+    }
+
+    #[test]
+    #[serial]
+    fn test09_una_rama_protegida_no_se_borra_ni_se_pushea_si_requiere_pull_request() {
+        let logger = Arc::new(Logger::new(PathBuf::from("tmp/receive_pack_09.txt")).unwrap());
+        let dir = "tmp/receive_pack_test09/";
+        let (primero, segundo, packfile) = preparar_repos(dir, logger.clone());
+        let nulo = "0".repeat(40);
+        let master = "refs/heads/master";
+        let mut protegidas = RamasProtegidas::cargar(Path::new(dir)).unwrap();
+        protegidas.proteger(ProteccionRama {
+            rama: "master".to_string(),
+            requiere_pull_request: true,
+            aprobaciones_requeridas: 1,
+            permite_force_push: false,
+        });
+        protegidas.guardar().unwrap();
+        let creacion = correr_receive_pack(
+            dir,
+            &[(&nulo, &primero, master)],
+            "report-status",
+            Some(packfile.clone()),
+            logger.clone(),
+        );
+
+        let push_directo = correr_receive_pack(
+            dir,
+            &[(&primero, &segundo, master)],
+            "report-status",
+            Some(packfile),
+            logger.clone(),
+        );
+        let borrado = correr_receive_pack(
+            dir,
+            &[(&primero, &nulo, master)],
+            "report-status delete-refs",
+            None,
+            logger,
+        );
+
+        assert_eq!(creacion[1], "ok refs/heads/master\n");
+        assert_eq!(
+            push_directo[1],
+            "ng refs/heads/master la rama master esta protegida, solo se actualiza mergeando un pull request aprobado\n"
+        );
+        assert_eq!(
+            borrado[1],
+            "ng refs/heads/master la rama master esta protegida\n"
+        );
+        assert_eq!(
+            io::leer_a_string(dir.to_string() + master).unwrap(),
+            primero
+        );
+    }
+
+    #[test]
+    #[serial]
+    fn test10_una_rama_protegida_solo_acepta_force_push_si_lo_permite() {
+        let logger = Arc::new(Logger::new(PathBuf::from("tmp/receive_pack_10.txt")).unwrap());
+        let dir = "tmp/receive_pack_test10/";
+        let (primero, segundo, packfile) = preparar_repos(dir, logger.clone());
+        let master = "refs/heads/master";
+        let proteger = |permite_force_push: bool| {
+            let mut protegidas = RamasProtegidas::cargar(Path::new(dir)).unwrap();
+            protegidas.proteger(ProteccionRama {
+                rama: "master".to_string(),
+                requiere_pull_request: false,
+                aprobaciones_requeridas: 0,
+                permite_force_push,
+            });
+            protegidas.guardar().unwrap();
+        };
+        proteger(false);
+        correr_receive_pack(
+            dir,
+            &[(&"0".repeat(40), &segundo, master)],
+            "",
+            Some(packfile.clone()),
+            logger.clone(),
+        );
+
+        let sin_force_push = correr_receive_pack(
+            dir,
+            &[(&segundo, &primero, master)],
+            "report-status",
+            Some(packfile.clone()),
+            logger.clone(),
+        );
+        proteger(true);
+        let con_force_push = correr_receive_pack(
+            dir,
+            &[(&segundo, &primero, master)],
+            "report-status",
+            Some(packfile),
+            logger,
+        );
+
+        assert_eq!(
+            sin_force_push[1],
+            "ng refs/heads/master la rama master esta protegida y no acepta force push\n"
+        );
+        assert_eq!(con_force_push[1], "ok refs/heads/master\n");
+        assert_eq!(
+            io::leer_a_string(dir.to_string() + master).unwrap(),
+            primero
+        );
+    }
+
+    #[test]
+    #[serial]
+    fn test11_los_objetos_de_un_push_rechazado_no_quedan_en_el_repositorio() {
+        let logger = Arc::new(Logger::new(PathBuf::from("tmp/receive_pack_11.txt")).unwrap());
+        let dir = "tmp/receive_pack_test11/";
+        let (primero, segundo, packfile) = preparar_repos(dir, logger.clone());
+        let dir_objetos = dir.to_string() + "objects/";
+        let hay_cuarentena = || {
+            io::leer_directorio(&dir).unwrap().any(|entrada| {
+                entrada
+                    .unwrap()
+                    .file_name()
+                    .to_string_lossy()
+                    .starts_with("cuarentena-")
+            })
+        };
+
+        let rechazado = correr_receive_pack(
+            dir,
+            &[(&"1".repeat(40), &segundo, "refs/heads/master")],
+            "report-status",
+            Some(packfile.clone()),
+            logger.clone(),
+        );
+        let existe_despues_del_rechazo = compresion::existe_objeto(&primero, &dir_objetos);
+        let cuarentena_despues_del_rechazo = hay_cuarentena();
+        let aceptado = correr_receive_pack(
+            dir,
+            &[(&"0".repeat(40), &segundo, "refs/heads/master")],
+            "report-status",
+            Some(packfile),
+            logger,
+        );
+
+        assert_eq!(
+            rechazado[1],
+            "ng refs/heads/master la referencia cambio en el servidor, haga fetch primero\n"
+        );
+        assert!(!existe_despues_del_rechazo);
+        assert!(!cuarentena_despues_del_rechazo);
+        assert_eq!(aceptado[1], "ok refs/heads/master\n");
+        assert!(compresion::existe_objeto(&primero, &dir_objetos));
+        assert!(compresion::existe_objeto(&segundo, &dir_objetos));
+        assert!(!hay_cuarentena());
     }
 }
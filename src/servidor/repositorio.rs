@@ -0,0 +1,272 @@
+use std::{collections::HashMap, fs, path::Path};
+
+use serde::Serialize;
+
+use crate::{
+    servidor::{config_servidor, gir_server::server_utils, pull_request::PullRequest},
+    tipos_de_dato::http::error::ErrorHttp,
+    utils::io,
+};
+
+const RAMA_POR_DEFECTO: &str = "master";
+const PREFIJO_HEAD: &str = "ref: refs/heads/";
+
+/// Repositorio del directorio de repositorios del servidor, como se muestra por la api.
+#[derive(Serialize, Debug, PartialEq)]
+pub struct Repositorio {
+    pub nombre: String,
+    /// Rama a la que apunta el HEAD del repositorio.
+    pub rama_por_defecto: String,
+    pub ramas: Vec<String>,
+}
+
+impl Repositorio {
+    ///Crea un repositorio vacio apartir del body de la request.
+    ///
+    /// ## Argumentos
+    /// - body: el cuerpo de la request recibida. Tiene que tener el campo `name` y puede tener
+    ///   `default_branch` (por defecto `master`).
+    ///
+    /// ## Errores
+    /// - Si falta el nombre o no es valido
+    /// - Si la rama por defecto no es un nombre de rama valido
+    /// - Si ya existe un repositorio con ese nombre
+    pub fn crear(body: &HashMap<String, String>) -> Result<Repositorio, ErrorHttp> {
+        let nombre = body.get("name").ok_or_else(|| {
+            ErrorHttp::ValidationFailed(
+                "Falta el parametro 'name' en el body de la request".to_string(),
+            )
+        })?;
+        Self::validar_nombre_nuevo(nombre)?;
+        let rama_por_defecto = body
+            .get("default_branch")
+            .map(String::as_str)
+            .unwrap_or(RAMA_POR_DEFECTO);
+        Self::validar_nombre_rama(rama_por_defecto)?;
+
+        let dir_gir = config_servidor::dir_repositorio(nombre).join(".gir");
+        server_utils::crear_repositorio_si_no_existe(&dir_gir)
+            .map_err(ErrorHttp::InternalServerError)?;
+        Self::escribir_head(nombre, rama_por_defecto)?;
+        Self::cargar(nombre)
+    }
+
+    ///Carga el repositorio `nombre` del directorio de repositorios.
+    ///
+    /// ## Errores
+    /// - Si el nombre no es valido
+    /// - Si no existe el repositorio
+    pub fn cargar(nombre: &str) -> Result<Repositorio, ErrorHttp> {
+        Self::validar_nombre(nombre)?;
+        let dir_gir = config_servidor::dir_repositorio(nombre).join(".gir");
+        if !dir_gir.exists() {
+            return Err(ErrorHttp::NotFound(format!(
+                "No existe en el server el repositorio {nombre}"
+            )));
+        }
+
+        let rama_por_defecto = io::leer_a_string(dir_gir.join("HEAD"))
+            .ok()
+            .and_then(|head| {
+                head.trim()
+                    .strip_prefix(PREFIJO_HEAD)
+                    .map(|rama| rama.to_string())
+            })
+            .unwrap_or(RAMA_POR_DEFECTO.to_string());
+        let mut ramas = Vec::new();
+        Self::obtener_ramas(&dir_gir.join("refs/heads"), "", &mut ramas)?;
+        ramas.sort();
+
+        Ok(Repositorio {
+            nombre: nombre.to_string(),
+            rama_por_defecto,
+            ramas,
+        })
+    }
+
+    ///Devuelve todos los repositorios del directorio de repositorios, ordenados por nombre.
+    pub fn listar() -> Result<Vec<Repositorio>, ErrorHttp> {
+        let dir_repositorios = config_servidor::dir_repositorios();
+        if !dir_repositorios.exists() {
+            return Ok(Vec::new());
+        }
+
+        let mut repositorios = Vec::new();
+        for entrada in
+            io::leer_directorio(&dir_repositorios).map_err(ErrorHttp::InternalServerError)?
+        {
+            let entrada = entrada.map_err(|e| {
+                ErrorHttp::InternalServerError(format!(
+                    "Fallo al leer el directorio {:?}: {e}",
+                    dir_repositorios
+                ))
+            })?;
+            if !entrada.path().join(".gir").exists() {
+                continue;
+            }
+            repositorios.push(Self::cargar(&entrada.file_name().to_string_lossy())?);
+        }
+        repositorios.sort_by(|a, b| a.nombre.cmp(&b.nombre));
+        Ok(repositorios)
+    }
+
+    ///Actualiza el repositorio con los parametros del body recibido. Devuelve si se actualizo
+    /// algo.
+    ///
+    /// ## Argumentos
+    /// - body: el cuerpo del request recibido. Los campos a actualizar pueden ser `name`, que
+    ///   renombra el repositorio (y sus pull requests), y `default_branch`, que tiene que ser
+    ///   una rama existente.
+    ///
+    /// ## Errores
+    /// - Si la rama por defecto no es valida o no existe
+    /// - Si el nuevo nombre no es valido o ya existe otro repositorio con ese nombre
+    pub fn actualizar(&mut self, body: &HashMap<String, String>) -> Result<bool, ErrorHttp> {
+        let mut se_actualizo = false;
+
+        if let Some(rama) = body.get("default_branch") {
+            Self::validar_nombre_rama(rama)?;
+            if !self.ramas.contains(rama) {
+                return Err(ErrorHttp::ValidationFailed(format!(
+                    "No existe la rama {} en el repositorio {}",
+                    rama, self.nombre
+                )));
+            }
+            if self.rama_por_defecto != *rama {
+                Self::escribir_head(&self.nombre, rama)?;
+                self.rama_por_defecto = rama.to_string();
+                se_actualizo = true;
+            }
+        }
+
+        if let Some(nombre) = body.get("name") {
+            if self.nombre != *nombre {
+                self.renombrar(nombre)?;
+                se_actualizo = true;
+            }
+        }
+
+        Ok(se_actualizo)
+    }
+
+    ///Borra el repositorio con todos sus objetos y pull requests.
+    pub fn eliminar(&self) -> Result<(), ErrorHttp> {
+        io::rm_directorio(config_servidor::dir_repositorio(&self.nombre))
+            .map_err(ErrorHttp::InternalServerError)
+    }
+
+    fn renombrar(&mut self, nombre: &str) -> Result<(), ErrorHttp> {
+        Self::validar_nombre_nuevo(nombre)?;
+        fs::rename(
+            config_servidor::dir_repositorio(&self.nombre),
+            config_servidor::dir_repositorio(nombre),
+        )
+        .map_err(|e| {
+            ErrorHttp::InternalServerError(format!(
+                "No se pudo renombrar el repositorio {} a {}: {}",
+                self.nombre, nombre, e
+            ))
+        })?;
+        self.nombre = nombre.to_string();
+
+        // los pull requests guardan el nombre de su repositorio
+        let dir_pulls = config_servidor::dir_repositorio(nombre).join("pulls");
+        if !dir_pulls.exists() {
+            return Ok(());
+        }
+        for entrada in io::leer_directorio(&dir_pulls).map_err(ErrorHttp::InternalServerError)? {
+            let dir_pull_request = entrada
+                .map_err(|e| {
+                    ErrorHttp::InternalServerError(format!(
+                        "Fallo al leer el directorio {:?}: {e}",
+                        dir_pulls
+                    ))
+                })?
+                .path();
+            let mut pull_request = PullRequest::cargar_pr(&dir_pull_request)?;
+            pull_request.repositorio = nombre.to_string();
+            pull_request.guardar_pr(&dir_pull_request)?;
+        }
+        Ok(())
+    }
+
+    ///Verifica que el nombre sea un solo componente de la ruta de los repositorios, antes de
+    /// usarlo para acceder a los archivos del repositorio.
+    pub fn validar_nombre(nombre: &str) -> Result<(), ErrorHttp> {
+        config_servidor::validar_nombre_repositorio(nombre).map_err(ErrorHttp::ValidationFailed)
+    }
+
+    // Ademas de ser un nombre valido, el de un repositorio nuevo tiene que poder usarse en las
+    // urls y no puede ser el de un repositorio que ya existe.
+    fn validar_nombre_nuevo(nombre: &str) -> Result<(), ErrorHttp> {
+        Self::validar_nombre(nombre)?;
+        let es_valido = !nombre.starts_with('.')
+            && nombre
+                .chars()
+                .all(|c| c.is_ascii_alphanumeric() || matches!(c, '-' | '_' | '.'));
+        if !es_valido {
+            return Err(ErrorHttp::ValidationFailed(format!(
+                "El nombre de repositorio {} no es valido",
+                nombre
+            )));
+        }
+        if config_servidor::dir_repositorio(nombre).exists() {
+            return Err(ErrorHttp::Conflict(format!(
+                "Ya existe en el server el repositorio {nombre}"
+            )));
+        }
+        Ok(())
+    }
+
+    // Verifica que la rama sea un nombre de referencia valido antes de escribirla en el HEAD:
+    // cada parte entre `/` sigue las mismas reglas que el nombre de un repositorio nuevo, asi
+    // que no hay `..`, `/` al principio ni caracteres de control, y como en git ninguna parte
+    // termina en `.lock`.
+    pub(crate) fn validar_nombre_rama(rama: &str) -> Result<(), ErrorHttp> {
+        let es_valida = rama.split('/').all(|parte| {
+            !parte.is_empty()
+                && !parte.starts_with('.')
+                && !parte.contains("..")
+                && !parte.ends_with(".lock")
+                && parte
+                    .chars()
+                    .all(|c| c.is_ascii_alphanumeric() || matches!(c, '-' | '_' | '.'))
+        });
+        if !es_valida {
+            return Err(ErrorHttp::ValidationFailed(format!(
+                "El nombre de rama {} no es valido",
+                rama
+            )));
+        }
+        Ok(())
+    }
+
+    fn escribir_head(nombre: &str, rama: &str) -> Result<(), ErrorHttp> {
+        let head = config_servidor::dir_repositorio(nombre).join(".gir/HEAD");
+        io::escribir_bytes(head, format!("{}{}", PREFIJO_HEAD, rama))
+            .map_err(ErrorHttp::InternalServerError)
+    }
+
+    // Agrega a `ramas` las ramas de `dir`, incluidas las que estan en subdirectorios (por
+    // ejemplo `feature/x`).
+    fn obtener_ramas(dir: &Path, prefijo: &str, ramas: &mut Vec<String>) -> Result<(), ErrorHttp> {
+        if !dir.exists() {
+            return Ok(());
+        }
+        for entrada in io::leer_directorio(dir).map_err(ErrorHttp::InternalServerError)? {
+            let entrada = entrada.map_err(|e| {
+                ErrorHttp::InternalServerError(format!(
+                    "Fallo al leer el directorio {:?}: {e}",
+                    dir
+                ))
+            })?;
+            let nombre = format!("{}{}", prefijo, entrada.file_name().to_string_lossy());
+            if entrada.path().is_dir() {
+                Self::obtener_ramas(&entrada.path(), &format!("{}/", nombre), ramas)?;
+            } else {
+                ramas.push(nombre);
+            }
+        }
+        Ok(())
+    }
+}
@@ -7,6 +7,7 @@ use crate::{
             request::Request, response::Response,
         },
         logger::Logger,
+        permiso::Permiso,
     },
 };
 
@@ -22,6 +23,7 @@ pub fn agregar_a_router(rutas: &mut Vec<Endpoint>) {
         MetodoHttp::Patch,
         "/repos/{repo}/pulls/{pull_number}".to_string(),
         actualizar_pull_request,
+        Permiso::Escritura,
     );
     rutas.push(endpoint)
 }
@@ -0,0 +1,38 @@
+use std::{collections::HashMap, sync::Arc};
+
+use crate::tipos_de_dato::{
+    http::{
+        endpoint::Endpoint, error::ErrorHttp, estado::EstadoHttp, metodos::MetodoHttp,
+        request::Request, response::Response,
+    },
+    logger::Logger,
+    permiso::Permiso,
+};
+
+use super::{
+    crear_repositorio::responder_repositorio_en_formato_json,
+    obtener_repositorio::obtener_repositorio_de_params,
+};
+
+pub fn agregar_a_router(rutas: &mut Vec<Endpoint>) {
+    let endpoint = Endpoint::new(
+        MetodoHttp::Patch,
+        "/repos/{repo}".to_string(),
+        actualizar_repositorio,
+        Permiso::Admin,
+    );
+    rutas.push(endpoint)
+}
+
+fn actualizar_repositorio(
+    request: Request,
+    params: HashMap<String, String>,
+    logger: Arc<Logger>,
+) -> Result<Response, ErrorHttp> {
+    let mut repositorio = obtener_repositorio_de_params(&params)?;
+
+    if let Some(body) = request.body {
+        repositorio.actualizar(&body)?;
+    }
+    responder_repositorio_en_formato_json(&repositorio, logger, EstadoHttp::Ok)
+}
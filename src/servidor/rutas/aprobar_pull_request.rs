@@ -0,0 +1,69 @@
+use std::{collections::HashMap, sync::Arc};
+
+use crate::tipos_de_dato::{
+    http::{
+        endpoint::Endpoint, error::ErrorHttp, estado::EstadoHttp, metodos::MetodoHttp,
+        request::Request, response::Response,
+    },
+    logger::Logger,
+    permiso::Permiso,
+};
+
+use super::{
+    crear_pull_request::guadar_pull_request_acorde_al_numero,
+    obtener_pull_request::obtener_pull_request_de_params,
+};
+
+const APROBAR: &str = "APPROVE";
+
+pub fn agregar_a_router(rutas: &mut Vec<Endpoint>) {
+    let endpoint = Endpoint::new(
+        MetodoHttp::Post,
+        "/repos/{repo}/pulls/{pull_number}/reviews".to_string(),
+        aprobar_pull_request,
+        Permiso::Escritura,
+    );
+    rutas.push(endpoint)
+}
+
+/// Agrega una revision al pull request. Por ahora la unica revision que se acepta es la
+/// aprobacion (`"event": "APPROVE"`), que es la que cuenta para mergear en una rama protegida.
+/// La revision es del usuario autenticado: si el servidor no tiene cuentas nadie puede aprobar,
+/// porque no hay forma de saber quien hace la revision.
+fn aprobar_pull_request(
+    request: Request,
+    params: HashMap<String, String>,
+    logger: Arc<Logger>,
+) -> Result<Response, ErrorHttp> {
+    let usuario = request.usuario.ok_or_else(|| {
+        ErrorHttp::Unauthorized("Hay que autenticarse para aprobar un pull request".to_string())
+    })?;
+    let mut pull_request = obtener_pull_request_de_params(&params)?;
+    let repo = params.get("repo").ok_or_else(|| {
+        ErrorHttp::InternalServerError("No se ha encontrado el nombre del repositorio".to_string())
+    })?;
+    let body = request.body.unwrap_or_default();
+
+    match body.get("event") {
+        Some(evento) if evento == APROBAR => {}
+        _ => {
+            return Err(ErrorHttp::ValidationFailed(format!(
+                "El unico event de revision aceptado es {}",
+                APROBAR
+            )))
+        }
+    }
+
+    pull_request.aprobar(&usuario)?;
+    guadar_pull_request_acorde_al_numero(&pull_request, repo)?;
+
+    let body_respuesta = serde_json::json!({
+        "usuario": usuario,
+        "estado": "APROBADO",
+        "numero": pull_request.numero,
+        "aprobaciones": pull_request.aprobaciones,
+    })
+    .to_string();
+    let response = Response::new(logger, EstadoHttp::Created, Some(&body_respuesta));
+    Ok(response)
+}
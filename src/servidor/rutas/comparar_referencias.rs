@@ -0,0 +1,56 @@
+use std::{collections::HashMap, sync::Arc};
+
+use crate::{
+    servidor::{comparacion::Comparacion, config_servidor},
+    tipos_de_dato::{
+        http::{
+            endpoint::Endpoint, error::ErrorHttp, estado::EstadoHttp, metodos::MetodoHttp,
+            request::Request, response::Response,
+        },
+        logger::Logger,
+        permiso::Permiso,
+    },
+};
+
+use super::obtener_repositorio::obtener_repositorio_de_params;
+
+const SEPARADOR_REFERENCIAS: &str = "...";
+
+pub fn agregar_a_router(rutas: &mut Vec<Endpoint>) {
+    let endpoint = Endpoint::new(
+        MetodoHttp::Get,
+        "/repos/{repo}/compare/{basehead*}".to_string(),
+        comparar_referencias,
+        Permiso::Lectura,
+    );
+    rutas.push(endpoint)
+}
+
+///Compara dos ramas, tags o commits del repositorio, pedidos como `{base}...{head}`.
+fn comparar_referencias(
+    _request: Request,
+    params: HashMap<String, String>,
+    logger: Arc<Logger>,
+) -> Result<Response, ErrorHttp> {
+    let repositorio = obtener_repositorio_de_params(&params)?;
+    let basehead = params.get("basehead").ok_or_else(|| {
+        ErrorHttp::InternalServerError(
+            "No se han encontrado las referencias a comparar".to_string(),
+        )
+    })?;
+    let (base, head) = basehead.split_once(SEPARADOR_REFERENCIAS).ok_or_else(|| {
+        ErrorHttp::ValidationFailed(format!(
+            "Las referencias a comparar tienen que tener el formato base...head, se recibio {}",
+            basehead
+        ))
+    })?;
+
+    let dir_repositorio = config_servidor::dir_repositorio(&repositorio.nombre);
+    let comparacion = Comparacion::entre(&dir_repositorio, base, head, logger.clone())?;
+
+    let body_respuesta = serde_json::to_string(&comparacion).map_err(|e| {
+        ErrorHttp::InternalServerError(format!("No se ha podido serializar la comparacion: {}", e))
+    })?;
+    let respuesta = Response::new(logger, EstadoHttp::Ok, Some(&body_respuesta));
+    Ok(respuesta)
+}
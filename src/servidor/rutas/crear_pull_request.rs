@@ -1,13 +1,14 @@
-use std::{collections::HashMap, path::PathBuf, sync::Arc};
+use std::{collections::HashMap, sync::Arc};
 
 use crate::{
-    servidor::{pull_request::PullRequest},
+    servidor::{config_servidor, pull_request::PullRequest},
     tipos_de_dato::{
         http::{
             endpoint::Endpoint, error::ErrorHttp, estado::EstadoHttp, metodos::MetodoHttp,
             request::Request, response::Response,
         },
         logger::Logger,
+        permiso::Permiso,
     },
 };
 
@@ -16,6 +17,7 @@ pub fn agregar_a_router(rutas: &mut Vec<Endpoint>) {
         MetodoHttp::Post,
         "/repos/{repo}/pulls".to_string(),
         crear_pull_request,
+        Permiso::Escritura,
     );
     rutas.push(endpoint)
 }
@@ -38,7 +40,8 @@ fn crear_pull_request(
         }
     };
 
-    let pull_request = PullRequest::crear_pr(repo, body)?;
+    // con cuentas el autor es el usuario autenticado, no el que se indica en `head`
+    let pull_request = PullRequest::crear_pr(repo, body, request.usuario)?;
     guadar_pull_request_acorde_al_numero(&pull_request, repo)?;
 
     responder_pull_request_en_formato_json(pull_request, logger, EstadoHttp::Created)
@@ -48,10 +51,11 @@ pub fn guadar_pull_request_acorde_al_numero(
     pull_request: &PullRequest,
     repo: &str,
 ) -> Result<(), ErrorHttp> {
-    pull_request.guardar_pr(&PathBuf::from(format!(
-        "srv/{repo}/pulls/{numero}",
-        numero = pull_request.numero
-    )))?;
+    pull_request.guardar_pr(
+        &config_servidor::dir_repositorio(repo)
+            .join("pulls")
+            .join(pull_request.numero.to_string()),
+    )?;
     Ok(())
 }
 
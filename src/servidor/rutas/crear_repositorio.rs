@@ -0,0 +1,53 @@
+use std::{collections::HashMap, sync::Arc};
+
+use crate::{
+    servidor::repositorio::Repositorio,
+    tipos_de_dato::{
+        http::{
+            endpoint::Endpoint, error::ErrorHttp, estado::EstadoHttp, metodos::MetodoHttp,
+            request::Request, response::Response,
+        },
+        logger::Logger,
+        permiso::Permiso,
+    },
+};
+
+pub fn agregar_a_router(rutas: &mut Vec<Endpoint>) {
+    let endpoint = Endpoint::new(
+        MetodoHttp::Post,
+        "/repos".to_string(),
+        crear_repositorio,
+        Permiso::Admin,
+    );
+    rutas.push(endpoint)
+}
+
+fn crear_repositorio(
+    request: Request,
+    _params: HashMap<String, String>,
+    logger: Arc<Logger>,
+) -> Result<Response, ErrorHttp> {
+    let body = match request.body {
+        Some(body) => body,
+        None => {
+            return Err(ErrorHttp::BadRequest(
+                "No se ha encontrado el cuerpo de la solicitud".to_string(),
+            ))
+        }
+    };
+
+    let repositorio = Repositorio::crear(&body)?;
+    responder_repositorio_en_formato_json(&repositorio, logger, EstadoHttp::Created)
+}
+
+pub fn responder_repositorio_en_formato_json<T: serde::Serialize>(
+    repositorio: &T,
+    logger: Arc<Logger>,
+    estado: EstadoHttp,
+) -> Result<Response, ErrorHttp> {
+    let body_respuesta = serde_json::to_string(repositorio).map_err(|e| {
+        ErrorHttp::InternalServerError(format!("No se ha podido serializar el repositorio: {}", e))
+    })?;
+    let respuesta = Response::new(logger, estado, Some(&body_respuesta));
+    Ok(respuesta)
+}
@@ -0,0 +1,32 @@
+use std::{collections::HashMap, sync::Arc};
+
+use crate::tipos_de_dato::{
+    http::{
+        endpoint::Endpoint, error::ErrorHttp, estado::EstadoHttp, metodos::MetodoHttp,
+        request::Request, response::Response,
+    },
+    logger::Logger,
+    permiso::Permiso,
+};
+
+use super::obtener_repositorio::obtener_repositorio_de_params;
+
+pub fn agregar_a_router(rutas: &mut Vec<Endpoint>) {
+    let endpoint = Endpoint::new(
+        MetodoHttp::Delete,
+        "/repos/{repo}".to_string(),
+        eliminar_repositorio,
+        Permiso::Admin,
+    );
+    rutas.push(endpoint)
+}
+
+fn eliminar_repositorio(
+    _request: Request,
+    params: HashMap<String, String>,
+    logger: Arc<Logger>,
+) -> Result<Response, ErrorHttp> {
+    let repositorio = obtener_repositorio_de_params(&params)?;
+    repositorio.eliminar()?;
+    Ok(Response::new(logger, EstadoHttp::NoContent, None))
+}
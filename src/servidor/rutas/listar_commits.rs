@@ -0,0 +1,92 @@
+use std::{collections::HashMap, sync::Arc};
+
+use crate::{
+    servidor::{config_servidor, explorador_repositorio},
+    tipos_de_dato::{
+        http::{
+            endpoint::Endpoint, error::ErrorHttp, estado::EstadoHttp, metodos::MetodoHttp,
+            request::Request, response::Response,
+        },
+        logger::Logger,
+        permiso::Permiso,
+    },
+};
+
+use super::obtener_repositorio::obtener_repositorio_de_params;
+
+const COMMITS_POR_PAGINA: usize = 30;
+const MAXIMO_COMMITS_POR_PAGINA: usize = 100;
+
+pub fn agregar_a_router(rutas: &mut Vec<Endpoint>) {
+    let endpoint = Endpoint::new(
+        MetodoHttp::Get,
+        "/repos/{repo}/commits".to_string(),
+        listar_commits,
+        Permiso::Lectura,
+    );
+    rutas.push(endpoint)
+}
+
+///Lista los commits del repositorio, del mas nuevo al mas viejo.
+///
+/// ## Query
+/// - sha: rama, tag o commit desde el que se listan (por defecto la rama por defecto)
+/// - path: si esta, solo se listan los commits que modificaron ese archivo o directorio
+/// - page y per_page: pagina (empezando en 1) y cantidad de commits por pagina (hasta 100)
+fn listar_commits(
+    request: Request,
+    params: HashMap<String, String>,
+    logger: Arc<Logger>,
+) -> Result<Response, ErrorHttp> {
+    let repositorio = obtener_repositorio_de_params(&params)?;
+    let pagina = obtener_numero_de_query(&request, "page", 1)?;
+    let por_pagina = obtener_numero_de_query(&request, "per_page", COMMITS_POR_PAGINA)?
+        .min(MAXIMO_COMMITS_POR_PAGINA);
+    let desde = request
+        .query
+        .get("sha")
+        .unwrap_or(&repositorio.rama_por_defecto);
+    let path = request.query.get("path").map(String::as_str);
+
+    // solo hacen falta los commits hasta el final de la pagina pedida
+    let hasta = pagina.checked_mul(por_pagina).ok_or_else(|| {
+        ErrorHttp::ValidationFailed(format!("La pagina {} esta fuera de rango", pagina))
+    })?;
+
+    let dir_repositorio = config_servidor::dir_repositorio(&repositorio.nombre);
+    let commits = explorador_repositorio::obtener_commits(
+        &dir_repositorio,
+        desde,
+        path,
+        hasta,
+        logger.clone(),
+    )?;
+    let pagina_commits = commits
+        .into_iter()
+        .skip(hasta - por_pagina)
+        .collect::<Vec<_>>();
+
+    let body_respuesta = serde_json::to_string(&pagina_commits).map_err(|e| {
+        ErrorHttp::InternalServerError(format!("No se han podido serializar los commits: {}", e))
+    })?;
+    let respuesta = Response::new(logger, EstadoHttp::Ok, Some(&body_respuesta));
+    Ok(respuesta)
+}
+
+fn obtener_numero_de_query(
+    request: &Request,
+    parametro: &str,
+    por_defecto: usize,
+) -> Result<usize, ErrorHttp> {
+    let valor = match request.query.get(parametro) {
+        Some(valor) => valor,
+        None => return Ok(por_defecto),
+    };
+    match valor.parse::<usize>() {
+        Ok(numero) if numero > 0 => Ok(numero),
+        _ => Err(ErrorHttp::ValidationFailed(format!(
+            "El parametro {} tiene que ser un numero mayor a 0, se recibio {}",
+            parametro, valor
+        ))),
+    }
+}
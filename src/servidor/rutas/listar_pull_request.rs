@@ -1,13 +1,14 @@
 use std::{collections::HashMap, path::PathBuf, sync::Arc};
 
 use crate::{
-    servidor::{pull_request::PullRequest},
+    servidor::{config_servidor, pull_request::PullRequest},
     tipos_de_dato::{
         http::{
             endpoint::Endpoint, error::ErrorHttp, estado::EstadoHttp, metodos::MetodoHttp,
             request::Request, response::Response,
         },
         logger::Logger,
+        permiso::Permiso,
     },
     utils::{self},
 };
@@ -17,6 +18,7 @@ pub fn agregar_a_router(rutas: &mut Vec<Endpoint>) {
         MetodoHttp::Get,
         "/repos/{repo}/pulls".to_string(),
         listar_pull_request,
+        Permiso::Lectura,
     );
     rutas.push(endpoint)
 }
@@ -103,7 +105,7 @@ fn obtener_pull_request_del_repositorio(
 /// se devuelve dir repositorio y caso contrario  error.
 ///
 /// ## Resultado
-/// - dir del repo recibo(EJ: `./srv/{repositorio}/pulls`)
+/// - dir de los pull requests del repo recibido(EJ: `srv/{repositorio}/pulls`)
 fn obtener_y_verificar_repositorio_de_los_parametros(
     params: &HashMap<String, String>,
 ) -> Result<PathBuf, ErrorHttp> {
@@ -111,7 +113,7 @@ fn obtener_y_verificar_repositorio_de_los_parametros(
         ErrorHttp::InternalServerError("No se ha encontrado el nombre del repositorio".to_string())
     })?;
 
-    let dir_repositorio = PathBuf::from(format!("./srv/{repo}"));
+    let dir_repositorio = config_servidor::dir_repositorio(repo);
 
     if dir_repositorio.exists() {
         Ok(dir_repositorio.join("pulls"))
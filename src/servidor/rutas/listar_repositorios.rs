@@ -0,0 +1,34 @@
+use std::{collections::HashMap, sync::Arc};
+
+use crate::{
+    servidor::repositorio::Repositorio,
+    tipos_de_dato::{
+        http::{
+            endpoint::Endpoint, error::ErrorHttp, estado::EstadoHttp, metodos::MetodoHttp,
+            request::Request, response::Response,
+        },
+        logger::Logger,
+        permiso::Permiso,
+    },
+};
+
+use super::crear_repositorio::responder_repositorio_en_formato_json;
+
+pub fn agregar_a_router(rutas: &mut Vec<Endpoint>) {
+    let endpoint = Endpoint::new(
+        MetodoHttp::Get,
+        "/repos".to_string(),
+        listar_repositorios,
+        Permiso::Lectura,
+    );
+    rutas.push(endpoint)
+}
+
+fn listar_repositorios(
+    _request: Request,
+    _params: HashMap<String, String>,
+    logger: Arc<Logger>,
+) -> Result<Response, ErrorHttp> {
+    let repositorios = Repositorio::listar()?;
+    responder_repositorio_en_formato_json(&repositorios, logger, EstadoHttp::Ok)
+}
@@ -0,0 +1,41 @@
+use std::{collections::HashMap, sync::Arc};
+
+use crate::{
+    servidor::{config_servidor, explorador_repositorio},
+    tipos_de_dato::{
+        http::{
+            endpoint::Endpoint, error::ErrorHttp, estado::EstadoHttp, metodos::MetodoHttp,
+            request::Request, response::Response,
+        },
+        logger::Logger,
+        permiso::Permiso,
+    },
+};
+
+use super::obtener_repositorio::obtener_repositorio_de_params;
+
+pub fn agregar_a_router(rutas: &mut Vec<Endpoint>) {
+    let endpoint = Endpoint::new(
+        MetodoHttp::Get,
+        "/repos/{repo}/tags".to_string(),
+        listar_tags,
+        Permiso::Lectura,
+    );
+    rutas.push(endpoint)
+}
+
+fn listar_tags(
+    _request: Request,
+    params: HashMap<String, String>,
+    logger: Arc<Logger>,
+) -> Result<Response, ErrorHttp> {
+    let repositorio = obtener_repositorio_de_params(&params)?;
+    let dir_repositorio = config_servidor::dir_repositorio(&repositorio.nombre);
+    let tags = explorador_repositorio::obtener_referencias(&dir_repositorio, true)?;
+
+    let body_respuesta = serde_json::to_string(&tags).map_err(|e| {
+        ErrorHttp::InternalServerError(format!("No se han podido serializar los tags: {}", e))
+    })?;
+    let respuesta = Response::new(logger, EstadoHttp::Ok, Some(&body_respuesta));
+    Ok(respuesta)
+}
@@ -14,6 +14,7 @@ use crate::{
             request::Request, response::Response,
         },
         logger::Logger,
+        permiso::Permiso,
     },
     utils::{index, io, ramas},
 };
@@ -32,6 +33,7 @@ pub fn agregar_a_router(rutas: &mut Vec<Endpoint>) {
         MetodoHttp::Put,
         "/repos/{repo}/pulls/{pull_number}/merge".to_string(),
         mergear_pull_request,
+        Permiso::Escritura,
     );
     rutas.push(endpoint)
 }
@@ -49,7 +51,7 @@ fn armar_body_merge(hash_merge: String) -> String {
 }
 
 fn verificar_sha_head(sha: &str, pull_request: &PullRequest) -> Result<bool, ErrorHttp> {
-    pull_request.entrar_a_repositorio()?;
+    let directorio_previo = pull_request.entrar_a_repositorio()?;
     let hash_head_previo_merge = ramas::obtener_hash_commit_asociado_rama(&pull_request.rama_head)
         .map_err(|error| {
             ErrorHttp::InternalServerError(format!(
@@ -57,7 +59,7 @@ fn verificar_sha_head(sha: &str, pull_request: &PullRequest) -> Result<bool, Err
                 pull_request.rama_head, error
             ))
         })?;
-    pull_request.salir_del_repositorio()?;
+    pull_request.salir_del_repositorio(&directorio_previo)?;
 
     Ok(sha == hash_head_previo_merge)
 }
@@ -166,9 +168,11 @@ fn mergear_pull_request_utilizando_merge(
         branch_a_mergear: rama_head,
         abort: false,
         no_fast_forward: true,
+        // los hooks del cliente no se corren en el servidor
+        sin_hooks: true,
     };
 
-    pull_request.entrar_a_repositorio()?;
+    let directorio_previo = pull_request.entrar_a_repositorio()?;
 
     let resultado = match merge.ejecutar() {
         Ok(_) => pr_mergeado_con_exito(&rama_base, pull_request, logger),
@@ -177,7 +181,7 @@ fn mergear_pull_request_utilizando_merge(
         }
     };
 
-    pull_request.salir_del_repositorio()?;
+    pull_request.salir_del_repositorio(&directorio_previo)?;
 
     resultado
 }
@@ -205,9 +209,10 @@ fn mergear_pull_request_utilizando_rebase(
         rama: Some(rama_head),
         continue_: false,
         abort: false,
+        sin_hooks: true,
     };
 
-    pull_request.entrar_a_repositorio()?;
+    let directorio_previo = pull_request.entrar_a_repositorio()?;
 
     let resultado = match rebase.ejecutar() {
         Ok(_) => pr_mergeado_con_exito(&rama_base, pull_request, logger),
@@ -216,7 +221,7 @@ fn mergear_pull_request_utilizando_rebase(
         }
     };
 
-    pull_request.salir_del_repositorio()?;
+    pull_request.salir_del_repositorio(&directorio_previo)?;
 
     resultado
 }
@@ -232,6 +237,7 @@ fn mergear_pull_request(
         let response = Response::new(logger, EstadoHttp::ValidationFailed, None);
         return Ok(response);
     }
+    pull_request.verificar_proteccion_rama_base()?;
 
     let merge_method = obtener_params_body(request, &pull_request)?;
 
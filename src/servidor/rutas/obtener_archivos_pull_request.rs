@@ -0,0 +1,41 @@
+use std::{collections::HashMap, sync::Arc};
+
+use crate::tipos_de_dato::{
+    http::{
+        endpoint::Endpoint, error::ErrorHttp, estado::EstadoHttp, metodos::MetodoHttp,
+        request::Request, response::Response,
+    },
+    logger::Logger,
+    permiso::Permiso,
+};
+
+use super::obtener_pull_request::obtener_pull_request_de_params;
+
+pub fn agregar_a_router(rutas: &mut Vec<Endpoint>) {
+    let endpoint = Endpoint::new(
+        MetodoHttp::Get,
+        "/repos/{repo}/pulls/{pull_number}/files".to_string(),
+        obtener_archivos_pull_request,
+        Permiso::Lectura,
+    );
+    rutas.push(endpoint)
+}
+
+fn obtener_archivos_pull_request(
+    _request: Request,
+    params: HashMap<String, String>,
+    logger: Arc<Logger>,
+) -> Result<Response, ErrorHttp> {
+    let pull_request = obtener_pull_request_de_params(&params)?;
+    let archivos = pull_request.obtener_archivos_modificados(logger.clone())?;
+
+    let body_response = serde_json::to_string(&archivos).map_err(|e| {
+        ErrorHttp::InternalServerError(format!(
+            "No se han podido serializar los archivos del pull request: {}",
+            e
+        ))
+    })?;
+
+    let response = Response::new(logger, EstadoHttp::Ok, Some(&body_response));
+    Ok(response)
+}
@@ -0,0 +1,46 @@
+use std::{collections::HashMap, sync::Arc};
+
+use crate::{
+    servidor::{config_servidor, explorador_repositorio},
+    tipos_de_dato::{
+        http::{
+            endpoint::Endpoint, error::ErrorHttp, estado::EstadoHttp, metodos::MetodoHttp,
+            request::Request, response::Response,
+        },
+        logger::Logger,
+        permiso::Permiso,
+    },
+};
+
+use super::obtener_repositorio::obtener_repositorio_de_params;
+
+pub fn agregar_a_router(rutas: &mut Vec<Endpoint>) {
+    let endpoint = Endpoint::new(
+        MetodoHttp::Get,
+        "/repos/{repo}/commits/{sha}".to_string(),
+        obtener_commit,
+        Permiso::Lectura,
+    );
+    rutas.push(endpoint)
+}
+
+fn obtener_commit(
+    _request: Request,
+    params: HashMap<String, String>,
+    logger: Arc<Logger>,
+) -> Result<Response, ErrorHttp> {
+    let repositorio = obtener_repositorio_de_params(&params)?;
+    let referencia = params.get("sha").ok_or_else(|| {
+        ErrorHttp::InternalServerError("No se ha encontrado el sha del commit".to_string())
+    })?;
+
+    let dir_repositorio = config_servidor::dir_repositorio(&repositorio.nombre);
+    let commit =
+        explorador_repositorio::obtener_commit(&dir_repositorio, referencia, logger.clone())?;
+
+    let body_respuesta = serde_json::to_string(&commit).map_err(|e| {
+        ErrorHttp::InternalServerError(format!("No se ha podido serializar el commit: {}", e))
+    })?;
+    let respuesta = Response::new(logger, EstadoHttp::Ok, Some(&body_respuesta));
+    Ok(respuesta)
+}
@@ -7,6 +7,7 @@ use crate::{
             request::Request, response::Response,
         },
         logger::Logger,
+        permiso::Permiso,
     },
 };
 
@@ -17,6 +18,7 @@ pub fn agregar_a_router(rutas: &mut Vec<Endpoint>) {
         MetodoHttp::Get,
         "/repos/{repo}/pulls/{pull_number}/commits".to_string(),
         obtener_commits_pull_request,
+        Permiso::Lectura,
     );
     rutas.push(endpoint)
 }
@@ -0,0 +1,57 @@
+use std::{collections::HashMap, sync::Arc};
+
+use crate::{
+    servidor::{config_servidor, explorador_repositorio},
+    tipos_de_dato::{
+        http::{
+            endpoint::Endpoint, error::ErrorHttp, estado::EstadoHttp, metodos::MetodoHttp,
+            request::Request, response::Response,
+        },
+        logger::Logger,
+        permiso::Permiso,
+    },
+};
+
+use super::obtener_repositorio::obtener_repositorio_de_params;
+
+pub fn agregar_a_router(rutas: &mut Vec<Endpoint>) {
+    // la raiz del repositorio no tiene path
+    for patron in ["/repos/{repo}/contents", "/repos/{repo}/contents/{path*}"] {
+        let endpoint = Endpoint::new(
+            MetodoHttp::Get,
+            patron.to_string(),
+            obtener_contenido,
+            Permiso::Lectura,
+        );
+        rutas.push(endpoint)
+    }
+}
+
+///Devuelve el contenido de un archivo (en base64) o las entradas de un directorio del
+/// repositorio, en la rama, tag o commit `ref` de la query (por defecto la rama por defecto).
+fn obtener_contenido(
+    request: Request,
+    params: HashMap<String, String>,
+    logger: Arc<Logger>,
+) -> Result<Response, ErrorHttp> {
+    let repositorio = obtener_repositorio_de_params(&params)?;
+    let path = params.get("path").map(String::as_str).unwrap_or("");
+    let referencia = request
+        .query
+        .get("ref")
+        .unwrap_or(&repositorio.rama_por_defecto);
+
+    let dir_repositorio = config_servidor::dir_repositorio(&repositorio.nombre);
+    let contenido = explorador_repositorio::obtener_contenido(
+        &dir_repositorio,
+        referencia,
+        path,
+        logger.clone(),
+    )?;
+
+    let body_respuesta = serde_json::to_string(&contenido).map_err(|e| {
+        ErrorHttp::InternalServerError(format!("No se ha podido serializar el contenido: {}", e))
+    })?;
+    let respuesta = Response::new(logger, EstadoHttp::Ok, Some(&body_respuesta));
+    Ok(respuesta)
+}
@@ -1,15 +1,14 @@
 use std::{collections::HashMap, path::PathBuf, sync::Arc};
 
 use crate::{
-    servidor::{
-        pull_request::PullRequest,
-    },
+    servidor::{config_servidor, pull_request::PullRequest},
     tipos_de_dato::{
         http::{
             endpoint::Endpoint, error::ErrorHttp, estado::EstadoHttp, metodos::MetodoHttp,
             request::Request, response::Response,
         },
         logger::Logger,
+        permiso::Permiso,
     },
 };
 
@@ -20,6 +19,7 @@ pub fn agregar_a_router(rutas: &mut Vec<Endpoint>) {
         MetodoHttp::Get,
         "/repos/{repo}/pulls/{pull_number}".to_string(),
         obtener_pull_request,
+        Permiso::Lectura,
     );
     rutas.push(endpoint)
 }
@@ -41,10 +41,11 @@ fn obtener_pull_request(
 /// - params: los parametros obtenidos de la ruta del pedido. Debe contener `repo` y `pull_number`   
 ///
 /// ## Resultado
-/// - el pull request guardado en el directorio `./srv/{repo}/pulls/{pull_number}`
+/// - el pull request guardado en el directorio `{repo}/pulls/{pull_number}` del directorio de
+///   repositorios
 ///
 /// ## Errores
-/// - Si no existe la carpeta `./srv/{repo}/pulls/{pull_number}`
+/// - Si no existe la carpeta del pull request
 pub fn obtener_pull_request_de_params(
     params: &HashMap<String, String>,
 ) -> Result<PullRequest, ErrorHttp> {
@@ -62,7 +63,9 @@ pub fn obtener_dir_pull_request(params: &HashMap<String, String>) -> Result<Path
             "No se ha encontrado el pull number del repositorio".to_string(),
         )
     })?;
-    let dir_pull_request = PathBuf::from(format!("./srv/{repo}/pulls/{pull_number}"));
+    let dir_pull_request = config_servidor::dir_repositorio(repo)
+        .join("pulls")
+        .join(pull_number);
 
     if dir_pull_request.exists() {
         Ok(dir_pull_request)
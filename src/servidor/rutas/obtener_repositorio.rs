@@ -0,0 +1,48 @@
+use std::{collections::HashMap, sync::Arc};
+
+use crate::{
+    servidor::repositorio::Repositorio,
+    tipos_de_dato::{
+        http::{
+            endpoint::Endpoint, error::ErrorHttp, estado::EstadoHttp, metodos::MetodoHttp,
+            request::Request, response::Response,
+        },
+        logger::Logger,
+        permiso::Permiso,
+    },
+};
+
+use super::crear_repositorio::responder_repositorio_en_formato_json;
+
+pub fn agregar_a_router(rutas: &mut Vec<Endpoint>) {
+    let endpoint = Endpoint::new(
+        MetodoHttp::Get,
+        "/repos/{repo}".to_string(),
+        obtener_repositorio,
+        Permiso::Lectura,
+    );
+    rutas.push(endpoint)
+}
+
+fn obtener_repositorio(
+    _request: Request,
+    params: HashMap<String, String>,
+    logger: Arc<Logger>,
+) -> Result<Response, ErrorHttp> {
+    let repositorio = obtener_repositorio_de_params(&params)?;
+    responder_repositorio_en_formato_json(&repositorio, logger, EstadoHttp::Ok)
+}
+
+///Carga el repositorio del parametro `repo` de la ruta.
+///
+/// ## Errores
+/// - Si falta el parametro, el nombre no es valido o no existe el repositorio
+pub fn obtener_repositorio_de_params(
+    params: &HashMap<String, String>,
+) -> Result<Repositorio, ErrorHttp> {
+    let repo = params.get("repo").ok_or_else(|| {
+        ErrorHttp::InternalServerError("No se ha encontrado el nombre del repositorio".to_string())
+    })?;
+    Repositorio::validar_nombre(repo)?;
+    Repositorio::cargar(repo)
+}
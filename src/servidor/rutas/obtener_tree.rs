@@ -0,0 +1,57 @@
+use std::{collections::HashMap, sync::Arc};
+
+use crate::{
+    servidor::{config_servidor, explorador_repositorio},
+    tipos_de_dato::{
+        http::{
+            endpoint::Endpoint, error::ErrorHttp, estado::EstadoHttp, metodos::MetodoHttp,
+            request::Request, response::Response,
+        },
+        logger::Logger,
+        permiso::Permiso,
+    },
+};
+
+use super::obtener_repositorio::obtener_repositorio_de_params;
+
+pub fn agregar_a_router(rutas: &mut Vec<Endpoint>) {
+    let endpoint = Endpoint::new(
+        MetodoHttp::Get,
+        "/repos/{repo}/git/trees/{sha}".to_string(),
+        obtener_tree,
+        Permiso::Lectura,
+    );
+    rutas.push(endpoint)
+}
+
+///Devuelve las entradas de un tree. El sha puede ser el de un tree o una rama, tag o commit,
+/// en cuyo caso se usa el tree del commit. Con `recursive` en la query (distinto de `0` y
+/// `false`) tambien se devuelven las entradas de los subdirectorios.
+fn obtener_tree(
+    request: Request,
+    params: HashMap<String, String>,
+    logger: Arc<Logger>,
+) -> Result<Response, ErrorHttp> {
+    let repositorio = obtener_repositorio_de_params(&params)?;
+    let referencia = params.get("sha").ok_or_else(|| {
+        ErrorHttp::InternalServerError("No se ha encontrado el sha del tree".to_string())
+    })?;
+    let recursivo = request
+        .query
+        .get("recursive")
+        .is_some_and(|valor| valor != "0" && valor != "false");
+
+    let dir_repositorio = config_servidor::dir_repositorio(&repositorio.nombre);
+    let entradas = explorador_repositorio::obtener_tree(
+        &dir_repositorio,
+        referencia,
+        recursivo,
+        logger.clone(),
+    )?;
+
+    let body_respuesta = serde_json::to_string(&entradas).map_err(|e| {
+        ErrorHttp::InternalServerError(format!("No se ha podido serializar el tree: {}", e))
+    })?;
+    let respuesta = Response::new(logger, EstadoHttp::Ok, Some(&body_respuesta));
+    Ok(respuesta)
+}
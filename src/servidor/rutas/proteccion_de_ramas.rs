@@ -0,0 +1,159 @@
+use std::{collections::HashMap, sync::Arc};
+
+use crate::{
+    servidor::{
+        config_servidor,
+        ramas_protegidas::{ProteccionRama, RamasProtegidas},
+        repositorio::Repositorio,
+    },
+    tipos_de_dato::{
+        http::{
+            endpoint::Endpoint, error::ErrorHttp, estado::EstadoHttp, metodos::MetodoHttp,
+            request::Request, response::Response,
+        },
+        logger::Logger,
+        permiso::Permiso,
+    },
+};
+
+const RUTA_PROTECCION: &str = "/repos/{repo}/branches/{branch*}/protection";
+
+pub fn agregar_a_router(rutas: &mut Vec<Endpoint>) {
+    rutas.push(Endpoint::new(
+        MetodoHttp::Get,
+        RUTA_PROTECCION.to_string(),
+        obtener_proteccion,
+        Permiso::Lectura,
+    ));
+    rutas.push(Endpoint::new(
+        MetodoHttp::Put,
+        RUTA_PROTECCION.to_string(),
+        proteger_rama,
+        Permiso::Admin,
+    ));
+    rutas.push(Endpoint::new(
+        MetodoHttp::Delete,
+        RUTA_PROTECCION.to_string(),
+        desproteger_rama,
+        Permiso::Admin,
+    ));
+}
+
+// Obtiene la rama de los parametros y las ramas protegidas de su repositorio.
+fn cargar_ramas_protegidas(
+    params: &HashMap<String, String>,
+) -> Result<(String, RamasProtegidas), ErrorHttp> {
+    let repo = params.get("repo").ok_or_else(|| {
+        ErrorHttp::BadRequest("No se ha encontrado el nombre del repositorio".to_string())
+    })?;
+    let rama = params
+        .get("branch")
+        .ok_or_else(|| ErrorHttp::BadRequest("No se ha encontrado la rama".to_string()))?;
+    Repositorio::validar_nombre_rama(rama)?;
+    let dir_gir = config_servidor::dir_repositorio(repo).join(".gir");
+    if !dir_gir.join("refs/heads").join(rama).exists() {
+        return Err(ErrorHttp::NotFound(format!(
+            "No existe la rama {} en el repositorio {}",
+            rama, repo
+        )));
+    }
+
+    let protegidas = RamasProtegidas::cargar(&dir_gir).map_err(ErrorHttp::InternalServerError)?;
+    Ok((rama.to_string(), protegidas))
+}
+
+fn responder_proteccion(
+    proteccion: &ProteccionRama,
+    logger: Arc<Logger>,
+) -> Result<Response, ErrorHttp> {
+    let body_respuesta = serde_json::to_string(proteccion).map_err(|e| {
+        ErrorHttp::InternalServerError(format!("No se ha podido serializar la proteccion: {}", e))
+    })?;
+    Ok(Response::new(logger, EstadoHttp::Ok, Some(&body_respuesta)))
+}
+
+fn obtener_proteccion(
+    _request: Request,
+    params: HashMap<String, String>,
+    logger: Arc<Logger>,
+) -> Result<Response, ErrorHttp> {
+    let (rama, protegidas) = cargar_ramas_protegidas(&params)?;
+    match protegidas.obtener(&rama) {
+        Some(proteccion) => responder_proteccion(proteccion, logger),
+        None => Err(ErrorHttp::NotFound(format!(
+            "La rama {} no esta protegida",
+            rama
+        ))),
+    }
+}
+
+// Lee del body el campo booleano `clave`, que si no esta vale `por_defecto`.
+fn obtener_booleano(
+    body: &HashMap<String, String>,
+    clave: &str,
+    por_defecto: bool,
+) -> Result<bool, ErrorHttp> {
+    match body.get(clave).map(String::as_str) {
+        None => Ok(por_defecto),
+        Some("true") => Ok(true),
+        Some("false") => Ok(false),
+        Some(valor) => Err(ErrorHttp::ValidationFailed(format!(
+            "{} tiene que ser true o false, no {}",
+            clave, valor
+        ))),
+    }
+}
+
+/// Protege la rama o reemplaza sus reglas. El body puede tener `require_pull_request`
+/// (`true` por defecto), `required_approving_review_count` (1 por defecto) y
+/// `allow_force_pushes` (`false` por defecto).
+fn proteger_rama(
+    request: Request,
+    params: HashMap<String, String>,
+    logger: Arc<Logger>,
+) -> Result<Response, ErrorHttp> {
+    let (rama, mut protegidas) = cargar_ramas_protegidas(&params)?;
+    let body = request.body.unwrap_or_default();
+
+    let requiere_pull_request = obtener_booleano(&body, "require_pull_request", true)?;
+    let permite_force_push = obtener_booleano(&body, "allow_force_pushes", false)?;
+    let aprobaciones_requeridas = match body.get("required_approving_review_count") {
+        Some(cantidad) => cantidad.parse::<u64>().map_err(|_| {
+            ErrorHttp::ValidationFailed(format!(
+                "required_approving_review_count tiene que ser un numero, no {}",
+                cantidad
+            ))
+        })?,
+        None => 1,
+    };
+
+    let proteccion = ProteccionRama {
+        rama,
+        requiere_pull_request,
+        aprobaciones_requeridas,
+        permite_force_push,
+    };
+    protegidas.proteger(proteccion.clone());
+    protegidas
+        .guardar()
+        .map_err(ErrorHttp::InternalServerError)?;
+    responder_proteccion(&proteccion, logger)
+}
+
+fn desproteger_rama(
+    _request: Request,
+    params: HashMap<String, String>,
+    logger: Arc<Logger>,
+) -> Result<Response, ErrorHttp> {
+    let (rama, mut protegidas) = cargar_ramas_protegidas(&params)?;
+    if !protegidas.desproteger(&rama) {
+        return Err(ErrorHttp::NotFound(format!(
+            "La rama {} no esta protegida",
+            rama
+        )));
+    }
+    protegidas
+        .guardar()
+        .map_err(ErrorHttp::InternalServerError)?;
+    Ok(Response::new(logger, EstadoHttp::NoContent, None))
+}
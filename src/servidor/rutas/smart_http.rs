@@ -0,0 +1,330 @@
+use std::{
+    collections::HashMap,
+    io::{self, Cursor, Read, Write},
+    path::PathBuf,
+    sync::Arc,
+};
+
+use crate::{
+    servidor::{
+        config_servidor,
+        gir_server::{server_utils, CAPABILITIES, CAPACIDADES_RECEIVE_PACK, VERSION},
+        receive_pack::receive_pack,
+        repositorio::Repositorio,
+        upload_pack::upload_pack,
+        upload_pack_v2::upload_pack_v2,
+    },
+    tipos_de_dato::{
+        comunicacion::Comunicacion,
+        http::{
+            endpoint::Endpoint, error::ErrorHttp, estado::EstadoHttp, metodos::MetodoHttp,
+            request::Request, response::Response,
+        },
+        logger::Logger,
+        permiso::Permiso,
+    },
+    utils::strings,
+};
+
+const UPLOAD_PACK: &str = "git-upload-pack";
+const RECEIVE_PACK: &str = "git-receive-pack";
+
+pub fn agregar_a_router(rutas: &mut Vec<Endpoint>) {
+    rutas.push(Endpoint::new(
+        MetodoHttp::Get,
+        "/{repo}/info/refs".to_string(),
+        anunciar_referencias,
+        Permiso::Lectura,
+    ));
+    rutas.push(Endpoint::new(
+        MetodoHttp::Post,
+        "/{repo}/git-upload-pack".to_string(),
+        git_upload_pack,
+        Permiso::Lectura,
+    ));
+    rutas.push(Endpoint::new(
+        MetodoHttp::Post,
+        "/{repo}/git-receive-pack".to_string(),
+        git_receive_pack,
+        Permiso::Escritura,
+    ));
+}
+
+/// Flujo en memoria para correr el protocolo de gir sobre un request: se lee del body del
+/// request y lo que se escribe queda para el body de la respuesta.
+struct FlujoEnMemoria {
+    lectura: Cursor<Vec<u8>>,
+    escritura: Vec<u8>,
+}
+
+impl FlujoEnMemoria {
+    fn new(contenido: Vec<u8>) -> Self {
+        Self {
+            lectura: Cursor::new(contenido),
+            escritura: Vec::new(),
+        }
+    }
+}
+
+impl Read for FlujoEnMemoria {
+    fn read(&mut self, buf: &mut [u8]) -> io::Result<usize> {
+        self.lectura.read(buf)
+    }
+}
+
+impl Write for FlujoEnMemoria {
+    fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+        self.escritura.write(buf)
+    }
+
+    fn flush(&mut self) -> io::Result<()> {
+        Ok(())
+    }
+}
+
+/// Devuelve el directorio .gir del repositorio pedido. Para upload-pack el repositorio tiene que
+/// existir; para receive-pack hace falta permiso de escritura y, si el repositorio no existe, que
+/// el cliente sea admin, igual que en el servidor gir. El repositorio se crea vacio solo si
+/// `crear` (en el POST del push), asi el anuncio de referencias no deja repositorios creados.
+fn preparar_repositorio(
+    params: &HashMap<String, String>,
+    servicio: &str,
+    permiso: Permiso,
+    crear: bool,
+) -> Result<String, ErrorHttp> {
+    let repo = params.get("repo").ok_or_else(|| {
+        ErrorHttp::BadRequest("No se ha encontrado el nombre del repositorio".to_string())
+    })?;
+    Repositorio::validar_nombre(repo)?;
+    let path = config_servidor::dir_repositorio(repo).join(".gir");
+    let dir_repo = format!("{}/", path.display());
+
+    if servicio == RECEIVE_PACK {
+        if permiso < Permiso::Escritura {
+            return Err(ErrorHttp::Forbidden(format!(
+                "Se necesita permiso de escritura para hacer push a {}",
+                repo
+            )));
+        }
+        if !path.exists() && permiso < Permiso::Admin {
+            return Err(ErrorHttp::Forbidden(format!(
+                "No existe el repositorio {} y crearlo requiere permiso de admin",
+                repo
+            )));
+        }
+        if crear {
+            server_utils::crear_repositorio_si_no_existe(&path)
+                .map_err(ErrorHttp::InternalServerError)?;
+        }
+    } else if !path.exists() {
+        return Err(ErrorHttp::NotFound(format!(
+            "No se ha encontrado el repositorio {}",
+            repo
+        )));
+    }
+    Ok(dir_repo)
+}
+
+/// Indica si el cliente pidio el protocolo v2 con el header `Git-Protocol: version=2`. El nombre
+/// del header no distingue mayusculas de minusculas.
+fn pide_version_2(request: &Request) -> bool {
+    request
+        .headers
+        .iter()
+        .find(|(nombre, _)| nombre.eq_ignore_ascii_case("Git-Protocol"))
+        .is_some_and(|(_, protocolo)| protocolo.split(':').any(|param| param == "version=2"))
+}
+
+fn obtener_refs(dir_repo: &str, servicio: &str) -> Result<Vec<String>, ErrorHttp> {
+    let capacidades = if servicio == RECEIVE_PACK {
+        CAPACIDADES_RECEIVE_PACK
+    } else {
+        CAPABILITIES
+    };
+    server_utils::obtener_refs_de(PathBuf::from(dir_repo), capacidades)
+        .map_err(ErrorHttp::InternalServerError)
+}
+
+/// Responde `GET /{repo}/info/refs?service=...` con el anuncio de referencias del servicio,
+/// precedido por la linea `# service=...` que pide smart http. Si el cliente pide el protocolo v2
+/// para upload-pack se anuncian las capacidades en lugar de las referencias.
+fn anunciar_referencias(
+    request: Request,
+    params: HashMap<String, String>,
+    logger: Arc<Logger>,
+) -> Result<Response, ErrorHttp> {
+    let servicio = match request.query.get("service").map(String::as_str) {
+        Some(servicio @ (UPLOAD_PACK | RECEIVE_PACK)) => servicio,
+        Some(servicio) => {
+            return Err(ErrorHttp::BadRequest(format!(
+                "Servicio desconocido: {}",
+                servicio
+            )))
+        }
+        None => {
+            return Err(ErrorHttp::Forbidden(
+                "Solo se soporta smart http, falta el parametro service".to_string(),
+            ))
+        }
+    };
+    let dir_repo = preparar_repositorio(&params, servicio, request.permiso, false)?;
+
+    let mut flujo = FlujoEnMemoria::new(Vec::new());
+    let mut comunicacion = Comunicacion::new_para_server_sobre(&mut flujo, logger.clone());
+    comunicacion
+        .enviar(&strings::obtener_linea_con_largo_hex(&format!(
+            "# service={}\n",
+            servicio
+        )))
+        .and_then(|_| comunicacion.enviar_flush_pkt())
+        .map_err(ErrorHttp::InternalServerError)?;
+    if servicio == UPLOAD_PACK && pide_version_2(&request) {
+        server_utils::anunciar_capacidades_v2(&mut comunicacion)
+            .map_err(ErrorHttp::InternalServerError)?;
+    } else {
+        let refs = obtener_refs(&dir_repo, servicio)?;
+        comunicacion
+            .enviar(&strings::obtener_linea_con_largo_hex(VERSION))
+            .and_then(|_| comunicacion.responder(&refs))
+            .map_err(ErrorHttp::InternalServerError)?;
+    }
+
+    Ok(Response::new_binario(
+        logger,
+        EstadoHttp::Ok,
+        &format!("application/x-{}-advertisement", servicio),
+        flujo.escritura,
+    ))
+}
+
+/// Responde `POST /{repo}/git-upload-pack`. Como el servidor no guarda estado entre requests,
+/// cada uno trae los wants, los haves que ya se sabe que son comunes y una tanda nueva de haves
+/// (stateless RPC, como git). Si el body termina antes del done se responde la tanda (los ACK y
+/// el NAK) y el cliente vuelve a pedir con la siguiente. En el protocolo v2 cada request trae un
+/// comando completo.
+fn git_upload_pack(
+    request: Request,
+    params: HashMap<String, String>,
+    logger: Arc<Logger>,
+) -> Result<Response, ErrorHttp> {
+    let dir_repo = preparar_repositorio(&params, UPLOAD_PACK, request.permiso, false)?;
+    let version_2 = pide_version_2(&request);
+    let refs = obtener_refs(&dir_repo, UPLOAD_PACK)?;
+
+    let mut flujo = FlujoEnMemoria::new(request.body_binario.unwrap_or_default());
+    let mut comunicacion = Comunicacion::new_para_server_sobre(&mut flujo, logger.clone());
+    let resultado = if version_2 {
+        upload_pack_v2(dir_repo, &mut comunicacion, logger.clone())
+    } else {
+        upload_pack(dir_repo, &mut comunicacion, &refs, logger.clone())
+    };
+    if let Err(e) = resultado {
+        logger.log(&format!(
+            "upload-pack por http termino sin completarse: {}",
+            e
+        ));
+    }
+
+    Ok(Response::new_binario(
+        logger,
+        EstadoHttp::Ok,
+        "application/x-git-upload-pack-result",
+        flujo.escritura,
+    ))
+}
+
+/// Responde `POST /{repo}/git-receive-pack`: recibe las referencias a actualizar y el packfile.
+/// Si el packfile es invalido pero ya se respondio el report-status, se responde el reporte para
+/// que el cliente sepa por que se rechazo el push.
+fn git_receive_pack(
+    request: Request,
+    params: HashMap<String, String>,
+    logger: Arc<Logger>,
+) -> Result<Response, ErrorHttp> {
+    let dir_repo = preparar_repositorio(&params, RECEIVE_PACK, request.permiso, true)?;
+
+    let mut flujo = FlujoEnMemoria::new(request.body_binario.unwrap_or_default());
+    let mut comunicacion = Comunicacion::new_para_server_sobre(&mut flujo, logger.clone());
+    if let Err(e) = receive_pack(dir_repo, &mut comunicacion, logger.clone()) {
+        if flujo.escritura.is_empty() {
+            return Err(ErrorHttp::InternalServerError(e));
+        }
+        logger.log(&format!("receive-pack por http rechazo el push: {}", e));
+    }
+
+    Ok(Response::new_binario(
+        logger,
+        EstadoHttp::Ok,
+        "application/x-git-receive-pack-result",
+        flujo.escritura,
+    ))
+}
+
+#[cfg(test)]
+mod tests {
+    use std::io::BufReader;
+
+    use crate::utils::testing::MockTcpStream;
+
+    use super::*;
+
+    fn request_con_header(header: &str) -> Request {
+        let logger = Arc::new(Logger::new(PathBuf::from("tmp/smart_http_test")).unwrap());
+        let mut mock_tcp = MockTcpStream {
+            lectura_data: format!("GET /repo/info/refs HTTP/1.1\n{}\n\r\n", header).into_bytes(),
+            escritura_data: vec![],
+        };
+        Request::from(&mut BufReader::new(&mut mock_tcp), logger).unwrap()
+    }
+
+    #[test]
+    fn test01_el_header_git_protocol_no_distingue_mayusculas() {
+        assert!(pide_version_2(&request_con_header(
+            "Git-Protocol: version=2"
+        )));
+        assert!(pide_version_2(&request_con_header(
+            "git-protocol: version=2"
+        )));
+        assert!(!pide_version_2(&request_con_header(
+            "GIT-PROTOCOL: version=1"
+        )));
+    }
+
+    #[test]
+    fn test02_no_se_puede_pedir_un_repositorio_fuera_del_directorio_de_repositorios() {
+        let params = HashMap::from([("repo".to_string(), "..".to_string())]);
+
+        let resultado = preparar_repositorio(&params, RECEIVE_PACK, Permiso::Admin, true);
+
+        assert!(matches!(resultado, Err(ErrorHttp::ValidationFailed(_))));
+    }
+
+    #[test]
+    fn test03_el_repositorio_de_un_push_se_crea_en_el_post_y_no_en_el_anuncio() {
+        let repo = "repo_smart_http_test03";
+        let params = HashMap::from([("repo".to_string(), repo.to_string())]);
+        let dir_repositorio = config_servidor::dir_repositorio(repo);
+        let _ = crate::utils::io::rm_directorio(&dir_repositorio);
+
+        let anuncio = preparar_repositorio(&params, RECEIVE_PACK, Permiso::Admin, false);
+        let existe_despues_del_anuncio = dir_repositorio.exists();
+        let push = preparar_repositorio(&params, RECEIVE_PACK, Permiso::Admin, true);
+        let existe_despues_del_push = dir_repositorio.join(".gir").exists();
+        let sin_admin = preparar_repositorio(
+            &HashMap::from([(
+                "repo".to_string(),
+                "repo_smart_http_test03_otro".to_string(),
+            )]),
+            RECEIVE_PACK,
+            Permiso::Escritura,
+            false,
+        );
+
+        crate::utils::io::rm_directorio(&dir_repositorio).unwrap();
+        assert!(anuncio.is_ok());
+        assert!(!existe_despues_del_anuncio);
+        assert!(push.is_ok());
+        assert!(existe_despues_del_push);
+        assert!(matches!(sin_admin, Err(ErrorHttp::Forbidden(_))));
+    }
+}
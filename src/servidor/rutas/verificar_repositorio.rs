@@ -0,0 +1,64 @@
+use std::{collections::HashMap, sync::Arc};
+
+use crate::{
+    servidor::{config_servidor, repositorio::Repositorio},
+    tipos_de_dato::{
+        comandos::fsck::Fsck,
+        http::{
+            endpoint::Endpoint, error::ErrorHttp, estado::EstadoHttp, metodos::MetodoHttp,
+            request::Request, response::Response,
+        },
+        logger::Logger,
+        permiso::Permiso,
+    },
+};
+
+pub fn agregar_a_router(rutas: &mut Vec<Endpoint>) {
+    let endpoint = Endpoint::new(
+        MetodoHttp::Get,
+        "/repos/{repo}/fsck".to_string(),
+        verificar_repositorio,
+        Permiso::Lectura,
+    );
+    rutas.push(endpoint)
+}
+
+/// Corre fsck sobre el repositorio `repo` y responde con los problemas encontrados.
+fn verificar_repositorio(
+    _request: Request,
+    params: HashMap<String, String>,
+    logger: Arc<Logger>,
+) -> Result<Response, ErrorHttp> {
+    let repo = params.get("repo").ok_or_else(|| {
+        ErrorHttp::BadRequest("No se ha encontrado el nombre del repositorio".to_string())
+    })?;
+    Repositorio::validar_nombre(repo)?;
+    let dir_repositorio = config_servidor::dir_repositorio(repo);
+    if !dir_repositorio.join(".gir").exists() {
+        return Err(ErrorHttp::NotFound(format!(
+            "No se ha encontrado el repositorio {}",
+            repo
+        )));
+    }
+
+    let problemas = Fsck::verificar(&dir_repositorio, logger.clone())
+        .map_err(|e| {
+            ErrorHttp::InternalServerError(format!(
+                "No se ha podido verificar el repositorio {}: {}",
+                repo, e
+            ))
+        })?
+        .iter()
+        .map(|problema| problema.to_string())
+        .collect::<Vec<String>>();
+
+    let body_respuesta = serde_json::json!({
+        "repo": repo,
+        "ok": problemas.is_empty(),
+        "problemas": problemas,
+    })
+    .to_string();
+
+    let response = Response::new(logger, EstadoHttp::Ok, Some(&body_respuesta));
+    Ok(response)
+}
@@ -0,0 +1,235 @@
+use crate::tipos_de_dato::objetos::tag::TagObj;
+use crate::utils::objects::{leer_commit, obtener_objetos_de_tree};
+use std::collections::{HashSet, VecDeque};
+
+/// Pedido de historia recortada que manda el cliente junto con los wants, con las lineas
+/// `shallow 'hash'`, `deepen 'n'` y `deepen-since 'fecha'`.
+#[derive(Debug, Default)]
+pub struct PedidoShallow {
+    /// Commits que el cliente ya tiene como borde de su historia (sin sus padres).
+    pub shallows_del_cliente: HashSet<String>,
+    /// Cantidad de commits a mandar desde cada want, contando al want.
+    pub profundidad: Option<u32>,
+    /// Solo se mandan los commits posteriores a esta fecha (en segundos desde epoch).
+    pub desde: Option<i64>,
+}
+
+impl PedidoShallow {
+    /// Agrega al pedido la linea dada si es una linea shallow o deepen. Devuelve si la linea era
+    /// parte del pedido.
+    pub fn agregar_linea(&mut self, linea: &str) -> Result<bool, String> {
+        let linea = linea.trim_end();
+        if let Some(hash) = linea.strip_prefix("shallow ") {
+            self.shallows_del_cliente.insert(hash.to_string());
+        } else if let Some(profundidad) = linea.strip_prefix("deepen ") {
+            let profundidad = profundidad
+                .parse::<u32>()
+                .map_err(|_| format!("Profundidad invalida: {}", profundidad))?;
+            if profundidad == 0 {
+                return Err("La profundidad tiene que ser mayor a 0".to_string());
+            }
+            self.profundidad = Some(profundidad);
+        } else if let Some(desde) = linea.strip_prefix("deepen-since ") {
+            self.desde = Some(
+                desde
+                    .parse::<i64>()
+                    .map_err(|_| format!("Fecha invalida: {}", desde))?,
+            );
+        } else {
+            return Ok(false);
+        }
+        Ok(true)
+    }
+
+    /// Indica si el cliente pidio cambiar la profundidad de su historia, en cuyo caso hay que
+    /// responderle que commits quedan como borde (shallow) y cuales dejan de serlo (unshallow).
+    pub fn pide_profundizar(&self) -> bool {
+        self.profundidad.is_some() || self.desde.is_some()
+    }
+
+    /// Indica si hay que recortar la historia al armar el packfile: el cliente pidio una
+    /// profundidad o ya tiene una historia recortada.
+    pub fn es_shallow(&self) -> bool {
+        self.pide_profundizar() || !self.shallows_del_cliente.is_empty()
+    }
+}
+
+/// Historia a mandar para un pedido shallow.
+#[derive(Debug, Default)]
+pub struct HistoriaRecortada {
+    /// Objetos a mandar: los commits de la historia, sus arboles y blobs y los tags pedidos.
+    pub objetos: HashSet<String>,
+    /// Commits que pasan a ser borde de la historia del cliente.
+    pub shallows: Vec<String>,
+    /// Commits que eran borde para el cliente y ahora se mandan con sus padres.
+    pub unshallows: Vec<String>,
+}
+
+impl HistoriaRecortada {
+    /// Lineas `shallow 'hash'` y `unshallow 'hash'` de la respuesta al pedido.
+    pub fn lineas(&self) -> Vec<String> {
+        self.shallows
+            .iter()
+            .map(|hash| format!("shallow {}\n", hash))
+            .chain(
+                self.unshallows
+                    .iter()
+                    .map(|hash| format!("unshallow {}\n", hash)),
+            )
+            .collect()
+    }
+}
+
+/// Recorre la historia desde los wants respetando la profundidad y la fecha pedidas y sin pasar
+/// los commits que el cliente ya tiene como borde (salvo que pida profundizar). Devuelve los
+/// objetos a mandar y como cambia el borde de la historia del cliente.
+pub fn recortar_historia(
+    dir_objetos: &str,
+    wants: &[String],
+    pedido: &PedidoShallow,
+) -> Result<HistoriaRecortada, String> {
+    let mut historia = HistoriaRecortada::default();
+    let mut commits_a_revisar = VecDeque::new();
+    for want in wants {
+        // un tag anotado se manda junto con la historia del commit al que apunta
+        if TagObj::es_tag(want, dir_objetos) {
+            historia.objetos.insert(want.clone());
+        }
+        commits_a_revisar.push_back((TagObj::pelar(want, dir_objetos)?, 1));
+    }
+
+    let mut commits_incluidos = HashSet::new();
+    let mut bordes = HashSet::new();
+    while let Some((hash, profundidad)) = commits_a_revisar.pop_front() {
+        if !commits_incluidos.insert(hash.clone()) {
+            continue;
+        }
+        let commit = leer_commit(&hash, dir_objetos)?;
+        historia.objetos.insert(hash.clone());
+        historia.objetos.extend(obtener_objetos_de_tree(
+            &commit.tree,
+            dir_objetos,
+            &HashSet::new(),
+        )?);
+
+        let es_borde_del_cliente =
+            !pedido.pide_profundizar() && pedido.shallows_del_cliente.contains(&hash);
+        let llego_a_la_profundidad = pedido
+            .profundidad
+            .is_some_and(|maxima| profundidad >= maxima);
+        if es_borde_del_cliente || llego_a_la_profundidad {
+            if !commit.padres.is_empty() {
+                bordes.insert(hash);
+            }
+            continue;
+        }
+
+        for padre in commit.padres {
+            if let Some(desde) = pedido.desde {
+                if leer_commit(&padre, dir_objetos)?.fecha < desde {
+                    bordes.insert(hash.clone());
+                    continue;
+                }
+            }
+            commits_a_revisar.push_back((padre, profundidad + 1));
+        }
+    }
+
+    let mut shallows: Vec<String> = bordes
+        .iter()
+        .filter(|hash| !pedido.shallows_del_cliente.contains(*hash))
+        .cloned()
+        .collect();
+    let mut unshallows: Vec<String> = pedido
+        .shallows_del_cliente
+        .iter()
+        .filter(|hash| commits_incluidos.contains(*hash) && !bordes.contains(*hash))
+        .cloned()
+        .collect();
+    shallows.sort();
+    unshallows.sort();
+    historia.shallows = shallows;
+    historia.unshallows = unshallows;
+    Ok(historia)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const COMMIT: &str = "4163eb28ec61fd1d0c17cf9b77f4c17e1e338b0b";
+    const PADRE: &str = "8f63722a025d936c53304d40ba3197ffebf194d1";
+
+    fn dir_objetos() -> String {
+        env!("CARGO_MANIFEST_DIR").to_string() + "/server_test_dir/test03/.gir/objects/"
+    }
+
+    #[test]
+    fn test01_con_profundidad_1_se_manda_solo_el_want_como_shallow() {
+        let pedido = PedidoShallow {
+            profundidad: Some(1),
+            ..Default::default()
+        };
+
+        let historia = recortar_historia(&dir_objetos(), &[COMMIT.to_string()], &pedido).unwrap();
+
+        assert_eq!(historia.shallows, vec![COMMIT.to_string()]);
+        assert!(historia.unshallows.is_empty());
+        assert!(historia.objetos.contains(COMMIT));
+        assert!(historia
+            .objetos
+            .contains("0a3965af663795bc6010f3eb8e07c3fc56e68c00"));
+        assert!(!historia.objetos.contains(PADRE));
+    }
+
+    #[test]
+    fn test02_profundizar_pasa_el_borde_del_cliente_a_unshallow() {
+        let pedido = PedidoShallow {
+            shallows_del_cliente: HashSet::from([COMMIT.to_string()]),
+            profundidad: Some(2),
+            ..Default::default()
+        };
+
+        let historia = recortar_historia(&dir_objetos(), &[COMMIT.to_string()], &pedido).unwrap();
+
+        assert_eq!(historia.shallows, vec![PADRE.to_string()]);
+        assert_eq!(historia.unshallows, vec![COMMIT.to_string()]);
+        assert!(historia.objetos.contains(PADRE));
+        assert_eq!(
+            historia.lineas(),
+            vec![
+                format!("shallow {}\n", PADRE),
+                format!("unshallow {}\n", COMMIT)
+            ]
+        );
+    }
+
+    #[test]
+    fn test03_sin_deepen_no_se_pasa_del_borde_del_cliente() {
+        let pedido = PedidoShallow {
+            shallows_del_cliente: HashSet::from([PADRE.to_string()]),
+            ..Default::default()
+        };
+        let abuelo = leer_commit(PADRE, &dir_objetos()).unwrap().padres[0].clone();
+
+        let historia = recortar_historia(&dir_objetos(), &[COMMIT.to_string()], &pedido).unwrap();
+
+        assert!(historia.lineas().is_empty());
+        assert!(historia.objetos.contains(PADRE));
+        assert!(!historia.objetos.contains(&abuelo));
+    }
+
+    #[test]
+    fn test04_deepen_since_corta_en_los_commits_anteriores_a_la_fecha() {
+        let fecha_commit = leer_commit(COMMIT, &dir_objetos()).unwrap().fecha;
+        let mut pedido = PedidoShallow::default();
+        assert!(pedido
+            .agregar_linea(&format!("deepen-since {}\n", fecha_commit))
+            .unwrap());
+
+        let historia = recortar_historia(&dir_objetos(), &[COMMIT.to_string()], &pedido).unwrap();
+
+        assert_eq!(historia.shallows, vec![COMMIT.to_string()]);
+        assert!(!historia.objetos.contains(PADRE));
+    }
+}
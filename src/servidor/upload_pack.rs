@@ -1,11 +1,17 @@
+use crate::err_comunicacion::ErrorDeComunicacion;
+use crate::servidor::shallow::{self, HistoriaRecortada, PedidoShallow};
 use crate::tipos_de_dato::comunicacion::Comunicacion;
 use crate::tipos_de_dato::logger::Logger;
+use crate::tipos_de_dato::objetos::tag::TagObj;
 use crate::tipos_de_dato::packfile;
-use crate::utils::strings::eliminar_prefijos;
-use crate::utils::{self, objects};
+use crate::utils::{self, compresion, objects};
+use std::collections::HashSet;
 use std::io::{Read, Write};
 use std::sync::Arc;
 
+const SIDE_BAND_64K: &str = "side-band-64k";
+const MULTI_ACK_DETAILED: &str = "multi_ack_detailed";
+
 /// Envia packfile al cliente,
 /// # Argumentos
 /// * `dir` - Direccion del repositorio
@@ -21,55 +27,276 @@ where
     T: Read + Write,
 {
     logger.log("Iniciando upload pack");
-    let wants = comunicacion.obtener_lineas()?; // obtengo los wants del cliente
-    if wants.is_empty() {
+    let lineas = comunicacion.obtener_lineas()?; // obtengo los wants del cliente
+    if lineas.is_empty() {
         println!("Se termino la conexion");
         return Ok(()); // el cliente esta actualizado
     }
+    // despues de los wants pueden venir las lineas shallow y deepen
+    let mut wants = Vec::new();
+    let mut pedido_shallow = PedidoShallow::default();
+    for linea in lineas {
+        if !pedido_shallow.agregar_linea(&linea)? {
+            wants.push(linea);
+        }
+    }
     comprobar_wants(&wants, refs_enviadas, comunicacion)?; // compruebo que los wants existan
+                                                           // las capacidades pedidas por el cliente vienen en el primer want: 'want hash cap1 cap2'
+    let capacidades: Vec<&str> = wants[0].split_whitespace().skip(2).collect();
+    let side_band = capacidades.contains(&SIDE_BAND_64K);
+    let multi_ack = capacidades.contains(&MULTI_ACK_DETAILED);
+    let hashes_wants: Vec<String> = wants
+        .iter()
+        .filter_map(|want| want.split_whitespace().nth(1))
+        .map(String::from)
+        .collect();
 
-    // ------- CLONE --------
-    let lineas_siguientes = comunicacion.obtener_lineas()?;
-    if lineas_siguientes[0].clone().contains("done") {
-        procesar_pedido_clone(&dir, comunicacion)?;
+    let historia = if pedido_shallow.es_shallow() {
+        let historia = shallow::recortar_historia(
+            &(dir.clone() + "objects/"),
+            &hashes_wants,
+            &pedido_shallow,
+        )?;
+        if pedido_shallow.pide_profundizar() {
+            enviar_actualizacion_shallow(comunicacion, &historia)?;
+        }
+        Some(historia)
     } else {
-        // -------- fetch ----------
-        procesar_pedido_fetch(&dir, comunicacion, lineas_siguientes)?;
-    }
+        None
+    };
+
+    // ------- negociacion --------
+    let comunes = negociar_commits_en_comun(
+        &(dir.clone() + "objects/"),
+        comunicacion,
+        &hashes_wants,
+        multi_ack,
+    )?;
+    let packfile = match historia {
+        Some(historia) => obtener_packfile_de_historia(&dir, historia, &comunes),
+        None => obtener_packfile_para(&dir, &hashes_wants, &comunes),
+    };
+    enviar_packfile(comunicacion, packfile, side_band)?;
     logger.log("Upload pack ejecutado con exito");
     Ok(())
 }
 
-// Funcion que se encarga de seguir el protocolo en caso de clone
-fn procesar_pedido_clone<T: Read + Write>(
-    dir: &str,
+// Responde el pedido de profundidad con una linea shallow o unshallow por cada commit que cambia
+// en el borde de la historia del cliente, terminando con un flush-pkt.
+fn enviar_actualizacion_shallow<T: Read + Write>(
     comunicacion: &mut Comunicacion<T>,
+    historia: &HistoriaRecortada,
 ) -> Result<(), String> {
-    comunicacion.responder(&vec![utils::strings::obtener_linea_con_largo_hex("NAK\n")])?; // respondo NAK
-    let packfile = packfile::Packfile::obtener_pack_entero(&(dir.to_string() + "objects/"))?; // obtengo el packfile
-    comunicacion.enviar_pack_file(packfile)?;
-    Ok(())
+    for linea in historia.lineas() {
+        comunicacion.enviar(&utils::strings::obtener_linea_con_largo_hex(&linea))?;
+    }
+    comunicacion.enviar_flush_pkt()
 }
 
-// Funcion que se encarga de seguir el protocolo en caso de fetch
-fn procesar_pedido_fetch<T: Read + Write>(
-    dir: &str,
+// Recibe los haves del cliente, en tandas terminadas por un flush-pkt, hasta el done y devuelve
+// los que el servidor tiene. Con multi_ack_detailed cada have conocido se responde con
+// 'ACK hash common', el primer have desconocido despues de que los commits en comun alcanzan con
+// 'ACK hash ready' y cada tanda termina con NAK. Sin multi_ack solo se responde el primer have
+// en comun. Al done se responde 'ACK hash' con el ultimo commit en comun, o NAK si no hay ninguno.
+// Por smart http el done puede llegar en la misma tanda que los haves.
+fn negociar_commits_en_comun<T: Read + Write>(
+    dir_objetos: &str,
     comunicacion: &mut Comunicacion<T>,
-    lineas: Vec<String>,
+    wants: &[String],
+    multi_ack: bool,
+) -> Result<Vec<String>, String> {
+    let mut comunes: Vec<String> = Vec::new();
+    let mut alcanzables_desde_comunes = HashSet::new();
+    let mut listo = false;
+    let mut comunes_revisados = 0;
+    loop {
+        let mut hay_comunes = false;
+        let mut hay_desconocidos = false;
+        for linea in comunicacion.obtener_lineas()? {
+            if linea.starts_with("done") {
+                match (comunes.last(), multi_ack) {
+                    (Some(ultimo), true) => enviar_ack(comunicacion, ultimo, "")?,
+                    (Some(_), false) => {}
+                    (None, _) => enviar_nak(comunicacion)?,
+                }
+                return Ok(comunes);
+            }
+            let have = match linea.strip_prefix("have ") {
+                Some(have) => have.trim().to_string(),
+                None => continue,
+            };
+            if compresion::existe_objeto(&have, dir_objetos) {
+                hay_comunes = true;
+                if !comunes.contains(&have) {
+                    comunes.push(have.clone());
+                }
+                if multi_ack {
+                    enviar_ack(comunicacion, &have, " common")?;
+                } else if comunes.len() == 1 {
+                    enviar_ack(comunicacion, &have, "")?;
+                }
+                continue;
+            }
+            hay_desconocidos = true;
+            if multi_ack && !listo && comunes_revisados < comunes.len() {
+                comunes_revisados = comunes.len();
+                listo = commits_en_comun_alcanzan(
+                    wants,
+                    &comunes,
+                    &mut alcanzables_desde_comunes,
+                    dir_objetos,
+                )?;
+                if listo {
+                    enviar_ack(comunicacion, &have, " ready")?;
+                }
+            }
+        }
+        // termino la tanda de haves
+        if multi_ack && hay_comunes && !hay_desconocidos && !listo {
+            listo = commits_en_comun_alcanzan(
+                wants,
+                &comunes,
+                &mut alcanzables_desde_comunes,
+                dir_objetos,
+            )?;
+            if let (true, Some(ultimo)) = (listo, comunes.last()) {
+                enviar_ack(comunicacion, ultimo, " ready")?;
+            }
+        }
+        if multi_ack || comunes.is_empty() {
+            enviar_nak(comunicacion)?;
+        }
+    }
+}
+
+fn enviar_ack<T: Read + Write>(
+    comunicacion: &mut Comunicacion<T>,
+    hash: &str,
+    estado: &str,
 ) -> Result<(), String> {
-    let have_objs_ids = eliminar_prefijos(&lineas);
-    let respuesta_acks_nak = utils::objects::obtener_objetos_en_comun(
-        have_objs_ids.clone(),
-        &(dir.to_string() + "objects/"),
-    );
-    comunicacion.responder(&respuesta_acks_nak)?;
-    let _ultimo_done = comunicacion.obtener_lineas()?;
-    let faltantes = objects::obtener_archivos_faltantes(have_objs_ids, dir);
-    let packfile =
-        packfile::Packfile::obtener_pack_con_archivos(faltantes, &(dir.to_string() + "objects/"))?;
-
-    comunicacion.enviar_pack_file(packfile)?;
-    Ok(())
+    comunicacion.enviar(&utils::strings::obtener_linea_con_largo_hex(&format!(
+        "ACK {}{}\n",
+        hash, estado
+    )))
+}
+
+fn enviar_nak<T: Read + Write>(comunicacion: &mut Comunicacion<T>) -> Result<(), String> {
+    comunicacion.enviar(&utils::strings::obtener_linea_con_largo_hex("NAK\n"))
+}
+
+/// Indica si los commits en comun alcanzan para armar el packfile: cada want tiene algun commit
+/// en comun entre sus ancestros, asi que el cliente puede dejar de mandar haves.
+/// `alcanzables_desde_comunes` guarda los commits en comun y sus ancestros entre llamadas, asi
+/// solo se recorre la historia de los comunes nuevos y la de cada want se corta apenas llega a
+/// uno de ellos.
+pub fn commits_en_comun_alcanzan(
+    wants: &[String],
+    comunes: &[String],
+    alcanzables_desde_comunes: &mut HashSet<String>,
+    dir_objetos: &str,
+) -> Result<bool, String> {
+    objects::agregar_commits_alcanzables(comunes, dir_objetos, alcanzables_desde_comunes);
+    for want in wants {
+        let mut pendientes = vec![TagObj::pelar(want, dir_objetos)?];
+        let mut visitados = HashSet::new();
+        let mut alcanza = false;
+        while let Some(hash) = pendientes.pop() {
+            if alcanzables_desde_comunes.contains(&hash) {
+                alcanza = true;
+                break;
+            }
+            if visitados.insert(hash.clone()) {
+                pendientes.extend(objects::leer_commit(&hash, dir_objetos)?.padres);
+            }
+        }
+        if !alcanza {
+            return Ok(false);
+        }
+    }
+    Ok(true)
+}
+
+// Envia el packfile tal cual o, si el cliente pidio side-band-64k, multiplexado junto con el
+// progreso
+fn enviar_packfile<T: Read + Write>(
+    comunicacion: &mut Comunicacion<T>,
+    packfile: Result<Vec<u8>, String>,
+    side_band: bool,
+) -> Result<(), String> {
+    if side_band {
+        enviar_packfile_por_side_band(comunicacion, packfile)
+    } else {
+        comunicacion.enviar_pack_file(packfile?)
+    }
+}
+
+/// Envia el packfile multiplexado por side-band-64k y termina con un flush-pkt. El packfile va
+/// por el canal 1 y el progreso por el canal 2; si no se pudo armar el packfile, el error se
+/// manda por el canal 3 para que el cliente no lo confunda con un packfile roto.
+pub fn enviar_packfile_por_side_band<T: Read + Write>(
+    comunicacion: &mut Comunicacion<T>,
+    packfile: Result<Vec<u8>, String>,
+) -> Result<(), String> {
+    let packfile = match packfile {
+        Ok(packfile) => packfile,
+        Err(error) => {
+            comunicacion.enviar_error_fatal(&format!("No se pudo armar el packfile: {}", error))?;
+            comunicacion.enviar_flush_pkt()?;
+            return Err(error);
+        }
+    };
+    // el header del packfile es 'PACK', la version y la cantidad de objetos (4 bytes cada uno)
+    let cantidad_objetos = packfile
+        .get(8..12)
+        .map(|bytes| u32::from_be_bytes([bytes[0], bytes[1], bytes[2], bytes[3]]))
+        .unwrap_or(0);
+
+    comunicacion.enviar_progreso(&format!("Enviando {} objetos\n", cantidad_objetos))?;
+    comunicacion.enviar_datos_multiplexados(&packfile)?;
+    comunicacion.enviar_progreso(&format!(
+        "Total {} objetos, {} bytes\n",
+        cantidad_objetos,
+        packfile.len()
+    ))?;
+    comunicacion.enviar_flush_pkt()
+}
+
+/// Arma el packfile con los objetos alcanzables desde los wants que no son alcanzables desde los
+/// commits en comun con el cliente. Sin commits en comun (clone) se manda toda la historia de los
+/// wants.
+pub fn obtener_packfile_para(
+    dir: &str,
+    wants: &[String],
+    comunes: &[String],
+) -> Result<Vec<u8>, String> {
+    let dir_objetos = dir.to_string() + "objects/";
+    let objetos =
+        objects::obtener_objetos_alcanzables(wants, comunes, &dir_objetos, &HashSet::new())?;
+    armar_packfile(objetos, &dir_objetos)
+}
+
+/// Arma el packfile con los objetos de la historia recortada de un pedido shallow, sin los de los
+/// commits en comun con el cliente.
+pub fn obtener_packfile_de_historia(
+    dir: &str,
+    historia: HistoriaRecortada,
+    comunes: &[String],
+) -> Result<Vec<u8>, String> {
+    let dir_objetos = dir.to_string() + "objects/";
+    let objetos_del_cliente = objects::obtener_objetos_de_commits(comunes, &dir_objetos)?;
+    let objetos = historia
+        .objetos
+        .into_iter()
+        .filter(|objeto| !objetos_del_cliente.contains(objeto))
+        .collect();
+    armar_packfile(objetos, &dir_objetos)
+}
+
+// Ordena los objetos para que el packfile sea siempre el mismo para el mismo pedido
+fn armar_packfile(objetos: HashSet<String>, dir_objetos: &str) -> Result<Vec<u8>, String> {
+    let mut objetos: Vec<String> = objetos.into_iter().collect();
+    objetos.sort();
+    packfile::Packfile::obtener_pack_con_archivos(objetos, dir_objetos)
 }
 
 // Funcion para comprobar si los wants enviados por el cliente son o no validos, en caso de que no lo sean se le envia un mensaje de error al cliente
@@ -78,6 +305,22 @@ fn comprobar_wants<T: Read + Write>(
     refs_enviadas: &Vec<String>,
     comunicacion: &mut Comunicacion<T>,
 ) -> Result<(), String> {
+    // sin wants no hay capacidades ni nada que mandar, y cada want tiene que tener su hash
+    let error_de_protocolo = if wants.is_empty() {
+        Some("no se recibio ningun want".to_string())
+    } else {
+        wants
+            .iter()
+            .find(|want| want.split_whitespace().nth(1).is_none())
+            .map(|want| format!("linea invalida en los wants: {}", want.trim_end()))
+    };
+    if let Some(mensaje) = error_de_protocolo {
+        comunicacion.responder(&vec![utils::strings::obtener_linea_con_largo_hex(
+            &format!("ERR {}\n", mensaje),
+        )])?;
+        return Err(ErrorDeComunicacion::ErrorDeProtocolo(mensaje).to_string());
+    }
+
     for want in wants {
         let want_split: Vec<&str> = want.split_whitespace().collect();
         let want_hash = want_split[1].to_string();
@@ -139,7 +382,7 @@ mod tests {
     #[test]
     #[serial]
     fn test01_clone() {
-        let wants = "4163eb28ec61fd1d0c17cf9b77f4c17e1e338b0".to_string();
+        let wants = "4163eb28ec61fd1d0c17cf9b77f4c17e1e338b0b".to_string();
         let test_dir = env!("CARGO_MANIFEST_DIR").to_string() + "/server_test_dir/test03/.gir/";
 
         let mock: MockTcpStream = MockTcpStream {
@@ -160,7 +403,7 @@ mod tests {
             test_dir,
             &mut comunicacion,
             &vec![utils::strings::obtener_linea_con_largo_hex(
-                "4163eb28ec61fd1d0c17cf9b77f4c17e1e338b0 refs/heads/master\n",
+                "4163eb28ec61fd1d0c17cf9b77f4c17e1e338b0b refs/heads/master\n",
             )],
             logger.clone(),
         )
@@ -175,7 +418,7 @@ mod tests {
     #[test]
     #[serial]
     fn test02_fetch() {
-        let wants = "4163eb28ec61fd1d0c17cf9b77f4c17e1e338b0".to_string();
+        let wants = "4163eb28ec61fd1d0c17cf9b77f4c17e1e338b0b".to_string();
         let test_dir = env!("CARGO_MANIFEST_DIR").to_string() + "/server_test_dir/test03/.gir/";
 
         let mock: MockTcpStream = MockTcpStream {
@@ -198,7 +441,7 @@ mod tests {
             test_dir,
             &mut comunicacion,
             &vec![utils::strings::obtener_linea_con_largo_hex(
-                "4163eb28ec61fd1d0c17cf9b77f4c17e1e338b0 refs/heads/master\n",
+                "4163eb28ec61fd1d0c17cf9b77f4c17e1e338b0b refs/heads/master\n",
             )],
             logger.clone(),
         )
@@ -228,7 +471,7 @@ mod tests {
             test_dir,
             &mut comunicacion,
             &vec![utils::strings::obtener_linea_con_largo_hex(
-                &("4163eb28ec61fd1d0c17cf9b77f4c17e1e338b0".to_string() + " refs/heads/master\n"),
+                &("4163eb28ec61fd1d0c17cf9b77f4c17e1e338b0b".to_string() + " refs/heads/master\n"),
             )],
             logger.clone(),
         );
@@ -242,4 +485,164 @@ mod tests {
         ];
         assert_eq!(respuesta, respuesta_esperada);
     }
+
+    #[test]
+    #[serial]
+    fn test04_clone_con_side_band_multiplexa_el_packfile_y_el_progreso() {
+        let wants = "4163eb28ec61fd1d0c17cf9b77f4c17e1e338b0b".to_string();
+        let test_dir = env!("CARGO_MANIFEST_DIR").to_string() + "/server_test_dir/test03/.gir/";
+
+        let mock: MockTcpStream = MockTcpStream {
+            lectura_data: Vec::new(),
+        };
+        let logger = Arc::new(Logger::new(PathBuf::from("tmp/upload_pack_test04")).unwrap());
+        let mut comunicacion = Comunicacion::new_para_testing(mock, logger.clone());
+        comunicacion
+            .enviar_pedidos_al_servidor_pkt(vec![wants], "ofs-delta side-band-64k".to_string())
+            .unwrap();
+        comunicacion
+            .enviar(&utils::strings::obtener_linea_con_largo_hex("done\n"))
+            .unwrap();
+
+        upload_pack(
+            test_dir,
+            &mut comunicacion,
+            &vec![utils::strings::obtener_linea_con_largo_hex(
+                "4163eb28ec61fd1d0c17cf9b77f4c17e1e338b0b refs/heads/master\n",
+            )],
+            logger.clone(),
+        )
+        .unwrap();
+        let respuesta = comunicacion.obtener_lineas().unwrap();
+        assert_eq!(respuesta, vec!["NAK\n".to_string()]);
+        let packfile = comunicacion.obtener_packfile_multiplexado().unwrap();
+        assert_eq!(&packfile[..4], "PACK".as_bytes());
+        let progreso = comunicacion.obtener_progreso();
+        assert!(progreso.iter().any(|mensaje| mensaje.starts_with("Total")));
+    }
+
+    #[test]
+    #[serial]
+    fn test05_clone_con_deepen_responde_el_borde_y_manda_solo_esa_historia() {
+        let commit = "4163eb28ec61fd1d0c17cf9b77f4c17e1e338b0b";
+        let padre = "8f63722a025d936c53304d40ba3197ffebf194d1";
+        let test_dir = env!("CARGO_MANIFEST_DIR").to_string() + "/server_test_dir/test03/.gir/";
+
+        let mock: MockTcpStream = MockTcpStream {
+            lectura_data: Vec::new(),
+        };
+        let logger = Arc::new(Logger::new(PathBuf::from("tmp/upload_pack_test05")).unwrap());
+        let mut comunicacion = Comunicacion::new_para_testing(mock, logger.clone());
+        comunicacion
+            .enviar_pedidos_y_lineas_al_servidor_pkt(
+                vec![commit.to_string()],
+                "shallow".to_string(),
+                &["deepen 1".to_string()],
+            )
+            .unwrap();
+        comunicacion
+            .enviar(&utils::strings::obtener_linea_con_largo_hex("done\n"))
+            .unwrap();
+
+        upload_pack(
+            test_dir,
+            &mut comunicacion,
+            &vec![utils::strings::obtener_linea_con_largo_hex(&format!(
+                "{} refs/heads/master\n",
+                commit
+            ))],
+            logger.clone(),
+        )
+        .unwrap();
+        assert_eq!(
+            comunicacion.obtener_lineas_v2().unwrap(),
+            vec![format!("shallow {}\n", commit)]
+        );
+        assert_eq!(comunicacion.obtener_lineas().unwrap(), vec!["NAK\n"]);
+        let packfile = comunicacion.obtener_packfile().unwrap();
+        let dir_recibido = "tmp/upload_pack_test05_objetos/";
+        let _ = utils::io::rm_directorio(dir_recibido);
+        packfile::Packfile::leer_packfile_y_escribir(&packfile, dir_recibido.to_string()).unwrap();
+        assert!(utils::compresion::existe_objeto(commit, dir_recibido));
+        assert!(!utils::compresion::existe_objeto(padre, dir_recibido));
+    }
+
+    #[test]
+    #[serial]
+    fn test06_fetch_con_multi_ack_detailed_avisa_ready_y_no_manda_lo_comun() {
+        let commit = "4163eb28ec61fd1d0c17cf9b77f4c17e1e338b0b";
+        let padre = "8f63722a025d936c53304d40ba3197ffebf194d1";
+        let desconocido = "1".repeat(40);
+        let test_dir = env!("CARGO_MANIFEST_DIR").to_string() + "/server_test_dir/test03/.gir/";
+
+        let mock: MockTcpStream = MockTcpStream {
+            lectura_data: Vec::new(),
+        };
+        let logger = Arc::new(Logger::new(PathBuf::from("tmp/upload_pack_test06")).unwrap());
+        let mut comunicacion = Comunicacion::new_para_testing(mock, logger.clone());
+        comunicacion
+            .enviar_pedidos_al_servidor_pkt(
+                vec![commit.to_string()],
+                "multi_ack_detailed".to_string(),
+            )
+            .unwrap();
+        comunicacion
+            .enviar_lo_que_tengo_al_servidor_pkt(&vec![padre.to_string(), desconocido.clone()])
+            .unwrap();
+        comunicacion
+            .enviar(&utils::strings::obtener_linea_con_largo_hex("done\n"))
+            .unwrap();
+
+        upload_pack(
+            test_dir,
+            &mut comunicacion,
+            &vec![utils::strings::obtener_linea_con_largo_hex(&format!(
+                "{} refs/heads/master\n",
+                commit
+            ))],
+            logger.clone(),
+        )
+        .unwrap();
+        let mut respuesta = Vec::new();
+        for _ in 0..4 {
+            respuesta.extend(comunicacion.obtener_lineas().unwrap());
+        }
+        assert_eq!(
+            respuesta,
+            vec![
+                format!("ACK {} common\n", padre),
+                format!("ACK {} ready\n", desconocido),
+                "NAK\n".to_string(),
+                format!("ACK {}\n", padre),
+            ]
+        );
+        let packfile = comunicacion.obtener_packfile().unwrap();
+        let dir_recibido = "tmp/upload_pack_test06_objetos/";
+        let _ = utils::io::rm_directorio(dir_recibido);
+        packfile::Packfile::leer_packfile_y_escribir(&packfile, dir_recibido.to_string()).unwrap();
+        assert!(utils::compresion::existe_objeto(commit, dir_recibido));
+        assert!(!utils::compresion::existe_objeto(padre, dir_recibido));
+    }
+
+    #[test]
+    fn test07_sin_wants_se_responde_un_error_en_lugar_de_entrar_en_panico() {
+        let test_dir = env!("CARGO_MANIFEST_DIR").to_string() + "/server_test_dir/test03/.gir/";
+        let mock: MockTcpStream = MockTcpStream {
+            lectura_data: Vec::new(),
+        };
+        let logger = Arc::new(Logger::new(PathBuf::from("tmp/upload_pack_test07")).unwrap());
+        let mut comunicacion = Comunicacion::new_para_testing(mock, logger.clone());
+        comunicacion
+            .enviar(&utils::strings::obtener_linea_con_largo_hex("deepen 1\n"))
+            .unwrap();
+        comunicacion.enviar("0000").unwrap();
+
+        let resultado = upload_pack(test_dir, &mut comunicacion, &Vec::new(), logger.clone());
+
+        assert!(resultado.unwrap_err().contains("no se recibio ningun want"));
+        assert_eq!(
+            comunicacion.obtener_lineas().unwrap(),
+            vec!["ERR no se recibio ningun want\n".to_string()]
+        );
+    }
 }
@@ -0,0 +1,420 @@
+use crate::servidor::shallow::{self, PedidoShallow};
+use crate::servidor::upload_pack::{
+    commits_en_comun_alcanzan, enviar_packfile_por_side_band, obtener_packfile_de_historia,
+    obtener_packfile_para,
+};
+use crate::tipos_de_dato::comunicacion::Comunicacion;
+use crate::tipos_de_dato::logger::Logger;
+use crate::tipos_de_dato::objetos::tag::TagObj;
+use crate::utils::{compresion, io, objects, referencia, strings};
+use std::collections::HashSet;
+use std::io::{Read, Write};
+use std::path::PathBuf;
+use std::sync::Arc;
+
+/// Atiende los comandos del protocolo v2 (`ls-refs` y `fetch`) que manda el cliente despues del
+/// anuncio de capacidades, hasta que el cliente termina la conexion.
+/// # Argumentos
+/// * `dir` - Direccion del repositorio (el directorio .gir)
+/// * `comunicacion` - Comunicacion con el cliente
+pub fn upload_pack_v2<T>(
+    dir: String,
+    comunicacion: &mut Comunicacion<T>,
+    logger: Arc<Logger>,
+) -> Result<(), String>
+where
+    T: Read + Write,
+{
+    logger.log("Iniciando upload pack v2");
+    while let Some((comando, argumentos)) = comunicacion.obtener_comando_v2()? {
+        logger.log(&format!("Comando v2 recibido: {}", comando));
+        match comando.as_str() {
+            "ls-refs" => ls_refs(&dir, &argumentos, comunicacion)?,
+            "fetch" => fetch(&dir, &argumentos, comunicacion)?,
+            _ => {
+                enviar_error(comunicacion, &format!("Comando desconocido: {}", comando))?;
+                return Err(format!("Comando desconocido: {}", comando));
+            }
+        }
+    }
+    logger.log("Upload pack v2 ejecutado con exito");
+    Ok(())
+}
+
+fn enviar_error<T: Read + Write>(
+    comunicacion: &mut Comunicacion<T>,
+    mensaje: &str,
+) -> Result<(), String> {
+    comunicacion.enviar(&strings::obtener_linea_con_largo_hex(&format!(
+        "ERR {}\n",
+        mensaje
+    )))
+}
+
+// Responde el comando ls-refs: una linea por referencia con el formato
+// 'hash' 'nombre'[ symref-target:'destino'][ peeled:'hash'], terminando con un flush-pkt.
+// Si el cliente manda algun ref-prefix solo se anuncian las referencias que empiezan con alguno.
+fn ls_refs<T: Read + Write>(
+    dir: &str,
+    argumentos: &[String],
+    comunicacion: &mut Comunicacion<T>,
+) -> Result<(), String> {
+    let symrefs = argumentos.iter().any(|argumento| argumento == "symrefs");
+    let peel = argumentos.iter().any(|argumento| argumento == "peel");
+    let prefijos: Vec<&str> = argumentos
+        .iter()
+        .filter_map(|argumento| argumento.strip_prefix("ref-prefix "))
+        .collect();
+
+    let dir_objetos = dir.to_string() + "objects/";
+    for (nombre, hash, destino) in obtener_referencias(dir)? {
+        if !prefijos.is_empty() && !prefijos.iter().any(|prefijo| nombre.starts_with(prefijo)) {
+            continue;
+        }
+        let mut linea = format!("{} {}", hash, nombre);
+        if let (true, Some(destino)) = (symrefs, destino) {
+            linea.push_str(&format!(" symref-target:{}", destino));
+        }
+        if peel && TagObj::es_tag(&hash, &dir_objetos) {
+            linea.push_str(&format!(" peeled:{}", TagObj::pelar(&hash, &dir_objetos)?));
+        }
+        linea.push('\n');
+        comunicacion.enviar(&strings::obtener_linea_con_largo_hex(&linea))?;
+    }
+    comunicacion.enviar_flush_pkt()
+}
+
+// Devuelve HEAD (con la rama a la que apunta), las ramas y los tags del repositorio como
+// (nombre, hash, destino del symref). Si HEAD apunta a una rama sin commits no se incluye.
+fn obtener_referencias(dir: &str) -> Result<Vec<(String, String, Option<String>)>, String> {
+    let dir = PathBuf::from(dir);
+    let mut referencias = Vec::new();
+
+    if let Ok(head) = io::leer_a_string(dir.join("HEAD")) {
+        if let Some(destino) = head.trim().strip_prefix("ref: ") {
+            if let Ok(hash) = io::leer_a_string(dir.join(destino)) {
+                if !hash.trim().is_empty() {
+                    referencias.push((
+                        "HEAD".to_string(),
+                        hash.trim().to_string(),
+                        Some(destino.to_string()),
+                    ));
+                }
+            }
+        }
+    }
+    for carpeta in ["refs/heads", "refs/tags"] {
+        let mut refs = referencia::obtener_refs_y_hashes(&dir.join(carpeta), &dir)?;
+        refs.sort();
+        referencias.extend(refs.into_iter().map(|(nombre, hash)| (nombre, hash, None)));
+    }
+    Ok(referencias)
+}
+
+// Responde el comando fetch. Si el cliente todavia no mando done se responde la seccion
+// acknowledgments con los haves que el servidor tiene (o NAK), y si con esos alcanza se sigue
+// como si hubiera mandado done. Con done se manda la seccion packfile con lo alcanzable desde los
+// wants y no desde los haves en comun, precedida por la seccion shallow-info si el cliente pidio
+// una profundidad.
+fn fetch<T: Read + Write>(
+    dir: &str,
+    argumentos: &[String],
+    comunicacion: &mut Comunicacion<T>,
+) -> Result<(), String> {
+    let mut wants = Vec::new();
+    let mut haves = Vec::new();
+    let mut done = false;
+    let mut pedido_shallow = PedidoShallow::default();
+    for argumento in argumentos {
+        if pedido_shallow.agregar_linea(argumento)? {
+            continue;
+        }
+        if let Some(want) = argumento.strip_prefix("want ") {
+            wants.push(want.trim().to_string());
+        } else if let Some(have) = argumento.strip_prefix("have ") {
+            haves.push(have.trim().to_string());
+        } else if argumento == "done" {
+            done = true;
+        }
+        // el resto de los argumentos (ofs-delta, thin-pack, no-progress...) no cambian la respuesta
+    }
+
+    let dir_objetos = dir.to_string() + "objects/";
+    if wants.is_empty() {
+        enviar_error(comunicacion, "El comando fetch no tiene ningun want")?;
+        return Err("El comando fetch no tiene ningun want".to_string());
+    }
+    if let Some(want) = buscar_want_no_anunciado(dir, &wants)? {
+        let mensaje = format!(
+            "El objeto {} no es alcanzable desde ninguna referencia anunciada",
+            want
+        );
+        enviar_error(comunicacion, &mensaje)?;
+        return Err(mensaje);
+    }
+
+    let comunes: Vec<String> = haves
+        .into_iter()
+        .filter(|have| compresion::existe_objeto(have, &dir_objetos))
+        .collect();
+    if !done && !enviar_acknowledgments(&dir_objetos, &wants, &comunes, comunicacion)? {
+        return comunicacion.enviar_flush_pkt();
+    }
+
+    if !pedido_shallow.es_shallow() {
+        comunicacion.enviar(&strings::obtener_linea_con_largo_hex("packfile\n"))?;
+        return enviar_packfile_por_side_band(
+            comunicacion,
+            obtener_packfile_para(dir, &wants, &comunes),
+        );
+    }
+
+    let historia = shallow::recortar_historia(&dir_objetos, &wants, &pedido_shallow)?;
+    if pedido_shallow.pide_profundizar() {
+        comunicacion.enviar(&strings::obtener_linea_con_largo_hex("shallow-info\n"))?;
+        for linea in historia.lineas() {
+            comunicacion.enviar(&strings::obtener_linea_con_largo_hex(&linea))?;
+        }
+        comunicacion.enviar_delimitador_pkt()?;
+    }
+    comunicacion.enviar(&strings::obtener_linea_con_largo_hex("packfile\n"))?;
+    enviar_packfile_por_side_band(
+        comunicacion,
+        obtener_packfile_de_historia(dir, historia, &comunes),
+    )
+}
+
+// Devuelve el primer want que no es una de las referencias que anuncia ls-refs (o el commit al
+// que apunta un tag anotado) ni un commit de su historia, asi no se puede pedir cualquier objeto
+// del repositorio. La historia solo se recorre si algun want no es una referencia.
+fn buscar_want_no_anunciado(dir: &str, wants: &[String]) -> Result<Option<String>, String> {
+    let dir_objetos = dir.to_string() + "objects/";
+    let mut anunciados = HashSet::new();
+    for (_, hash, _) in obtener_referencias(dir)? {
+        if TagObj::es_tag(&hash, &dir_objetos) {
+            anunciados.insert(TagObj::pelar(&hash, &dir_objetos)?);
+        }
+        anunciados.insert(hash);
+    }
+    if wants.iter().all(|want| anunciados.contains(want)) {
+        return Ok(None);
+    }
+
+    let referencias: Vec<String> = anunciados.iter().cloned().collect();
+    let mut historia = HashSet::new();
+    objects::agregar_commits_alcanzables(&referencias, &dir_objetos, &mut historia);
+    Ok(wants
+        .iter()
+        .find(|want| !anunciados.contains(*want) && !historia.contains(*want))
+        .cloned())
+}
+
+// Envia la seccion acknowledgments con un 'ACK hash' por cada commit en comun (o NAK si no hay
+// ninguno). Si los commits en comun ya alcanzan para armar el packfile se agrega 'ready' y la
+// seccion termina con un delim-pkt, porque el packfile se manda en la misma respuesta. Devuelve si
+// se mando ready.
+fn enviar_acknowledgments<T: Read + Write>(
+    dir_objetos: &str,
+    wants: &[String],
+    comunes: &[String],
+    comunicacion: &mut Comunicacion<T>,
+) -> Result<bool, String> {
+    let mut lineas = vec!["acknowledgments\n".to_string()];
+    lineas.extend(comunes.iter().map(|comun| format!("ACK {}\n", comun)));
+    if comunes.is_empty() {
+        lineas.push("NAK\n".to_string());
+    }
+    let listo = !comunes.is_empty()
+        && commits_en_comun_alcanzan(wants, comunes, &mut HashSet::new(), dir_objetos)?;
+    if listo {
+        lineas.push("ready\n".to_string());
+    }
+    for linea in lineas {
+        comunicacion.enviar(&strings::obtener_linea_con_largo_hex(&linea))?;
+    }
+    if listo {
+        comunicacion.enviar_delimitador_pkt()?;
+    }
+    Ok(listo)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::utils::testing::MockTcpStream;
+    use serial_test::serial;
+
+    const COMMIT: &str = "4163eb28ec61fd1d0c17cf9b77f4c17e1e338b0b";
+    const OBJETO: &str = "8f63722a025d936c53304d40ba3197ffebf194d1";
+
+    fn dir_test() -> String {
+        env!("CARGO_MANIFEST_DIR").to_string() + "/server_test_dir/test03/.gir/"
+    }
+
+    // Corre upload pack v2 con los comandos dados y devuelve una comunicacion para leer la respuesta.
+    fn correr_comandos(
+        comandos: Vec<(&str, Vec<String>)>,
+        logger: Arc<Logger>,
+    ) -> Comunicacion<MockTcpStream> {
+        let mut pedido = MockTcpStream {
+            lectura_data: Vec::new(),
+            escritura_data: Vec::new(),
+        };
+        let mut comunicacion_cliente = Comunicacion::new_para_testing(&mut pedido, logger.clone());
+        for (comando, argumentos) in comandos {
+            comunicacion_cliente
+                .enviar_comando_v2(comando, &argumentos)
+                .unwrap();
+        }
+
+        let mut flujo = MockTcpStream {
+            lectura_data: pedido.escritura_data,
+            escritura_data: Vec::new(),
+        };
+        let mut comunicacion_servidor = Comunicacion::new_para_testing(&mut flujo, logger.clone());
+        upload_pack_v2(dir_test(), &mut comunicacion_servidor, logger.clone()).unwrap();
+
+        Comunicacion::new_para_testing(
+            MockTcpStream {
+                lectura_data: flujo.escritura_data,
+                escritura_data: Vec::new(),
+            },
+            logger,
+        )
+    }
+
+    #[test]
+    #[serial]
+    fn test01_ls_refs_anuncia_head_con_su_symref() {
+        let logger = Arc::new(Logger::new(PathBuf::from("tmp/upload_pack_v2_test01")).unwrap());
+        let argumentos = vec!["symrefs".to_string()];
+
+        let mut respuesta = correr_comandos(vec![("ls-refs", argumentos)], logger);
+
+        assert_eq!(
+            respuesta.obtener_lineas_v2().unwrap(),
+            vec![
+                format!("{} HEAD symref-target:refs/heads/master\n", COMMIT),
+                format!("{} refs/heads/master\n", COMMIT),
+            ]
+        );
+    }
+
+    #[test]
+    #[serial]
+    fn test02_ls_refs_filtra_por_prefijo() {
+        let logger = Arc::new(Logger::new(PathBuf::from("tmp/upload_pack_v2_test02")).unwrap());
+        let solo_heads = vec!["ref-prefix refs/heads/".to_string()];
+        let solo_tags = vec!["ref-prefix refs/tags/".to_string()];
+
+        let mut respuesta = correr_comandos(
+            vec![("ls-refs", solo_heads), ("ls-refs", solo_tags)],
+            logger,
+        );
+
+        assert_eq!(
+            respuesta.obtener_lineas_v2().unwrap(),
+            vec![format!("{} refs/heads/master\n", COMMIT)]
+        );
+        assert!(respuesta.obtener_lineas_v2().unwrap().is_empty());
+    }
+
+    #[test]
+    #[serial]
+    fn test03_fetch_sin_done_responde_la_seccion_acknowledgments() {
+        let logger = Arc::new(Logger::new(PathBuf::from("tmp/upload_pack_v2_test03")).unwrap());
+        // el tree de OBJETO es un objeto en comun, pero no un commit de la historia de COMMIT, asi
+        // que no alcanza para mandar COMMIT
+        let tree = crate::utils::objects::leer_commit(OBJETO, &(dir_test() + "objects/"))
+            .unwrap()
+            .tree;
+        let argumentos = vec![
+            format!("want {}", COMMIT),
+            format!("have {}", tree),
+            format!("have {}", "1".repeat(40)),
+        ];
+
+        let mut respuesta = correr_comandos(vec![("fetch", argumentos)], logger);
+
+        assert_eq!(
+            respuesta.obtener_lineas_v2().unwrap(),
+            vec!["acknowledgments\n".to_string(), format!("ACK {}\n", tree)]
+        );
+    }
+
+    #[test]
+    #[serial]
+    fn test04_fetch_con_done_responde_la_seccion_packfile() {
+        let logger = Arc::new(Logger::new(PathBuf::from("tmp/upload_pack_v2_test04")).unwrap());
+        let argumentos = vec![
+            "ofs-delta".to_string(),
+            format!("want {}", OBJETO),
+            "done".to_string(),
+        ];
+
+        let mut respuesta = correr_comandos(vec![("fetch", argumentos)], logger);
+
+        let packfile = respuesta.obtener_seccion_packfile().unwrap();
+        assert_eq!(&packfile[..4], b"PACK");
+        assert!(respuesta
+            .obtener_progreso()
+            .iter()
+            .any(|mensaje| mensaje.starts_with("Total")));
+    }
+
+    #[test]
+    #[serial]
+    fn test05_fetch_con_haves_suficientes_responde_ready_y_el_packfile_sin_lo_comun() {
+        let logger = Arc::new(Logger::new(PathBuf::from("tmp/upload_pack_v2_test05")).unwrap());
+        let argumentos = vec![format!("want {}", COMMIT), format!("have {}", OBJETO)];
+
+        let mut respuesta = correr_comandos(vec![("fetch", argumentos)], logger.clone());
+
+        assert_eq!(
+            respuesta.obtener_lineas_v2().unwrap(),
+            vec![
+                "acknowledgments\n".to_string(),
+                format!("ACK {}\n", OBJETO),
+                "ready\n".to_string()
+            ]
+        );
+        let packfile = respuesta.obtener_seccion_packfile().unwrap();
+        let dir_recibido = "tmp/upload_pack_v2_test05_objetos/";
+        let _ = io::rm_directorio(dir_recibido);
+        crate::tipos_de_dato::packfile::Packfile::leer_packfile_y_escribir(
+            &packfile,
+            dir_recibido.to_string(),
+        )
+        .unwrap();
+        assert!(compresion::existe_objeto(COMMIT, dir_recibido));
+        assert!(!compresion::existe_objeto(OBJETO, dir_recibido));
+    }
+
+    #[test]
+    #[serial]
+    fn test06_fetch_rechaza_wants_que_no_salen_de_las_referencias_anunciadas() {
+        let logger = Arc::new(Logger::new(PathBuf::from("tmp/upload_pack_v2_test06")).unwrap());
+        // el tree existe en el servidor, pero no es una referencia ni un commit de su historia
+        let tree = crate::utils::objects::leer_commit(COMMIT, &(dir_test() + "objects/"))
+            .unwrap()
+            .tree;
+        let argumentos = vec![format!("want {}", tree), "done".to_string()];
+        let mut flujo = MockTcpStream {
+            lectura_data: Vec::new(),
+            escritura_data: Vec::new(),
+        };
+        let mut comunicacion = Comunicacion::new_para_testing(&mut flujo, logger.clone());
+
+        let resultado = fetch(&dir_test(), &argumentos, &mut comunicacion);
+
+        assert!(resultado.unwrap_err().contains("no es alcanzable"));
+        let mut respuesta = Comunicacion::new_para_testing(
+            MockTcpStream {
+                lectura_data: flujo.escritura_data,
+                escritura_data: Vec::new(),
+            },
+            logger,
+        );
+        assert!(respuesta.obtener_lineas().unwrap()[0].starts_with("ERR "));
+    }
+}
@@ -3,9 +3,10 @@ use std::sync::Arc;
 use super::{
     comandos::{
         add::Add, branch::Branch, cat_file::CatFile, check_ignore::CheckIgnore, checkout::Checkout,
-        clone::Clone, commit::Commit, fetch::Fetch, hash_object::HashObject, init::Init, log::Log,
-        ls_files::LsFiles, ls_tree::LsTree, merge::Merge, pull::Pull, push::Push, rebase::Rebase,
-        remote::Remote, rm::Remove, show_ref::ShowRef, status::Status, tag::Tag, version::Version,
+        clone::Clone, commit::Commit, fetch::Fetch, fsck::Fsck, gc::Gc, hash_object::HashObject,
+        init::Init, log::Log, ls_files::LsFiles, ls_tree::LsTree, merge::Merge, pull::Pull,
+        push::Push, rebase::Rebase, remote::Remote, rm::Remove, show_ref::ShowRef, status::Status,
+        tag::Tag, version::Version,
     },
     logger::Logger,
 };
@@ -35,6 +36,8 @@ pub enum Comando {
     LsTree(LsTree),
     LsFiles(LsFiles),
     Rebase(Rebase),
+    Gc(Gc),
+    Fsck(Fsck),
     Unknown,
 }
 
@@ -72,6 +75,8 @@ impl Comando {
             "ls-files" => Comando::LsFiles(LsFiles::from(logger, &mut vector_args)?),
             "check-ignore" => Comando::CheckIgnore(CheckIgnore::from(vector_args, logger)?),
             "rebase" => Comando::Rebase(Rebase::from(vector_args, logger)?),
+            "gc" => Comando::Gc(Gc::from(vector_args, logger)?),
+            "fsck" => Comando::Fsck(Fsck::from(vector_args, logger)?),
             _ => Comando::Unknown,
         };
 
@@ -104,6 +109,8 @@ impl Comando {
             Comando::Rebase(ref mut rebase) => rebase.ejecutar(),
             Comando::LsFiles(ref mut ls_files) => ls_files.ejecutar(),
             Comando::CheckIgnore(ref mut check_ignore) => check_ignore.ejecutar(),
+            Comando::Gc(ref mut gc) => gc.ejecutar(),
+            Comando::Fsck(ref mut fsck) => fsck.ejecutar(),
             Comando::Unknown => Err("Comando desconocido".to_string()),
         }
     }
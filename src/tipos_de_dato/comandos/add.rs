@@ -1,8 +1,10 @@
 use std::{path::PathBuf, sync::Arc};
 
 use crate::{
-    tipos_de_dato::{comando::Ejecutar, logger::Logger, objeto::Objeto},
-    utils::index::{crear_index, escribir_index, leer_index, ObjetoIndex},
+    tipos_de_dato::{comando::Ejecutar, logger::Logger, objeto::Objeto, objetos::blob::Blob},
+    utils::index::{
+        crear_index, escribir_index, leer_index, CacheStat, MetadataIndex, ObjetoIndex,
+    },
 };
 
 use super::{check_ignore::CheckIgnore, status::obtener_arbol_del_commit_head};
@@ -14,23 +16,26 @@ pub struct Add {
     ubicaciones: Vec<PathBuf>,
     /// Objetos que ya estan en el index del repositorio.
     index: Vec<ObjetoIndex>,
+    /// Cache de stat para no volver a hashear los archivos que no cambiaron.
+    cache: CacheStat,
 }
 
 impl Add {
     /// Devuelve un vector con las ubicaciones de cada archivo dentro de las ubicaciones que se le pasaron.
     /// Si se le pasa un archivo, devuelve el path de ese archivo.
     /// Si se le pasa un directorio, devuelve el path de todos los archivos que se encuentran dentro de ese directorio.
+    /// Los links simbolicos se consideran hojas, aunque apunten a un directorio.
     pub fn obtener_ubicaciones_hoja(ubicaciones: Vec<PathBuf>) -> Result<Vec<PathBuf>, String> {
         let mut ubicaciones_hoja: Vec<PathBuf> = Vec::new();
         for ubicacion in ubicaciones {
-            if ubicacion.is_file() {
+            if ubicacion.is_file() || ubicacion.is_symlink() {
                 ubicaciones_hoja.push(ubicacion);
             } else if ubicacion.is_dir() {
                 let mut directorios = std::fs::read_dir(ubicacion)
                     .map_err(|_| "Error al obtener directorios hoja".to_string())?;
                 while let Some(Ok(directorio)) = directorios.next() {
                     let path = directorio.path();
-                    if path.is_file() {
+                    if path.is_file() || path.is_symlink() {
                         ubicaciones_hoja.push(path);
                     } else if path.is_dir() {
                         ubicaciones_hoja.append(&mut Self::obtener_ubicaciones_hoja(vec![path])?);
@@ -51,20 +56,26 @@ impl Add {
             logger,
             ubicaciones,
             index,
+            cache: CacheStat::leer(),
         })
     }
 
     /// Crea un objeto index a partir de una ubicacion.
+    /// Si la metadata del archivo no cambio desde la ultima vez que se hasheo, no lo vuelve a hashear.
     fn crear_objeto_index_from_ubicacion(
         ubicacion: PathBuf,
+        cache: &mut CacheStat,
         logger: Arc<Logger>,
     ) -> Result<ObjetoIndex, String> {
-        let nuevo_objeto = Objeto::from_directorio(ubicacion.clone(), None, logger.clone())?;
+        let metadata = MetadataIndex::de_archivo(&ubicacion);
+        let blob = Blob::from_directorio(ubicacion, Some(cache), logger)?;
 
         Ok(ObjetoIndex {
             merge: false,
             es_eliminado: false,
-            objeto: nuevo_objeto.clone(),
+            objeto: Objeto::Blob(blob),
+            metadata,
+            conflicto: None,
         })
     }
 
@@ -84,8 +95,15 @@ impl Add {
     /// Si el objeto contiene la misma version que en el commit anterior, no lo agrega.
     /// Si el objeto tiene modificaciones, lo agrega.
     fn aniadir_ubicacion_pedida_al_index(&mut self, ubicacion: PathBuf) -> Result<(), String> {
-        let nuevo_objeto_index =
-            Self::crear_objeto_index_from_ubicacion(ubicacion.clone(), self.logger.clone())?;
+        let ubicacion = match ubicacion.strip_prefix("./") {
+            Ok(ubicacion) => ubicacion.to_path_buf(),
+            Err(_) => ubicacion,
+        };
+        let nuevo_objeto_index = Self::crear_objeto_index_from_ubicacion(
+            ubicacion.clone(),
+            &mut self.cache,
+            self.logger.clone(),
+        )?;
 
         let indice = self.obtener_indice_objeto_index(ubicacion);
 
@@ -126,12 +144,13 @@ impl Ejecutar for Add {
                     .to_str()
                     .ok_or_else(|| "Path invalido".to_string())?,
             ));
-            if ubicacion.is_dir() {
+            if ubicacion.is_dir() && !ubicacion.is_symlink() {
                 Err("No se puede agregar un directorio")?;
             }
             self.aniadir_ubicacion_pedida_al_index(ubicacion)?;
         }
         escribir_index(self.logger.clone(), &mut self.index)?;
+        self.cache.guardar()?;
         Ok("".to_string())
     }
 }
@@ -148,7 +167,7 @@ mod tests {
             logger::Logger,
             objeto::Objeto,
         },
-        utils::io,
+        utils::{io, testing::leer_index_como_texto},
     };
 
     fn create_test_file() {
@@ -187,7 +206,7 @@ mod tests {
 
         assert_eq!(add.index.len(), 1);
 
-        let file = io::leer_a_string("./.gir/index").unwrap();
+        let file = leer_index_como_texto();
         assert_eq!(
             file,
             "+ 0 100644 bdf08de0f3095da5030fecd9bafc0b00c1aced7c test_file.txt\n"
@@ -220,7 +239,7 @@ mod tests {
             assert_eq!(blob.hash, "678e12dc5c03a7cf6e9f64e688868962ab5d8b65");
         }
 
-        let file = io::leer_a_string("./.gir/index").unwrap();
+        let file = leer_index_como_texto();
         assert_eq!(
             file,
             "+ 0 100644 678e12dc5c03a7cf6e9f64e688868962ab5d8b65 test_file.txt\n"
@@ -238,7 +257,7 @@ mod tests {
         let mut add = Add::from(vec![path], logger.clone()).unwrap();
         add.ejecutar().unwrap();
 
-        let file = io::leer_a_string("./.gir/index").unwrap();
+        let file = leer_index_como_texto();
 
         assert_eq!(
             file,
@@ -273,7 +292,7 @@ mod tests {
             assert_eq!(blob.hash, "2b824e648965b94c6c6b3dd0702feb91f699ed62");
         }
 
-        let file = io::leer_a_string("./.gir/index").unwrap();
+        let file = leer_index_como_texto();
 
         assert_eq!(
             file,
@@ -291,7 +310,7 @@ mod tests {
         let mut add = Add::from(vec![path], logger.clone()).unwrap();
         add.ejecutar().unwrap();
 
-        let file = io::leer_a_string("./.gir/index").unwrap();
+        let file = leer_index_como_texto();
 
         assert_eq!(
             file,
@@ -323,7 +342,7 @@ mod tests {
             assert_eq!(blob.hash, "678e12dc5c03a7cf6e9f64e688868962ab5d8b65");
         }
 
-        let file = io::leer_a_string("./.gir/index").unwrap();
+        let file = leer_index_como_texto();
 
         assert_eq!(
             file,
@@ -1,6 +1,9 @@
 use crate::{
     tipos_de_dato::{
-        comando::Ejecutar, logger::Logger, objeto::flag_es_un_objeto_, objetos::tree::Tree,
+        comando::Ejecutar,
+        logger::Logger,
+        objeto::flag_es_un_objeto_,
+        objetos::{blob::ModoBlob, tree::Tree},
         visualizaciones::Visualizaciones,
     },
     utils::compresion::descomprimir_objeto,
@@ -28,7 +31,7 @@ pub fn obtener_tipo_objeto_de(hash_objeto: &str, dir: &str) -> Result<String, St
 
 /// Obtiene el contenido de un objeto ubicado en cierto directorio a partir de su hash.
 /// En caso de no encontrar el objeto devuelve error.
-fn obtener_contenido_objeto_de(hash: &str, dir: &str) -> Result<(String, String), String> {
+pub fn obtener_contenido_objeto_de(hash: &str, dir: &str) -> Result<(String, String), String> {
     // sacar el hardcode de esto
     let objeto = descomprimir_objeto(hash, dir)?;
     match objeto.split_once('\0') {
@@ -40,12 +43,7 @@ fn obtener_contenido_objeto_de(hash: &str, dir: &str) -> Result<(String, String)
 /// Obtiene el contenido de un objeto ubicado en el directorio de objetos del .gir a partir de su hash.
 /// En caso de no encontrar el objeto devuelve error.
 pub fn obtener_contenido_objeto(hash: &str) -> Result<(String, String), String> {
-    // sacar el hardcode de esto
-    let objeto = descomprimir_objeto(hash, ".gir/objects/")?;
-    match objeto.split_once('\0') {
-        Some((header, contenido)) => Ok((header.to_string(), contenido.to_string())),
-        None => Err("Objeto invalido".to_string()),
-    }
+    obtener_contenido_objeto_de(hash, ".gir/objects/")
 }
 
 /// Obtiene el tipo de objeto a partir de su header.
@@ -61,13 +59,13 @@ pub fn conseguir_tipo_objeto(header: &str) -> Result<String, String> {
 
 /// Obtiene el contenido de un objeto en formato pretty print.
 /// El contenido del objeto se muestra en formato pretty print dependiendo de su tipo.
-/// En caso de ser un blob o un commit devuelve el contenido sin modificar.
+/// En caso de ser un blob, un commit o un tag devuelve el contenido sin modificar.
 /// En caso de ser un tree devuelve el contenido formatteado a pretty print.
 /// En caso de no ser un objeto valido devuelve error.
 pub fn conseguir_contenido_pretty(header: &str, contenido: &str) -> Result<String, String> {
     let tipo = conseguir_tipo_objeto(header)?;
     match tipo.as_str() {
-        "blob" | "commit" => Ok(contenido.to_string()),
+        "blob" | "commit" | "tag" => Ok(contenido.to_string()),
         "tree" => {
             let mut pretty_print = String::new();
             let contenido_parseado = Tree::rearmar_contenido_descomprimido(contenido)?;
@@ -75,9 +73,9 @@ pub fn conseguir_contenido_pretty(header: &str, contenido: &str) -> Result<Strin
             for linea in lineas {
                 let atributos_objeto = linea.split(' ').collect::<Vec<&str>>();
                 let mut modo = atributos_objeto[0].to_string();
-                let tipo = match modo.as_str() {
-                    "100644" => "blob".to_string(),
-                    "40000" => "tree".to_string(),
+                let tipo = match (modo.as_str(), ModoBlob::desde_str(&modo)) {
+                    ("40000", _) => "tree".to_string(),
+                    (_, Some(modo_blob)) => modo_blob.tipo_objeto().to_string(),
                     _ => return Err("Objeto invalido".to_string()),
                 };
                 if modo == "40000" {
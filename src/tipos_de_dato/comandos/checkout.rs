@@ -5,12 +5,13 @@ use crate::{
         comando::Ejecutar, comandos::branch::Branch, config::Config, info_ramas::RamasInfo,
         logger::Logger, objeto::Objeto, objetos::tree::Tree, tipo_de_rama::TipoRama,
     },
-    utils::{self, io},
+    utils::{self, hooks, io},
 };
 
 use super::{show_ref::ShowRef, write_tree::conseguir_arbol_en_directorio};
 
 const PATH_HEAD: &str = "./.gir/HEAD";
+const HOOK_POST_CHECKOUT: &str = "post-checkout";
 
 pub struct Checkout {
     /// Si es true, se crea una nueva rama.
@@ -111,6 +112,14 @@ impl Checkout {
         Err(format!("Fallo: No existe la rama {}", self.rama_a_cambiar))
     }
 
+    /// Devuelve el commit al que apunta HEAD, o el hash nulo si la rama actual no tiene commits.
+    fn obtener_commit_head() -> String {
+        match utils::ramas::obtener_hash_commit_asociado_rama_actual() {
+            Ok(commit) if !commit.is_empty() => commit,
+            _ => "0".repeat(40),
+        }
+    }
+
     /// Devuelve el nombre de la rama actual.
     /// O sea, la rama a la que apunta el archivo HEAD.
     fn conseguir_rama_actual(contenidio_head: &str) -> Result<String, String> {
@@ -243,24 +252,36 @@ impl Ejecutar for Checkout {
     /// Ejecuta el comando checkout en su totalidad.
     /// Si se crea una nueva rama, se crea y se cambia a ella.
     /// Si se cambia de rama, se cambia y se actualiza el contenido.
+    /// Al terminar corre el hook post-checkout con el commit de HEAD anterior y el nuevo. Si el
+    /// hook falla el checkout igual queda hecho y su salida se agrega como advertencia.
     fn ejecutar(&mut self) -> Result<String, String> {
         self.comprobar_que_no_haya_contenido_index()?;
+        let head_viejo = Self::obtener_commit_head();
 
-        if self.crear_rama {
+        let mut mensaje = if self.crear_rama {
             self.crear_rama()?;
             self.cambiar_rama()?;
-            return Ok(format!("Cambiado a nueva rama {}", self.rama_a_cambiar));
-        };
-
-        if !self.crear_rama {
+            format!("Cambiado a nueva rama {}", self.rama_a_cambiar)
+        } else {
             let tree_viejo = Self::obtener_arbol_commit_actual(self.logger.clone())?;
             self.cambiar_rama()?;
             let tree_futuro = Self::obtener_arbol_commit_actual(self.logger.clone())?;
             let objetos_a_eliminar = Self::obtener_objetos_eliminados(&tree_viejo, &tree_futuro);
             self.eliminar_objetos(&objetos_a_eliminar)?;
             tree_futuro.escribir_en_directorio()?;
+            format!("Cambiado a rama {}", self.rama_a_cambiar)
         };
-        Ok(format!("Cambiado a rama {}", self.rama_a_cambiar))
+
+        // el ultimo argumento indica que se cambio de rama y no se sacaron archivos del index
+        let head_nuevo = Self::obtener_commit_head();
+        let argumentos = [head_viejo.as_str(), &head_nuevo, "1"];
+        if let Some(advertencia) =
+            hooks::ejecutar_hook_local_posterior(HOOK_POST_CHECKOUT, &argumentos)
+        {
+            self.logger.log(&advertencia);
+            mensaje = format!("{}\nAdvertencia: {}", mensaje, advertencia);
+        }
+        Ok(mensaje)
     }
 }
 
@@ -275,11 +296,14 @@ mod tests {
             comandos::branch::Branch,
             logger::Logger,
             objeto::Objeto,
-            objetos::{blob::Blob, tree::Tree},
+            objetos::{
+                blob::{Blob, ModoBlob},
+                tree::Tree,
+            },
         },
         utils::{
             io,
-            testing::{addear_archivos_y_comittear, limpiar_archivo_gir},
+            testing::{addear_archivos_y_comittear, escribir_hook, limpiar_archivo_gir},
         },
     };
 
@@ -295,12 +319,14 @@ mod tests {
     fn tree_con_un_tree_y_un_objeto(logger: Arc<Logger>) -> Tree {
         let objeto_nieto = Objeto::Blob(Blob {
             hash: "hash_nieto".to_string(),
+            modo: ModoBlob::Normal,
             ubicacion: PathBuf::from("./tree_hijo/nieto"),
             logger: logger.clone(),
             nombre: "nieto".to_string(),
         });
         let objeto_hijo = Objeto::Blob(Blob {
             hash: "hash_hijo".to_string(),
+            modo: ModoBlob::Normal,
             ubicacion: PathBuf::from("./hijo"),
             logger: logger.clone(),
             nombre: "hijo".to_string(),
@@ -322,6 +348,7 @@ mod tests {
     fn tree_con_un_objeto(logger: Arc<Logger>) -> Tree {
         let objeto_hijo = Objeto::Blob(Blob {
             hash: "hash_hijo".to_string(),
+            modo: ModoBlob::Normal,
             ubicacion: PathBuf::from("./hijo"),
             logger: logger.clone(),
             nombre: "hijo".to_string(),
@@ -469,4 +496,42 @@ mod tests {
         );
         assert!(PathBuf::from("test_file.txt").exists());
     }
+
+    #[test]
+    #[serial]
+    fn test07_post_checkout_recibe_los_heads_y_si_falla_advierte_su_salida() {
+        let logger = Arc::new(Logger::new(PathBuf::from("tmp/checkout_test07")).unwrap());
+        limpiar_archivo_gir(logger.clone());
+        addear_archivos_y_comittear(
+            vec!["test_dir/objetos/archivo.txt".to_string()],
+            logger.clone(),
+        );
+        let commit = io::leer_a_string(".gir/refs/heads/master").unwrap();
+        escribir_hook(
+            ".gir/hooks",
+            "post-checkout",
+            "#!/bin/sh\necho \"$1 $2 $3\" > .gir/post-checkout.txt\necho no se puede\nexit 1\n",
+        );
+
+        let resultado = Checkout::from(
+            vec!["-b".to_string(), "una_rama".to_string()],
+            logger.clone(),
+        )
+        .unwrap()
+        .ejecutar();
+
+        assert_eq!(
+            resultado.unwrap(),
+            "Cambiado a nueva rama una_rama\nAdvertencia: El hook post-checkout fallo\nno se puede"
+        );
+        assert_eq!(
+            io::leer_a_string(".gir/post-checkout.txt").unwrap(),
+            format!("{} {} 1\n", commit, commit)
+        );
+        // el hook no deshace el checkout
+        assert_eq!(
+            std::fs::read_to_string(".gir/HEAD").unwrap(),
+            "ref: refs/heads/una_rama"
+        );
+    }
 }
@@ -14,11 +14,16 @@ use super::set_upstream::SetUpstream;
 use super::write_tree;
 
 const UBICACION_RAMA_MASTER: &str = "./.gir/refs/heads/master";
-const GIR_CLONE: &str = "gir clone <ip:puerto/repositorio/>";
+const GIR_CLONE: &str =
+    "gir clone [--depth <n>] [--shallow-since <fecha>] <ip:puerto/repositorio/>";
 pub struct Clone {
     logger: Arc<Logger>,
     url: String,
     clonar_en_dir_actual: bool,
+    /// Cantidad de commits a traer desde cada cabeza de rama, si se clona con `--depth`.
+    profundidad: Option<u32>,
+    /// Solo se traen los commits posteriores a esta fecha, si se clona con `--shallow-since`.
+    desde: Option<i64>,
 }
 
 impl Clone {
@@ -28,6 +33,7 @@ impl Clone {
         logger: Arc<Logger>,
         clonar_en_dir_actual: bool,
     ) -> Result<Clone, String> {
+        let (profundidad, desde) = Fetch::parsear_flags_shallow(args)?;
         Self::verificar_argumentos(args)?;
 
         let url = args.remove(0);
@@ -38,6 +44,8 @@ impl Clone {
             logger,
             url,
             clonar_en_dir_actual,
+            profundidad,
+            desde,
         })
     }
 
@@ -114,14 +122,16 @@ impl Clone {
             utils::io::leer_a_string(path_master_remoto)
         }
     }
-    /// Crea el repositorio en el sistema
-    fn crear_repositorio(&mut self) -> Result<(), String> {
+    /// Crea el repositorio en el sistema. Devuelve los mensajes de progreso del remoto.
+    fn crear_repositorio(&mut self) -> Result<String, String> {
         Init::from(Vec::new(), self.logger.clone())?.ejecutar()?;
 
         let remote_args = &mut vec!["add".to_string(), "origin".to_string(), self.url.clone()];
         Remote::from(remote_args, self.logger.clone())?.ejecutar()?;
 
-        Fetch::new(vec!["origin".to_string()], self.logger.clone())?.ejecutar()?;
+        let mut fetch = Fetch::new(vec!["origin".to_string()], self.logger.clone())?;
+        fetch.limitar_historia(self.profundidad, self.desde);
+        fetch.ejecutar()?;
         let rama_predeterminada = Self::obtener_rama_predeterminada()?;
 
         let commit_head_remoto = self.obtener_head_remoto("origin", &rama_predeterminada)?;
@@ -135,7 +145,7 @@ impl Clone {
         )?
         .ejecutar()?;
 
-        Ok(())
+        Ok(fetch.mensajes_del_remoto())
     }
 }
 
@@ -158,10 +168,10 @@ impl Ejecutar for Clone {
             utils::io::cambiar_directorio("..")?;
         }
 
-        resutado?;
+        let mensajes_del_remoto = resutado?;
 
         let mensaje = "Clone ejecutado con exito".to_string();
         self.logger.log(&mensaje);
-        Ok(mensaje)
+        Ok(mensajes_del_remoto + &mensaje)
     }
 }
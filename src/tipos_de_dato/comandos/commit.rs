@@ -7,6 +7,7 @@ use crate::{
     utils::{
         compresion::comprimir_contenido,
         gir_config::{armar_config_con_mail_y_nombre, conseguir_nombre_y_mail_del_config},
+        hooks,
         index::limpiar_archivo_index,
         io, ramas,
     },
@@ -14,12 +15,19 @@ use crate::{
 
 use super::{hash_object::HashObject, merge::Merge, write_tree};
 
+const HOOK_PRE_COMMIT: &str = "pre-commit";
+const HOOK_COMMIT_MSG: &str = "commit-msg";
+const UBICACION_MENSAJE: &str = ".gir/COMMIT_EDITMSG";
+
 pub struct Commit {
     /// Logger para imprimir mensajes en el archivo log.
     pub logger: Arc<Logger>,
     /// Mensaje del commit.
     pub mensaje: String,
     pub rama_actual: String,
+    /// Indica si no se corren los hooks pre-commit y commit-msg, como cuando el servidor
+    /// mergea un pull request en su repositorio.
+    pub sin_hooks: bool,
 }
 
 /// Arma el timestamp del commit en formato unix.
@@ -28,7 +36,7 @@ pub struct Commit {
 /// Donde timestamp es la cantidad de segundos desde el 1 de enero de 1970 y offset es la diferencia
 /// en horas y minutos con respecto a UTC. Se asumio que el offset es -0300.
 /// Ejemplo: 1614550000 -0300
-fn armar_timestamp_commit() -> Result<String, String> {
+pub fn armar_timestamp_commit() -> Result<String, String> {
     let zona_horaria = match chrono::FixedOffset::west_opt(3 * 3600) {
         Some(zona_horaria) => zona_horaria,
         None => return Err("No se pudo obtener la zona horaria".to_string()),
@@ -77,16 +85,18 @@ impl Commit {
             mensaje,
             logger,
             rama_actual,
+            sin_hooks: false,
         })
     }
 
     /// Crea un commit a partir del mensaje en el archivo COMMIT_EDITMSG.
     pub fn from_merge(logger: Arc<Logger>, rama_actual: &str) -> Result<Commit, String> {
-        let mensaje = io::leer_a_string(path::Path::new(".gir/COMMIT_EDITMSG"))?;
+        let mensaje = io::leer_a_string(path::Path::new(UBICACION_MENSAJE))?;
         Ok(Commit {
             mensaje,
             logger,
             rama_actual: rama_actual.to_string(),
+            sin_hooks: false,
         })
     }
 
@@ -149,6 +159,20 @@ impl Commit {
         Ok(())
     }
 
+    /// Corre los hooks pre-commit y commit-msg. El hook commit-msg recibe la ubicacion de un
+    /// archivo con el mensaje y puede modificarlo, asi que despues se vuelve a leer.
+    /// Si algun hook falla devuelve un error y no se crea el commit.
+    fn correr_hooks(&mut self) -> Result<(), String> {
+        if self.sin_hooks {
+            return Ok(());
+        }
+        hooks::ejecutar_hook_local(HOOK_PRE_COMMIT, &[], "")?;
+        io::escribir_bytes(UBICACION_MENSAJE, &self.mensaje)?;
+        hooks::ejecutar_hook_local(HOOK_COMMIT_MSG, &[UBICACION_MENSAJE], "")?;
+        self.mensaje = io::leer_a_string(UBICACION_MENSAJE)?;
+        Ok(())
+    }
+
     /// Ejecuta el comando commit.
     /// Devuelve un mensaje indicando si se pudo crear el commit o no.
     fn ejecutar_wrapper(&self, contenido_total: &str) -> Result<(), String> {
@@ -170,6 +194,7 @@ impl Ejecutar for Commit {
     /// Utiliza un ejecutar wrapper para que en caso de error limpiar los archivos creados.
     fn ejecutar(&mut self) -> Result<String, String> {
         armar_config_con_mail_y_nombre()?;
+        self.correr_hooks()?;
         let (hash_arbol, contenido_total) = self.crear_contenido_commit()?;
         match self.ejecutar_wrapper(&contenido_total) {
             Ok(_) => (),
@@ -201,7 +226,7 @@ mod tests {
         utils::{
             compresion::{descomprimir_objeto, descomprimir_objeto_gir},
             io,
-            testing::{addear_archivos_y_comittear, limpiar_archivo_gir},
+            testing::{addear_archivos_y_comittear, escribir_hook, limpiar_archivo_gir},
         },
     };
 
@@ -330,4 +355,85 @@ mod tests {
         io::escribir_bytes("test_dir/muchos_objetos/archivo.txt", "mas contenido").unwrap();
         assert_eq!(hash_arbol_git, hash_arbol);
     }
+
+    #[test]
+    #[serial]
+    fn test06_pre_commit_puede_cortar_el_commit_y_commit_msg_cambiar_el_mensaje() {
+        let logger = Arc::new(Logger::new(PathBuf::from("tmp/commit_test06")).unwrap());
+        limpiar_archivo_gir(logger.clone());
+        Add::from(vec!["test_file.txt".to_string()], logger.clone())
+            .unwrap()
+            .ejecutar()
+            .unwrap();
+        escribir_hook(
+            ".gir/hooks",
+            "pre-commit",
+            "#!/bin/sh\necho falta correr los tests\nexit 1\n",
+        );
+
+        let rechazado = Commit::from(
+            &mut vec!["-m".to_string(), "mensaje".to_string()],
+            logger.clone(),
+        )
+        .unwrap()
+        .ejecutar();
+        let rama_despues_del_rechazo = io::leer_a_string(".gir/refs/heads/master");
+
+        io::rm_directorio(".gir/hooks/pre-commit").unwrap();
+        escribir_hook(
+            ".gir/hooks",
+            "commit-msg",
+            "#!/bin/sh\nprintf \"[gir] %s\" \"$(cat $1)\" > $1\n",
+        );
+        Commit::from(&mut vec!["-m".to_string(), "mensaje".to_string()], logger)
+            .unwrap()
+            .ejecutar()
+            .unwrap();
+        let commit = io::leer_a_string(".gir/refs/heads/master").unwrap();
+
+        assert_eq!(
+            rechazado.unwrap_err(),
+            "El hook pre-commit fallo\nfalta correr los tests"
+        );
+        assert!(rama_despues_del_rechazo.unwrap_or_default().is_empty());
+        assert!(descomprimir_objeto_gir(&commit)
+            .unwrap()
+            .ends_with("\n\n[gir] mensaje"));
+    }
+
+    #[test]
+    #[serial]
+    fn test07_sin_hooks_no_se_corren_pre_commit_ni_commit_msg() {
+        let logger = Arc::new(Logger::new(PathBuf::from("tmp/commit_test07")).unwrap());
+        limpiar_archivo_gir(logger.clone());
+        Add::from(vec!["test_file.txt".to_string()], logger.clone())
+            .unwrap()
+            .ejecutar()
+            .unwrap();
+        escribir_hook(
+            ".gir/hooks",
+            "pre-commit",
+            "#!/bin/sh
+exit 1
+",
+        );
+        escribir_hook(
+            ".gir/hooks",
+            "commit-msg",
+            "#!/bin/sh
+exit 1
+",
+        );
+
+        let mut commit =
+            Commit::from(&mut vec!["-m".to_string(), "mensaje".to_string()], logger).unwrap();
+        commit.sin_hooks = true;
+        commit.ejecutar().unwrap();
+
+        io::rm_directorio(".gir/hooks").unwrap();
+        let commit = io::leer_a_string(".gir/refs/heads/master").unwrap();
+        assert!(descomprimir_objeto_gir(&commit)
+            .unwrap()
+            .ends_with("\n\nmensaje"));
+    }
 }
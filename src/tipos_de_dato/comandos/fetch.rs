@@ -1,41 +1,181 @@
 use crate::tipos_de_dato::comando::Ejecutar;
 use crate::tipos_de_dato::comunicacion::Comunicacion;
 use crate::tipos_de_dato::config::Config;
+use crate::tipos_de_dato::flujo::Flujo;
 use crate::tipos_de_dato::logger::Logger;
+use crate::tipos_de_dato::negociacion::Negociacion;
 use crate::tipos_de_dato::packfile::Packfile;
 use crate::tipos_de_dato::referencia_commit::ReferenciaCommit;
-use crate::utils::{self, io, objects};
+use crate::utils::{self, fase_descubrimiento, io, shallow};
 use std::io::{Read, Write};
-use std::net::TcpStream;
 use std::path::PathBuf;
 use std::sync::Arc;
 
 const SE_ENVIO_ALGUN_PEDIDO: bool = true;
 const NO_SE_ENVIO_NINGUN_PEDIDO: bool = false;
-const GIR_FETCH: &str = "gir fetch <remoto>";
+const GIR_FETCH: &str = "gir fetch [--depth <n>] [--shallow-since <fecha>] <remoto>";
+const FLAG_DEPTH: &str = "--depth";
+const FLAG_SHALLOW_SINCE: &str = "--shallow-since";
+/// Version del protocolo que se le pide al servidor. Si no la soporta responde en version 1.
+const VERSION_PROTOCOLO: u32 = 2;
+const LINEA_VERSION_2: &str = "version 2\n";
+const SIDE_BAND_64K: &str = "side-band-64k";
+const SHALLOW: &str = "shallow";
+const DEEPEN_SINCE: &str = "deepen-since";
+const MULTI_ACK_DETAILED: &str = "multi_ack_detailed";
+/// Cantidad de haves que se mandan antes de esperar la respuesta del servidor.
+const TAMANIO_TANDA_HAVES: usize = 32;
 
 pub struct Fetch {
     remoto: String,
     capacidades_local: Vec<String>,
+    /// Si el servidor respondio en el protocolo v2. Se sabe despues de la fase de descubrimiento.
+    protocolo_v2: bool,
+    /// Si el packfile llega multiplexado por side-band-64k (siempre en el protocolo v2).
+    side_band: bool,
+    /// Mensajes de progreso que mando el servidor durante el fetch.
+    progreso: Vec<String>,
+    /// Cantidad de commits a traer desde cada cabeza de rama (`--depth`).
+    profundidad: Option<u32>,
+    /// Solo traer los commits posteriores a esta fecha, en segundos desde epoch (`--shallow-since`).
+    desde: Option<i64>,
+    /// Commits que el servidor indico que pasan a ser borde de la historia local y los que dejan
+    /// de serlo. Se guardan en el archivo shallow despues de recibir el packfile.
+    actualizacion_shallow: (Vec<String>, Vec<String>),
     logger: Arc<Logger>,
 }
 
 impl Fetch {
-    pub fn new(args: Vec<String>, logger: Arc<Logger>) -> Result<Fetch, String> {
+    pub fn new(mut args: Vec<String>, logger: Arc<Logger>) -> Result<Fetch, String> {
+        let (profundidad, desde) = Self::parsear_flags_shallow(&mut args)?;
         Self::verificar_argumentos(&args)?;
 
         let remoto = Self::obtener_remoto(args)?;
 
-        let capacidades_local = vec!["ofs-delta".to_string()];
+        let capacidades_local = vec![
+            "ofs-delta".to_string(),
+            SIDE_BAND_64K.to_string(),
+            SHALLOW.to_string(),
+            DEEPEN_SINCE.to_string(),
+            MULTI_ACK_DETAILED.to_string(),
+        ];
         //esto lo deberia tener la comunicacion creo yo
 
         Ok(Fetch {
             remoto,
             capacidades_local,
+            protocolo_v2: false,
+            side_band: false,
+            progreso: Vec::new(),
+            profundidad,
+            desde,
+            actualizacion_shallow: (Vec::new(), Vec::new()),
             logger,
         })
     }
 
+    /// Saca de los argumentos los flags `--depth <n>` y `--shallow-since <fecha>` (tambien con
+    /// la forma `--flag=valor`) y devuelve la profundidad y la fecha pedidas.
+    pub fn parsear_flags_shallow(
+        args: &mut Vec<String>,
+    ) -> Result<(Option<u32>, Option<i64>), String> {
+        let mut profundidad = None;
+        let mut desde = None;
+        while let Some(posicion) = args.iter().position(|arg| arg.starts_with("--")) {
+            let flag = args.remove(posicion);
+            let (nombre, valor) = match flag.split_once('=') {
+                Some((nombre, valor)) => (nombre.to_string(), valor.to_string()),
+                None if posicion < args.len() => (flag.clone(), args.remove(posicion)),
+                None => return Err(format!("Falta el valor de {}\n {}", flag, GIR_FETCH)),
+            };
+            match nombre.as_str() {
+                FLAG_DEPTH => match valor.parse::<u32>() {
+                    Ok(valor) if valor > 0 => profundidad = Some(valor),
+                    _ => return Err(format!("Profundidad invalida: {}", valor)),
+                },
+                FLAG_SHALLOW_SINCE => desde = Some(shallow::parsear_fecha(&valor)?),
+                _ => return Err(format!("Parametros desconocidos {}\n {}", flag, GIR_FETCH)),
+            }
+        }
+        Ok((profundidad, desde))
+    }
+
+    /// Limita la historia que se trae del servidor a `profundidad` commits desde cada cabeza de
+    /// rama y/o a los commits posteriores a `desde`.
+    pub fn limitar_historia(&mut self, profundidad: Option<u32>, desde: Option<i64>) {
+        self.profundidad = profundidad;
+        self.desde = desde;
+    }
+
+    /// Indica si se pidio cambiar la profundidad de la historia local.
+    fn pide_profundizar(&self) -> bool {
+        self.profundidad.is_some() || self.desde.is_some()
+    }
+
+    /// Lineas del pedido shallow que acompanian a los wants: los commits borde que ya hay en el
+    /// repositorio y la profundidad o fecha pedidas.
+    fn obtener_lineas_shallow(&self) -> Vec<String> {
+        let mut commits_shallow: Vec<String> =
+            shallow::obtener_commits_shallow().into_iter().collect();
+        commits_shallow.sort();
+        let mut lineas: Vec<String> = commits_shallow
+            .into_iter()
+            .map(|commit| format!("shallow {}", commit))
+            .collect();
+        if let Some(profundidad) = self.profundidad {
+            lineas.push(format!("deepen {}", profundidad));
+        }
+        if let Some(desde) = self.desde {
+            lineas.push(format!("deepen-since {}", desde));
+        }
+        lineas
+    }
+
+    /// Verifica que el servidor soporte lo que hace falta para el pedido shallow.
+    fn verificar_soporte_shallow(
+        &self,
+        lineas_shallow: &[String],
+        capacidades_servidor: &[String],
+    ) -> Result<(), String> {
+        let soporta = |capacidad: &str| {
+            capacidades_servidor.iter().any(|capacidad_servidor| {
+                let capacidad_servidor = capacidad_servidor.trim();
+                capacidad_servidor == capacidad
+                    || (self.protocolo_v2
+                        && capacidad_servidor
+                            .strip_prefix("fetch=")
+                            .is_some_and(|opciones| opciones.split(' ').any(|o| o == capacidad)))
+            })
+        };
+        if !lineas_shallow.is_empty() && !soporta(SHALLOW) {
+            return Err("El servidor no soporta repositorios shallow".to_string());
+        }
+        if self.desde.is_some() && !self.protocolo_v2 && !soporta(DEEPEN_SINCE) {
+            return Err("El servidor no soporta --shallow-since".to_string());
+        }
+        Ok(())
+    }
+
+    /// Interpreta las lineas `shallow 'hash'` y `unshallow 'hash'` con las que el servidor
+    /// responde el pedido de profundidad.
+    fn recibir_actualizacion_shallow(&mut self, lineas: Vec<String>) -> Result<(), String> {
+        for linea in lineas {
+            let linea = linea.trim_end();
+            if let Some(commit) = linea.strip_prefix("shallow ") {
+                self.actualizacion_shallow.0.push(commit.to_string());
+            } else if let Some(commit) = linea.strip_prefix("unshallow ") {
+                self.actualizacion_shallow.1.push(commit.to_string());
+            } else if let Some(mensaje) = linea.strip_prefix("ERR ") {
+                return Err(format!("Error, {}", mensaje));
+            }
+        }
+        self.logger.log(&format!(
+            "Actualizacion shallow recibida en fetch: {:?}",
+            self.actualizacion_shallow
+        ));
+        Ok(())
+    }
+
     fn verificar_argumentos(args: &Vec<String>) -> Result<(), String> {
         if args.len() > 1 {
             return Err(format!(
@@ -94,39 +234,170 @@ impl Fetch {
         Ok(())
     }
 
+    /// Descarta las referencias `refs/tags/<tag>^{}` anunciadas por el servidor. Solo indican el commit al
+    /// que apunta un tag anotado: no hay que pedirlas (el commit llega junto con el objeto tag) ni guardarlas.
+    fn descartar_tags_pelados(commits_y_tags_asosiados: ReferenciaCommit) -> ReferenciaCommit {
+        commits_y_tags_asosiados
+            .into_iter()
+            .filter(|(_, tag)| !tag.to_string_lossy().ends_with("^{}"))
+            .collect()
+    }
+
     fn fase_de_negociacion(
-        &self,
+        &mut self,
         capacidades_servidor: Vec<String>,
         commits_cabezas_y_dir_rama_asosiado: &Vec<(String, PathBuf)>,
         commit_y_tags_asosiado: &Vec<(String, PathBuf)>,
-        comunicacion: &mut Comunicacion<TcpStream>,
+        comunicacion: &mut Comunicacion<Flujo>,
     ) -> Result<bool, String> {
+        let lineas_shallow = self.obtener_lineas_shallow();
+        self.verificar_soporte_shallow(&lineas_shallow, &capacidades_servidor)?;
+        if self.protocolo_v2 {
+            return self.fase_de_negociacion_v2(
+                lineas_shallow,
+                commits_cabezas_y_dir_rama_asosiado,
+                commit_y_tags_asosiado,
+                comunicacion,
+            );
+        }
+        self.side_band = self.capacidades_local.contains(&SIDE_BAND_64K.to_string())
+            && capacidades_servidor.contains(&SIDE_BAND_64K.to_string());
+        let multi_ack = self
+            .capacidades_local
+            .contains(&MULTI_ACK_DETAILED.to_string())
+            && capacidades_servidor.contains(&MULTI_ACK_DETAILED.to_string());
         // no hay pedidos :D
         if !self.enviar_pedidos(
             &capacidades_servidor,
+            &lineas_shallow,
             commits_cabezas_y_dir_rama_asosiado,
             commit_y_tags_asosiado,
             comunicacion,
         )? {
             return Ok(NO_SE_ENVIO_NINGUN_PEDIDO);
         }
+        // si se pidio una profundidad el servidor responde primero como queda el borde de la historia
+        if self.pide_profundizar() {
+            let lineas = comunicacion.obtener_lineas_v2()?;
+            self.recibir_actualizacion_shallow(lineas)?;
+        }
 
-        self.enviar_lo_que_tengo(comunicacion)?;
+        self.enviar_lo_que_tengo(multi_ack, comunicacion)?;
 
         self.logger
             .log("Se completo correctamente la fase de negociacion en Fetch");
         Ok(SE_ENVIO_ALGUN_PEDIDO)
     }
 
-    fn recibir_packfile_y_guardar_objetos(
+    /// Negociacion del protocolo v2 con el comando fetch. Mientras queden commits locales se manda
+    /// un fetch sin done con los haves en comun hasta ahora y una nueva tanda, y de la seccion
+    /// acknowledgments se obtienen los que el servidor tiene. Si el servidor responde ready, el
+    /// packfile llega en esa misma respuesta; si no, al final se manda el fetch con los haves en
+    /// comun y done. Las lineas del pedido shallow van en todos los fetch, despues de los wants.
+    fn fase_de_negociacion_v2(
         &self,
-        comunicacion: &mut Comunicacion<TcpStream>,
+        lineas_shallow: Vec<String>,
+        commits_cabezas_y_dir_rama_asosiado: &Vec<(String, PathBuf)>,
+        commit_y_tags_asosiado: &Vec<(String, PathBuf)>,
+        comunicacion: &mut Comunicacion<Flujo>,
+    ) -> Result<bool, String> {
+        let pedidos =
+            self.obtener_pedidos(commits_cabezas_y_dir_rama_asosiado, commit_y_tags_asosiado)?;
+        if pedidos.is_empty() {
+            self.logger
+                .log("Se completo la fase de negociacion v2 en Fetch pero no se pidio nada");
+            return Ok(NO_SE_ENVIO_NINGUN_PEDIDO);
+        }
+
+        // en el protocolo v2 el packfile siempre viene multiplexado, side-band-64k no se pide
+        let mut argumentos: Vec<String> = self
+            .capacidades_local
+            .iter()
+            .filter(|capacidad| *capacidad != SIDE_BAND_64K && *capacidad != MULTI_ACK_DETAILED)
+            .cloned()
+            .collect();
+        argumentos.extend(pedidos.iter().map(|pedido| format!("want {}", pedido)));
+        argumentos.extend(lineas_shallow);
+
+        let mut negociacion = Negociacion::new("./.gir")?;
+        loop {
+            let haves = negociacion.siguiente_tanda(TAMANIO_TANDA_HAVES);
+            if haves.is_empty() {
+                break;
+            }
+            let mut argumentos_con_haves = argumentos.clone();
+            argumentos_con_haves.extend(haves.iter().map(|have| format!("have {}", have)));
+            comunicacion.enviar_comando_v2("fetch", &argumentos_con_haves)?;
+            let (en_comun, listo) = self.recibir_acknowledgments(comunicacion)?;
+            for commit in &en_comun {
+                negociacion.marcar_comun(commit);
+            }
+            argumentos.extend(en_comun.iter().map(|commit| format!("have {}", commit)));
+            if listo {
+                self.logger
+                    .log("El servidor tiene suficientes commits en comun para armar el packfile");
+                return Ok(SE_ENVIO_ALGUN_PEDIDO);
+            }
+        }
+        argumentos.push("done".to_string());
+        comunicacion.enviar_comando_v2("fetch", &argumentos)?;
+
+        self.logger
+            .log("Se completo correctamente la fase de negociacion v2 en Fetch");
+        Ok(SE_ENVIO_ALGUN_PEDIDO)
+    }
+
+    ///Recibe la seccion acknowledgments de la respuesta al fetch del protocolo v2 y devuelve los
+    /// objetos que el servidor reconocio como comunes y si respondio ready
+    fn recibir_acknowledgments(
+        &self,
+        comunicacion: &mut Comunicacion<Flujo>,
+    ) -> Result<(Vec<String>, bool), String> {
+        let lineas = comunicacion.obtener_lineas_v2()?;
+        match lineas.first().map(String::as_str) {
+            Some("acknowledgments\n") => {}
+            Some(linea) if linea.starts_with("ERR ") => {
+                return Err(format!("Error, {}", linea.trim_start_matches("ERR ")))
+            }
+            _ => return Err("El servidor no envio la seccion acknowledgments".to_string()),
+        }
+        let en_comun = lineas
+            .iter()
+            .filter_map(|linea| linea.strip_prefix("ACK "))
+            .map(|objeto| objeto.trim().to_string())
+            .collect();
+        let listo = lineas.iter().any(|linea| linea == "ready\n");
+        Ok((en_comun, listo))
+    }
+
+    fn recibir_packfile_y_guardar_objetos(
+        &mut self,
+        comunicacion: &mut Comunicacion<Flujo>,
     ) -> Result<(), String> {
         self.logger.log("Obteniendo paquete..");
 
-        let packfile = comunicacion.obtener_packfile()?;
-        let primeros_bytes = &packfile[..4];
-        if primeros_bytes != "PACK".as_bytes() {
+        if self.protocolo_v2 && self.pide_profundizar() {
+            let lineas = comunicacion.obtener_lineas_v2()?;
+            match lineas.first().map(String::as_str) {
+                Some("shallow-info\n") => {
+                    self.recibir_actualizacion_shallow(lineas[1..].to_vec())?
+                }
+                Some(linea) if linea.starts_with("ERR ") => {
+                    return Err(format!("Error, {}", linea.trim_start_matches("ERR ")))
+                }
+                _ => return Err("El servidor no envio la seccion shallow-info".to_string()),
+            }
+        }
+        let packfile = if self.protocolo_v2 {
+            comunicacion.obtener_seccion_packfile()
+        } else if self.side_band {
+            comunicacion.obtener_packfile_multiplexado()
+        } else {
+            Ok(comunicacion.obtener_packfile()?)
+        };
+        self.progreso.extend(comunicacion.obtener_progreso());
+        let packfile = packfile.map_err(|error| error.to_string())?;
+        if !packfile.starts_with("PACK".as_bytes()) {
             self.logger.log(&format!(
                 "Se recibio: {}",
                 String::from_utf8_lossy(packfile.as_slice())
@@ -138,13 +409,22 @@ impl Fetch {
             ));
         }
         self.logger.log("Recepcion del pack file en fetch exitoso");
-        Packfile::leer_packfile_y_escribir(&packfile, "./.gir/objects/".to_string()).unwrap();
+
+        let entradas =
+            Packfile::verificar_packfile(&packfile, "./.gir/objects/").map_err(|error| {
+                self.logger
+                    .log(&format!("Se rechaza el packfile recibido: {}", error));
+                error.to_string()
+            })?;
+        Packfile::guardar_packfile(&packfile, entradas, "./.gir/objects/")?;
+        let (shallows, unshallows) = &self.actualizacion_shallow;
+        shallow::actualizar_commits_shallow(shallows, unshallows)?;
         Ok(())
     }
 
     ///Envia un mensaje al servidor para avisarle que ya se termino de de mandarle lineas.
     /// Para seguir el protocolo el mensaje que se envia es done
-    fn finalizar_pedido(&self, comunicacion: &mut Comunicacion<TcpStream>) -> Result<(), String> {
+    fn finalizar_pedido(&self, comunicacion: &mut Comunicacion<Flujo>) -> Result<(), String> {
         comunicacion.enviar(&utils::strings::obtener_linea_con_largo_hex("done\n"))
     }
 
@@ -169,29 +449,76 @@ impl Fetch {
         Ok(())
     }
 
-    ///Envia todo los objetos (sus hash) que ya se tienen y por lo tanto no es necesario que el servidor manda
+    ///Negocia con el servidor los commits en comun mandandole los commits locales como haves, de
+    /// los mas nuevos a los mas viejos y en tandas. Con multi_ack_detailed el servidor responde cada
+    /// tanda con los que tiene ('ACK hash common'), cuyos ancestros ya no se mandan, y avisa con
+    /// 'ACK hash ready' cuando le alcanza para armar el packfile. Sin multi_ack se manda todo en
+    /// una sola tanda. Al final se manda done y se recibe el ultimo ACK o NAK.
     fn enviar_lo_que_tengo(
         &self,
-        comunicacion: &mut Comunicacion<TcpStream>,
+        multi_ack: bool,
+        comunicacion: &mut Comunicacion<Flujo>,
     ) -> Result<(), String> {
-        let objetos = objects::obtener_objetos_del_dir(&PathBuf::from("./.gir/objects"))?;
-
-        if !objetos.is_empty() {
-            comunicacion.enviar_lo_que_tengo_al_servidor_pkt(&objetos)?;
-            self.recivir_nack(comunicacion)?;
-            self.finalizar_pedido(comunicacion)?
+        let mut negociacion = Negociacion::new("./.gir")?;
+        let tamanio_tanda = if multi_ack {
+            TAMANIO_TANDA_HAVES
         } else {
-            self.finalizar_pedido(comunicacion)?;
-            self.recivir_nack(comunicacion)?;
+            usize::MAX
+        };
+        let mut recibio_ack = false;
+        loop {
+            let haves = negociacion.siguiente_tanda(tamanio_tanda);
+            if haves.is_empty() {
+                break;
+            }
+            comunicacion.enviar_lo_que_tengo_al_servidor_pkt(&haves)?;
+            if !multi_ack {
+                // sin multi_ack el servidor responde solo el primer have en comun o NAK
+                let respuesta = comunicacion.obtener_lineas()?;
+                recibio_ack = respuesta.iter().any(|linea| linea.starts_with("ACK "));
+                break;
+            }
+            if self.recibir_acks_de_la_tanda(&mut negociacion, comunicacion)? {
+                break;
+            }
+        }
+        self.finalizar_pedido(comunicacion)?;
+        // sin multi_ack, si ya se recibio un ACK el servidor no responde el done
+        if multi_ack || !recibio_ack {
+            let _ack_nak = comunicacion.obtener_lineas()?;
         }
         self.logger.log("Se envio con exito lo que tengo en Fetch");
         Ok(())
     }
 
-    ///Recibe el la repusta Nack del servidor del envio de HAVE
-    fn recivir_nack(&self, comunicacion: &mut Comunicacion<TcpStream>) -> Result<(), String> {
-        let _acks_nak = comunicacion.obtener_lineas()?;
-        Ok(())
+    ///Recibe la respuesta del servidor a una tanda de haves, hasta el NAK que la termina, y marca
+    /// como comunes los commits que reconocio. Devuelve si el servidor respondio ready.
+    fn recibir_acks_de_la_tanda(
+        &self,
+        negociacion: &mut Negociacion,
+        comunicacion: &mut Comunicacion<Flujo>,
+    ) -> Result<bool, String> {
+        let mut listo = false;
+        loop {
+            for linea in comunicacion.obtener_lineas()? {
+                let partes: Vec<&str> = linea.split_whitespace().collect();
+                match partes[..] {
+                    ["NAK"] => return Ok(listo),
+                    // el have de un ACK ready puede ser uno que el servidor no tiene
+                    ["ACK", _, "ready"] => listo = true,
+                    ["ACK", commit, _] => negociacion.marcar_comun(commit),
+                    ["ERR", ..] => {
+                        return Err(format!("Error, {}", linea.trim_start_matches("ERR ")))
+                    }
+                    _ => {
+                        return Err(format!(
+                            "Respuesta inesperada del servidor en la negociacion: {}",
+                            linea
+                        ))
+                    }
+                }
+            }
+        }
     }
 
     ///Envia al servidor todos los commits cabeza de rama que se quieren actulizar junto con las capacidades del
@@ -202,22 +529,16 @@ impl Fetch {
     fn enviar_pedidos(
         &self,
         capacidades_servidor: &[String],
+        lineas_shallow: &[String],
         commits_cabezas_y_dir_rama_asosiado: &Vec<(String, PathBuf)>,
         commit_y_tags_asosiado: &Vec<(String, PathBuf)>,
-        comunicacion: &mut Comunicacion<TcpStream>,
+        comunicacion: &mut Comunicacion<Flujo>,
     ) -> Result<bool, String> {
         let capacidades_a_usar_en_la_comunicacion =
             self.obtener_capacidades_en_comun_con_el_servidor(capacidades_servidor);
 
-        let commits_de_cabeza_de_rama_faltantes =
-            self.obtener_commits_cabeza_de_rama_faltantes(commits_cabezas_y_dir_rama_asosiado)?;
-        let tags_faltantes = self.obtener_tags_faltantes(commit_y_tags_asosiado)?;
-
-        let pedidos = [
-            &commits_de_cabeza_de_rama_faltantes[..],
-            &tags_faltantes[..],
-        ]
-        .concat();
+        let pedidos =
+            self.obtener_pedidos(commits_cabezas_y_dir_rama_asosiado, commit_y_tags_asosiado)?;
 
         if pedidos.is_empty() {
             comunicacion.enviar_flush_pkt()?;
@@ -227,14 +548,43 @@ impl Fetch {
             return Ok(NO_SE_ENVIO_NINGUN_PEDIDO);
         }
 
-        comunicacion
-            .enviar_pedidos_al_servidor_pkt(pedidos, capacidades_a_usar_en_la_comunicacion)?;
+        comunicacion.enviar_pedidos_y_lineas_al_servidor_pkt(
+            pedidos,
+            capacidades_a_usar_en_la_comunicacion,
+            lineas_shallow,
+        )?;
 
         self.logger
             .log("Se completo correctamente el envio de pedidos en Fetch");
         Ok(SE_ENVIO_ALGUN_PEDIDO)
     }
 
+    ///Obtiene los commits de las ramas y los tags que hay que pedirle al servidor. Si se pide
+    /// cambiar la profundidad y no falta nada, se vuelven a pedir todas las ramas
+    fn obtener_pedidos(
+        &self,
+        commits_cabezas_y_dir_rama_asosiado: &Vec<(String, PathBuf)>,
+        commit_y_tags_asosiado: &Vec<(String, PathBuf)>,
+    ) -> Result<Vec<String>, String> {
+        let commits_de_cabeza_de_rama_faltantes =
+            self.obtener_commits_cabeza_de_rama_faltantes(commits_cabezas_y_dir_rama_asosiado)?;
+        let tags_faltantes = self.obtener_tags_faltantes(commit_y_tags_asosiado)?;
+
+        let pedidos = [
+            &commits_de_cabeza_de_rama_faltantes[..],
+            &tags_faltantes[..],
+        ]
+        .concat();
+        // aunque las ramas esten actualizadas, para cambiar la profundidad hay que volver a pedirlas
+        if pedidos.is_empty() && self.pide_profundizar() {
+            return Ok(commits_cabezas_y_dir_rama_asosiado
+                .iter()
+                .map(|(commit, _)| commit.clone())
+                .collect());
+        }
+        Ok(pedidos)
+    }
+
     ///Obtiene los commits que son necesarios a actulizar y por lo tanto hay que pedirle al servidor esas ramas.
     /// Obtiene aquellos commits que pertenecesen a ramas cuyas cabezas en el servidor apuntan commits distintos
     /// que sus equivalencias en el repositorio local, implicando que la rama local esta desacululizada.
@@ -339,8 +689,11 @@ impl Fetch {
     /// - vector de tuplas con los hash del commit cabeza de rama y la direccion de la
     ///     carpeta de la rama en el servidor(ojo!! la direccion para el servidor no para el local)
     /// - vector de tuplas con el hash del commit y el tag asosiado
+    ///
+    /// Si el servidor responde en el protocolo v2, la primera linea es `version 2` seguida de sus
+    /// capacidades y las referencias se piden con ls-refs.
     fn fase_de_descubrimiento<T: Write + Read>(
-        &self,
+        &mut self,
         comunicacion: &mut Comunicacion<T>,
     ) -> Result<
         (
@@ -351,7 +704,14 @@ impl Fetch {
         ),
         String,
     > {
-        let resultado = utils::fase_descubrimiento::fase_de_descubrimiento(comunicacion)?;
+        let mut lineas_recibidas = comunicacion.obtener_lineas()?;
+        self.protocolo_v2 = lineas_recibidas.first().map(String::as_str) == Some(LINEA_VERSION_2);
+        let resultado = if self.protocolo_v2 {
+            lineas_recibidas.remove(0);
+            fase_descubrimiento::fase_de_descubrimiento_v2(comunicacion, lineas_recibidas)?
+        } else {
+            fase_descubrimiento::interpretar_anuncio_de_referencias(lineas_recibidas)?
+        };
 
         self.logger.log(&format!(
             "Se ejecuto correctamte la fase de decubrimiento en Fetch: {:?}",
@@ -381,10 +741,19 @@ impl Fetch {
         Ok(())
     }
 
-    fn iniciar_git_upload_pack_con_servidor(&self) -> Result<Comunicacion<TcpStream>, String> {
+    /// Devuelve los mensajes de progreso que mando el servidor, uno por linea y con el prefijo
+    /// `remoto: `, para mostrarselos al usuario junto al resultado del comando.
+    pub fn mensajes_del_remoto(&self) -> String {
+        self.progreso
+            .iter()
+            .map(|mensaje| format!("remoto: {}\n", mensaje))
+            .collect()
+    }
+
+    fn iniciar_git_upload_pack_con_servidor(&self) -> Result<Comunicacion<Flujo>, String> {
         let url = self.obtener_url(&self.remoto)?;
-        let mut comunicacion = Comunicacion::<TcpStream>::new_desde_url(&url, self.logger.clone())?;
-        comunicacion.iniciar_git_upload_pack_con_servidor()?;
+        let mut comunicacion = Comunicacion::<Flujo>::new_desde_url(&url, self.logger.clone())?;
+        comunicacion.iniciar_git_upload_pack_con_servidor(VERSION_PROTOCOLO)?;
         Ok(comunicacion)
     }
 }
@@ -400,6 +769,7 @@ impl Ejecutar for Fetch {
             commits_cabezas_y_dir_rama_asosiado,
             commits_y_tags_asosiados,
         ) = self.fase_de_descubrimiento(&mut comunicacion)?;
+        let commits_y_tags_asosiados = Self::descartar_tags_pelados(commits_y_tags_asosiados);
 
         if !self.fase_de_negociacion(
             capacidades_servidor,
@@ -420,7 +790,7 @@ impl Ejecutar for Fetch {
 
         let mensaje = "Fetch ejecutado con exito".to_string();
         self.logger.log(&mensaje);
-        Ok(mensaje)
+        Ok(self.mensajes_del_remoto() + &mensaje)
     }
 }
 
@@ -686,4 +1056,23 @@ mod tests {
                 .unwrap();
         assert_eq!(rama_contendio_obtenido, rama_contenido);
     }
+
+    #[test]
+    fn test_06_los_tags_pelados_no_se_piden_ni_se_guardan() {
+        let tags = vec![
+            (
+                "525128480b96c89e6418b1e40909bf6c5b2d580f".to_string(),
+                PathBuf::from("refs/tags/v1.0"),
+            ),
+            (
+                "e92df48743b7bc7d26bcaabfddde0a1e20cae47c".to_string(),
+                PathBuf::from("refs/tags/v1.0^{}"),
+            ),
+        ];
+
+        assert_eq!(
+            Fetch::descartar_tags_pelados(tags.clone()),
+            vec![tags[0].clone()]
+        );
+    }
 }
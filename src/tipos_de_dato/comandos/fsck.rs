@@ -0,0 +1,490 @@
+use std::{
+    collections::HashSet,
+    fmt,
+    path::{Path, PathBuf},
+    sync::Arc,
+};
+
+use crate::{
+    tipos_de_dato::{
+        comando::Ejecutar,
+        comandos::hash_object::HashObject,
+        logger::Logger,
+        objetos::{commit::CommitObj, tag::TagObj, tree::Tree},
+    },
+    utils::{compresion, io, objects, referencia},
+};
+
+const DIR_GIR: &str = ".gir";
+
+/// Problema encontrado al verificar la base de objetos.
+#[derive(Debug, PartialEq, Eq, Hash, Clone)]
+pub enum ProblemaFsck {
+    /// El contenido del objeto no hashea al nombre con el que esta guardado.
+    HashIncorrecto { hash: String, calculado: String },
+    /// El objeto no se pudo leer o no tiene un formato valido.
+    ObjetoCorrupto { hash: String, motivo: String },
+    /// Un objeto apunta a otro que no existe (por ejemplo un commit a su tree).
+    LinkRoto {
+        tipo: String,
+        desde: String,
+        hacia: String,
+    },
+    /// El commit tiene un padre que no existe.
+    PadreFaltante { commit: String, padre: String },
+    /// El tree tiene un blob que no existe.
+    BlobFaltante { tree: String, blob: String },
+    /// Commit al que no apunta ninguna referencia ni ningun otro commit.
+    CommitColgante(String),
+    /// La referencia apunta a un objeto que no existe.
+    RefRota { referencia: String, hash: String },
+}
+
+impl fmt::Display for ProblemaFsck {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            ProblemaFsck::HashIncorrecto { hash, calculado } => write!(
+                f,
+                "hash incorrecto: el objeto {} tiene hash {}",
+                hash, calculado
+            ),
+            ProblemaFsck::ObjetoCorrupto { hash, motivo } => {
+                write!(f, "objeto corrupto {}: {}", hash, motivo)
+            }
+            ProblemaFsck::LinkRoto { tipo, desde, hacia } => {
+                write!(f, "link roto desde {} {} a {}", tipo, desde, hacia)
+            }
+            ProblemaFsck::PadreFaltante { commit, padre } => {
+                write!(f, "falta el padre {} del commit {}", padre, commit)
+            }
+            ProblemaFsck::BlobFaltante { tree, blob } => {
+                write!(f, "falta el blob {} del tree {}", blob, tree)
+            }
+            ProblemaFsck::CommitColgante(hash) => write!(f, "commit colgante {}", hash),
+            ProblemaFsck::RefRota { referencia, hash } => write!(
+                f,
+                "la referencia {} apunta al objeto inexistente {}",
+                referencia, hash
+            ),
+        }
+    }
+}
+
+pub struct Fsck {
+    /// Logger para registrar los eventos ocurridos durante la ejecucion del comando.
+    logger: Arc<Logger>,
+}
+
+impl Fsck {
+    /// Crea un comando fsck. No recibe argumentos.
+    pub fn from(args: Vec<String>, logger: Arc<Logger>) -> Result<Fsck, String> {
+        if !args.is_empty() {
+            return Err("Cantidad de argumentos invalida\ngir fsck".to_string());
+        }
+        Ok(Fsck { logger })
+    }
+
+    /// Verifica la base de objetos del repositorio ubicado en `dir_repositorio`.
+    /// Rehashea todos los objetos, parsea los commits, trees y tags chequeando que existan los objetos a los
+    /// que apuntan, y verifica que las referencias (y el HEAD, si esta desacoplado) apunten a objetos existentes.
+    /// Devuelve la lista de problemas encontrados, vacia si el repositorio esta sano.
+    pub fn verificar(
+        dir_repositorio: &Path,
+        logger: Arc<Logger>,
+    ) -> Result<Vec<ProblemaFsck>, String> {
+        let dir_gir = dir_repositorio.join(DIR_GIR);
+        let dir_objetos = format!("{}/objects/", dir_gir.display());
+        let mut problemas = Vec::new();
+        let objetos = objects::obtener_objetos_del_dir(&PathBuf::from(&dir_objetos))?;
+        let existentes: HashSet<String> = HashSet::from_iter(objetos.iter().cloned());
+
+        let mut commits = Vec::new();
+        let mut commits_referenciados = HashSet::new();
+
+        for hash in &objetos {
+            let tipo = match Self::verificar_hash(hash, &dir_objetos) {
+                Ok(tipo) => tipo,
+                Err(problema) => {
+                    problemas.push(problema);
+                    continue;
+                }
+            };
+            match tipo.as_str() {
+                "commit" => {
+                    let padres = Self::verificar_commit(
+                        hash,
+                        &dir_objetos,
+                        &existentes,
+                        &mut problemas,
+                        logger.clone(),
+                    );
+                    commits_referenciados.extend(padres);
+                    commits.push(hash.clone());
+                }
+                "tree" => Self::verificar_tree(hash, &dir_objetos, &existentes, &mut problemas),
+                "tag" => {
+                    let objeto =
+                        Self::verificar_tag(hash, &dir_objetos, &existentes, &mut problemas);
+                    if let Some(objeto) = objeto {
+                        commits_referenciados.insert(objeto);
+                    }
+                }
+                _ => {}
+            }
+        }
+
+        let mut refs = referencia::obtener_refs_y_hashes(&dir_gir.join("refs"), &dir_gir)?;
+        // con el HEAD desacoplado el commit al que apunta tambien es una raiz
+        if let Ok(head) = io::leer_a_string(dir_gir.join("HEAD")) {
+            let head = head.trim();
+            if !head.is_empty() && !head.starts_with("ref:") {
+                refs.push(("HEAD".to_string(), head.to_string()));
+            }
+        }
+        for (nombre, hash) in refs {
+            if existentes.contains(&hash) {
+                commits_referenciados.insert(hash);
+            } else {
+                problemas.push(ProblemaFsck::RefRota {
+                    referencia: nombre,
+                    hash,
+                });
+            }
+        }
+
+        for commit in commits {
+            if !commits_referenciados.contains(&commit) {
+                problemas.push(ProblemaFsck::CommitColgante(commit));
+            }
+        }
+
+        Ok(problemas)
+    }
+
+    // Rehashea el objeto y verifica su header. Devuelve el tipo del objeto.
+    fn verificar_hash(hash: &str, dir_objetos: &str) -> Result<String, ProblemaFsck> {
+        let corrupto = |motivo: String| ProblemaFsck::ObjetoCorrupto {
+            hash: hash.to_string(),
+            motivo,
+        };
+
+        let objeto =
+            compresion::obtener_objeto_descomprimido(hash, dir_objetos).map_err(corrupto)?;
+        let calculado = HashObject::hashear_contenido_objeto(&objeto);
+        if calculado != hash {
+            return Err(ProblemaFsck::HashIncorrecto {
+                hash: hash.to_string(),
+                calculado,
+            });
+        }
+
+        let fin_header = objeto
+            .iter()
+            .position(|byte| *byte == 0)
+            .ok_or_else(|| corrupto("no tiene header".to_string()))?;
+        let header = String::from_utf8_lossy(&objeto[..fin_header]).to_string();
+        let (tipo, tamanio) = header
+            .split_once(' ')
+            .ok_or_else(|| corrupto(format!("header invalido: {}", header)))?;
+        if tamanio.parse::<usize>() != Ok(objeto.len() - fin_header - 1) {
+            return Err(corrupto(format!(
+                "el header declara {} bytes pero tiene {}",
+                tamanio,
+                objeto.len() - fin_header - 1
+            )));
+        }
+        match tipo {
+            "commit" | "tree" | "blob" | "tag" => Ok(tipo.to_string()),
+            _ => Err(corrupto(format!("tipo desconocido: {}", tipo))),
+        }
+    }
+
+    // Verifica que existan el tree y los padres del commit. Devuelve los padres del commit.
+    fn verificar_commit(
+        hash: &str,
+        dir_objetos: &str,
+        existentes: &HashSet<String>,
+        problemas: &mut Vec<ProblemaFsck>,
+        logger: Arc<Logger>,
+    ) -> Vec<String> {
+        let commit = match CommitObj::from_hash_de(hash.to_string(), dir_objetos, logger) {
+            Ok(commit) => commit,
+            Err(motivo) => {
+                problemas.push(ProblemaFsck::ObjetoCorrupto {
+                    hash: hash.to_string(),
+                    motivo,
+                });
+                return Vec::new();
+            }
+        };
+
+        if !existentes.contains(&commit.hash_tree) {
+            problemas.push(ProblemaFsck::LinkRoto {
+                tipo: "commit".to_string(),
+                desde: hash.to_string(),
+                hacia: commit.hash_tree.clone(),
+            });
+        }
+        for padre in &commit.padres {
+            if !existentes.contains(padre) {
+                problemas.push(ProblemaFsck::PadreFaltante {
+                    commit: hash.to_string(),
+                    padre: padre.clone(),
+                });
+            }
+        }
+        commit.padres
+    }
+
+    // Verifica que exista el objeto al que apunta el tag. Devuelve ese objeto.
+    fn verificar_tag(
+        hash: &str,
+        dir_objetos: &str,
+        existentes: &HashSet<String>,
+        problemas: &mut Vec<ProblemaFsck>,
+    ) -> Option<String> {
+        let tag = match TagObj::from_hash_de(hash, dir_objetos) {
+            Ok(tag) => tag,
+            Err(motivo) => {
+                problemas.push(ProblemaFsck::ObjetoCorrupto {
+                    hash: hash.to_string(),
+                    motivo,
+                });
+                return None;
+            }
+        };
+
+        if !existentes.contains(&tag.objeto) {
+            problemas.push(ProblemaFsck::LinkRoto {
+                tipo: "tag".to_string(),
+                desde: hash.to_string(),
+                hacia: tag.objeto.clone(),
+            });
+        }
+        Some(tag.objeto)
+    }
+
+    // Verifica que existan los hijos directos del tree.
+    // No se usa Tree::from_hash porque se corta en el primer subarbol faltante y ademas recorre
+    // todos los subarboles, que igual se verifican por separado.
+    fn verificar_tree(
+        hash: &str,
+        dir_objetos: &str,
+        existentes: &HashSet<String>,
+        problemas: &mut Vec<ProblemaFsck>,
+    ) {
+        let hijos = compresion::descomprimir_objeto(hash, dir_objetos)
+            .and_then(|contenido| Tree::obtener_datos_de_contenido(&contenido));
+        let hijos = match hijos {
+            Ok(hijos) => hijos,
+            Err(motivo) => {
+                problemas.push(ProblemaFsck::ObjetoCorrupto {
+                    hash: hash.to_string(),
+                    motivo,
+                });
+                return;
+            }
+        };
+
+        for (modo, _, hash_hijo) in hijos {
+            if existentes.contains(&hash_hijo) || modo == "160000" {
+                continue;
+            }
+            if modo == "40000" {
+                problemas.push(ProblemaFsck::LinkRoto {
+                    tipo: "tree".to_string(),
+                    desde: hash.to_string(),
+                    hacia: hash_hijo,
+                });
+            } else {
+                problemas.push(ProblemaFsck::BlobFaltante {
+                    tree: hash.to_string(),
+                    blob: hash_hijo,
+                });
+            }
+        }
+    }
+}
+
+impl Ejecutar for Fsck {
+    /// Ejecuta el comando fsck.
+    /// Devuelve un string con un problema encontrado por linea.
+    fn ejecutar(&mut self) -> Result<String, String> {
+        self.logger.log("Ejecutando comando fsck");
+        let problemas = Self::verificar(Path::new("."), self.logger.clone())?;
+        self.logger.log(&format!(
+            "fsck encontro {} problemas en la base de objetos",
+            problemas.len()
+        ));
+
+        Ok(problemas
+            .iter()
+            .map(|problema| problema.to_string())
+            .collect::<Vec<String>>()
+            .join("\n"))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use serial_test::serial;
+    use std::{path::PathBuf, sync::Arc};
+
+    use crate::utils::{
+        io, ramas,
+        testing::{addear_archivos_y_comittear, limpiar_archivo_gir},
+    };
+
+    use super::*;
+
+    fn ruta_objeto(hash: &str) -> String {
+        format!(".gir/objects/{}/{}", &hash[..2], &hash[2..])
+    }
+
+    fn crear_repo_con_un_commit(logger: Arc<Logger>) -> CommitObj {
+        limpiar_archivo_gir(logger.clone());
+        io::escribir_bytes("tmp/fsck_archivo", "contenido").unwrap();
+        addear_archivos_y_comittear(vec!["tmp/fsck_archivo".to_string()], logger.clone());
+        let hash = ramas::obtener_hash_commit_asociado_rama_actual().unwrap();
+        CommitObj::from_hash(hash, logger).unwrap()
+    }
+
+    #[test]
+    #[serial]
+    fn test01_un_repositorio_sano_no_tiene_problemas() {
+        let logger = Arc::new(Logger::new(PathBuf::from("tmp/fsck_test01")).unwrap());
+        crear_repo_con_un_commit(logger.clone());
+
+        let problemas = Fsck::verificar(Path::new("."), logger).unwrap();
+        assert!(problemas.is_empty());
+    }
+
+    #[test]
+    #[serial]
+    fn test02_detecta_objetos_cuyo_contenido_no_coincide_con_el_hash() {
+        let logger = Arc::new(Logger::new(PathBuf::from("tmp/fsck_test02")).unwrap());
+        crear_repo_con_un_commit(logger.clone());
+        let hash = "0123456789012345678901234567890123456789";
+        let contenido = compresion::comprimir_contenido("blob 4\0hola").unwrap();
+        io::escribir_bytes(ruta_objeto(hash), contenido).unwrap();
+
+        let problemas = Fsck::verificar(Path::new("."), logger).unwrap();
+        assert_eq!(
+            problemas,
+            vec![ProblemaFsck::HashIncorrecto {
+                hash: hash.to_string(),
+                calculado: HashObject::hashear_contenido_objeto(&b"blob 4\0hola".to_vec()),
+            }]
+        );
+    }
+
+    #[test]
+    #[serial]
+    fn test03_detecta_blobs_faltantes() {
+        let logger = Arc::new(Logger::new(PathBuf::from("tmp/fsck_test03")).unwrap());
+        let commit = crear_repo_con_un_commit(logger.clone());
+        let tree = Tree::from_hash(&commit.hash_tree, PathBuf::from("."), logger.clone()).unwrap();
+        let blob = tree.obtener_objetos_hoja()[0].obtener_hash();
+        std::fs::remove_file(ruta_objeto(&blob)).unwrap();
+
+        let problemas = Fsck::verificar(Path::new("."), logger).unwrap();
+        assert_eq!(problemas.len(), 1);
+        assert!(matches!(
+            &problemas[0],
+            ProblemaFsck::BlobFaltante { blob: faltante, .. } if *faltante == blob
+        ));
+    }
+
+    #[test]
+    #[serial]
+    fn test04_detecta_padres_faltantes() {
+        let logger = Arc::new(Logger::new(PathBuf::from("tmp/fsck_test04")).unwrap());
+        let primer_commit = crear_repo_con_un_commit(logger.clone());
+        io::escribir_bytes("tmp/fsck_archivo", "contenido nuevo").unwrap();
+        addear_archivos_y_comittear(vec!["tmp/fsck_archivo".to_string()], logger.clone());
+        let segundo_commit = ramas::obtener_hash_commit_asociado_rama_actual().unwrap();
+        std::fs::remove_file(ruta_objeto(&primer_commit.hash)).unwrap();
+
+        let problemas = Fsck::verificar(Path::new("."), logger).unwrap();
+        assert_eq!(
+            problemas,
+            vec![ProblemaFsck::PadreFaltante {
+                commit: segundo_commit,
+                padre: primer_commit.hash,
+            }]
+        );
+    }
+
+    #[test]
+    #[serial]
+    fn test05_detecta_commits_colgantes() {
+        let logger = Arc::new(Logger::new(PathBuf::from("tmp/fsck_test05")).unwrap());
+        let primer_commit = crear_repo_con_un_commit(logger.clone());
+        io::escribir_bytes("tmp/fsck_archivo", "contenido nuevo").unwrap();
+        addear_archivos_y_comittear(vec!["tmp/fsck_archivo".to_string()], logger.clone());
+        let segundo_commit = ramas::obtener_hash_commit_asociado_rama_actual().unwrap();
+        io::escribir_bytes(".gir/refs/heads/master", &primer_commit.hash).unwrap();
+
+        let problemas = Fsck::verificar(Path::new("."), logger).unwrap();
+        assert_eq!(
+            problemas,
+            vec![ProblemaFsck::CommitColgante(segundo_commit)]
+        );
+    }
+
+    #[test]
+    #[serial]
+    fn test06_detecta_referencias_a_objetos_inexistentes() {
+        let logger = Arc::new(Logger::new(PathBuf::from("tmp/fsck_test06")).unwrap());
+        crear_repo_con_un_commit(logger.clone());
+        let hash = "0".repeat(39) + "1";
+        io::escribir_bytes(".gir/refs/heads/rota", &hash).unwrap();
+
+        let problemas = Fsck::verificar(Path::new("."), logger).unwrap();
+        assert_eq!(
+            problemas,
+            vec![ProblemaFsck::RefRota {
+                referencia: "refs/heads/rota".to_string(),
+                hash,
+            }]
+        );
+    }
+
+    #[test]
+    #[serial]
+    fn test07_verifica_el_repositorio_del_directorio_indicado() {
+        let logger = Arc::new(Logger::new(PathBuf::from("tmp/fsck_test07")).unwrap());
+        crear_repo_con_un_commit(logger.clone());
+        let hash = "0".repeat(39) + "1";
+        io::escribir_bytes(".gir/refs/heads/rota", &hash).unwrap();
+
+        io::cambiar_directorio("tmp").unwrap();
+        let problemas = Fsck::verificar(Path::new(".."), logger);
+        io::cambiar_directorio("..").unwrap();
+
+        assert_eq!(
+            problemas.unwrap(),
+            vec![ProblemaFsck::RefRota {
+                referencia: "refs/heads/rota".to_string(),
+                hash,
+            }]
+        );
+    }
+
+    #[test]
+    #[serial]
+    fn test08_el_commit_del_head_desacoplado_no_es_colgante() {
+        let logger = Arc::new(Logger::new(PathBuf::from("tmp/fsck_test08")).unwrap());
+        let primer_commit = crear_repo_con_un_commit(logger.clone());
+        io::escribir_bytes("tmp/fsck_archivo", "contenido nuevo").unwrap();
+        addear_archivos_y_comittear(vec!["tmp/fsck_archivo".to_string()], logger.clone());
+        let segundo_commit = ramas::obtener_hash_commit_asociado_rama_actual().unwrap();
+        io::escribir_bytes(".gir/refs/heads/master", &primer_commit.hash).unwrap();
+        io::escribir_bytes(".gir/HEAD", &segundo_commit).unwrap();
+
+        let problemas = Fsck::verificar(Path::new("."), logger);
+        io::escribir_bytes(".gir/HEAD", "ref: refs/heads/master").unwrap();
+
+        assert!(problemas.unwrap().is_empty());
+    }
+}
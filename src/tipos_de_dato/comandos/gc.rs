@@ -0,0 +1,514 @@
+use std::{
+    collections::HashSet,
+    path::{Path, PathBuf},
+    sync::Arc,
+    time::{Duration, SystemTime},
+};
+
+use crate::{
+    tipos_de_dato::{
+        comando::Ejecutar,
+        logger::Logger,
+        objetos::{tag::TagObj, tree::Tree},
+        packfile::Packfile,
+    },
+    utils::{compresion, index, io, objects, referencia, shallow},
+};
+
+// referencias especiales que, si apuntan a un commit, tambien son raices de lo alcanzable
+const CABEZAS: [&str; 3] = ["HEAD", "ORIG_HEAD", "MERGE_HEAD"];
+// periodo de gracia por defecto para los objetos inalcanzables, igual que git (2 semanas)
+const DIAS_DE_GRACIA_DEFAULT: u64 = 14;
+const SEGUNDOS_POR_DIA: u64 = 24 * 60 * 60;
+
+pub struct Gc {
+    /// Antiguedad minima que tiene que tener un objeto suelto inalcanzable para ser borrado.
+    /// Si es None no se borra ningun objeto.
+    periodo_de_gracia: Option<Duration>,
+    /// Directorio del repositorio sobre el que se ejecuta el comando (el que contiene `.gir`).
+    dir_repositorio: PathBuf,
+    /// Logger para registrar los eventos ocurridos durante la ejecucion del comando.
+    logger: Arc<Logger>,
+}
+
+impl Gc {
+    /// Crea un comando gc a partir de los argumentos pasados por linea de comandos.
+    /// Acepta `--prune=now`, `--prune=<dias>` y `--no-prune`. Por defecto se borran los objetos
+    /// inalcanzables con mas de 14 dias.
+    /// En caso de tener argumentos invalidos devuelve error.
+    pub fn from(args: Vec<String>, logger: Arc<Logger>) -> Result<Gc, String> {
+        Self::from_de(args, PathBuf::from("."), logger)
+    }
+
+    /// Igual que `from`, pero el comando se ejecuta sobre el repositorio ubicado en
+    /// `dir_repositorio` en lugar del directorio actual.
+    pub fn from_de(
+        args: Vec<String>,
+        dir_repositorio: PathBuf,
+        logger: Arc<Logger>,
+    ) -> Result<Gc, String> {
+        let mut periodo_de_gracia = Some(Duration::from_secs(
+            DIAS_DE_GRACIA_DEFAULT * SEGUNDOS_POR_DIA,
+        ));
+
+        for arg in args {
+            periodo_de_gracia = match arg.as_str() {
+                "--no-prune" => None,
+                "--prune=now" => Some(Duration::ZERO),
+                _ => match arg.strip_prefix("--prune=") {
+                    Some(dias) => {
+                        let dias = dias
+                            .parse::<u64>()
+                            .map_err(|_| format!("Periodo de gracia invalido: {}", dias))?;
+                        Some(Duration::from_secs(dias * SEGUNDOS_POR_DIA))
+                    }
+                    None => return Err(format!("Argumento invalido: {}", arg)),
+                },
+            };
+        }
+
+        Ok(Gc {
+            periodo_de_gracia,
+            dir_repositorio,
+            logger,
+        })
+    }
+
+    /// Devuelve los hashes de todos los objetos alcanzables en el repositorio ubicado en
+    /// `dir_repositorio` desde alguna referencia (ramas, tags y ramas remotas) o desde HEAD,
+    /// ORIG_HEAD y MERGE_HEAD: los tags anotados, los commits, sus trees y los blobs de esos
+    /// trees. Tambien son alcanzables los objetos del index, aunque todavia no esten en ningun
+    /// commit.
+    pub fn obtener_objetos_alcanzables(dir_repositorio: &Path) -> Result<HashSet<String>, String> {
+        let dir_gir = dir_repositorio.join(".gir");
+        let dir_objetos = Self::obtener_dir_objetos(dir_repositorio);
+        let commits_shallow = shallow::obtener_commits_shallow_de(dir_repositorio);
+        let mut alcanzables = HashSet::new();
+
+        let mut raices: Vec<String> =
+            referencia::obtener_refs_y_hashes(&dir_gir.join("refs"), &dir_gir)?
+                .into_iter()
+                .map(|(_, hash)| hash)
+                .collect();
+        raices.extend(Self::obtener_hashes_de_cabezas(&dir_gir)?);
+        for raiz in raices {
+            Self::agregar_alcanzables_desde(
+                raiz,
+                &dir_objetos,
+                &commits_shallow,
+                &mut alcanzables,
+            )?;
+        }
+
+        alcanzables.extend(index::obtener_hashes_del_index_de(dir_repositorio)?);
+        Ok(alcanzables)
+    }
+
+    // Agrega los objetos alcanzables desde el commit o tag anotado `hash`. Los objetos ya
+    // alcanzables no se vuelven a recorrer.
+    fn agregar_alcanzables_desde(
+        hash: String,
+        dir_objetos: &str,
+        commits_shallow: &HashSet<String>,
+        alcanzables: &mut HashSet<String>,
+    ) -> Result<(), String> {
+        let mut hash_commit = hash;
+        while TagObj::es_tag(&hash_commit, dir_objetos) {
+            alcanzables.insert(hash_commit.clone());
+            hash_commit = TagObj::from_hash_de(&hash_commit, dir_objetos)?.objeto;
+        }
+
+        let mut pendientes = vec![hash_commit];
+        while let Some(hash_commit) = pendientes.pop() {
+            if !alcanzables.insert(hash_commit.clone()) {
+                continue;
+            }
+            let commit = objects::leer_commit(&hash_commit, dir_objetos)?;
+            let objetos_tree =
+                objects::obtener_objetos_de_tree(&commit.tree, dir_objetos, alcanzables)?;
+            alcanzables.extend(objetos_tree);
+            if !commits_shallow.contains(&hash_commit) {
+                pendientes.extend(commit.padres);
+            }
+        }
+        Ok(())
+    }
+
+    // Devuelve los commits a los que apuntan HEAD, ORIG_HEAD y MERGE_HEAD cuando tienen un hash
+    // en lugar de una referencia a una rama (como HEAD desacoplado).
+    fn obtener_hashes_de_cabezas(dir_gir: &Path) -> Result<Vec<String>, String> {
+        let mut hashes = Vec::new();
+        for cabeza in CABEZAS {
+            let ruta = dir_gir.join(cabeza);
+            if !ruta.exists() {
+                continue;
+            }
+            let contenido = io::leer_a_string(ruta)?;
+            if contenido.trim().starts_with("ref:") {
+                continue;
+            }
+            // MERGE_HEAD puede tener un commit por linea
+            hashes.extend(
+                contenido
+                    .lines()
+                    .filter_map(|linea| linea.split_whitespace().next())
+                    .map(str::to_string),
+            );
+        }
+        Ok(hashes)
+    }
+
+    fn obtener_dir_objetos(dir_repositorio: &Path) -> String {
+        dir_repositorio
+            .join(".gir/objects/")
+            .to_string_lossy()
+            .to_string()
+    }
+
+    // Devuelve las rutas de los archivos que hay en objects/pack
+    fn obtener_archivos_de_packs(dir_objetos: &str) -> Result<Vec<PathBuf>, String> {
+        let dir_packs = PathBuf::from(dir_objetos).join("pack");
+        if !dir_packs.exists() {
+            return Ok(Vec::new());
+        }
+
+        let mut archivos = Vec::new();
+        for entrada in io::leer_directorio(&dir_packs)? {
+            archivos.push(entrada.map_err(|e| e.to_string())?.path());
+        }
+        Ok(archivos)
+    }
+
+    // Devuelve los hashes de los objetos sueltos (los que no estan en ningun packfile)
+    fn obtener_objetos_sueltos(dir_objetos: &str) -> Result<Vec<String>, String> {
+        let objetos = objects::obtener_objetos_del_dir(&PathBuf::from(dir_objetos))?;
+        Ok(objetos
+            .into_iter()
+            .filter(|hash| Self::ruta_objeto_suelto(dir_objetos, hash).exists())
+            .collect())
+    }
+
+    fn ruta_objeto_suelto(dir_objetos: &str, hash: &str) -> PathBuf {
+        PathBuf::from(format!("{}{}/{}", dir_objetos, &hash[..2], &hash[2..]))
+    }
+
+    // Empaqueta los objetos alcanzables en un unico packfile y borra los packfiles anteriores.
+    // Los objetos inalcanzables que estaban empaquetados se vuelven a escribir sueltos, asi el periodo
+    // de gracia corre para ellos desde ahora. Con periodo de gracia cero directamente se descartan.
+    fn reempaquetar(&self, alcanzables: &HashSet<String>) -> Result<(), String> {
+        let dir_objetos = Self::obtener_dir_objetos(&self.dir_repositorio);
+        let dir_objetos = dir_objetos.as_str();
+        let packs_anteriores = Self::obtener_archivos_de_packs(dir_objetos)?;
+        let empaquetados = Packfile::obtener_hashes_de_packs(dir_objetos)?;
+
+        if self.periodo_de_gracia != Some(Duration::ZERO) {
+            for hash in empaquetados
+                .iter()
+                .filter(|hash| !alcanzables.contains(*hash))
+            {
+                let ruta = Self::ruta_objeto_suelto(dir_objetos, hash);
+                if ruta.exists() {
+                    continue;
+                }
+                let objeto = compresion::obtener_objeto_descomprimido(hash, dir_objetos)?;
+                io::escribir_bytes(ruta, compresion::comprimir_contenido_u8(&objeto)?)?;
+            }
+        }
+
+        let mut objetos = Vec::from_iter(alcanzables.iter().cloned());
+        objetos.sort();
+        let packfile = Packfile::obtener_pack_con_archivos(objetos, dir_objetos)?;
+        let entradas = Packfile::verificar_packfile(&packfile, dir_objetos)?;
+        Packfile::guardar_packfile(&packfile, entradas, dir_objetos)?;
+
+        let nombre_pack_nuevo = format!(
+            "pack-{}",
+            Tree::encode_hex(&packfile[packfile.len() - 20..])
+        );
+        for archivo in packs_anteriores {
+            let es_pack_nuevo = archivo
+                .file_stem()
+                .is_some_and(|nombre| nombre.to_string_lossy() == nombre_pack_nuevo);
+            if !es_pack_nuevo {
+                std::fs::remove_file(&archivo).map_err(|e| e.to_string())?;
+            }
+        }
+        Ok(())
+    }
+
+    // Borra los objetos sueltos que ya quedaron en el packfile y los inalcanzables cuya antiguedad
+    // supera el periodo de gracia. Devuelve la cantidad de objetos inalcanzables borrados.
+    fn podar_objetos_sueltos(
+        &self,
+        sueltos: Vec<String>,
+        alcanzables: &HashSet<String>,
+    ) -> Result<usize, String> {
+        let dir_objetos = Self::obtener_dir_objetos(&self.dir_repositorio);
+        let mut podados = 0;
+        for hash in sueltos {
+            let ruta = Self::ruta_objeto_suelto(&dir_objetos, &hash);
+            if alcanzables.contains(&hash) {
+                std::fs::remove_file(&ruta).map_err(|e| e.to_string())?;
+            } else if self.supera_periodo_de_gracia(&ruta)? {
+                std::fs::remove_file(&ruta).map_err(|e| e.to_string())?;
+                podados += 1;
+            } else {
+                continue;
+            }
+
+            if let Some(carpeta) = ruta.parent() {
+                if io::cantidad_entradas_dir(&carpeta)? == 0 {
+                    io::rm_directorio(carpeta)?;
+                }
+            }
+        }
+        Ok(podados)
+    }
+
+    fn supera_periodo_de_gracia(&self, ruta: &Path) -> Result<bool, String> {
+        let periodo_de_gracia = match self.periodo_de_gracia {
+            Some(periodo_de_gracia) => periodo_de_gracia,
+            None => return Ok(false),
+        };
+        let modificacion = std::fs::metadata(ruta)
+            .and_then(|metadata| metadata.modified())
+            .map_err(|e| format!("No se pudo leer la fecha de {}: {}", ruta.display(), e))?;
+        let antiguedad = SystemTime::now()
+            .duration_since(modificacion)
+            .unwrap_or(Duration::ZERO);
+        Ok(antiguedad >= periodo_de_gracia)
+    }
+}
+
+impl Ejecutar for Gc {
+    /// Ejecuta el comando gc: empaqueta todos los objetos alcanzables en un unico packfile y
+    /// borra los objetos sueltos inalcanzables que superan el periodo de gracia.
+    fn ejecutar(&mut self) -> Result<String, String> {
+        self.logger.log("Ejecutando comando gc");
+
+        let alcanzables = Self::obtener_objetos_alcanzables(&self.dir_repositorio)?;
+        let sueltos =
+            Self::obtener_objetos_sueltos(&Self::obtener_dir_objetos(&self.dir_repositorio))?;
+
+        if !alcanzables.is_empty() {
+            self.reempaquetar(&alcanzables)?;
+        }
+        let podados = self.podar_objetos_sueltos(sueltos, &alcanzables)?;
+
+        let mensaje = format!(
+            "Se empaquetaron {} objetos y se borraron {} objetos inalcanzables",
+            alcanzables.len(),
+            podados
+        );
+        self.logger.log(&mensaje);
+        Ok(mensaje)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use serial_test::serial;
+    use std::{path::PathBuf, sync::Arc};
+
+    use crate::{
+        tipos_de_dato::{
+            comandos::{add::Add, hash_object::HashObject, tag::Tag},
+            logger::Logger,
+        },
+        utils::testing::{addear_archivos_y_comittear, limpiar_archivo_gir},
+    };
+
+    use super::*;
+
+    const DIR_OBJETOS: &str = ".gir/objects/";
+
+    fn escribir_objeto_inalcanzable(logger: Arc<Logger>) -> String {
+        io::escribir_bytes("tmp/gc_inalcanzable", "nadie me referencia").unwrap();
+        let mut args = vec!["-w".to_string(), "tmp/gc_inalcanzable".to_string()];
+        HashObject::from(&mut args, logger)
+            .unwrap()
+            .ejecutar()
+            .unwrap()
+    }
+
+    #[test]
+    fn test01_creacion_de_gc_con_periodo_de_gracia() {
+        let logger = Arc::new(Logger::new(PathBuf::from("tmp/gc_test01")).unwrap());
+        let gc = Gc::from(vec![], logger.clone()).unwrap();
+        assert_eq!(
+            gc.periodo_de_gracia,
+            Some(Duration::from_secs(14 * SEGUNDOS_POR_DIA))
+        );
+
+        let gc = Gc::from(vec!["--prune=now".to_string()], logger.clone()).unwrap();
+        assert_eq!(gc.periodo_de_gracia, Some(Duration::ZERO));
+
+        let gc = Gc::from(vec!["--prune=3".to_string()], logger.clone()).unwrap();
+        assert_eq!(
+            gc.periodo_de_gracia,
+            Some(Duration::from_secs(3 * SEGUNDOS_POR_DIA))
+        );
+
+        let gc = Gc::from(vec!["--no-prune".to_string()], logger.clone()).unwrap();
+        assert_eq!(gc.periodo_de_gracia, None);
+
+        assert!(Gc::from(vec!["--prune=ayer".to_string()], logger).is_err());
+    }
+
+    #[test]
+    #[serial]
+    fn test02_gc_empaqueta_los_objetos_alcanzables_y_borra_los_sueltos() {
+        let logger = Arc::new(Logger::new(PathBuf::from("tmp/gc_test02")).unwrap());
+        limpiar_archivo_gir(logger.clone());
+        io::escribir_bytes("tmp/gc_archivo", "contenido").unwrap();
+        addear_archivos_y_comittear(vec!["tmp/gc_archivo".to_string()], logger.clone());
+
+        let alcanzables = Gc::obtener_objetos_alcanzables(Path::new(".")).unwrap();
+        Gc::from(vec![], logger.clone())
+            .unwrap()
+            .ejecutar()
+            .unwrap();
+
+        assert!(Gc::obtener_objetos_sueltos(DIR_OBJETOS).unwrap().is_empty());
+        let mut empaquetados = Packfile::obtener_hashes_de_packs(DIR_OBJETOS).unwrap();
+        empaquetados.sort();
+        let mut esperados = Vec::from_iter(alcanzables.iter().cloned());
+        esperados.sort();
+        assert_eq!(empaquetados, esperados);
+        for hash in alcanzables {
+            assert!(compresion::obtener_objeto_descomprimido(&hash, DIR_OBJETOS).is_ok());
+        }
+    }
+
+    #[test]
+    #[serial]
+    fn test03_gc_deja_un_unico_packfile() {
+        let logger = Arc::new(Logger::new(PathBuf::from("tmp/gc_test03")).unwrap());
+        limpiar_archivo_gir(logger.clone());
+        io::escribir_bytes("tmp/gc_archivo", "contenido").unwrap();
+        addear_archivos_y_comittear(vec!["tmp/gc_archivo".to_string()], logger.clone());
+        Gc::from(vec![], logger.clone())
+            .unwrap()
+            .ejecutar()
+            .unwrap();
+
+        io::escribir_bytes("tmp/gc_archivo", "contenido nuevo").unwrap();
+        addear_archivos_y_comittear(vec!["tmp/gc_archivo".to_string()], logger.clone());
+        Gc::from(vec![], logger.clone())
+            .unwrap()
+            .ejecutar()
+            .unwrap();
+
+        assert_eq!(Gc::obtener_archivos_de_packs(DIR_OBJETOS).unwrap().len(), 2);
+        let alcanzables = Gc::obtener_objetos_alcanzables(Path::new(".")).unwrap();
+        assert_eq!(
+            Packfile::obtener_hashes_de_packs(DIR_OBJETOS)
+                .unwrap()
+                .len(),
+            alcanzables.len()
+        );
+    }
+
+    #[test]
+    #[serial]
+    fn test04_gc_respeta_el_periodo_de_gracia_de_los_objetos_inalcanzables() {
+        let logger = Arc::new(Logger::new(PathBuf::from("tmp/gc_test04")).unwrap());
+        limpiar_archivo_gir(logger.clone());
+        io::escribir_bytes("tmp/gc_archivo", "contenido").unwrap();
+        addear_archivos_y_comittear(vec!["tmp/gc_archivo".to_string()], logger.clone());
+        let inalcanzable = escribir_objeto_inalcanzable(logger.clone());
+
+        Gc::from(vec![], logger.clone())
+            .unwrap()
+            .ejecutar()
+            .unwrap();
+        assert!(Gc::ruta_objeto_suelto(DIR_OBJETOS, &inalcanzable).exists());
+
+        Gc::from(vec!["--prune=now".to_string()], logger.clone())
+            .unwrap()
+            .ejecutar()
+            .unwrap();
+        assert!(!compresion::existe_objeto(&inalcanzable, DIR_OBJETOS));
+    }
+
+    #[test]
+    #[serial]
+    fn test05_gc_conserva_los_tags_anotados() {
+        let logger = Arc::new(Logger::new(PathBuf::from("tmp/gc_test05")).unwrap());
+        limpiar_archivo_gir(logger.clone());
+        io::escribir_bytes("tmp/gc_archivo", "contenido").unwrap();
+        addear_archivos_y_comittear(vec!["tmp/gc_archivo".to_string()], logger.clone());
+        let args = ["-a", "v1.0", "-m", "mensaje"].map(String::from).to_vec();
+        Tag::from(args, logger.clone()).unwrap().ejecutar().unwrap();
+        let hash_tag = io::leer_a_string(".gir/refs/tags/v1.0").unwrap();
+
+        Gc::from(vec!["--prune=now".to_string()], logger)
+            .unwrap()
+            .ejecutar()
+            .unwrap();
+
+        assert!(Packfile::obtener_hashes_de_packs(DIR_OBJETOS)
+            .unwrap()
+            .contains(&hash_tag));
+        assert!(TagObj::from_hash(&hash_tag).is_ok());
+    }
+
+    #[test]
+    #[serial]
+    fn test06_gc_conserva_los_objetos_del_index_y_de_head_desacoplado() {
+        let logger = Arc::new(Logger::new(PathBuf::from("tmp/gc_test06")).unwrap());
+        limpiar_archivo_gir(logger.clone());
+        io::escribir_bytes("tmp/gc_archivo", "contenido").unwrap();
+        addear_archivos_y_comittear(vec!["tmp/gc_archivo".to_string()], logger.clone());
+        let commit = io::leer_a_string(".gir/refs/heads/master").unwrap();
+        io::escribir_bytes(".gir/HEAD", &commit).unwrap();
+        io::rm_directorio(".gir/refs/heads/master").unwrap();
+
+        io::escribir_bytes("tmp/gc_archivo", "contenido sin commitear").unwrap();
+        Add::from(vec!["tmp/gc_archivo".to_string()], logger.clone())
+            .unwrap()
+            .ejecutar()
+            .unwrap();
+        let blob_en_index = index::leer_index(logger.clone()).unwrap()[0]
+            .objeto
+            .obtener_hash();
+
+        Gc::from(vec!["--prune=now".to_string()], logger)
+            .unwrap()
+            .ejecutar()
+            .unwrap();
+
+        assert!(compresion::existe_objeto(&blob_en_index, DIR_OBJETOS));
+        assert!(compresion::existe_objeto(&commit, DIR_OBJETOS));
+        io::escribir_bytes(".gir/HEAD", "ref: refs/heads/master").unwrap();
+    }
+
+    #[test]
+    #[serial]
+    fn test07_gc_conserva_los_commits_de_orig_head_y_merge_head() {
+        let logger = Arc::new(Logger::new(PathBuf::from("tmp/gc_test07")).unwrap());
+        limpiar_archivo_gir(logger.clone());
+        io::escribir_bytes("tmp/gc_archivo", "contenido").unwrap();
+        addear_archivos_y_comittear(vec!["tmp/gc_archivo".to_string()], logger.clone());
+        let primero = io::leer_a_string(".gir/refs/heads/master").unwrap();
+        io::escribir_bytes("tmp/gc_archivo", "contenido nuevo").unwrap();
+        addear_archivos_y_comittear(vec!["tmp/gc_archivo".to_string()], logger.clone());
+        let segundo = io::leer_a_string(".gir/refs/heads/master").unwrap();
+        io::escribir_bytes(".gir/ORIG_HEAD", &primero).unwrap();
+        io::escribir_bytes(".gir/MERGE_HEAD", &segundo).unwrap();
+        io::rm_directorio(".gir/refs/heads/master").unwrap();
+
+        let dir_repositorio = std::env::current_dir().unwrap();
+        Gc::from_de(vec!["--prune=now".to_string()], dir_repositorio, logger)
+            .unwrap()
+            .ejecutar()
+            .unwrap();
+
+        assert!(compresion::existe_objeto(&primero, DIR_OBJETOS));
+        assert!(compresion::existe_objeto(&segundo, DIR_OBJETOS));
+        let tree = objects::leer_commit(&primero, DIR_OBJETOS).unwrap().tree;
+        assert!(compresion::existe_objeto(&tree, DIR_OBJETOS));
+        io::rm_directorio(".gir/ORIG_HEAD").unwrap();
+        io::rm_directorio(".gir/MERGE_HEAD").unwrap();
+    }
+}
@@ -3,6 +3,7 @@ use crate::tipos_de_dato::logger::Logger;
 use crate::utils::compresion::comprimir_contenido_u8;
 use crate::utils::io;
 use sha1::{Digest, Sha1};
+use std::fs;
 use std::path::PathBuf;
 use std::sync::Arc;
 
@@ -55,8 +56,19 @@ impl HashObject {
     /// Construye el contenido del objeto blob a partir del archivo pasado por parametro.
     /// El contenido del objeto blob es el contenido del archivo con un header que indica
     /// el tipo de objeto y su tamaño.
+    /// Si el archivo es un link simbolico, el contenido es la ruta a la que apunta el link.
     fn construir_contenido(&self) -> Result<Vec<u8>, String> {
-        let contenido = io::leer_bytes(self.ubicacion_archivo.clone())?;
+        let contenido = if self.ubicacion_archivo.is_symlink() {
+            let destino = fs::read_link(&self.ubicacion_archivo).map_err(|_| {
+                format!(
+                    "No se pudo leer el link {}",
+                    self.ubicacion_archivo.display()
+                )
+            })?;
+            destino.to_string_lossy().as_bytes().to_vec()
+        } else {
+            io::leer_bytes(self.ubicacion_archivo.clone())?
+        };
         let header = format!("blob {}\0", contenido.len());
         let contenido_total = [header.as_bytes(), &contenido].concat();
 
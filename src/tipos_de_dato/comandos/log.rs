@@ -1,5 +1,6 @@
 use std::cmp::Reverse;
 use std::collections::HashMap;
+use std::path::Path;
 use std::sync::Arc;
 
 use crate::tipos_de_dato::comando::Ejecutar;
@@ -7,7 +8,7 @@ use crate::tipos_de_dato::logger::Logger;
 
 use crate::tipos_de_dato::comandos::checkout::Checkout;
 use crate::tipos_de_dato::objetos::commit::CommitObj;
-use crate::utils::{io, ramas};
+use crate::utils::{io, ramas, shallow};
 
 pub struct Log {
     /// Rama de la cual se quiere obtener el log.
@@ -48,10 +49,25 @@ impl Log {
     /// Obtiene todos los commits que son padres del commit pasado por parametro.
     /// Devuelve un vector con los commits ordenados por fecha.
     /// En caso de haber un commit repetido, solo se utiliza uno.
+    /// Si el repositorio es shallow, el recorrido termina en los commits borde (sus padres no
+    /// estan en el repositorio).
     pub fn obtener_listas_de_commits(
         commit: CommitObj,
         logger: Arc<Logger>,
     ) -> Result<Vec<CommitObj>, String> {
+        Self::obtener_listas_de_commits_de(commit, Path::new("."), logger)
+    }
+
+    /// Igual que `obtener_listas_de_commits`, pero lee los commits del repositorio ubicado en
+    /// `dir_repositorio` en lugar del directorio actual.
+    pub fn obtener_listas_de_commits_de(
+        commit: CommitObj,
+        dir_repositorio: &Path,
+        logger: Arc<Logger>,
+    ) -> Result<Vec<CommitObj>, String> {
+        let dir_objetos = dir_repositorio.join(".gir/objects/");
+        let dir_objetos = dir_objetos.to_string_lossy();
+        let commits_shallow = shallow::obtener_commits_shallow_de(dir_repositorio);
         let mut commits: HashMap<String, CommitObj> = HashMap::new();
         let mut commits_a_revisar: Vec<CommitObj> = Vec::new();
         commits_a_revisar.push(commit);
@@ -61,8 +77,11 @@ impl Log {
                 continue;
             }
             commits.insert(commit.hash.clone(), commit.clone());
+            if commits_shallow.contains(&commit.hash) {
+                continue;
+            }
             for padre in commit.padres {
-                let commit_padre = CommitObj::from_hash(padre, logger.clone())?;
+                let commit_padre = CommitObj::from_hash_de(padre, &dir_objetos, logger.clone())?;
                 commits_a_revisar.push(commit_padre);
             }
         }
@@ -174,4 +193,22 @@ mod tests {
         assert_eq!(mail_commit, format!("<{}>", mail_original));
         assert_eq!(mensaje_commit, "mensaje");
     }
+
+    #[test]
+    #[serial]
+    fn test06_log_no_sigue_los_padres_de_un_commit_shallow() {
+        let logger = Arc::new(Logger::new(PathBuf::from("tmp/log_test06")).unwrap());
+        utils::testing::limpiar_archivo_gir(logger.clone());
+        addear_archivos_y_comittear(vec!["test_file.txt".to_string()], logger.clone());
+        io::escribir_bytes("test_file2.txt", "otro contenido").unwrap();
+        addear_archivos_y_comittear(vec!["test_file2.txt".to_string()], logger.clone());
+        let hash_segundo = io::leer_a_string(".gir/refs/heads/master").unwrap();
+        io::escribir_bytes(".gir/shallow", format!("{}\n", hash_segundo)).unwrap();
+
+        let commit = CommitObj::from_hash(hash_segundo.clone(), logger.clone()).unwrap();
+        let commits = Log::obtener_listas_de_commits(commit, logger).unwrap();
+
+        assert_eq!(commits.len(), 1);
+        assert_eq!(commits[0].hash, hash_segundo);
+    }
 }
@@ -1,7 +1,13 @@
 use std::{path::PathBuf, sync::Arc};
 
 use crate::tipos_de_dato::{
-    comando::Ejecutar, logger::Logger, objeto::Objeto, objetos::tree::Tree,
+    comando::Ejecutar,
+    logger::Logger,
+    objeto::Objeto,
+    objetos::{
+        blob::{Blob, ModoBlob},
+        tree::Tree,
+    },
     visualizaciones::Visualizaciones,
 };
 
@@ -52,11 +58,13 @@ impl LsTree {
     }
 
     /// Dado un objeto blob, devuelve un string con el formato de salida de ls-tree.
-    fn obtener_string_blob(blob: &Objeto) -> String {
+    fn obtener_string_blob(blob: &Blob) -> String {
         format!(
-            "100644 blob {}    {}\n",
+            "{} {} {}    {}\n",
+            blob.modo,
+            blob.modo.tipo_objeto(),
             blob.obtener_hash(),
-            blob.obtener_path().display()
+            blob.ubicacion.display()
         )
     }
 
@@ -100,7 +108,15 @@ impl Ejecutar for LsTree {
         for objeto in objetos_a_mostrar {
             match objeto {
                 Objeto::Blob(ref blob) => {
-                    if self.con_size {
+                    if self.con_size && blob.modo == ModoBlob::Gitlink {
+                        string_resultante.push_str(&format!(
+                            "{} {} {}       -    {}\n",
+                            blob.modo,
+                            blob.modo.tipo_objeto(),
+                            blob.obtener_hash(),
+                            blob.ubicacion.display()
+                        ));
+                    } else if self.con_size {
                         let tamanio = CatFile {
                             hash_objeto: blob.obtener_hash(),
                             logger: self.logger.clone(),
@@ -109,13 +125,14 @@ impl Ejecutar for LsTree {
                         .ejecutar()?;
 
                         string_resultante.push_str(&format!(
-                            "100644 blob {} {: >7}    {}\n",
+                            "{} blob {} {: >7}    {}\n",
+                            blob.modo,
                             blob.obtener_hash(),
                             tamanio,
                             blob.ubicacion.display()
                         ));
                     } else {
-                        string_resultante.push_str(&Self::obtener_string_blob(&objeto));
+                        string_resultante.push_str(&Self::obtener_string_blob(blob));
                     }
                 }
                 Objeto::Tree(ref tree) => {
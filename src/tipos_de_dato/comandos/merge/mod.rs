@@ -8,7 +8,7 @@ use crate::{
         objeto::Objeto,
         region::{unificar_regiones, Region},
     },
-    utils::{index, ramas},
+    utils::{hooks, index, ramas},
 };
 use std::{
     path::{self, Path, PathBuf},
@@ -25,7 +25,7 @@ use crate::{
     },
     utils::{
         compresion::descomprimir_objeto_gir,
-        index::{escribir_index, leer_index, ObjetoIndex},
+        index::{escribir_index, leer_index, MetadataIndex, ObjetoIndex, VersionesConflicto},
         io,
     },
 };
@@ -39,12 +39,17 @@ use super::{
     write_tree::{self, conseguir_arbol_en_directorio},
 };
 
+const HOOK_POST_MERGE: &str = "post-merge";
+
 pub struct Merge {
     pub logger: Arc<Logger>,
     pub branch_actual: String,
     pub branch_a_mergear: String,
     pub abort: bool,
     pub no_fast_forward: bool,
+    /// Indica si no se corren los hooks del cliente, como cuando el servidor mergea un pull
+    /// request en su repositorio.
+    pub sin_hooks: bool,
 }
 
 impl Merge {
@@ -60,6 +65,7 @@ impl Merge {
                 branch_a_mergear: "".to_string(),
                 abort: true,
                 no_fast_forward: false,
+                sin_hooks: false,
             });
         }
 
@@ -76,6 +82,7 @@ impl Merge {
             branch_a_mergear,
             abort: false,
             no_fast_forward: false,
+            sin_hooks: false,
         })
     }
 
@@ -384,16 +391,19 @@ impl Merge {
     }
 
     /// Crea un objeto index dependiendo asiganndo si hubo conflictos o no
-    /// entre los archivos a mergear y lo agrega al vector de objetos index
+    /// entre los archivos a mergear y lo agrega al vector de objetos index.
+    /// Si hubo conflictos se guardan las tres versiones del archivo.
     fn agregar_objeto_index_merge_con_conflicto(
         objeto_base: Objeto,
         objetos_index: &mut Vec<ObjetoIndex>,
-        hubo_conflictos: bool,
+        conflicto: Option<VersionesConflicto>,
     ) {
         let objeto = ObjetoIndex {
             objeto: objeto_base,
             es_eliminado: false,
-            merge: hubo_conflictos,
+            merge: conflicto.is_some(),
+            metadata: MetadataIndex::default(),
+            conflicto,
         };
 
         objetos_index.push(objeto);
@@ -426,6 +436,8 @@ impl Merge {
                     objeto: objeto_actual.clone(),
                     es_eliminado: false,
                     merge: false,
+                    metadata: MetadataIndex::default(),
+                    conflicto: None,
                 };
 
                 objetos_index.push(objeto);
@@ -460,10 +472,15 @@ impl Merge {
                     paths_con_conflictos,
                 )?;
 
+                let conflicto = hubo_conflictos.then(|| VersionesConflicto {
+                    base: Some(objeto_base.obtener_hash()),
+                    nuestra: Some(objeto_actual.obtener_hash()),
+                    suya: Some(objeto_a_mergear.obtener_hash()),
+                });
                 Self::agregar_objeto_index_merge_con_conflicto(
                     objeto_base,
                     objetos_index,
-                    hubo_conflictos,
+                    conflicto,
                 );
             }
         }
@@ -484,6 +501,7 @@ impl Merge {
             ))
         } else {
             let mut commit = Commit::from_merge(self.logger.clone(), &self.branch_actual)?;
+            commit.sin_hooks = self.sin_hooks;
             commit.ejecutar()?;
             Ok("Merge completado".to_string())
         }
@@ -632,7 +650,7 @@ impl Ejecutar for Merge {
             return Ok("No hay nada para mergear".to_string());
         }
 
-        let mensaje = if commit_base == commit_actual && !self.no_fast_forward {
+        let mut mensaje = if commit_base == commit_actual && !self.no_fast_forward {
             self.logger.log("Haciendo fast-forward");
             self.fast_forward()
         } else {
@@ -641,6 +659,18 @@ impl Ejecutar for Merge {
         }?;
 
         self.escribir_merge_head()?;
+
+        // si hubo conflictos la rama no se movio: el merge termina cuando se hace el commit
+        let commit_nuevo = Self::obtener_commit_de_branch(&self.branch_actual)?;
+        if commit_nuevo != commit_actual && !self.sin_hooks {
+            let argumentos = [commit_actual.as_str(), &commit_nuevo];
+            if let Some(advertencia) =
+                hooks::ejecutar_hook_local_posterior(HOOK_POST_MERGE, &argumentos)
+            {
+                self.logger.log(&advertencia);
+                mensaje = format!("{}\nAdvertencia: {}", mensaje, advertencia);
+            }
+        }
         Ok(mensaje)
     }
 }
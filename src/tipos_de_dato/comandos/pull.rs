@@ -192,7 +192,8 @@ impl Pull {
 
 impl Ejecutar for Pull {
     fn ejecutar(&mut self) -> Result<String, String> {
-        Fetch::new(vec![self.remoto.clone()], self.logger.clone())?.ejecutar()?;
+        let mut fetch = Fetch::new(vec![self.remoto.clone()], self.logger.clone())?;
+        fetch.ejecutar()?;
 
         let commit_head_remoto = self.obtener_head_remoto()?;
 
@@ -213,6 +214,6 @@ impl Ejecutar for Pull {
         }
         let mensaje = "Pull ejecutado con exito";
         self.logger.log(mensaje);
-        Ok(mensaje.to_string())
+        Ok(fetch.mensajes_del_remoto() + mensaje)
     }
 }
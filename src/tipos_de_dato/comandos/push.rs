@@ -1,21 +1,19 @@
 use super::set_upstream::SetUpstream;
 use crate::tipos_de_dato::comando::Ejecutar;
-use crate::tipos_de_dato::comandos::write_tree;
 use crate::tipos_de_dato::comunicacion::Comunicacion;
 use crate::tipos_de_dato::config::Config;
+use crate::tipos_de_dato::flujo::Flujo;
 use crate::tipos_de_dato::logger::Logger;
-use crate::tipos_de_dato::objetos::commit::CommitObj;
-use crate::tipos_de_dato::objetos::tree::Tree;
+use crate::tipos_de_dato::objetos::tag::TagObj;
 use crate::tipos_de_dato::packfile::Packfile;
 
 use crate::tipos_de_dato::referencia::Referencia;
+use crate::tipos_de_dato::referencia_commit::ReferenciaCommit;
 use crate::utils;
-use crate::utils::io;
 use crate::utils::path_buf::obtener_nombre;
+use crate::utils::shallow;
+use crate::utils::{compresion, hooks, io, objects};
 
-use std::collections::HashSet;
-use std::net::TcpStream;
-use std::path::Path;
 use std::path::PathBuf;
 use std::sync::Arc;
 
@@ -23,6 +21,14 @@ const FLAG_SET_UPSTREAM: &str = "--set-upstream";
 const FLAG_U: &str = "-u";
 const GIR_PUSH: &str = "\tgir push <remoto> <rama-local>:<rama-remota>\n";
 const GIR_PUSH_U: &str = "gir push --set-upstream/-u <nombre-remoto> <nombre-rama-local>";
+const REPORT_STATUS: &str = "report-status";
+const SIDE_BAND_64K: &str = "side-band-64k";
+/// Capacidades de receive-pack que se le piden al servidor si las anuncia.
+const CAPACIDADES_LOCAL: [&str; 3] = [REPORT_STATUS, "atomic", SIDE_BAND_64K];
+/// Capacidad con la que el servidor pide que no se le mande un pack thin.
+const NO_THIN: &str = "no-thin";
+const HOOK_PRE_PUSH: &str = "pre-push";
+const DIR_OBJETOS: &str = "./.gir/objects/";
 pub struct Push {
     referencia: Referencia,
     remoto: String,
@@ -159,8 +165,8 @@ impl Push {
     ///
     /// # Resultado
     /// - Devuelve la Comunicacion establecida con el server
-    fn iniciar_git_recive_pack_con_servidor(&self) -> Result<Comunicacion<TcpStream>, String> {
-        let mut comunicacion = Comunicacion::<TcpStream>::new_desde_url(
+    fn iniciar_git_recive_pack_con_servidor(&self) -> Result<Comunicacion<Flujo>, String> {
+        let mut comunicacion = Comunicacion::<Flujo>::new_desde_url(
             &self.obtener_url(&self.remoto)?,
             self.logger.clone(),
         )?;
@@ -168,42 +174,99 @@ impl Push {
         Ok(comunicacion)
     }
 
-    ///termina la comunicacion con el servidor, mandando un flush pkt y deespues un pack file vacio
-    fn terminar_y_mandar_pack_file_vacio(
+    ///termina la comunicacion con el servidor sin pedirle actualizar ninguna referencia, mandando
+    /// solo un flush pkt
+    fn terminar_sin_actualizar(
         &self,
-        comunicacion: &mut Comunicacion<TcpStream>,
+        comunicacion: &mut Comunicacion<Flujo>,
     ) -> Result<(), String> {
-        comunicacion.enviar_flush_pkt()?;
-        // el server pide que se le mande un packfile vacio
-        comunicacion.enviar_pack_file(Packfile::obtener_pack_con_archivos(
-            vec![],
-            "./.gir/objects/",
-        )?)
+        comunicacion.enviar_flush_pkt()
+    }
+
+    ///Corre el hook pre-push con el nombre y la url del remoto como argumentos y la referencia a
+    /// actualizar en stdin ('ref local' 'hash local' 'ref remota' 'hash remoto'). Si el hook falla
+    /// termina la comunicacion sin actualizar nada.
+    fn correr_hook_pre_push(
+        &self,
+        (commit_viejo, commit_nuevo, referencia_remota): &(String, String, PathBuf),
+        comunicacion: &mut Comunicacion<Flujo>,
+    ) -> Result<(), String> {
+        let url = self.obtener_url(&self.remoto)?;
+        let entrada = format!(
+            "{} {} {} {}\n",
+            self.referencia.dar_ref_local().to_string_lossy(),
+            commit_nuevo,
+            referencia_remota.to_string_lossy(),
+            commit_viejo
+        );
+        if let Err(error) =
+            hooks::ejecutar_hook_local(HOOK_PRE_PUSH, &[&self.remoto, &url], &entrada)
+        {
+            self.terminar_sin_actualizar(comunicacion)?;
+            return Err(error);
+        }
+        Ok(())
     }
 
     fn es_necesario_actualizar(&self, referencia_actualizar: &(String, String, PathBuf)) -> bool {
         referencia_actualizar.0 != referencia_actualizar.1
     }
 
-    //obtiene todo los objetos de una referencia hasta el viejo commit. Si no esta el viejo commit entonces termina la comunicacion
-    //y envia un pack file vacio
+    ///Obtiene los objetos que hay que mandar para actualizar la referencia: los alcanzables desde
+    /// el commit nuevo que no son alcanzables desde ninguna referencia anunciada por el servidor que
+    /// este en el repositorio local, porque esos el servidor ya los tiene. Tambien devuelve los
+    /// objetos de esas referencias, que se pueden usar como bases de un pack thin.
+    /// Si el commit viejo del servidor no es ancestro del nuevo termina la comunicacion sin
+    /// actualizar nada.
     fn obtener_objetos_a_enviar(
         &self,
-        referencia: &Path,
-        viejo_commit: &str,
-        comunicacion: &mut Comunicacion<TcpStream>,
-    ) -> Result<HashSet<String>, String> {
-        let objetos_a_enviar =
-            obtener_commits_y_objetos_asociados(referencia, viejo_commit, self.logger.clone());
-
-        match objetos_a_enviar {
-            Ok(objetos_a_enviar) => Ok(objetos_a_enviar),
-            Err(msj_err) => {
-                //error
-                self.terminar_y_mandar_pack_file_vacio(comunicacion)?;
-                Err(msj_err)
+        referencia_actualizar: &(String, String, PathBuf),
+        commits_y_refs_asosiado: &ReferenciaCommit,
+        comunicacion: &mut Comunicacion<Flujo>,
+    ) -> Result<(Vec<String>, Vec<String>), String> {
+        let (commit_viejo, commit_nuevo, _) = referencia_actualizar;
+        if *commit_viejo != "0".repeat(40) && !self.es_fast_forward(commit_viejo, commit_nuevo)? {
+            self.terminar_sin_actualizar(comunicacion)?;
+            return Err(
+                "El servidor tiene cambios, por favor, actualice su repositorio".to_string(),
+            );
+        }
+
+        let mut refs_del_servidor: Vec<String> = Vec::new();
+        for (hash, _) in commits_y_refs_asosiado {
+            if !compresion::existe_objeto(hash, DIR_OBJETOS) {
+                continue;
+            }
+            refs_del_servidor.push(hash.clone());
+            if TagObj::es_tag(hash, DIR_OBJETOS) {
+                refs_del_servidor.push(TagObj::pelar(hash, DIR_OBJETOS)?);
             }
         }
+
+        // en un repositorio shallow no se sigue mas alla de los commits borde
+        let mut objetos: Vec<String> = objects::obtener_objetos_alcanzables(
+            std::slice::from_ref(commit_nuevo),
+            &refs_del_servidor,
+            DIR_OBJETOS,
+            &shallow::obtener_commits_shallow(),
+        )?
+        .into_iter()
+        .collect();
+        let mut bases: Vec<String> =
+            objects::obtener_objetos_de_commits(&refs_del_servidor, DIR_OBJETOS)?
+                .into_iter()
+                .collect();
+        // ordenados para que el packfile no dependa del orden del HashSet
+        objetos.sort();
+        bases.sort();
+        Ok((objetos, bases))
+    }
+
+    //El commit viejo tiene que estar en la historia del nuevo. Si la referencia es un tag anotado
+    //se busca en la historia del commit al que apunta
+    fn es_fast_forward(&self, commit_viejo: &str, commit_nuevo: &str) -> Result<bool, String> {
+        let commit_nuevo = TagObj::pelar(commit_nuevo, DIR_OBJETOS)?;
+        objects::es_ancestro(commit_viejo, &commit_nuevo, DIR_OBJETOS)
     }
 
     ///Obtiene la referecia que hay que actulizar del servidor y todos sus componentes(viejo commit, nuevo commit y ref).
@@ -252,29 +315,33 @@ impl Push {
         Ok((commit_viejo, commit_nuevo, nombre_referencia))
     }
 
-    ///Le envia las referencia a acualizar al servidor junto con todos sus objetos asosiados
-    /// dentro del pack file. Finaliza la comunicacion
+    ///Le envia las referencia a acualizar al servidor, junto con las capacidades pedidas, y todos
+    /// sus objetos asosiados dentro del pack file. Si `bases` no esta vacio el pack es thin: los
+    /// objetos pueden ser deltas de las bases, que el servidor ya tiene y no se mandan.
+    /// Finaliza la comunicacion
     fn enviar_actualizaciones_y_objetos(
         &self,
         referencia_actualizar: (String, String, PathBuf),
-        objetos_a_enviar: HashSet<String>,
-        comunicacion: &mut Comunicacion<TcpStream>,
+        capacidades: &[String],
+        (objetos_a_enviar, bases): (Vec<String>, Vec<String>),
+        comunicacion: &mut Comunicacion<Flujo>,
     ) -> Result<(), String> {
         self.logger.log(&format!(
             "Se envia en push la referencia: {:?}",
             referencia_actualizar
         ));
 
-        comunicacion.enviar_referencia(referencia_actualizar)?;
+        comunicacion.enviar_referencia(referencia_actualizar, capacidades)?;
 
         self.logger.log(&format!(
             "Se envia en push los objetos: {:?}",
             objetos_a_enviar
         ));
 
-        comunicacion.enviar_pack_file(Packfile::obtener_pack_con_archivos(
-            objetos_a_enviar.into_iter().collect(),
-            "./.gir/objects/",
+        comunicacion.enviar_pack_file(Packfile::obtener_pack_thin(
+            objetos_a_enviar,
+            bases,
+            DIR_OBJETOS,
         )?)?;
         Ok(())
     }
@@ -288,14 +355,15 @@ impl Push {
     ///
     /// # Resultado
     ///
+    /// - las capacidades que anuncio el servidor
     /// - vector de tuplas con los commit cabeza de rama y la ref de la
     ///     del tag o la rama oen el servidor(ojo!! la direccion para el servidor no para el local)
     fn fase_de_descubrimiento(
         &self,
-        comunicacion: &mut Comunicacion<TcpStream>,
-    ) -> Result<Vec<(String, PathBuf)>, String> {
+        comunicacion: &mut Comunicacion<Flujo>,
+    ) -> Result<(Vec<String>, ReferenciaCommit), String> {
         let (
-            _capacidades_servidor,
+            capacidades_servidor,
             _commit_head_remoto,
             commits_cabezas_y_ref_rama_asosiado,
             commits_y_tags_asosiados,
@@ -303,101 +371,129 @@ impl Push {
 
         self.logger.log("Fase de descubrimiento ejecuta con exito");
 
-        Ok([
-            &commits_cabezas_y_ref_rama_asosiado[..],
-            &commits_y_tags_asosiados[..],
-        ]
-        .concat())
-    }
-}
-// funcion para obtener los commits que faltan para llegar al commit limite y los objetos asociados a cada commit
-// en caso de que sea una referencia nula, se enviara todo. En caso de que el commit limite no sea una referencia nula
-// y no se encuentre al final de la cadena de commits, se enviara un error, ya que el servidor tiene cambios que el cliente no tiene
-fn obtener_commits_y_objetos_asociados(
-    referencia: &Path,
-    commit_limite: &str,
-    logger: Arc<Logger>,
-) -> Result<HashSet<String>, String> {
-    //let ruta = format!(".gir/{}", referencia);
-    let ruta = format!(".gir/{}", referencia.to_string_lossy());
-    let ultimo_commit = io::leer_a_string(Path::new(&ruta))?;
-    if ultimo_commit.is_empty() {
-        return Ok(HashSet::new());
+        Ok((
+            capacidades_servidor,
+            [
+                &commits_cabezas_y_ref_rama_asosiado[..],
+                &commits_y_tags_asosiados[..],
+            ]
+            .concat(),
+        ))
     }
 
-    // let mut objetos_a_agregar: HashMap<String, CommitObj> = HashMap::new();
-    let mut objetos_a_agregar: HashSet<String> = HashSet::new();
-    let mut commits_a_revisar: Vec<CommitObj> = Vec::new();
-
-    let ultimo_commit = CommitObj::from_hash(ultimo_commit, logger.clone());
+    ///Lee la respuesta del servidor despues de mandarle el packfile. Si se pidio side-band-64k
+    /// llega multiplexada, con lo que escribieron los hooks del servidor como mensajes de progreso.
+    ///
+    /// # Resultado
+    /// - los mensajes del servidor, uno por linea y con el prefijo `remoto: `. Si el servidor
+    ///   rechazo alguna referencia el error tambien empieza con estos mensajes
+    fn recibir_respuesta(
+        &self,
+        capacidades: &[String],
+        comunicacion: &mut Comunicacion<Flujo>,
+    ) -> Result<String, String> {
+        let report_status = capacidades.iter().any(|c| c == REPORT_STATUS);
+        let reporte = if capacidades.iter().any(|c| c == SIDE_BAND_64K) {
+            comunicacion
+                .obtener_lineas_multiplexadas()
+                .map_err(|error| error.to_string())
+                .and_then(|lineas| match report_status {
+                    true => self.verificar_reporte(lineas),
+                    false => Ok(()),
+                })
+        } else if report_status {
+            comunicacion
+                .obtener_lineas_v2()
+                .and_then(|lineas| self.verificar_reporte(lineas))
+        } else {
+            Ok(())
+        };
 
-    match ultimo_commit {
-        Ok(ultimo_commit) => {
-            commits_a_revisar.push(ultimo_commit);
-        }
-        Err(_) => {
-            return Err(
-                "El servidor tiene cambios, por favor, actualice su repositorio".to_string(),
-            );
+        let mensajes: String = comunicacion
+            .obtener_progreso()
+            .iter()
+            .map(|mensaje| format!("remoto: {}\n", mensaje))
+            .collect();
+        match reporte {
+            Ok(()) => Ok(mensajes),
+            Err(error) => Err(mensajes + &error),
         }
     }
 
-    while let Some(commit) = commits_a_revisar.pop() {
-        if objetos_a_agregar.contains(&commit.hash) {
-            continue;
-        }
-        if commit.hash == commit_limite {
-            objetos_a_agregar.insert(commit.hash.clone());
-            break;
+    ///Verifica el report-status del servidor: una linea `unpack ok` (o `unpack 'error'`) y una
+    /// linea `ok 'ref'` o `ng 'ref' 'motivo'` por cada referencia enviada. Devuelve un error con
+    /// las referencias rechazadas y sus motivos.
+    fn verificar_reporte(&self, lineas: Vec<String>) -> Result<(), String> {
+        self.logger
+            .log(&format!("Report-status recibido en push: {:?}", lineas));
+
+        let mut lineas = lineas.iter().map(|linea| linea.trim_end_matches('\n'));
+        match lineas.next() {
+            Some("unpack ok") => {}
+            Some(linea) => {
+                let error = linea.strip_prefix("unpack ").unwrap_or(linea);
+                return Err(format!(
+                    "El servidor no pudo desempaquetar los objetos: {}",
+                    error
+                ));
+            }
+            None => return Err("El servidor no respondio el estado del push".to_string()),
         }
-        objetos_a_agregar.insert(commit.hash.clone());
-        let hash_tree = write_tree::conseguir_arbol_en_directorio(&commit.hash, "./.gir/objects/")?;
-        let tree = Tree::from_hash(&hash_tree, PathBuf::from("."), logger.clone())?;
-        objetos_a_agregar.insert(hash_tree.clone());
-        objetos_a_agregar.extend(
-            tree.obtener_objetos()
-                .iter()
-                .map(|objeto| objeto.obtener_hash()),
-        );
 
-        for padre in commit.padres {
-            let commit_padre = CommitObj::from_hash(padre, logger.clone())?;
-            commits_a_revisar.push(commit_padre);
+        let rechazos: Vec<String> = lineas
+            .filter_map(|linea| linea.strip_prefix("ng "))
+            .map(|rechazo| match rechazo.split_once(' ') {
+                Some((referencia, motivo)) => format!("\t{} ({})", referencia, motivo),
+                None => format!("\t{}", rechazo),
+            })
+            .collect();
+        if !rechazos.is_empty() {
+            return Err(format!(
+                "El servidor rechazo las referencias:\n{}",
+                rechazos.join("\n")
+            ));
         }
+        Ok(())
     }
-    if ("0".repeat(40) != *commit_limite) && !objetos_a_agregar.contains(commit_limite) {
-        return Err("El servidor tiene cambios, por favor, actualice su repositorio".to_string());
-    } else if ("0".repeat(40) != *commit_limite) && objetos_a_agregar.contains(commit_limite) {
-        objetos_a_agregar.remove(commit_limite);
-    }
-    Ok(objetos_a_agregar)
 }
-
 impl Ejecutar for Push {
     fn ejecutar(&mut self) -> Result<String, String> {
         let mut comunicacion = self.iniciar_git_recive_pack_con_servidor()?;
 
-        let commits_y_refs_asosiado = self.fase_de_descubrimiento(&mut comunicacion)?;
+        let (capacidades_servidor, commits_y_refs_asosiado) =
+            self.fase_de_descubrimiento(&mut comunicacion)?;
+        let capacidades: Vec<String> = CAPACIDADES_LOCAL
+            .iter()
+            .filter(|capacidad| capacidades_servidor.iter().any(|c| c == *capacidad))
+            .map(|capacidad| capacidad.to_string())
+            .collect();
 
         let referencia_acualizar = self.obtener_referencia_acualizar(&commits_y_refs_asosiado)?;
 
         let mensaje = if self.es_necesario_actualizar(&referencia_acualizar) {
-            let objetos_a_enviar = self.obtener_objetos_a_enviar(
-                &self.referencia.dar_ref_local(),
-                &referencia_acualizar.0,
+            self.correr_hook_pre_push(&referencia_acualizar, &mut comunicacion)?;
+            let (objetos_a_enviar, mut bases) = self.obtener_objetos_a_enviar(
+                &referencia_acualizar,
+                &commits_y_refs_asosiado,
                 &mut comunicacion,
             )?;
+            if capacidades_servidor.iter().any(|c| c == NO_THIN) {
+                bases.clear();
+            }
 
             self.enviar_actualizaciones_y_objetos(
                 referencia_acualizar,
-                objetos_a_enviar,
+                &capacidades,
+                (objetos_a_enviar, bases),
                 &mut comunicacion,
             )?;
-            "Push ejecutado con exito".to_string()
+            let mensajes_del_remoto = self.recibir_respuesta(&capacidades, &mut comunicacion)?;
+            mensajes_del_remoto + "Push ejecutado con exito"
         } else {
-            self.terminar_y_mandar_pack_file_vacio(&mut comunicacion)?;
+            self.terminar_sin_actualizar(&mut comunicacion)?;
             "Nada que actualizar".to_string()
         };
+        comunicacion.finalizar()?;
 
         if self.set_upstream && !self.referencia.es_tag() {
             SetUpstream::new(
@@ -26,6 +26,9 @@ pub struct Rebase {
     pub abort: bool,
     /// Indica si se debe continuar el rebase.
     pub continue_: bool,
+    /// Indica si no se corren los hooks del cliente al crear los commits, como cuando el
+    /// servidor mergea un pull request en su repositorio.
+    pub sin_hooks: bool,
 }
 
 impl Rebase {
@@ -44,6 +47,7 @@ impl Rebase {
                 logger,
                 abort: true,
                 continue_: false,
+                sin_hooks: false,
             }),
             "--continue" => Ok(Rebase {
                 rama: None,
@@ -51,6 +55,7 @@ impl Rebase {
                 logger,
                 abort: false,
                 continue_: true,
+                sin_hooks: false,
             }),
             _ => Ok(Rebase {
                 rama: Some(arg.clone()),
@@ -58,6 +63,7 @@ impl Rebase {
                 logger,
                 abort: false,
                 continue_: false,
+                sin_hooks: false,
             }),
         }
     }
@@ -255,6 +261,7 @@ impl Rebase {
                 mensaje: commit.mensaje,
                 logger: self.logger.clone(),
                 rama_actual: self.rama_actual.clone(),
+                sin_hooks: self.sin_hooks,
             };
             comando_commit.ejecutar()?;
         }
@@ -301,6 +308,7 @@ impl Rebase {
             &mut vec!["-m".to_string(), mensaje_commit],
             self.logger.clone(),
         )?;
+        commit.sin_hooks = self.sin_hooks;
         commit.ejecutar()?;
 
         let contenido_to_do = io::leer_a_string(".gir/rebase-merge/git-rebase-todo")?;
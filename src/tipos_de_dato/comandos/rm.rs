@@ -3,7 +3,7 @@ use std::{collections::HashSet, path::PathBuf, sync::Arc};
 use crate::{
     tipos_de_dato::{comando::Ejecutar, logger::Logger, objeto::Objeto},
     utils::{
-        index::{crear_index, escribir_index, leer_index, ObjetoIndex},
+        index::{crear_index, escribir_index, leer_index, MetadataIndex, ObjetoIndex},
         io::{self, rm_directorio},
     },
 };
@@ -129,11 +129,13 @@ impl Ejecutar for Remove {
                 Err("No se puede borrar un directorio sin la opcion -r".to_string())?;
             }
             let nuevo_objeto =
-                Objeto::from_directorio(ubicacion.clone(), None, self.logger.clone())?;
+                Objeto::from_directorio(ubicacion.clone(), None, None, self.logger.clone())?;
             let nuevo_objeto_index = ObjetoIndex {
                 merge: false,
                 es_eliminado: true,
                 objeto: nuevo_objeto.clone(),
+                metadata: MetadataIndex::default(),
+                conflicto: None,
             };
 
             let indice = self
@@ -169,6 +171,7 @@ mod tests {
         comando::Ejecutar,
         comandos::{add::Add, commit::Commit},
     };
+    use crate::utils::testing::leer_index_como_texto;
 
     use super::*;
 
@@ -217,7 +220,7 @@ mod tests {
         let args = vec!["--cached".to_string(), "test_file.txt".to_string()];
         Remove::from(args, logger).unwrap().ejecutar().unwrap();
 
-        let index = io::leer_a_string(".gir/index").unwrap();
+        let index = leer_index_como_texto();
         assert_eq!(
             index,
             "- 0 100644 678e12dc5c03a7cf6e9f64e688868962ab5d8b65 test_file.txt\n"
@@ -250,7 +253,7 @@ mod tests {
         ];
         Remove::from(args, logger).unwrap().ejecutar().unwrap();
 
-        let index = io::leer_a_string(".gir/index").unwrap();
+        let index = leer_index_como_texto();
 
         assert_eq!(
             index,
@@ -281,7 +284,7 @@ mod tests {
         let mut remove = Remove::from(args, logger).unwrap();
         remove.ejecutar().unwrap();
 
-        let index = io::leer_a_string(".gir/index").unwrap();
+        let index = leer_index_como_texto();
 
         assert_eq!(
             index,
@@ -313,7 +316,7 @@ mod tests {
         let args = vec!["-r".to_string(), "tmp/test_dir".to_string()];
         Remove::from(args, logger).unwrap().ejecutar().unwrap();
 
-        let index = io::leer_a_string(".gir/index").unwrap();
+        let index = leer_index_como_texto();
 
         assert_eq!(
             index,
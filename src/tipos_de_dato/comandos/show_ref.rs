@@ -5,7 +5,7 @@ use std::{
 };
 
 use crate::{
-    tipos_de_dato::{comando::Ejecutar, logger::Logger},
+    tipos_de_dato::{comando::Ejecutar, logger::Logger, objetos::tag::TagObj},
     utils::{io, path_buf},
 };
 
@@ -15,10 +15,16 @@ pub struct ShowRef {
     show_heads: bool,
     show_remotes: bool,
     show_tags: bool,
+    /// Si es true, por cada tag anotado se muestra tambien el commit al que apunta como `<tag>^{}`.
+    dereference: bool,
 }
 
 impl ShowRef {
-    pub fn from(args: Vec<String>, logger: Arc<Logger>) -> Result<Self, String> {
+    pub fn from(mut args: Vec<String>, logger: Arc<Logger>) -> Result<Self, String> {
+        let cantidad_args = args.len();
+        args.retain(|arg| arg != "-d" && arg != "--dereference");
+        let dereference = args.len() != cantidad_args;
+
         if args.is_empty() {
             return Ok(ShowRef {
                 logger,
@@ -26,6 +32,7 @@ impl ShowRef {
                 show_heads: true,
                 show_remotes: true,
                 show_tags: true,
+                dereference,
             });
         }
 
@@ -36,6 +43,7 @@ impl ShowRef {
                 show_heads: true,
                 show_remotes: true,
                 show_tags: true,
+                dereference,
             });
         }
 
@@ -58,6 +66,7 @@ impl ShowRef {
             show_heads,
             show_tags,
             show_remotes: false,
+            dereference,
         })
     }
 
@@ -145,6 +154,10 @@ impl Ejecutar for ShowRef {
         let mut salida: Vec<String> = Vec::new();
 
         for (ubicacion, contenido) in refs {
+            if self.dereference && TagObj::es_tag(&contenido, ".gir/objects/") {
+                let commit = TagObj::pelar(&contenido, ".gir/objects/")?;
+                salida.push(format!("{} {}^{{}}\n", commit, ubicacion));
+            }
             salida.push(format!("{} {}\n", contenido, ubicacion));
         }
 
@@ -163,8 +176,15 @@ mod tests {
     use serial_test::serial;
 
     use crate::{
-        tipos_de_dato::{comando::Ejecutar, comandos::show_ref::ShowRef, logger::Logger},
-        utils::testing::limpiar_archivo_gir,
+        tipos_de_dato::{
+            comando::Ejecutar,
+            comandos::{show_ref::ShowRef, tag::Tag},
+            logger::Logger,
+        },
+        utils::{
+            self, ramas,
+            testing::{addear_archivos_y_comittear, limpiar_archivo_gir},
+        },
     };
 
     fn crear_referencias() -> io::Result<()> {
@@ -230,4 +250,31 @@ mod tests {
 
         assert_eq!(resultado, "hash0 HEAD\nhash0 refs/heads/master\nhash1 refs/heads/rama1\nhash2 refs/heads/rama2\nhash3 refs/remotes/origin/rama1\nhash4 refs/remotes/origin/rama2\nhash5 refs/tags/tag1\nhash6 refs/tags/tag2\n");
     }
+
+    #[test]
+    #[serial]
+    fn test05_show_ref_dereference_muestra_el_commit_de_los_tags_anotados() {
+        let logger = Arc::new(Logger::new(PathBuf::from("tmp/show_ref_test05")).unwrap());
+        limpiar_archivo_gir(logger.clone());
+        utils::io::escribir_bytes("tmp/show_ref_test05_archivo", "contenido").unwrap();
+        addear_archivos_y_comittear(
+            vec!["tmp/show_ref_test05_archivo".to_string()],
+            logger.clone(),
+        );
+        let commit = ramas::obtener_hash_commit_asociado_rama_actual().unwrap();
+        let args = ["-a", "v1.0", "-m", "mensaje"].map(String::from).to_vec();
+        Tag::from(args, logger.clone()).unwrap().ejecutar().unwrap();
+        let hash_tag = utils::io::leer_a_string(".gir/refs/tags/v1.0").unwrap();
+
+        let mut show_ref =
+            ShowRef::from(vec!["--tags".to_string(), "-d".to_string()], logger).unwrap();
+        let resultado = show_ref.ejecutar().unwrap();
+
+        let mut esperado = [
+            format!("{} refs/tags/v1.0\n", hash_tag),
+            format!("{} refs/tags/v1.0^{{}}\n", commit),
+        ];
+        esperado.sort();
+        assert_eq!(resultado, esperado.join(""));
+    }
 }
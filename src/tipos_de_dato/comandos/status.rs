@@ -10,7 +10,7 @@ const RESET: &str = "\x1B[0m";
 use crate::{
     tipos_de_dato::{comando::Ejecutar, logger::Logger, objeto::Objeto, objetos::tree::Tree},
     utils::{
-        index::{leer_index, ObjetoIndex},
+        index::{leer_index, CacheStat, ObjetoIndex},
         io, ramas,
     },
 };
@@ -52,8 +52,12 @@ impl Status {
     pub fn from(logger: Arc<Logger>) -> Result<Status, String> {
         let index = leer_index(logger.clone())?;
         let tree_commit_head = obtener_arbol_del_commit_head(logger.clone());
+        let mut cache = CacheStat::leer();
         let tree_directorio_actual =
-            Tree::from_directorio(PathBuf::from("./"), None, logger.clone())?;
+            Tree::from_directorio(PathBuf::from("./"), None, Some(&mut cache), logger.clone())?;
+        if let Err(error) = cache.guardar() {
+            logger.log(&format!("No se pudo guardar el cache del index: {}", error));
+        }
         Ok(Status {
             logger,
             index,
@@ -422,4 +426,27 @@ mod tests {
         assert_eq!(trackeados[0], "modificado: test_file.txt");
         assert!(!nombre_esta_en_vector(untrackeados, "test_file.txt"));
     }
+
+    #[test]
+    #[serial]
+    fn test07_una_modificacion_del_mismo_tamanio_se_detecta_aunque_haya_cache() {
+        limpiar_archivo_gir();
+        let logger = Arc::new(Logger::new(PathBuf::from("tmp/status_test07")).unwrap());
+        crear_test_file();
+        addear_archivos_y_comittear(vec!["test_file.txt".to_string()], logger.clone());
+        std::fs::File::options()
+            .write(true)
+            .open("test_file.txt")
+            .unwrap()
+            .set_modified(std::time::SystemTime::now() - std::time::Duration::from_secs(10))
+            .unwrap();
+        let status = Status::from(logger.clone()).unwrap();
+        assert!(status.obtener_trackeados().unwrap().is_empty());
+
+        io::escribir_bytes("test_file.txt", "test_filE").unwrap();
+        let status = Status::from(logger).unwrap();
+        let trackeados = status.obtener_trackeados().unwrap();
+        modicar_test_file();
+        assert_eq!(trackeados, vec!["modificado: test_file.txt".to_string()]);
+    }
 }
@@ -1,34 +1,63 @@
 use std::sync::Arc;
 
 use crate::{
-    tipos_de_dato::{comando::Ejecutar, logger::Logger},
-    utils::{self, io, ramas},
+    tipos_de_dato::{
+        comando::Ejecutar, comandos::commit::armar_timestamp_commit, logger::Logger,
+        objetos::tag::TagObj,
+    },
+    utils::{self, compresion, gir_config, io, ramas},
 };
 
+const GIR_TAG: &str = "gir tag [-a <nombre> -m <mensaje> | <nombre>] [<commit>]";
+
 pub struct Tag {
     logger: Arc<Logger>,
     tag_to_create: Option<String>,
+    /// Mensaje del tag anotado. Si es None se crea un tag liviano.
+    mensaje: Option<String>,
+    /// Commit (o rama) al que apunta el tag. Si es None se usa el commit de la rama actual.
+    commit: Option<String>,
 }
 
 impl Tag {
     /// Devuelve un Tag con los parametros ingresados por el usuario.
+    /// Con `-a` y `-m` se crea un tag anotado, que se guarda como un objeto tag.
     pub fn from(args: Vec<String>, logger: Arc<Logger>) -> Result<Tag, String> {
-        if args.is_empty() {
-            return Ok(Tag {
-                logger,
-                tag_to_create: None,
-            });
-        }
+        let mut anotado = false;
+        let mut mensaje = None;
+        let mut posicionales = Vec::new();
 
-        if args.len() != 1 {
-            return Err("Cantidad de argumentos invalida".to_string());
+        let mut args = args.into_iter();
+        while let Some(arg) = args.next() {
+            match arg.as_str() {
+                "-a" => anotado = true,
+                "-m" => {
+                    let texto = args.next().ok_or(format!(
+                        "No se especifico un mensaje luego de -m\n{}",
+                        GIR_TAG
+                    ))?;
+                    mensaje = Some(texto);
+                }
+                _ => posicionales.push(arg),
+            }
         }
 
-        let tag_to_create = Some(args[0].clone());
+        if anotado && mensaje.is_none() {
+            return Err(format!(
+                "Un tag anotado necesita un mensaje (-m)\n{}",
+                GIR_TAG
+            ));
+        }
+        if posicionales.len() > 2 || (posicionales.is_empty() && mensaje.is_some()) {
+            return Err(format!("Cantidad de argumentos invalida\n{}", GIR_TAG));
+        }
 
+        let mut posicionales = posicionales.into_iter();
         Ok(Tag {
             logger,
-            tag_to_create,
+            tag_to_create: posicionales.next(),
+            mensaje,
+            commit: posicionales.next(),
         })
     }
 
@@ -41,6 +70,42 @@ impl Tag {
         Ok(tags)
     }
 
+    /// Devuelve el hash del commit al que tiene que apuntar el tag: el de la rama o hash pedido
+    /// o, si no se indico ninguno, el de la rama actual.
+    fn obtener_commit_a_taggear(&self) -> Result<String, String> {
+        let commit = match &self.commit {
+            None => return ramas::obtener_hash_commit_asociado_rama_actual(),
+            Some(rama) if ramas::existe_la_rama(rama) => {
+                ramas::obtener_hash_commit_asociado_rama(rama)?
+            }
+            Some(hash) => hash.to_string(),
+        };
+        if !compresion::existe_objeto(&commit, ".gir/objects/") {
+            return Err(format!("No existe el objeto {}", commit));
+        }
+        Ok(commit)
+    }
+
+    /// Crea el objeto tag anotado apuntando al commit dado y devuelve su hash.
+    /// El tagger y el mail se toman del archivo de configuracion.
+    fn crear_objeto_tag(&self, tag: &str, commit: &str, mensaje: &str) -> Result<String, String> {
+        if commit.is_empty() {
+            return Err("No hay ningun commit para taggear".to_string());
+        }
+        gir_config::armar_config_con_mail_y_nombre()?;
+        let (nombre, mail) = gir_config::conseguir_nombre_y_mail_del_config()?;
+        let contenido = TagObj::formatear_contenido(
+            commit,
+            "commit",
+            tag,
+            &nombre,
+            &mail,
+            &armar_timestamp_commit()?,
+            &format!("{}\n", mensaje),
+        );
+        TagObj::escribir(&contenido, ".gir/objects/")
+    }
+
     /// Crea un tag con el nombre ingresado por el usuario.
     /// Si el tag ya existe, devuelve un error.
     fn crear_tag(&self, tag: &str) -> Result<(), String> {
@@ -49,9 +114,13 @@ impl Tag {
         }
 
         let ubicacion = format!(".gir/refs/tags/{}", tag);
-        let commit = ramas::obtener_hash_commit_asociado_rama_actual()?;
+        let commit = self.obtener_commit_a_taggear()?;
+        let hash = match &self.mensaje {
+            Some(mensaje) => self.crear_objeto_tag(tag, &commit, mensaje)?,
+            None => commit,
+        };
 
-        io::escribir_bytes(ubicacion, commit)?;
+        io::escribir_bytes(ubicacion, hash)?;
 
         self.logger.log(&format!("Tag {} creado con exito", tag));
 
@@ -82,8 +151,17 @@ mod tests {
     use serial_test::serial;
 
     use crate::{
-        tipos_de_dato::{comando::Ejecutar, comandos::tag::Tag, logger::Logger},
-        utils::{tags::existe_tag, testing::limpiar_archivo_gir},
+        tipos_de_dato::{
+            comando::Ejecutar,
+            comandos::{cat_file::CatFile, tag::Tag},
+            logger::Logger,
+            objetos::tag::TagObj,
+        },
+        utils::{
+            io, ramas,
+            tags::existe_tag,
+            testing::{addear_archivos_y_comittear, limpiar_archivo_gir},
+        },
     };
 
     #[test]
@@ -120,4 +198,68 @@ mod tests {
 
         assert_eq!(tags, "mi_tag\notro_tag");
     }
+
+    #[test]
+    fn test04_tag_anotado_sin_mensaje_es_invalido() {
+        let logger = Arc::new(Logger::new(PathBuf::from("tmp/tag_test04")).unwrap());
+        let args = vec!["-a".to_string(), "v1.0".to_string()];
+
+        assert!(Tag::from(args, logger).is_err());
+    }
+
+    #[test]
+    #[serial]
+    fn test05_crear_tag_anotado_escribe_un_objeto_tag() {
+        let logger = Arc::new(Logger::new(PathBuf::from("tmp/tag_test05")).unwrap());
+        limpiar_archivo_gir(logger.clone());
+        io::escribir_bytes("tmp/tag_test05_archivo", "contenido").unwrap();
+        addear_archivos_y_comittear(vec!["tmp/tag_test05_archivo".to_string()], logger.clone());
+        let commit = ramas::obtener_hash_commit_asociado_rama_actual().unwrap();
+        let args = vec![
+            "-a".to_string(),
+            "v1.0".to_string(),
+            "-m".to_string(),
+            "primera version".to_string(),
+        ];
+
+        Tag::from(args, logger.clone()).unwrap().ejecutar().unwrap();
+
+        let hash_tag = io::leer_a_string(".gir/refs/tags/v1.0").unwrap();
+        let tag = TagObj::from_hash(&hash_tag).unwrap();
+        assert_eq!(tag.objeto, commit);
+        assert_eq!(tag.nombre, "v1.0");
+        assert_eq!(tag.mensaje, "primera version\n");
+
+        let mut cat_file = CatFile::from(
+            &mut vec!["-t".to_string(), hash_tag.clone()],
+            logger.clone(),
+        )
+        .unwrap();
+        assert_eq!(cat_file.ejecutar().unwrap(), "tag");
+        let mut cat_file = CatFile::from(&mut vec!["-p".to_string(), hash_tag], logger).unwrap();
+        assert!(cat_file.ejecutar().unwrap().starts_with(&format!(
+            "object {}\ntype commit\ntag v1.0\ntagger ",
+            commit
+        )));
+    }
+
+    #[test]
+    #[serial]
+    fn test06_crear_tag_liviano_sobre_otro_commit() {
+        let logger = Arc::new(Logger::new(PathBuf::from("tmp/tag_test06")).unwrap());
+        limpiar_archivo_gir(logger.clone());
+        io::escribir_bytes("tmp/tag_test06_archivo", "contenido").unwrap();
+        addear_archivos_y_comittear(vec!["tmp/tag_test06_archivo".to_string()], logger.clone());
+        let primer_commit = ramas::obtener_hash_commit_asociado_rama_actual().unwrap();
+        io::escribir_bytes("tmp/tag_test06_archivo", "otro contenido").unwrap();
+        addear_archivos_y_comittear(vec!["tmp/tag_test06_archivo".to_string()], logger.clone());
+
+        let args = vec!["viejo".to_string(), primer_commit.clone()];
+        Tag::from(args, logger).unwrap().ejecutar().unwrap();
+
+        assert_eq!(
+            io::leer_a_string(".gir/refs/tags/viejo").unwrap(),
+            primer_commit
+        );
+    }
 }
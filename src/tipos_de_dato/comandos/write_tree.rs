@@ -6,7 +6,7 @@ use crate::tipos_de_dato::logger::Logger;
 use crate::tipos_de_dato::objeto::Objeto;
 use crate::tipos_de_dato::objetos::tree::Tree;
 use crate::utils::compresion::descomprimir_objeto;
-use crate::utils::index::{generar_objetos_raiz, leer_index, ObjetoIndex};
+use crate::utils::index::{generar_objetos_raiz, leer_index, MetadataIndex, ObjetoIndex};
 
 /// Dado un hash de un commit y una ubicacion de donde buscar el objeto.
 /// Devuelve el hash del arbol de ese commit.
@@ -38,6 +38,8 @@ fn aplicar_index_a_arbol(arbol_index: &[ObjetoIndex], arbol_padre: &[Objeto]) ->
             es_eliminado: false,
             merge: false,
             objeto: objeto_padre.clone(),
+            metadata: MetadataIndex::default(),
+            conflicto: None,
         };
         arbol_mergeado.insert(objeto_padre.obtener_path(), objeto_index);
     }
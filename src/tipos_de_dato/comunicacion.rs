@@ -1,37 +1,100 @@
 use crate::err_comunicacion::ErrorDeComunicacion;
 use crate::tipos_de_dato::packfile::Packfile;
-use crate::utils::{self, strings};
-use std::io::{Read, Write};
+use crate::utils::{self, gir_config, strings};
+use std::io::{Cursor, ErrorKind, Read, Write};
 use std::net::TcpStream;
-use std::path::PathBuf;
+use std::path::{Path, PathBuf};
 use std::str;
 use std::sync::Arc;
 
+use super::flujo::{self, Flujo};
+use super::http::flujo_http::FlujoHttp;
 use super::logger::Logger;
 use super::respuesta_pedido::RespuestaDePedido;
 
+/// Largo maximo del contenido de una linea pkt (65520 bytes menos los 4 del largo).
+const LARGO_MAXIMO_CONTENIDO_PKT: usize = 65516;
+/// Canales de la multiplexacion por side-band.
+const CANAL_DATOS: u8 = 1;
+const CANAL_PROGRESO: u8 = 2;
+const CANAL_ERROR: u8 = 3;
+
+/// Linea pkt leida en el protocolo v2, donde ademas del flush-pkt (`0000`) existe el
+/// delim-pkt (`0001`) que separa las partes de un pedido o de una respuesta.
+enum LineaPkt {
+    Flush,
+    Delimitador,
+    Contenido(Vec<u8>),
+}
+
 pub struct Comunicacion<T: Read + Write> {
     flujo: T,
     repositorio: Option<String>,
     logger: Arc<Logger>,
+    /// Mensajes de progreso recibidos del servidor por side-band.
+    progreso: Vec<String>,
+    /// `usuario:contrasenia` de la url en base64, que se mandan en el pedido al servidor.
+    credenciales: Option<String>,
 }
 
 impl<T: Write + Read> Comunicacion<T> {
     ///Crea una comunicacion en base a una url.
-    /// La url tiene el formato ip:puerto/repositorio/
-    pub fn new_desde_url(
+    /// La url tiene el formato ip:puerto/repositorio/ para hablar con el servidor gir, o
+    /// http://ip:puerto/repositorio/ para hablar con el servidor http por smart http. Con
+    /// girs:// y https:// se habla con los mismos servidores por TLS.
+    /// El ip:puerto puede empezar con `usuario:contrasenia@`, pero solo con TLS para que las
+    /// credenciales no viajen sin cifrar.
+    /// Para TLS, ademas de los certificados del sistema se confia en el `certificado_tls` del
+    /// ~/.girconfig (o de la variable GIR_CERTIFICADO_TLS), si tiene.
+    pub fn new_desde_url(url: &str, logger: Arc<Logger>) -> Result<Comunicacion<Flujo>, String> {
+        let certificado = gir_config::conseguir_certificado_tls().map(PathBuf::from);
+        Self::new_desde_url_con_certificado(url, certificado.as_deref(), logger)
+    }
+
+    /// Igual que `new_desde_url`, pero para TLS confia en `certificado` en lugar del
+    /// configurado.
+    pub fn new_desde_url_con_certificado(
         url: &str,
+        certificado: Option<&Path>,
         logger: Arc<Logger>,
-    ) -> Result<Comunicacion<TcpStream>, String> {
+    ) -> Result<Comunicacion<Flujo>, String> {
         let (ip_puerto, repositorio) = utils::strings::obtener_ip_puerto_y_repositorio(url)?;
+        let (credenciales, ip_puerto) = strings::separar_credenciales(&ip_puerto);
+        let credenciales =
+            credenciales.map(|credenciales| strings::codificar_base64(credenciales.as_bytes()));
+        let usa_tls = url.starts_with(strings::PREFIJO_URL_HTTPS)
+            || url.starts_with(strings::PREFIJO_URL_GIR_TLS);
+        if credenciales.is_some() && !usa_tls {
+            return Err(
+                "No se mandan credenciales sin TLS, use una url https:// o girs://".to_string(),
+            );
+        }
 
-        let flujo = TcpStream::connect(ip_puerto)
-            .map_err(|e| format!("Fallo en en la coneccion con el servidor.\n{}\n", e))?;
+        let flujo = if url.starts_with(strings::PREFIJO_URL_HTTP) {
+            Flujo::Http(Box::new(FlujoHttp::new(&ip_puerto, &repositorio, None)))
+        } else if url.starts_with(strings::PREFIJO_URL_HTTPS) {
+            let conector = flujo::crear_conector_tls(certificado)?;
+            Flujo::Http(Box::new(FlujoHttp::new(
+                &ip_puerto,
+                &repositorio,
+                Some(conector),
+            )))
+        } else if url.starts_with(strings::PREFIJO_URL_GIR_TLS) {
+            let conector = flujo::crear_conector_tls(certificado)?;
+            Flujo::Tls(Box::new(flujo::conectar_tls(&conector, &ip_puerto)?))
+        } else {
+            Flujo::Tcp(
+                TcpStream::connect(ip_puerto)
+                    .map_err(|e| format!("Fallo en en la coneccion con el servidor.\n{}\n", e))?,
+            )
+        };
 
         Ok(Comunicacion {
             flujo,
             repositorio: Some(repositorio),
             logger,
+            progreso: Vec::new(),
+            credenciales,
         })
     }
 
@@ -40,6 +103,20 @@ impl<T: Write + Read> Comunicacion<T> {
             flujo,
             repositorio: None,
             logger,
+            progreso: Vec::new(),
+            credenciales: None,
+        }
+    }
+
+    /// Crea una comunicacion del lado del servidor sobre cualquier flujo, por ejemplo el body
+    /// de un request de smart http.
+    pub fn new_para_server_sobre(flujo: T, logger: Arc<Logger>) -> Comunicacion<T> {
+        Comunicacion {
+            flujo,
+            repositorio: None,
+            logger,
+            progreso: Vec::new(),
+            credenciales: None,
         }
     }
 
@@ -50,6 +127,8 @@ impl<T: Write + Read> Comunicacion<T> {
             logger,
             flujo,
             repositorio: Some(repositorio),
+            progreso: Vec::new(),
+            credenciales: None,
         }
     }
 
@@ -68,7 +147,11 @@ impl<T: Write + Read> Comunicacion<T> {
     ///
     /// - ''git-upload-pack 'directorio'\0host='host'\0\0verision='numero de version'\0''
     ///
-    pub fn iniciar_git_upload_pack_con_servidor(&mut self) -> Result<(), String> {
+    /// Si se pide la version 2 y el servidor no la soporta, responde igual en version 1.
+    pub fn iniciar_git_upload_pack_con_servidor(
+        &mut self,
+        numero_de_version: u32,
+    ) -> Result<(), String> {
         self.logger.log("Iniciando git upload pack con el servidor");
         let comando = "git-upload-pack";
         let repositorio = self
@@ -76,12 +159,12 @@ impl<T: Write + Read> Comunicacion<T> {
             .clone()
             .ok_or("No se puede iniciar la comunicacion falta repositorio".to_string())?;
         let host = "gir.com";
-        let numero_de_version = 1;
 
-        let mensaje = format!(
+        let mut mensaje = format!(
             "{} {}\0host={}\0\0version={}\0",
             comando, repositorio, host, numero_de_version
         );
+        self.agregar_credenciales(&mut mensaje);
         let pedido = strings::obtener_linea_con_largo_hex(&mensaje);
         self.enviar(&pedido)?;
         Ok(())
@@ -103,15 +186,23 @@ impl<T: Write + Read> Comunicacion<T> {
         let host = "gir.com";
         let numero_de_version = 1;
 
-        let mensaje = format!(
+        let mut mensaje = format!(
             "{} {}\0host={}\0\0version={}\0",
             comando, repositorio, host, numero_de_version
         );
+        self.agregar_credenciales(&mut mensaje);
         let pedido = strings::obtener_linea_con_largo_hex(&mensaje);
         self.enviar(&pedido)?;
         Ok(())
     }
 
+    /// Agrega las credenciales de la url, si tiene, a los parametros extra del pedido.
+    fn agregar_credenciales(&self, mensaje: &mut String) {
+        if let Some(credenciales) = &self.credenciales {
+            mensaje.push_str(&format!("credenciales={}\0", credenciales));
+        }
+    }
+
     ///acepta a una sola linea de la comunicacion
     pub fn aceptar_pedido(&mut self) -> Result<RespuestaDePedido, String> {
         let tamanio = self.obtener_largo_de_la_linea()?;
@@ -246,6 +337,10 @@ impl<T: Write + Read> Comunicacion<T> {
                 format!("Fallo en la lectura de la respuesta del servidor.\n{}\n", e)
             })?;
 
+            // si se cerro la conexion se devuelve lo leido, y el packfile se valida despues
+            if bytes_read == 0 {
+                break;
+            }
             // Copiar los bytes leídos al búfer principal
             buffer.extend_from_slice(&temp_buffer[0..bytes_read]);
             if buffer.len() > 20 && Packfile::verificar_checksum(&buffer) {
@@ -305,9 +400,20 @@ impl<T: Write + Read> Comunicacion<T> {
     ///     con respecto a las del servidor
     /// - capacidades: las capacidades que va a ver en la comunicacion con el servidor
     pub fn enviar_pedidos_al_servidor_pkt(
+        &mut self,
+        pedidos: Vec<String>,
+        capacidades: String,
+    ) -> Result<(), String> {
+        self.enviar_pedidos_y_lineas_al_servidor_pkt(pedidos, capacidades, &[])
+    }
+
+    ///Igual que `enviar_pedidos_al_servidor_pkt`, pero antes del flush pkt manda las lineas
+    /// extra dadas (por ejemplo las lineas `shallow` y `deepen` de un pedido shallow)
+    pub fn enviar_pedidos_y_lineas_al_servidor_pkt(
         &mut self,
         mut pedidos: Vec<String>,
         capacidades: String,
+        lineas_extra: &[String],
     ) -> Result<(), String> {
         self.anadir_capacidades_primer_pedido(&mut pedidos, capacidades);
 
@@ -315,6 +421,12 @@ impl<T: Write + Read> Comunicacion<T> {
             let pedido_con_formato = self.dar_formato_de_solicitud(pedido);
             self.enviar(&pedido_con_formato)?;
         }
+        for linea in lineas_extra {
+            self.enviar(&strings::obtener_linea_con_largo_hex(&format!(
+                "{}\n",
+                linea
+            )))?;
+        }
 
         self.enviar_flush_pkt()?;
 
@@ -345,6 +457,15 @@ impl<T: Write + Read> Comunicacion<T> {
         Ok(())
     }
 
+    /// Se asegura de que el servidor reciba todo lo enviado. Hace falta cuando despues de enviar
+    /// no se lee ninguna respuesta, porque por smart http lo escrito recien se manda al leer o
+    /// al finalizar.
+    pub fn finalizar(&mut self) -> Result<(), String> {
+        self.flujo
+            .flush()
+            .map_err(|e| format!("Fallo en el envio del mensaje.\n{}\n", e))
+    }
+
     ///Envia al servidor todo el contendio(los hash de los objetos) que ya se tiene y que no debe
     /// mandarle
     ///
@@ -371,18 +492,232 @@ impl<T: Write + Read> Comunicacion<T> {
     pub fn enviar_referencia(
         &mut self,
         referencia_actualizar: (String, String, PathBuf),
+        capacidades: &[String],
     ) -> Result<(), String> {
-        self.enviar(&utils::strings::obtener_linea_con_largo_hex(&format!(
-            "{} {} {}\n",
+        let mut linea = format!(
+            "{} {} {}",
             referencia_actualizar.0,
             referencia_actualizar.1,
             referencia_actualizar.2.to_string_lossy()
-        )))?;
+        );
+        if !capacidades.is_empty() {
+            linea.push_str(&format!("\0{}", capacidades.join(" ")));
+        }
+        linea.push('\n');
+        self.enviar(&utils::strings::obtener_linea_con_largo_hex(&linea))?;
 
         self.enviar_flush_pkt()?;
         Ok(())
     }
 
+    /// Lee una linea pkt completa del flujo. Devuelve None si el otro lado cerro la conexion
+    /// antes de empezar la linea.
+    fn leer_linea_pkt(&mut self) -> Result<Option<LineaPkt>, String> {
+        let mut largo = [0u8; 4];
+        match self.flujo.read_exact(&mut largo) {
+            Ok(()) => {}
+            Err(e) if e.kind() == ErrorKind::UnexpectedEof => return Ok(None),
+            Err(e) => return Err(format!("Fallo en la lectura de la linea.\n{}\n", e)),
+        }
+        let largo = str::from_utf8(&largo)
+            .ok()
+            .and_then(|largo| usize::from_str_radix(largo, 16).ok())
+            .ok_or("Fallo en la conversion a entero del largo de la linea\n".to_string())?;
+
+        match largo {
+            0 => Ok(Some(LineaPkt::Flush)),
+            1 => Ok(Some(LineaPkt::Delimitador)),
+            2..=4 => Err(format!("Largo de linea invalido: {}", largo)),
+            _ => {
+                let mut contenido = vec![0u8; largo - 4];
+                self.flujo
+                    .read_exact(&mut contenido)
+                    .map_err(|e| format!("Fallo en la lectura de la linea.\n{}\n", e))?;
+                Ok(Some(LineaPkt::Contenido(contenido)))
+            }
+        }
+    }
+
+    fn contenido_a_string(contenido: Vec<u8>) -> Result<String, String> {
+        String::from_utf8(contenido).map_err(|e| {
+            format!(
+                "Fallo en castear el contenido a String en leer del flujo.\n{}\n",
+                e
+            )
+        })
+    }
+
+    pub fn enviar_delimitador_pkt(&mut self) -> Result<(), String> {
+        self.enviar("0001")
+    }
+
+    /// Envia un comando del protocolo v2: la linea `command='comando'`, un delim-pkt, un
+    /// argumento por linea y el flush-pkt final.
+    pub fn enviar_comando_v2(
+        &mut self,
+        comando: &str,
+        argumentos: &[String],
+    ) -> Result<(), String> {
+        self.enviar(&strings::obtener_linea_con_largo_hex(&format!(
+            "command={}\n",
+            comando
+        )))?;
+        self.enviar_delimitador_pkt()?;
+        for argumento in argumentos {
+            self.enviar(&strings::obtener_linea_con_largo_hex(&format!(
+                "{}\n",
+                argumento
+            )))?;
+        }
+        self.enviar_flush_pkt()
+    }
+
+    /// Recibe un comando del protocolo v2. Devuelve el nombre del comando y sus argumentos (sin
+    /// el salto de linea), o None si el cliente termino la conexion o mando un flush-pkt.
+    /// Las capacidades que el cliente manda junto al comando se ignoran.
+    pub fn obtener_comando_v2(&mut self) -> Result<Option<(String, Vec<String>)>, String> {
+        let mut comando = None;
+        let mut argumentos = Vec::new();
+        let mut en_argumentos = false;
+        loop {
+            match self.leer_linea_pkt()? {
+                None | Some(LineaPkt::Flush) => break,
+                Some(LineaPkt::Delimitador) => en_argumentos = true,
+                Some(LineaPkt::Contenido(contenido)) => {
+                    let linea = Self::contenido_a_string(contenido)?;
+                    let linea = linea.trim_end_matches('\n').to_string();
+                    if en_argumentos {
+                        argumentos.push(linea);
+                    } else if let Some(nombre) = linea.strip_prefix("command=") {
+                        comando = Some(nombre.to_string());
+                    }
+                }
+            }
+        }
+        match comando {
+            Some(comando) => Ok(Some((comando, argumentos))),
+            None if argumentos.is_empty() => Ok(None),
+            None => Err("Se recibieron argumentos sin ningun comando".to_string()),
+        }
+    }
+
+    /// Obtiene las lineas de una seccion de una respuesta del protocolo v2, hasta el flush-pkt o
+    /// el delim-pkt que la termina. A diferencia de `obtener_lineas` no corta en los ACK o NAK.
+    /// Tambien sirve para los comandos y el report-status de receive-pack.
+    pub fn obtener_lineas_v2(&mut self) -> Result<Vec<String>, String> {
+        let mut lineas = Vec::new();
+        while let Some(LineaPkt::Contenido(contenido)) = self.leer_linea_pkt()? {
+            lineas.push(Self::contenido_a_string(contenido)?);
+        }
+        Ok(lineas)
+    }
+
+    fn enviar_por_canal(&mut self, canal: u8, datos: &[u8]) -> Result<(), String> {
+        for parte in datos.chunks(LARGO_MAXIMO_CONTENIDO_PKT - 1) {
+            let mut linea = format!("{:04x}", parte.len() + 5).into_bytes();
+            linea.push(canal);
+            linea.extend_from_slice(parte);
+            self.enviar_bytes(&linea)?;
+        }
+        Ok(())
+    }
+
+    /// Envia datos (normalmente el packfile) por el canal 1 de side-band-64k.
+    pub fn enviar_datos_multiplexados(&mut self, datos: &[u8]) -> Result<(), String> {
+        self.enviar_por_canal(CANAL_DATOS, datos)
+    }
+
+    /// Envia un mensaje de progreso por el canal 2 de side-band-64k, para que lo muestre el cliente.
+    pub fn enviar_progreso(&mut self, mensaje: &str) -> Result<(), String> {
+        self.enviar_por_canal(CANAL_PROGRESO, mensaje.as_bytes())
+    }
+
+    /// Envia un error fatal por el canal 3 de side-band-64k. El cliente deja de leer al recibirlo.
+    pub fn enviar_error_fatal(&mut self, mensaje: &str) -> Result<(), String> {
+        self.enviar_por_canal(CANAL_ERROR, mensaje.as_bytes())
+    }
+
+    /// Recibe la seccion packfile de la respuesta al comando fetch del protocolo v2 (la linea
+    /// `packfile` seguida del packfile multiplexado) y devuelve el packfile.
+    pub fn obtener_seccion_packfile(&mut self) -> Result<Vec<u8>, ErrorDeComunicacion> {
+        match self.leer_linea_pkt() {
+            Ok(Some(LineaPkt::Contenido(contenido))) if contenido == b"packfile\n" => {}
+            Ok(Some(LineaPkt::Contenido(contenido))) if contenido.starts_with(b"ERR ") => {
+                return Err(ErrorDeComunicacion::ErrorDelServidor(
+                    String::from_utf8_lossy(&contenido[4..]).trim().to_string(),
+                ))
+            }
+            Ok(_) => {
+                return Err(ErrorDeComunicacion::ErrorDeProtocolo(
+                    "El servidor no envio la seccion packfile".to_string(),
+                ))
+            }
+            Err(e) => return Err(ErrorDeComunicacion::ErrorDeProtocolo(e)),
+        }
+        self.obtener_packfile_multiplexado()
+    }
+
+    /// Recibe el packfile multiplexado por side-band-64k hasta el flush-pkt. Lo del canal 1 se
+    /// junta en el packfile, los mensajes de progreso del canal 2 se guardan para mostrarlos (ver
+    /// `obtener_progreso`) y un mensaje por el canal 3 corta la recepcion con un error.
+    pub fn obtener_packfile_multiplexado(&mut self) -> Result<Vec<u8>, ErrorDeComunicacion> {
+        let mut packfile = Vec::new();
+        while let Some(LineaPkt::Contenido(contenido)) = self
+            .leer_linea_pkt()
+            .map_err(ErrorDeComunicacion::ErrorDeProtocolo)?
+        {
+            let (canal, datos) =
+                contenido
+                    .split_first()
+                    .ok_or(ErrorDeComunicacion::ErrorDeProtocolo(
+                        "Linea vacia en el packfile multiplexado".to_string(),
+                    ))?;
+            match *canal {
+                CANAL_DATOS => packfile.extend_from_slice(datos),
+                CANAL_PROGRESO => self.guardar_progreso(datos),
+                CANAL_ERROR => {
+                    let mensaje = String::from_utf8_lossy(datos).trim().to_string();
+                    self.logger
+                        .log(&format!("Error fatal del servidor: {}", mensaje));
+                    return Err(ErrorDeComunicacion::ErrorDelServidor(mensaje));
+                }
+                otro => {
+                    return Err(ErrorDeComunicacion::ErrorDeProtocolo(format!(
+                        "Canal de side-band desconocido: {}",
+                        otro
+                    )))
+                }
+            }
+        }
+        Ok(packfile)
+    }
+
+    /// Recibe lineas pkt que llegan multiplexadas por el canal 1 de side-band-64k, por ejemplo el
+    /// report-status de receive-pack, hasta el flush-pkt. Los mensajes de progreso se guardan
+    /// igual que al recibir el packfile.
+    pub fn obtener_lineas_multiplexadas(&mut self) -> Result<Vec<String>, ErrorDeComunicacion> {
+        let datos = self.obtener_packfile_multiplexado()?;
+        Comunicacion::new_para_server_sobre(Cursor::new(datos), self.logger.clone())
+            .obtener_lineas_v2()
+            .map_err(ErrorDeComunicacion::ErrorDeProtocolo)
+    }
+
+    // Un mensaje de progreso puede traer varias lineas, separadas por \n o por \r cuando el
+    // servidor actualiza la misma linea
+    fn guardar_progreso(&mut self, datos: &[u8]) {
+        for linea in String::from_utf8_lossy(datos).split(['\n', '\r']) {
+            if !linea.trim().is_empty() {
+                self.logger.log(&format!("remoto: {}", linea.trim()));
+                self.progreso.push(linea.trim().to_string());
+            }
+        }
+    }
+
+    /// Devuelve los mensajes de progreso que mando el servidor hasta ahora y los descarta.
+    pub fn obtener_progreso(&mut self) -> Vec<String> {
+        std::mem::take(&mut self.progreso)
+    }
+
     ///recibi el hash de un objeto y le da el formato correcto para hacer el have
     fn dar_formato_have(&self, hash_commit: &str) -> String {
         strings::obtener_linea_con_largo_hex(&("have ".to_string() + hash_commit + "\n"))
@@ -532,7 +867,7 @@ mod tests {
         );
 
         Comunicacion::new_para_testing(&mut mock, logger)
-            .enviar_referencia(referencia_actulizar)
+            .enviar_referencia(referencia_actulizar, &[])
             .unwrap();
 
         let contenido_esperado_enviar_lo_que_tengo = "\
@@ -544,4 +879,42 @@ mod tests {
             mock.escritura_data.as_slice()
         )
     }
+
+    #[test]
+    #[serial]
+    fn test06_un_error_por_el_canal_3_corta_la_recepcion_del_packfile() {
+        let logger = Arc::new(Logger::new(PathBuf::from("tmp/comunicacion_test06")).unwrap());
+        let mut servidor = MockTcpStream {
+            lectura_data: Vec::new(),
+            escritura_data: Vec::new(),
+        };
+        let mut comunicacion_servidor =
+            Comunicacion::new_para_testing(&mut servidor, logger.clone());
+        comunicacion_servidor
+            .enviar_progreso("Contando objetos\r")
+            .unwrap();
+        comunicacion_servidor
+            .enviar_datos_multiplexados(b"PACK")
+            .unwrap();
+        comunicacion_servidor
+            .enviar_error_fatal("se rompio el disco")
+            .unwrap();
+        comunicacion_servidor.enviar_flush_pkt().unwrap();
+
+        let mut cliente = MockTcpStream {
+            lectura_data: servidor.escritura_data,
+            escritura_data: Vec::new(),
+        };
+        let mut comunicacion_cliente = Comunicacion::new_para_testing(&mut cliente, logger);
+        let resultado = comunicacion_cliente.obtener_packfile_multiplexado();
+
+        assert!(matches!(
+            resultado,
+            Err(ErrorDeComunicacion::ErrorDelServidor(mensaje)) if mensaje == "se rompio el disco"
+        ));
+        assert_eq!(
+            comunicacion_cliente.obtener_progreso(),
+            vec!["Contando objetos".to_string()]
+        );
+    }
 }
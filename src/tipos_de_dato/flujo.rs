@@ -0,0 +1,84 @@
+use std::{
+    io::{self, Read, Write},
+    net::TcpStream,
+    path::Path,
+};
+
+use native_tls::{Certificate, TlsConnector, TlsStream};
+
+use crate::utils::io as gir_io;
+
+use super::http::flujo_http::FlujoHttp;
+
+/// Flujo por el que el cliente habla con el servidor, segun el esquema de la url del remoto.
+pub enum Flujo {
+    /// Conexion directa al servidor gir.
+    Tcp(TcpStream),
+    /// Conexion directa al servidor gir por TLS (`girs://`).
+    Tls(Box<TlsStream<TcpStream>>),
+    /// Smart http contra el servidor http, por TLS si la url es `https://`.
+    Http(Box<FlujoHttp>),
+}
+
+/// Arma el conector TLS del cliente. Ademas de los certificados del sistema confia en
+/// `certificado`, si se pasa, para poder usar servidores con certificados propios.
+pub fn crear_conector_tls(certificado: Option<&Path>) -> Result<TlsConnector, String> {
+    let mut builder = TlsConnector::builder();
+    if let Some(certificado) = certificado {
+        let pem = gir_io::leer_bytes(certificado)?;
+        let certificado =
+            Certificate::from_pem(&pem).map_err(|e| format!("Certificado TLS invalido: {}", e))?;
+        builder.add_root_certificate(certificado);
+    }
+    builder
+        .build()
+        .map_err(|e| format!("No se pudo configurar TLS: {}", e))
+}
+
+/// Se conecta por TLS a `ip_puerto`, verificando que el certificado del servidor sea del host.
+pub fn conectar_tls(
+    conector: &TlsConnector,
+    ip_puerto: &str,
+) -> Result<TlsStream<TcpStream>, String> {
+    let stream = TcpStream::connect(ip_puerto)
+        .map_err(|e| format!("Fallo en en la coneccion con el servidor.\n{}\n", e))?;
+    conector
+        .connect(obtener_host(ip_puerto), stream)
+        .map_err(|e| format!("Fallo el handshake TLS con el servidor.\n{}\n", e))
+}
+
+// El host de `ip:puerto`, que es el nombre con el que se valida el certificado.
+fn obtener_host(ip_puerto: &str) -> &str {
+    ip_puerto
+        .rsplit_once(':')
+        .map(|(host, _)| host)
+        .unwrap_or(ip_puerto)
+}
+
+impl Read for Flujo {
+    fn read(&mut self, buf: &mut [u8]) -> io::Result<usize> {
+        match self {
+            Flujo::Tcp(stream) => stream.read(buf),
+            Flujo::Tls(stream) => stream.read(buf),
+            Flujo::Http(flujo) => flujo.read(buf),
+        }
+    }
+}
+
+impl Write for Flujo {
+    fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+        match self {
+            Flujo::Tcp(stream) => stream.write(buf),
+            Flujo::Tls(stream) => stream.write(buf),
+            Flujo::Http(flujo) => flujo.write(buf),
+        }
+    }
+
+    fn flush(&mut self) -> io::Result<()> {
+        match self {
+            Flujo::Tcp(stream) => stream.flush(),
+            Flujo::Tls(stream) => stream.flush(),
+            Flujo::Http(flujo) => flujo.flush(),
+        }
+    }
+}
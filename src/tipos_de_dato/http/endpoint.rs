@@ -1,40 +1,79 @@
 use std::collections::HashMap;
 
 use super::{endpoint_handler::EndpointHandler, metodos::MetodoHttp};
+use crate::tipos_de_dato::permiso::Permiso;
 #[derive(Debug)]
 
 pub struct Endpoint {
     pub metodo: MetodoHttp,
     pub patron: String,
     pub handler: EndpointHandler,
+    /// Permiso que necesita el cliente sobre el repositorio de la ruta para usar el endpoint.
+    pub permiso: Permiso,
 }
 
 impl Endpoint {
-    pub fn new(metodo: MetodoHttp, patron: String, handler: EndpointHandler) -> Self {
+    pub fn new(
+        metodo: MetodoHttp,
+        patron: String,
+        handler: EndpointHandler,
+        permiso: Permiso,
+    ) -> Self {
         Self {
             metodo,
             patron,
             handler,
+            permiso,
         }
     }
 
+    /// Si la ruta matchea con el patron devuelve los parametros de la ruta. Un parametro de la
+    /// forma `{nombre*}` toma todos los segmentos de la ruta que sobran, con sus `/` (por ejemplo
+    /// el path de un archivo o una rama como `feature/x`), y puede estar en cualquier parte del
+    /// patron.
     pub fn matchea_con_patron(&self, ruta: &str) -> Option<HashMap<String, String>> {
-        let ruta_endpoint = self.patron.split('/').collect::<Vec<&str>>();
-        let ruta_request = ruta.split('/').collect::<Vec<&str>>();
+        let mut ruta_endpoint = self.patron.split('/').collect::<Vec<&str>>();
+        let mut ruta_request = ruta.split('/').collect::<Vec<&str>>();
+
+        let posicion_resto = ruta_endpoint
+            .iter()
+            .position(|entrada| entrada.starts_with('{') && entrada.ends_with("*}"));
+        let resto = match posicion_resto {
+            Some(posicion) => {
+                if ruta_request.len() < ruta_endpoint.len() {
+                    return None;
+                }
+                let entrada = ruta_endpoint.remove(posicion);
+                let key = entrada[1..entrada.len() - 2].to_string();
+                let fin_resto = ruta_request.len() - (ruta_endpoint.len() - posicion);
+                let resto = ruta_request
+                    .drain(posicion..fin_resto)
+                    .collect::<Vec<&str>>()
+                    .join("/");
+                Some((key, resto))
+            }
+            None => None,
+        };
 
         if ruta_endpoint.len() != ruta_request.len() {
             return None;
         }
 
-        let ultimo_ruta_request = match ruta_request.last() {
-            Some(ruta) => ruta,
-            None => return None,
+        let ultimo_ruta_request = match (&resto, posicion_resto) {
+            (Some((_, resto)), Some(posicion)) if posicion == ruta_endpoint.len() => resto.as_str(),
+            _ => ruta_request.last()?,
         };
         if ultimo_ruta_request.is_empty() {
             return None;
         }
+        if matches!(&resto, Some((_, resto)) if resto.is_empty()) {
+            return None;
+        }
 
         let mut params = HashMap::new();
+        if let Some((key, resto)) = resto {
+            params.insert(key, resto);
+        }
 
         for (entrada_endpoint, entrada_request) in ruta_endpoint.iter().zip(ruta_request.iter()) {
             if entrada_endpoint.starts_with('{') && entrada_endpoint.ends_with('}') {
@@ -76,6 +115,7 @@ mod tests {
                     None,
                 ))
             },
+            Permiso::Lectura,
         );
 
         let params = endpoint.matchea_con_patron("/repos/messi/pulls").unwrap();
@@ -103,6 +143,7 @@ mod tests {
                     None,
                 ))
             },
+            Permiso::Lectura,
         );
 
         let params = endpoint.matchea_con_patron("/repos/messi/pulls/1").unwrap();
@@ -118,4 +159,72 @@ mod tests {
         let params = endpoint.matchea_con_patron("/repos/messi/typo/1");
         assert!(params.is_none());
     }
+
+    #[test]
+    fn extraer_parametros_de_ruta_con_param_que_toma_el_resto() {
+        let endpoint = Endpoint::new(
+            MetodoHttp::Get,
+            "/repos/{repo}/contents/{path*}".to_string(),
+            |_, _, _| {
+                Ok(Response::new(
+                    Arc::new(Logger::new(std::path::PathBuf::from("server_logger.txt")).unwrap()),
+                    EstadoHttp::Ok,
+                    None,
+                ))
+            },
+            Permiso::Lectura,
+        );
+
+        let params = endpoint
+            .matchea_con_patron("/repos/messi/contents/src/main.rs")
+            .unwrap();
+        assert_eq!(params.get("repo").unwrap(), "messi");
+        assert_eq!(params.get("path").unwrap(), "src/main.rs");
+
+        let params = endpoint.matchea_con_patron("/repos/messi/contents/README");
+        assert_eq!(params.unwrap().get("path").unwrap(), "README");
+
+        let params = endpoint.matchea_con_patron("/repos/messi/contents/");
+        assert!(params.is_none());
+
+        let params = endpoint.matchea_con_patron("/repos/messi/contents");
+        assert!(params.is_none());
+
+        let params = endpoint.matchea_con_patron("/repos/messi/typo/main.rs");
+        assert!(params.is_none());
+    }
+
+    #[test]
+    fn extraer_parametros_de_ruta_con_param_que_toma_el_resto_en_el_medio() {
+        let endpoint = Endpoint::new(
+            MetodoHttp::Get,
+            "/repos/{repo}/branches/{branch*}/protection".to_string(),
+            |_, _, _| {
+                Ok(Response::new(
+                    Arc::new(Logger::new(std::path::PathBuf::from("server_logger.txt")).unwrap()),
+                    EstadoHttp::Ok,
+                    None,
+                ))
+            },
+            Permiso::Lectura,
+        );
+
+        let params = endpoint
+            .matchea_con_patron("/repos/messi/branches/feature/x/protection")
+            .unwrap();
+        assert_eq!(params.get("repo").unwrap(), "messi");
+        assert_eq!(params.get("branch").unwrap(), "feature/x");
+
+        let params = endpoint.matchea_con_patron("/repos/messi/branches/master/protection");
+        assert_eq!(params.unwrap().get("branch").unwrap(), "master");
+
+        let params = endpoint.matchea_con_patron("/repos/messi/branches/protection");
+        assert!(params.is_none());
+
+        let params = endpoint.matchea_con_patron("/repos/messi/branches//protection");
+        assert!(params.is_none());
+
+        let params = endpoint.matchea_con_patron("/repos/messi/branches/master/typo");
+        assert!(params.is_none());
+    }
 }
@@ -6,6 +6,7 @@ pub enum ErrorHttp {
     InternalServerError(String),
     ValidationFailed(String),
     Forbidden(String),
+    Unauthorized(String),
     BadRequest(String),
     Conflict(String),
     NotImplemented(String),
@@ -18,6 +19,7 @@ impl ToString for ErrorHttp {
             Self::InternalServerError(mensaje) => format!("500 Internal Server Error: {}", mensaje),
             Self::ValidationFailed(mensaje) => format!("422 Validation Failed: {}", mensaje),
             Self::Forbidden(mensaje) => format!("403 Forbidden: {}", mensaje),
+            Self::Unauthorized(mensaje) => format!("401 Unauthorized: {}", mensaje),
             Self::BadRequest(mensaje) => format!("400 Bad Request: {}", mensaje),
             Self::Conflict(mensaje) => format!("409 Conflict: {}", mensaje),
             Self::NotImplemented(mensaje) => format!("501 Not Implemented: {}", mensaje),
@@ -32,6 +34,7 @@ impl ErrorHttp {
             Self::InternalServerError(_) => EstadoHttp::InternalServerError,
             Self::ValidationFailed(_) => EstadoHttp::ValidationFailed,
             Self::Forbidden(_) => EstadoHttp::Forbidden,
+            Self::Unauthorized(_) => EstadoHttp::Unauthorized,
             Self::BadRequest(_) => EstadoHttp::BadRequest,
             Self::Conflict(_) => EstadoHttp::Conflict,
             Self::NotImplemented(_) => EstadoHttp::MethodNotAllowed,
@@ -44,6 +47,7 @@ impl ErrorHttp {
             Self::InternalServerError(mensaje) => mensaje.to_string(),
             Self::ValidationFailed(mensaje) => mensaje.to_string(),
             Self::Forbidden(mensaje) => mensaje.to_string(),
+            Self::Unauthorized(mensaje) => mensaje.to_string(),
             Self::BadRequest(mensaje) => mensaje.to_string(),
             Self::Conflict(mensaje) => mensaje.to_string(),
             Self::NotImplemented(mensaje) => mensaje.to_string(),
@@ -11,6 +11,7 @@ pub enum EstadoHttp {
     Conflict,
     ValidationFailed,
     Forbidden,
+    Unauthorized,
 }
 
 impl EstadoHttp {
@@ -26,6 +27,7 @@ impl EstadoHttp {
             EstadoHttp::Created => (201, "Created".to_string()),
             EstadoHttp::ValidationFailed => (422, "Validacion Failed".to_string()),
             EstadoHttp::Forbidden => (403, "Forbidden".to_string()),
+            EstadoHttp::Unauthorized => (401, "Unauthorized".to_string()),
         }
     }
 }
@@ -0,0 +1,482 @@
+use std::{
+    collections::HashMap,
+    io::{self, Cursor, Read, Write},
+    net::TcpStream,
+};
+
+use native_tls::TlsConnector;
+
+use crate::{tipos_de_dato::flujo, utils::strings};
+
+/// Flujo del lado del cliente para hablar el protocolo de gir por smart http.
+///
+/// Lo que se escribe se acumula y se manda en un POST recien cuando hace falta leer la respuesta
+/// (o cuando se hace flush). Como el servidor no guarda estado entre requests, en la negociacion
+/// de upload-pack cada POST lleva, como el stateless RPC de git, los wants, los haves que el
+/// servidor ya reconocio como comunes y lo nuevo que escribio el cliente (la siguiente tanda de
+/// haves o el done). De la respuesta se saltea lo que responde a lo reenviado, que el cliente ya
+/// leyo. En el protocolo v2 y en receive-pack cada POST lleva solo lo nuevo, porque cada comando
+/// es completo. Asi el resto del cliente puede usarlo igual que a un `TcpStream`.
+pub struct FlujoHttp {
+    /// Direccion del servidor en formato ip:puerto.
+    direccion: String,
+    /// Conector con el que se habla por TLS, si la url es `https://`.
+    tls: Option<TlsConnector>,
+    /// Ruta del repositorio en el servidor, sin la barra final.
+    repositorio: String,
+    /// Servicio pedido por el cliente (`git-upload-pack` o `git-receive-pack`).
+    servicio: Option<String>,
+    /// Version del protocolo pedida por el cliente (`version=2`), que por http viaja en el
+    /// header `Git-Protocol` de cada request.
+    protocolo: Option<String>,
+    /// Credenciales en base64 que el cliente mando en la primera linea (`\0credenciales=...\0`),
+    /// que por http viajan en el header `Authorization` de cada request.
+    credenciales: Option<String>,
+    /// Lo que escribio el cliente desde el ultimo POST.
+    pedido: Vec<u8>,
+    /// Seccion de los wants de la negociacion de upload-pack v1 (hasta su flush-pkt), que se
+    /// repite en cada POST.
+    wants: Option<Vec<u8>>,
+    /// Indica si en los wants se pidio multi_ack_detailed.
+    multi_ack: bool,
+    /// Haves que el servidor reconocio como comunes, que se repiten en cada POST de la
+    /// negociacion.
+    comunes: Vec<String>,
+    /// Indica si hay algo escrito que el servidor todavia no recibio.
+    hay_pendiente: bool,
+    /// Indica si ya se hizo el GET de `info/refs`.
+    anuncio_recibido: bool,
+    /// Indica si ya se hizo algun POST.
+    hubo_post: bool,
+    /// Respuesta del ultimo request, con la posicion hasta donde la leyo el cliente.
+    respuesta: Cursor<Vec<u8>>,
+}
+
+impl FlujoHttp {
+    /// Crea un flujo para el repositorio dado del servidor en `direccion` (ip:puerto). Si se
+    /// pasa un conector los requests van por TLS.
+    /// No se conecta hasta que el cliente pide un servicio y lee la respuesta.
+    pub fn new(direccion: &str, repositorio: &str, tls: Option<TlsConnector>) -> FlujoHttp {
+        FlujoHttp {
+            direccion: direccion.to_string(),
+            tls,
+            repositorio: repositorio.trim_end_matches('/').to_string(),
+            servicio: None,
+            protocolo: None,
+            credenciales: None,
+            pedido: Vec::new(),
+            wants: None,
+            multi_ack: false,
+            comunes: Vec::new(),
+            hay_pendiente: false,
+            anuncio_recibido: false,
+            hubo_post: false,
+            respuesta: Cursor::new(Vec::new()),
+        }
+    }
+
+    /// Obtiene el servicio de la primera linea que manda el cliente, con el formato
+    /// `'largo'git-upload-pack /repositorio/\0host=...`.
+    fn parsear_servicio(linea: &[u8]) -> io::Result<String> {
+        let contenido = String::from_utf8_lossy(linea.get(4..).unwrap_or_default()).to_string();
+        match contenido.split_whitespace().next() {
+            Some(servicio @ ("git-upload-pack" | "git-receive-pack")) => Ok(servicio.to_string()),
+            _ => Err(io::Error::new(
+                io::ErrorKind::InvalidInput,
+                format!("Servicio desconocido: {}", contenido),
+            )),
+        }
+    }
+
+    /// Obtiene la version del protocolo de los parametros extra de la primera linea
+    /// (`\0\0version=2\0`). La version 1 no hace falta pedirla.
+    fn parsear_protocolo(linea: &[u8]) -> Option<String> {
+        String::from_utf8_lossy(linea)
+            .split('\0')
+            .skip(1)
+            .find(|param| param.starts_with("version=") && *param != "version=1")
+            .map(|param| param.to_string())
+    }
+
+    /// Obtiene las credenciales de los parametros extra de la primera linea, si las tiene.
+    fn parsear_credenciales(linea: &[u8]) -> Option<String> {
+        String::from_utf8_lossy(linea)
+            .split('\0')
+            .skip(1)
+            .find_map(|param| param.strip_prefix("credenciales="))
+            .map(|credenciales| credenciales.to_string())
+    }
+
+    fn obtener_servicio(&self) -> io::Result<String> {
+        self.servicio.clone().ok_or(io::Error::new(
+            io::ErrorKind::InvalidInput,
+            "No se pidio ningun servicio al servidor",
+        ))
+    }
+
+    /// Manda el request y devuelve el estado, los headers (en minuscula) y el body de la respuesta.
+    fn enviar_request(
+        &self,
+        metodo: &str,
+        ruta: &str,
+        contenido: Option<(&str, &[u8])>,
+    ) -> io::Result<(usize, HashMap<String, String>, Vec<u8>)> {
+        let mut request = format!(
+            "{} {} HTTP/1.1\r\nHost: {}\r\nConnection: close\r\n",
+            metodo, ruta, self.direccion
+        )
+        .into_bytes();
+        if let Some(protocolo) = &self.protocolo {
+            request.extend(format!("Git-Protocol: {}\r\n", protocolo).as_bytes());
+        }
+        if let Some(credenciales) = &self.credenciales {
+            request.extend(format!("Authorization: Basic {}\r\n", credenciales).as_bytes());
+        }
+        if let Some((tipo, body)) = contenido {
+            request.extend(format!("Content-Type: {}\r\n", tipo).as_bytes());
+            request.extend(format!("Content-Length: {}\r\n", body.len()).as_bytes());
+            request.extend(b"\r\n");
+            request.extend(body);
+        } else {
+            request.extend(b"\r\n");
+        }
+
+        let respuesta = match &self.tls {
+            Some(conector) => {
+                let mut stream =
+                    flujo::conectar_tls(conector, &self.direccion).map_err(io::Error::other)?;
+                Self::intercambiar(&mut stream, &request)?
+            }
+            None => Self::intercambiar(&mut TcpStream::connect(&self.direccion)?, &request)?,
+        };
+
+        Self::parsear_respuesta(respuesta)
+    }
+
+    // Manda el request y lee la respuesta hasta que el servidor cierra la conexion. Si la
+    // conexion se corta sin cerrar bien (por ejemplo TLS sin close_notify) pero la respuesta ya
+    // esta completa segun su Content-Length, se usa igual.
+    fn intercambiar<S: Read + Write>(stream: &mut S, request: &[u8]) -> io::Result<Vec<u8>> {
+        stream.write_all(request)?;
+        let mut respuesta = Vec::new();
+        if let Err(error) = stream.read_to_end(&mut respuesta) {
+            if !Self::respuesta_completa(&respuesta) {
+                return Err(error);
+            }
+        }
+        Ok(respuesta)
+    }
+
+    fn respuesta_completa(respuesta: &[u8]) -> bool {
+        match Self::parsear_respuesta(respuesta.to_vec()) {
+            Ok((_, headers, body)) => headers
+                .get("content-length")
+                .and_then(|largo| largo.parse::<usize>().ok())
+                .is_some_and(|largo| body.len() >= largo),
+            Err(_) => false,
+        }
+    }
+
+    fn parsear_respuesta(
+        respuesta: Vec<u8>,
+    ) -> io::Result<(usize, HashMap<String, String>, Vec<u8>)> {
+        let error_respuesta = || {
+            io::Error::new(
+                io::ErrorKind::InvalidData,
+                "Respuesta http invalida del servidor",
+            )
+        };
+        let fin_headers = respuesta
+            .windows(4)
+            .position(|ventana| ventana == b"\r\n\r\n")
+            .ok_or_else(error_respuesta)?;
+        let encabezado = String::from_utf8_lossy(&respuesta[..fin_headers]).to_string();
+        let mut body = respuesta[fin_headers + 4..].to_vec();
+
+        let mut lineas = encabezado.split("\r\n");
+        let estado = lineas
+            .next()
+            .and_then(|linea| linea.split_whitespace().nth(1))
+            .and_then(|estado| estado.parse::<usize>().ok())
+            .ok_or_else(error_respuesta)?;
+        let mut headers = HashMap::new();
+        for linea in lineas {
+            if let Some((clave, valor)) = linea.split_once(':') {
+                headers.insert(clave.trim().to_lowercase(), valor.trim().to_string());
+            }
+        }
+        if let Some(largo) = headers
+            .get("content-length")
+            .and_then(|largo| largo.parse::<usize>().ok())
+        {
+            body.truncate(largo);
+        }
+        Ok((estado, headers, body))
+    }
+
+    fn verificar_estado(estado: usize, body: &[u8]) -> io::Result<()> {
+        if estado == 200 {
+            return Ok(());
+        }
+        let mensaje = serde_json::from_slice::<HashMap<String, String>>(body)
+            .ok()
+            .and_then(|body| body.get("message").cloned())
+            .unwrap_or_else(|| String::from_utf8_lossy(body).to_string());
+        Err(io::Error::other(format!(
+            "El servidor respondio {}: {}",
+            estado, mensaje
+        )))
+    }
+
+    /// Pide `info/refs` para el servicio y deja en la respuesta el anuncio de referencias, sin la
+    /// linea `# service=...` que solo se manda por http.
+    fn pedir_anuncio_de_referencias(&mut self) -> io::Result<()> {
+        let servicio = self.obtener_servicio()?;
+        let ruta = format!("{}/info/refs?service={}", self.repositorio, servicio);
+        let (estado, headers, body) = self.enviar_request("GET", &ruta, None)?;
+        Self::verificar_estado(estado, &body)?;
+
+        let tipo_esperado = format!("application/x-{}-advertisement", servicio);
+        let encabezado = format!("# service={}\n", servicio);
+        let largo_encabezado = encabezado.len() + 4;
+        if headers.get("content-type") != Some(&tipo_esperado)
+            || body.get(4..largo_encabezado) != Some(encabezado.as_bytes())
+            || body.get(largo_encabezado..largo_encabezado + 4) != Some(b"0000")
+        {
+            return Err(io::Error::new(
+                io::ErrorKind::InvalidData,
+                "El servidor no soporta smart http",
+            ));
+        }
+
+        self.respuesta = Cursor::new(body[largo_encabezado + 4..].to_vec());
+        self.anuncio_recibido = true;
+        Ok(())
+    }
+
+    /// Manda en un POST lo escrito desde el anterior, con los wants y los haves comunes si es la
+    /// negociacion de upload-pack v1, y deja la respuesta lista para leer.
+    fn enviar_pedido(&mut self) -> io::Result<()> {
+        let servicio = self.obtener_servicio()?;
+        let ruta = format!("{}/{}", self.repositorio, servicio);
+        let tipo = format!("application/x-{}-request", servicio);
+        let pedido = std::mem::take(&mut self.pedido);
+        let negocia = servicio == "git-upload-pack" && self.protocolo.is_none();
+        let contenido = if negocia {
+            self.armar_pedido_de_negociacion(pedido)
+        } else {
+            pedido
+        };
+        let (estado, _, body) = self.enviar_request("POST", &ruta, Some((&tipo, &contenido)))?;
+        Self::verificar_estado(estado, &body)?;
+
+        let mut inicio = 0;
+        if negocia && self.hubo_post {
+            inicio = self.saltear_respuesta_a_lo_reenviado(&body);
+        }
+        if negocia {
+            self.agregar_comunes(&body[inicio..]);
+        }
+        self.respuesta = Cursor::new(body);
+        self.respuesta.set_position(inicio as u64);
+        self.hubo_post = true;
+        Ok(())
+    }
+
+    // Arma el body de un POST de la negociacion: los wants (que se separan del primer pedido),
+    // un have por cada commit en comun y lo nuevo del pedido.
+    fn armar_pedido_de_negociacion(&mut self, mut pedido: Vec<u8>) -> Vec<u8> {
+        if self.wants.is_none() {
+            let resto = pedido.split_off(Self::fin_de_seccion(&pedido, 0));
+            let wants = std::mem::replace(&mut pedido, resto);
+            // las capacidades vienen en el primer want
+            self.multi_ack = Self::lineas_pkt(&wants).next().is_some_and(|linea| {
+                String::from_utf8_lossy(linea)
+                    .split_whitespace()
+                    .any(|capacidad| capacidad == "multi_ack_detailed")
+            });
+            self.wants = Some(wants);
+        }
+        let mut contenido = self.wants.clone().unwrap_or_default();
+        for comun in &self.comunes {
+            contenido
+                .extend(strings::obtener_linea_con_largo_hex(&format!("have {}\n", comun)).bytes());
+        }
+        contenido.extend(pedido);
+        contenido
+    }
+
+    // Devuelve donde empieza lo que responde a lo nuevo del pedido. Antes el servidor vuelve a
+    // mandar la actualizacion shallow, si se pidio profundidad, y responde los haves comunes
+    // reenviados: con multi_ack_detailed un `ACK hash common` por cada uno y sin multi_ack un
+    // solo ACK por el primero.
+    fn saltear_respuesta_a_lo_reenviado(&self, respuesta: &[u8]) -> usize {
+        let wants = self.wants.as_deref().unwrap_or_default();
+        let mut inicio = 0;
+        if Self::lineas_pkt(wants).any(|linea| linea.starts_with(b"deepen")) {
+            inicio = Self::fin_de_seccion(respuesta, 0);
+        }
+        let acks = if self.multi_ack {
+            self.comunes.len()
+        } else {
+            self.comunes.len().min(1)
+        };
+        for _ in 0..acks {
+            match Self::largo_pkt(&respuesta[inicio..]) {
+                Some(largo) if largo > 0 => inicio += largo,
+                _ => break,
+            }
+        }
+        inicio
+    }
+
+    // Guarda los haves de la respuesta que el servidor reconocio como comunes: los
+    // `ACK hash common` con multi_ack_detailed, o el `ACK hash` sin multi_ack.
+    fn agregar_comunes(&mut self, respuesta: &[u8]) {
+        for linea in Self::lineas_pkt(respuesta) {
+            let linea = String::from_utf8_lossy(linea);
+            let hash = match linea.split_whitespace().collect::<Vec<_>>()[..] {
+                ["ACK", hash, "common"] if self.multi_ack => hash.to_string(),
+                ["ACK", hash] if !self.multi_ack => hash.to_string(),
+                _ => continue,
+            };
+            if !self.comunes.contains(&hash) {
+                self.comunes.push(hash);
+            }
+        }
+    }
+
+    // Devuelve la posicion despues del primer flush-pkt desde `posicion`, o el largo si no hay
+    // ninguno.
+    fn fin_de_seccion(contenido: &[u8], mut posicion: usize) -> usize {
+        while let Some(largo) = Self::largo_pkt(&contenido[posicion..]) {
+            posicion += largo.max(4);
+            if largo == 0 {
+                return posicion;
+            }
+        }
+        contenido.len()
+    }
+
+    // Recorre el contenido de las lineas pkt del principio, sin los flush-pkt, hasta la primera
+    // que no lo es (por ejemplo un packfile sin side-band).
+    fn lineas_pkt(contenido: &[u8]) -> impl Iterator<Item = &[u8]> {
+        let mut posicion = 0;
+        std::iter::from_fn(move || loop {
+            let largo = Self::largo_pkt(&contenido[posicion..])?;
+            let linea = &contenido[posicion..];
+            posicion += largo.max(4);
+            if largo > 0 {
+                return Some(&linea[4..largo]);
+            }
+        })
+    }
+
+    // Largo de la linea pkt al principio del contenido (0 para un flush-pkt), si hay una completa.
+    fn largo_pkt(contenido: &[u8]) -> Option<usize> {
+        let largo = std::str::from_utf8(contenido.get(..4)?).ok()?;
+        let largo = usize::from_str_radix(largo, 16).ok()?;
+        match largo {
+            0 => Some(0),
+            1..=3 => None,
+            _ if largo <= contenido.len() => Some(largo),
+            _ => None,
+        }
+    }
+}
+
+impl Read for FlujoHttp {
+    fn read(&mut self, buf: &mut [u8]) -> io::Result<usize> {
+        self.flush()?;
+        self.respuesta.read(buf)
+    }
+}
+
+impl Write for FlujoHttp {
+    fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+        if self.servicio.is_none() {
+            self.servicio = Some(Self::parsear_servicio(buf)?);
+            self.protocolo = Self::parsear_protocolo(buf);
+            self.credenciales = Self::parsear_credenciales(buf);
+        } else {
+            self.pedido.extend_from_slice(buf);
+        }
+        self.hay_pendiente = true;
+        Ok(buf.len())
+    }
+
+    /// Manda al servidor lo que este pendiente: el pedido del anuncio de referencias o el POST
+    /// con todo lo escrito.
+    fn flush(&mut self) -> io::Result<()> {
+        if !self.hay_pendiente {
+            return Ok(());
+        }
+        self.hay_pendiente = false;
+        if !self.anuncio_recibido {
+            self.pedir_anuncio_de_referencias()
+        } else {
+            self.enviar_pedido()
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test01_se_parsea_el_servicio_de_la_primera_linea() {
+        let linea = b"0036git-upload-pack /repo/\0host=gir.com\0\0version=1\0";
+        assert_eq!(
+            FlujoHttp::parsear_servicio(linea).unwrap(),
+            "git-upload-pack"
+        );
+        assert!(FlujoHttp::parsear_servicio(b"0010git-otra-cosa").is_err());
+    }
+
+    #[test]
+    fn test02_se_parsea_una_respuesta_con_content_length() {
+        let respuesta = b"HTTP/1.1 200 OK\r\nContent-Type: application/x-git-upload-pack-result\r\nContent-Length: 4\r\n\r\n0000basura".to_vec();
+
+        let (estado, headers, body) = FlujoHttp::parsear_respuesta(respuesta).unwrap();
+
+        assert_eq!(estado, 200);
+        assert_eq!(
+            headers.get("content-type").unwrap(),
+            "application/x-git-upload-pack-result"
+        );
+        assert_eq!(body, b"0000");
+    }
+
+    #[test]
+    fn test03_un_estado_de_error_devuelve_el_mensaje_del_servidor() {
+        let error = FlujoHttp::verificar_estado(404, br#"{"message":"No existe"}"#).unwrap_err();
+        assert!(error.to_string().contains("No existe"));
+    }
+
+    #[test]
+    fn test04_se_parsea_la_version_del_protocolo_pedida() {
+        let linea_v2 = b"0036git-upload-pack /repo/\0host=gir.com\0\0version=2\0";
+        let linea_v1 = b"0036git-upload-pack /repo/\0host=gir.com\0\0version=1\0";
+
+        assert_eq!(
+            FlujoHttp::parsear_protocolo(linea_v2),
+            Some("version=2".to_string())
+        );
+        assert_eq!(FlujoHttp::parsear_protocolo(linea_v1), None);
+    }
+
+    #[test]
+    fn test05_se_parsean_las_credenciales_de_la_primera_linea() {
+        let linea = b"0052git-receive-pack /repo/\0host=gir.com\0\0version=1\0credenciales=anVhbjpjb250cmFzZW5pYQ==\0";
+
+        assert_eq!(
+            FlujoHttp::parsear_credenciales(linea),
+            Some("anVhbjpjb250cmFzZW5pYQ==".to_string())
+        );
+        assert_eq!(
+            FlujoHttp::parsear_credenciales(b"0010git-upload-pack"),
+            None
+        );
+    }
+}
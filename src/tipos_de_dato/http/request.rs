@@ -6,14 +6,24 @@ use std::{
 };
 
 use super::{error::ErrorHttp, metodos::MetodoHttp, tipo_contenido::TipoContenido};
-use crate::tipos_de_dato::logger::Logger;
+use crate::tipos_de_dato::{logger::Logger, permiso::Permiso};
 pub struct Request {
     pub metodo: MetodoHttp,
+    /// Ruta pedida, sin la query.
     pub ruta: String,
+    /// Parametros de la query de la ruta (lo que sigue al `?`).
+    pub query: HashMap<String, String>,
     pub version: String,
     pub headers: HashMap<String, String>,
     pub body: Option<HashMap<String, String>>,
+    /// Body sin parsear de los requests con contenido binario, como los de smart http.
+    pub body_binario: Option<Vec<u8>>,
     pub logger: Arc<Logger>,
+    /// Cuenta con la que se autentico el cliente, si el servidor tiene cuentas.
+    pub usuario: Option<String>,
+    /// Permiso del cliente sobre el repositorio de la ruta. Lo completa el servidor al
+    /// autorizar el pedido; hasta entonces es el minimo.
+    pub permiso: Permiso,
 }
 
 impl Request {
@@ -68,20 +78,35 @@ impl Request {
         let (metodo, ruta, version) = Self::obtener_primera_linea(reader)?;
 
         let metodo = MetodoHttp::from_string(&metodo)?;
+        let (ruta, query) = Self::separar_query(&ruta)?;
 
         let headers = Self::obtener_headers(reader)?;
-        let body = Self::obtener_body(reader, &headers)?;
+        let (body, body_binario) = Self::obtener_body(reader, &headers)?;
 
         Ok(Self {
             metodo,
             ruta,
+            query,
             version,
             headers,
             body,
+            body_binario,
             logger,
+            usuario: None,
+            permiso: Permiso::Lectura,
         })
     }
 
+    fn separar_query(ruta: &str) -> Result<(String, HashMap<String, String>), ErrorHttp> {
+        let (ruta, query) = match ruta.split_once('?') {
+            Some((ruta, query)) => (ruta, query),
+            None => return Ok((ruta.to_string(), HashMap::new())),
+        };
+        let query = serde_urlencoded::from_str(query)
+            .map_err(|_| ErrorHttp::BadRequest("No se pudo parsear la query".to_string()))?;
+        Ok((ruta.to_string(), query))
+    }
+
     fn obtener_headers<T>(
         reader: &mut BufReader<&mut T>,
     ) -> Result<HashMap<String, String>, ErrorHttp>
@@ -136,10 +161,13 @@ impl Request {
         Ok((metodo, ruta, version))
     }
 
+    /// Lee el body del request. Si el contenido es binario lo devuelve sin parsear en el segundo
+    /// elemento de la tupla.
+    #[allow(clippy::type_complexity)]
     fn obtener_body<T>(
         reader: &mut BufReader<&mut T>,
         headers: &HashMap<String, String>,
-    ) -> Result<Option<HashMap<String, String>>, ErrorHttp>
+    ) -> Result<(Option<HashMap<String, String>>, Option<Vec<u8>>), ErrorHttp>
     where
         T: Read + Write,
     {
@@ -147,23 +175,21 @@ impl Request {
 
         let (largo, tipo) = match headers {
             Some((largo, tipo)) => (largo, tipo),
-            None => return Ok(None),
+            None => return Ok((None, None)),
         };
 
         let mut body_buf = vec![0; largo];
-        let leidos = reader
-            .read(&mut body_buf)
-            .map_err(|e| ErrorHttp::InternalServerError(e.to_string()))?;
+        reader
+            .read_exact(&mut body_buf)
+            .map_err(|_| ErrorHttp::BadRequest("No se pudo leer el body completo".to_string()))?;
 
-        if leidos != largo {
-            return Err(ErrorHttp::BadRequest(
-                "No se pudo leer el body completo".to_string(),
-            ));
+        if tipo.es_binario() {
+            return Ok((None, Some(body_buf)));
         }
 
         let body = tipo.parsear_contenido(&body_buf)?;
 
-        Ok(Some(body))
+        Ok((Some(body), None))
     }
 }
 
@@ -174,6 +200,7 @@ impl Debug for Request {
             .field("ruta", &self.ruta)
             .field("version", &self.version)
             .field("headers", &self.headers)
+            .field("query", &self.query)
             .field("body", &self.body)
             .finish()
     }
@@ -245,6 +272,7 @@ mod tests {
         assert_eq!(request.headers, headers);
         assert_eq!(request.body, None);
     }
+
     #[test]
     fn test03_from_con_content_length_0_no_lee_body() {
         let logger = Arc::new(Logger::new(PathBuf::from("tmp/request_test03")).unwrap());
@@ -313,4 +341,24 @@ mod tests {
 
         Request::from(&mut reader, logger).unwrap();
     }
+
+    #[test]
+    fn test06_from_separa_la_query_y_guarda_el_body_binario() {
+        let logger = Arc::new(Logger::new(PathBuf::from("tmp/request_test06")).unwrap());
+
+        let mut mock_tcp = MockTcpStream {
+            lectura_data: b"POST /repo/git-upload-pack?service=git-upload-pack&x=a%20b HTTP/1.1\r\nContent-Type: application/x-git-upload-pack-request\r\nContent-Length: 6\r\n\r\n0000\x00\xff".to_vec(),
+            escritura_data: vec![],
+        };
+
+        let mut reader = BufReader::new(&mut mock_tcp);
+
+        let request = Request::from(&mut reader, logger).unwrap();
+
+        assert_eq!(request.ruta, "/repo/git-upload-pack".to_string());
+        assert_eq!(request.query.get("service").unwrap(), "git-upload-pack");
+        assert_eq!(request.query.get("x").unwrap(), "a b");
+        assert_eq!(request.body, None);
+        assert_eq!(request.body_binario.unwrap(), b"0000\x00\xff".to_vec());
+    }
 }
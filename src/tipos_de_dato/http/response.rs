@@ -13,6 +13,8 @@ pub struct Response {
     pub version: String,
     pub headers: HashMap<String, String>,
     pub body: Option<String>,
+    /// Body de las respuestas binarias, como los paquetes de smart http.
+    pub body_binario: Option<Vec<u8>>,
     pub logger: Arc<Logger>,
 }
 
@@ -21,7 +23,15 @@ impl Response {
         let msj_error = error.obtener_mensaje();
         let body = format!(r#"{{"message":"{}"}}"#, msj_error);
 
-        Response::new(logger.clone(), error.obtener_estado(), Some(&body))
+        let mut response = Response::new(logger.clone(), error.obtener_estado(), Some(&body));
+        if let ErrorHttp::Unauthorized(_) = error {
+            // para que el cliente sepa que tiene que mandar usuario y contrasenia
+            response.headers.insert(
+                "WWW-Authenticate".to_string(),
+                r#"Basic realm="gir""#.to_string(),
+            );
+        }
+        response
     }
 
     pub fn new(logger: Arc<Logger>, estado: EstadoHttp, body: Option<&str>) -> Self {
@@ -41,10 +51,32 @@ impl Response {
             version: "HTTP/1.1".to_string(),
             headers,
             body: body.map(|s| s.to_string()),
+            body_binario: None,
             logger,
         }
     }
 
+    /// Crea una respuesta con un body binario del tipo de contenido dado.
+    pub fn new_binario(
+        logger: Arc<Logger>,
+        estado: EstadoHttp,
+        tipo_contenido: &str,
+        body: Vec<u8>,
+    ) -> Self {
+        let mut response = Self::new(logger, estado, None);
+        response
+            .headers
+            .insert("Content-Length".to_string(), body.len().to_string());
+        response
+            .headers
+            .insert("Content-Type".to_string(), tipo_contenido.to_string());
+        response
+            .headers
+            .insert("Cache-Control".to_string(), "no-cache".to_string());
+        response.body_binario = Some(body);
+        response
+    }
+
     pub fn enviar<T>(&self, stream: &mut T) -> Result<(), ErrorHttp>
     where
         T: Read + Write,
@@ -64,8 +96,12 @@ impl Response {
         if let Some(body) = &self.body {
             response.push_str(body);
         }
+        let mut response = response.into_bytes();
+        if let Some(body_binario) = &self.body_binario {
+            response.extend(body_binario);
+        }
 
-        stream.write_all(response.as_bytes()).map_err(|e| {
+        stream.write_all(&response).map_err(|e| {
             ErrorHttp::InternalServerError(format!("Error al enviar la respuesta: {}", e))
         })?;
 
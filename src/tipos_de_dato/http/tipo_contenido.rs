@@ -6,6 +6,9 @@ pub enum TipoContenido {
     Json,
     Xml,
     UrlEncoded,
+    /// Pedidos de smart http (`application/x-git-upload-pack-request` y
+    /// `application/x-git-receive-pack-request`), que no se parsean.
+    PaqueteGit,
 }
 
 impl TipoContenido {
@@ -14,10 +17,18 @@ impl TipoContenido {
             "application/json" => Ok(Self::Json),
             "application/xml" => Ok(Self::Xml),
             "application/x-www-form-urlencoded" => Ok(Self::UrlEncoded),
+            "application/x-git-upload-pack-request" | "application/x-git-receive-pack-request" => {
+                Ok(Self::PaqueteGit)
+            }
             _ => Err(format!("Tipo de contenido {} no soportado", string)),
         }
     }
 
+    /// Devuelve true si el contenido se guarda tal cual, sin parsearlo.
+    pub fn es_binario(&self) -> bool {
+        matches!(self, Self::PaqueteGit)
+    }
+
     pub fn parsear_contenido(
         &self,
         contenido: &[u8],
@@ -26,6 +37,7 @@ impl TipoContenido {
             Self::Json => serde_json::from_slice(contenido).ok(),
             Self::UrlEncoded => serde_urlencoded::from_bytes(contenido).ok(),
             Self::Xml => serde_xml_rs::from_reader(contenido).ok(),
+            Self::PaqueteGit => None,
         };
 
         resultado.ok_or(ErrorHttp::BadRequest(
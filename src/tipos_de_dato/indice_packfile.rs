@@ -0,0 +1,230 @@
+use sha1::{Digest, Sha1};
+
+use super::objetos::tree::Tree;
+
+const FIRMA_IDX: [u8; 4] = [0xff, b't', b'O', b'c'];
+const VERSION_IDX: u32 = 2;
+// los offsets que no entran en 31 bits se guardan en la tabla de offsets de 8 bytes
+const BIT_OFFSET_LARGO: u32 = 0x8000_0000;
+
+/// Entrada del indice: el hash de un objeto, el crc32 de su entrada comprimida
+/// dentro del packfile y el offset donde empieza esa entrada.
+#[derive(Debug, Clone, PartialEq)]
+pub struct EntradaIndice {
+    pub hash: String,
+    pub crc: u32,
+    pub offset: u64,
+}
+
+/// Indice (.idx) en formato version 2 de git de un packfile guardado en `objects/pack/`.
+/// Permite encontrar el offset de un objeto dentro del packfile sin recorrerlo entero.
+#[derive(Debug, PartialEq)]
+pub struct IndicePackfile {
+    entradas: Vec<EntradaIndice>,
+    checksum_packfile: Vec<u8>,
+}
+
+impl IndicePackfile {
+    /// Crea el indice a partir de las entradas de los objetos del packfile y el checksum del mismo.
+    pub fn new(mut entradas: Vec<EntradaIndice>, checksum_packfile: &[u8]) -> IndicePackfile {
+        entradas.sort_by(|a, b| a.hash.cmp(&b.hash));
+        IndicePackfile {
+            entradas,
+            checksum_packfile: checksum_packfile.to_vec(),
+        }
+    }
+
+    /// Devuelve el offset dentro del packfile del objeto con el hash dado, si esta en el indice.
+    pub fn buscar_offset(&self, hash: &str) -> Option<u64> {
+        self.entradas
+            .binary_search_by(|entrada| entrada.hash.as_str().cmp(hash))
+            .ok()
+            .map(|posicion| self.entradas[posicion].offset)
+    }
+
+    /// Devuelve los hashes de todos los objetos del indice, ordenados.
+    pub fn obtener_hashes(&self) -> Vec<String> {
+        self.entradas
+            .iter()
+            .map(|entrada| entrada.hash.clone())
+            .collect()
+    }
+
+    /// Devuelve los offsets de todas las entradas, en el orden de los hashes.
+    pub fn obtener_offsets(&self) -> Vec<u64> {
+        self.entradas.iter().map(|entrada| entrada.offset).collect()
+    }
+
+    /// Arma los bytes del archivo .idx:
+    /// firma, version, tabla fanout, hashes, crcs, offsets, offsets largos, checksum del packfile y
+    /// checksum del indice.
+    pub fn codificar(&self) -> Result<Vec<u8>, String> {
+        let mut bytes: Vec<u8> = Vec::new();
+        bytes.extend(FIRMA_IDX);
+        bytes.extend(VERSION_IDX.to_be_bytes());
+
+        let mut hashes: Vec<Vec<u8>> = Vec::new();
+        for entrada in &self.entradas {
+            hashes.push(Tree::decode_hex(&entrada.hash)?);
+        }
+
+        // fanout[i] = cantidad de objetos cuyo primer byte es menor o igual a i
+        let mut fanout = [0u32; 256];
+        for hash in &hashes {
+            fanout[hash[0] as usize] += 1;
+        }
+        let mut acumulado = 0;
+        for cantidad in fanout.iter_mut() {
+            acumulado += *cantidad;
+            *cantidad = acumulado;
+        }
+        for cantidad in fanout {
+            bytes.extend(cantidad.to_be_bytes());
+        }
+
+        for hash in &hashes {
+            bytes.extend(hash);
+        }
+        for entrada in &self.entradas {
+            bytes.extend(entrada.crc.to_be_bytes());
+        }
+
+        let mut offsets_largos: Vec<u64> = Vec::new();
+        for entrada in &self.entradas {
+            if entrada.offset < BIT_OFFSET_LARGO as u64 {
+                bytes.extend((entrada.offset as u32).to_be_bytes());
+            } else {
+                bytes.extend((BIT_OFFSET_LARGO | offsets_largos.len() as u32).to_be_bytes());
+                offsets_largos.push(entrada.offset);
+            }
+        }
+        for offset in offsets_largos {
+            bytes.extend(offset.to_be_bytes());
+        }
+
+        bytes.extend(&self.checksum_packfile);
+        let checksum_indice = Sha1::digest(&bytes);
+        bytes.extend(checksum_indice);
+
+        Ok(bytes)
+    }
+
+    /// Lee un archivo .idx version 2.
+    ///
+    /// ## Errores
+    /// - Si la firma o la version no son las de un indice version 2
+    /// - Si el archivo esta truncado
+    pub fn decodificar(bytes: &[u8]) -> Result<IndicePackfile, String> {
+        if bytes.len() < 8 + 256 * 4 + 40 || bytes[..4] != FIRMA_IDX {
+            return Err("El archivo no es un indice de packfile valido".to_string());
+        }
+        if Self::leer_u32(bytes, 4)? != VERSION_IDX {
+            return Err("Version de indice de packfile no soportada".to_string());
+        }
+        let cantidad = Self::leer_u32(bytes, 8 + 255 * 4)? as usize;
+
+        let inicio_hashes = 8 + 256 * 4;
+        let inicio_crcs = inicio_hashes + cantidad * 20;
+        let inicio_offsets = inicio_crcs + cantidad * 4;
+        let inicio_offsets_largos = inicio_offsets + cantidad * 4;
+
+        let mut entradas = Vec::new();
+        for i in 0..cantidad {
+            let hash = bytes
+                .get(inicio_hashes + i * 20..inicio_hashes + (i + 1) * 20)
+                .ok_or("Indice de packfile truncado".to_string())?;
+            let crc = Self::leer_u32(bytes, inicio_crcs + i * 4)?;
+            let offset_corto = Self::leer_u32(bytes, inicio_offsets + i * 4)?;
+
+            let offset = if offset_corto & BIT_OFFSET_LARGO == 0 {
+                offset_corto as u64
+            } else {
+                let posicion =
+                    inicio_offsets_largos + (offset_corto & !BIT_OFFSET_LARGO) as usize * 8;
+                let offset_largo = bytes
+                    .get(posicion..posicion + 8)
+                    .ok_or("Indice de packfile truncado".to_string())?;
+                u64::from_be_bytes([
+                    offset_largo[0],
+                    offset_largo[1],
+                    offset_largo[2],
+                    offset_largo[3],
+                    offset_largo[4],
+                    offset_largo[5],
+                    offset_largo[6],
+                    offset_largo[7],
+                ])
+            };
+
+            entradas.push(EntradaIndice {
+                hash: Tree::encode_hex(hash),
+                crc,
+                offset,
+            });
+        }
+
+        let checksum_packfile = &bytes[bytes.len() - 40..bytes.len() - 20];
+        Ok(IndicePackfile {
+            entradas,
+            checksum_packfile: checksum_packfile.to_vec(),
+        })
+    }
+
+    fn leer_u32(bytes: &[u8], posicion: usize) -> Result<u32, String> {
+        let leido = bytes
+            .get(posicion..posicion + 4)
+            .ok_or("Indice de packfile truncado".to_string())?;
+        Ok(u32::from_be_bytes([leido[0], leido[1], leido[2], leido[3]]))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn entradas_de_prueba() -> Vec<EntradaIndice> {
+        vec![
+            EntradaIndice {
+                hash: "877e9f62c1031de82130f279f009469cc9e09ab0".to_string(),
+                crc: 1234,
+                offset: 12,
+            },
+            EntradaIndice {
+                hash: "5122b1de1b7a07e36b01cd62bd622a0715f92478".to_string(),
+                crc: 5678,
+                offset: 1 << 33,
+            },
+        ]
+    }
+
+    #[test]
+    fn test01_el_indice_codificado_se_decodifica_igual() {
+        let indice = IndicePackfile::new(entradas_de_prueba(), &[7; 20]);
+        let bytes = indice.codificar().unwrap();
+
+        assert_eq!(IndicePackfile::decodificar(&bytes).unwrap(), indice);
+    }
+
+    #[test]
+    fn test02_buscar_offset_de_un_objeto() {
+        let indice = IndicePackfile::new(entradas_de_prueba(), &[7; 20]);
+
+        assert_eq!(
+            indice.buscar_offset("5122b1de1b7a07e36b01cd62bd622a0715f92478"),
+            Some(1 << 33)
+        );
+        assert_eq!(
+            indice.buscar_offset("877e9f62c1031de82130f279f009469cc9e09ab0"),
+            Some(12)
+        );
+        assert_eq!(
+            indice.buscar_offset("0000000000000000000000000000000000000000"),
+            None
+        );
+    }
+
+    #[test]
+    fn test03_no_se_decodifica_un_archivo_que_no_es_indice() {
+        assert!(IndicePackfile::decodificar(&[0; 2000]).is_err());
+    }
+}
@@ -0,0 +1,113 @@
+use crate::tipos_de_dato::objetos::tag::TagObj;
+use crate::utils::{objects, referencia};
+use std::collections::{BinaryHeap, HashMap, HashSet};
+use std::path::Path;
+
+/// Recorrido de los commits locales para mandarle al servidor los haves durante la negociacion
+/// del fetch. Los commits salen de los mas nuevos a los mas viejos, empezando por las cabezas de
+/// todas las referencias locales. Cuando el servidor reconoce un commit como comun, ni ese commit
+/// ni sus ancestros hace falta mandarlos.
+pub struct Negociacion {
+    dir_objetos: String,
+    /// Commits por mandar, ordenados por fecha del committer.
+    pendientes: BinaryHeap<(i64, String)>,
+    /// Commits que ya se agregaron a pendientes alguna vez.
+    vistos: HashSet<String>,
+    /// Padres de los commits que ya se mandaron, para marcarlos como comunes junto con su hijo.
+    padres: HashMap<String, Vec<String>>,
+    /// Commits que el servidor ya tiene, ya sea porque los reconocio o porque son padres de uno
+    /// que reconocio.
+    comunes: HashSet<String>,
+}
+
+impl Negociacion {
+    /// Arma el recorrido desde las ramas, las ramas remotas y los tags del repositorio en
+    /// `dir_gir` (el directorio .gir).
+    pub fn new(dir_gir: &str) -> Result<Negociacion, String> {
+        let dir_objetos = format!("{}/objects/", dir_gir.trim_end_matches('/'));
+        let mut negociacion = Negociacion {
+            dir_objetos,
+            pendientes: BinaryHeap::new(),
+            vistos: HashSet::new(),
+            padres: HashMap::new(),
+            comunes: HashSet::new(),
+        };
+        let dir_gir = Path::new(dir_gir);
+        for (_, hash) in referencia::obtener_refs_y_hashes(&dir_gir.join("refs"), dir_gir)? {
+            let commit = TagObj::pelar(&hash, &negociacion.dir_objetos)?;
+            negociacion.agregar_pendiente(commit);
+        }
+        Ok(negociacion)
+    }
+
+    // Los commits que no estan en el repositorio (por ejemplo los padres de un commit shallow) no
+    // se pueden mandar como haves.
+    fn agregar_pendiente(&mut self, commit: String) {
+        if !self.vistos.insert(commit.clone()) {
+            return;
+        }
+        if let Ok(datos) = objects::leer_commit(&commit, &self.dir_objetos) {
+            self.padres.insert(commit.clone(), datos.padres);
+            self.pendientes.push((datos.fecha, commit));
+        }
+    }
+
+    /// Devuelve los proximos commits a mandar como haves, a lo sumo `cantidad`. Si no queda
+    /// ninguno devuelve un vector vacio.
+    pub fn siguiente_tanda(&mut self, cantidad: usize) -> Vec<String> {
+        let mut tanda = Vec::new();
+        while tanda.len() < cantidad {
+            let commit = match self.pendientes.pop() {
+                Some((_, commit)) => commit,
+                None => break,
+            };
+            if self.comunes.contains(&commit) {
+                continue;
+            }
+            for padre in self.padres.get(&commit).cloned().unwrap_or_default() {
+                self.agregar_pendiente(padre);
+            }
+            tanda.push(commit);
+        }
+        tanda
+    }
+
+    /// Marca el commit como comun con el servidor: sus padres ya no se mandan como haves.
+    pub fn marcar_comun(&mut self, commit: &str) {
+        self.comunes.insert(commit.to_string());
+        if let Some(padres) = self.padres.get(commit) {
+            self.comunes.extend(padres.iter().cloned());
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::tipos_de_dato::logger::Logger;
+    use crate::utils::{io, ramas, testing};
+    use serial_test::serial;
+    use std::path::PathBuf;
+    use std::sync::Arc;
+
+    #[test]
+    #[serial]
+    fn test01_los_haves_salen_del_mas_nuevo_y_no_siguen_los_padres_de_un_comun() {
+        let logger = Arc::new(Logger::new(PathBuf::from("tmp/negociacion_test01")).unwrap());
+        testing::limpiar_archivo_gir(logger.clone());
+        let mut commits = Vec::new();
+        for contenido in ["uno", "dos", "tres"] {
+            io::escribir_bytes("test_file.txt", contenido).unwrap();
+            testing::addear_archivos_y_comittear(vec!["test_file.txt".to_string()], logger.clone());
+            commits.push(ramas::obtener_hash_commit_asociado_rama_actual().unwrap());
+        }
+
+        let mut negociacion = Negociacion::new(".gir").unwrap();
+        let primera_tanda = negociacion.siguiente_tanda(1);
+        negociacion.marcar_comun(&primera_tanda[0]);
+        let segunda_tanda = negociacion.siguiente_tanda(10);
+
+        assert_eq!(primera_tanda, vec![commits[2].clone()]);
+        assert!(segunda_tanda.is_empty());
+    }
+}
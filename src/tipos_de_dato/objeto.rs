@@ -1,8 +1,13 @@
 use std::{path::PathBuf, sync::Arc};
 
+use crate::utils::index::CacheStat;
+
 use super::{
     logger::Logger,
-    objetos::{blob::Blob, tree::Tree},
+    objetos::{
+        blob::{Blob, ModoBlob},
+        tree::Tree,
+    },
 };
 
 #[derive(Clone, Debug, PartialEq, Eq)]
@@ -45,7 +50,7 @@ impl Objeto {
     }
 
     /// Dada una linea en formato del archivo index, devuelve una instancia del objeto.
-    /// Si el modo es 100644 devuelve un Blob
+    /// Si el modo es 100644, 100755, 120000 o 160000 devuelve un Blob con ese modo
     /// Si el modo es 40000 devuelve un Tree
     pub fn from_index(linea_index: &str, logger: Arc<Logger>) -> Result<Objeto, String> {
         let mut line = linea_index.split_whitespace();
@@ -60,18 +65,28 @@ impl Objeto {
             None => Err("Error al leer el nombre")?,
         };
 
-        match modo {
-            "100644" => Ok(Objeto::Blob(Blob {
+        if modo == "40000" {
+            let tree = Tree::from_hash(hash, ubicacion, logger)?;
+            return Ok(Objeto::Tree(tree));
+        }
+        match ModoBlob::desde_str(modo) {
+            Some(modo) => Ok(Objeto::Blob(Blob {
                 nombre: nombre.to_string(),
                 ubicacion,
                 hash: hash.to_string(),
+                modo,
                 logger,
             })),
-            "40000" => {
-                let tree = Tree::from_hash(hash, ubicacion, logger)?;
-                Ok(Objeto::Tree(tree))
-            }
-            _ => Err("Modo no soportado".to_string()),
+            None => Err("Modo no soportado".to_string()),
+        }
+    }
+
+    /// Devuelve true si el objeto es un gitlink, o sea una referencia a un commit de otro
+    /// repositorio que no esta en la base de objetos.
+    pub fn es_gitlink(&self) -> bool {
+        match self {
+            Objeto::Blob(blob) => blob.modo == ModoBlob::Gitlink,
+            Objeto::Tree(_) => false,
         }
     }
 
@@ -81,6 +96,7 @@ impl Objeto {
     pub fn from_directorio(
         mut directorio: PathBuf,
         hijos_especificados: Option<&Vec<PathBuf>>,
+        cache: Option<&mut CacheStat>,
         logger: Arc<Logger>,
     ) -> Result<Objeto, String> {
         if directorio.starts_with("./") && directorio != PathBuf::from("./") {
@@ -90,11 +106,15 @@ impl Objeto {
             };
         }
 
-        if directorio.is_dir() {
-            let tree = Tree::from_directorio(directorio.clone(), hijos_especificados, logger)?;
+        if directorio.is_symlink() {
+            let blob = Blob::from_directorio(directorio.clone(), cache, logger)?;
+            Ok(Objeto::Blob(blob))
+        } else if directorio.is_dir() {
+            let tree =
+                Tree::from_directorio(directorio.clone(), hijos_especificados, cache, logger)?;
             Ok(Objeto::Tree(tree))
         } else if directorio.is_file() {
-            let blob = Blob::from_directorio(directorio.clone(), logger)?;
+            let blob = Blob::from_directorio(directorio.clone(), cache, logger)?;
             Ok(Objeto::Blob(blob))
         } else {
             Err(format!("No se pudo leer el directorio {directorio:#?}"))
@@ -121,6 +141,7 @@ mod tests {
             Objeto::Blob(Blob {
                 nombre: "hola.txt".to_string(),
                 hash: "1234567890".to_string(),
+                modo: ModoBlob::Normal,
                 ubicacion: PathBuf::from("./hola.txt"),
                 logger: logger.clone()
             })
@@ -134,6 +155,7 @@ mod tests {
         let objeto = Objeto::from_directorio(
             PathBuf::from("test_dir/objetos/archivo.txt"),
             None,
+            None,
             logger.clone(),
         )
         .unwrap();
@@ -143,6 +165,7 @@ mod tests {
             Objeto::Blob(Blob {
                 nombre: "archivo.txt".to_string(),
                 hash: "2b824e648965b94c6c6b3dd0702feb91f699ed62".to_string(),
+                modo: ModoBlob::Normal,
                 ubicacion: PathBuf::from("test_dir/objetos/archivo.txt"),
                 logger
             })
@@ -154,13 +177,18 @@ mod tests {
 
     fn test03_tree_from_directorio() {
         let logger = Arc::new(logger::Logger::new(PathBuf::from("tmp/objeto_test03")).unwrap());
-        let objeto =
-            Objeto::from_directorio(PathBuf::from("test_dir/objetos"), None, logger.clone())
-                .unwrap();
+        let objeto = Objeto::from_directorio(
+            PathBuf::from("test_dir/objetos"),
+            None,
+            None,
+            logger.clone(),
+        )
+        .unwrap();
 
         let hijo = Objeto::Blob(Blob {
             nombre: "archivo.txt".to_string(),
             hash: "2b824e648965b94c6c6b3dd0702feb91f699ed62".to_string(),
+            modo: ModoBlob::Normal,
             ubicacion: PathBuf::from("test_dir/objetos/archivo.txt"),
             logger: logger.clone(),
         });
@@ -180,7 +208,7 @@ mod tests {
     fn test04_tree_from_index() {
         let logger = Arc::new(logger::Logger::new(PathBuf::from("tmp/objeto_test04")).unwrap());
         let objeto_a_escibir =
-            Objeto::from_directorio(PathBuf::from("test_dir"), None, logger.clone()).unwrap();
+            Objeto::from_directorio(PathBuf::from("test_dir"), None, None, logger.clone()).unwrap();
 
         if let Objeto::Tree(ref tree) = objeto_a_escibir {
             tree.escribir_en_base().unwrap();
@@ -197,6 +225,7 @@ mod tests {
         let nieto_1 = Objeto::Blob(Blob {
             nombre: "archivo.txt".to_string(),
             hash: "2b824e648965b94c6c6b3dd0702feb91f699ed62".to_string(),
+            modo: ModoBlob::Normal,
             ubicacion: PathBuf::from("test_dir/objetos/archivo.txt"),
             logger: logger.clone(),
         });
@@ -204,6 +233,7 @@ mod tests {
         let nieto_2 = Objeto::Blob(Blob {
             nombre: "archivo.txt".to_string(),
             hash: "ba1d9d6871ba93f7e070c8663e6739cc22f07d3f".to_string(),
+            modo: ModoBlob::Normal,
             ubicacion: PathBuf::from("test_dir/muchos_objetos/archivo.txt"),
             logger: logger.clone(),
         });
@@ -211,6 +241,7 @@ mod tests {
         let nieto_3 = Objeto::Blob(Blob {
             nombre: "archivo_copy.txt".to_string(),
             hash: "2b824e648965b94c6c6b3dd0702feb91f699ed62".to_string(),
+            modo: ModoBlob::Normal,
             ubicacion: PathBuf::from("test_dir/muchos_objetos/archivo_copy.txt"),
             logger: logger.clone(),
         });
@@ -244,6 +275,7 @@ mod tests {
         let objeto = Objeto::from_directorio(
             PathBuf::from("test_dir/objetos/archivo.txt"),
             None,
+            None,
             logger.clone(),
         )
         .unwrap();
@@ -257,4 +289,23 @@ mod tests {
 
         assert_eq!(objeto.obtener_tamanio().unwrap(), 23);
     }
+
+    #[test]
+    #[serial]
+    fn test06_blob_from_index_conserva_el_modo() {
+        let logger = Arc::new(logger::Logger::new(PathBuf::from("tmp/objeto_test06")).unwrap());
+        let ejecutable = Objeto::from_index("100755 1234567890 script.sh", logger.clone()).unwrap();
+        let symlink = Objeto::from_index("120000 1234567890 link", logger.clone()).unwrap();
+        let gitlink = Objeto::from_index("160000 1234567890 sub", logger.clone()).unwrap();
+
+        match (ejecutable, symlink, &gitlink) {
+            (Objeto::Blob(ejecutable), Objeto::Blob(symlink), Objeto::Blob(_)) => {
+                assert_eq!(ejecutable.modo, ModoBlob::Ejecutable);
+                assert_eq!(symlink.modo, ModoBlob::Symlink);
+                assert!(gitlink.es_gitlink());
+            }
+            _ => panic!("Se esperaban blobs"),
+        }
+        assert!(Objeto::from_index("100600 1234567890 hola.txt", logger).is_err());
+    }
 }
@@ -5,14 +5,84 @@ use crate::{
         logger::Logger,
     },
     utils::compresion::descomprimir_objeto,
-    utils::path_buf::obtener_nombre,
+    utils::{
+        index::{CacheStat, MetadataIndex},
+        io,
+        path_buf::obtener_nombre,
+    },
 };
-use std::{fmt::Display, path::PathBuf, sync::Arc};
+use std::{fmt::Display, fs, path::PathBuf, sync::Arc};
+
+/// Modos con los que puede aparecer una hoja dentro de un arbol.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum ModoBlob {
+    /// Archivo comun (100644).
+    Normal,
+    /// Archivo ejecutable (100755).
+    Ejecutable,
+    /// Link simbolico (120000), el contenido del blob es el destino del link.
+    Symlink,
+    /// Referencia a un commit de otro repositorio (160000). Solo se soporta leerlo y
+    /// conservarlo, nunca se arma a partir del directorio de trabajo.
+    Gitlink,
+}
+
+impl ModoBlob {
+    /// Devuelve el modo correspondiente al string en formato git o None si no es un modo de hoja.
+    pub fn desde_str(modo: &str) -> Option<ModoBlob> {
+        match modo {
+            "100644" => Some(ModoBlob::Normal),
+            "100755" => Some(ModoBlob::Ejecutable),
+            "120000" => Some(ModoBlob::Symlink),
+            "160000" => Some(ModoBlob::Gitlink),
+            _ => None,
+        }
+    }
+
+    /// Devuelve el modo en el formato que usa git en los arboles y en el index.
+    pub fn como_str(&self) -> &'static str {
+        match self {
+            ModoBlob::Normal => "100644",
+            ModoBlob::Ejecutable => "100755",
+            ModoBlob::Symlink => "120000",
+            ModoBlob::Gitlink => "160000",
+        }
+    }
+
+    /// Devuelve el tipo del objeto al que apunta una hoja con este modo.
+    pub fn tipo_objeto(&self) -> &'static str {
+        match self {
+            ModoBlob::Gitlink => "commit",
+            _ => "blob",
+        }
+    }
+
+    /// Obtiene el modo de un archivo del directorio de trabajo a partir de su metadata.
+    pub fn desde_archivo(ubicacion: &PathBuf) -> Result<ModoBlob, String> {
+        let metadata = fs::symlink_metadata(ubicacion)
+            .map_err(|_| format!("No se pudo leer la metadata de {}", ubicacion.display()))?;
+        if metadata.file_type().is_symlink() {
+            Ok(ModoBlob::Symlink)
+        } else if io::es_ejecutable(ubicacion) {
+            Ok(ModoBlob::Ejecutable)
+        } else {
+            Ok(ModoBlob::Normal)
+        }
+    }
+}
+
+impl Display for ModoBlob {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{}", self.como_str())
+    }
+}
 
 #[derive(Clone, Debug)]
 pub struct Blob {
     /// Hash del objeto blob.
     pub hash: String,
+    /// Modo del archivo que representa el blob.
+    pub modo: ModoBlob,
     /// Ubicacion del objeto blob.
     pub ubicacion: PathBuf,
     /// Nombre del archivo que representa el blob.
@@ -23,7 +93,7 @@ pub struct Blob {
 
 impl PartialEq for Blob {
     fn eq(&self, other: &Self) -> bool {
-        self.hash == other.hash
+        self.hash == other.hash && self.modo == other.modo
     }
 }
 
@@ -52,22 +122,44 @@ impl Blob {
     }
 
     /// Crea un objeto blob a partir de un archivo.
-    pub fn from_directorio(directorio: PathBuf, logger: Arc<Logger>) -> Result<Blob, String> {
-        if directorio.is_dir() {
+    /// El modo del blob se toma de la metadata del archivo, sin seguir links simbolicos.
+    /// Si se pasa un cache que tiene el hash del archivo y su metadata no cambio, no lo vuelve
+    /// a hashear. Si lo hashea, agrega el hash al cache junto con la metadata que tenia el archivo.
+    pub fn from_directorio(
+        directorio: PathBuf,
+        cache: Option<&mut CacheStat>,
+        logger: Arc<Logger>,
+    ) -> Result<Blob, String> {
+        if directorio.is_dir() && !directorio.is_symlink() {
             return Err("No se puede crear un blob a partir de un directorio".to_string());
         }
-        let hash = HashObject {
-            logger: logger.clone(),
-            escribir: false,
-            ubicacion_archivo: directorio.clone(),
-        }
-        .ejecutar()?;
+        let modo = ModoBlob::desde_archivo(&directorio)?;
+        let metadata = MetadataIndex::de_archivo(&directorio);
+        let hash_cacheado = cache
+            .as_ref()
+            .and_then(|cache| cache.obtener_hash(&directorio, modo, &metadata));
+        let hash = match hash_cacheado {
+            Some(hash) => hash,
+            None => {
+                let hash = HashObject {
+                    logger: logger.clone(),
+                    escribir: false,
+                    ubicacion_archivo: directorio.clone(),
+                }
+                .ejecutar()?;
+                if let Some(cache) = cache {
+                    cache.registrar(directorio.clone(), modo, metadata, hash.clone());
+                }
+                hash
+            }
+        };
 
         let nombre = obtener_nombre(&directorio)?;
 
         Ok(Blob {
             nombre,
             hash,
+            modo,
             ubicacion: directorio,
             logger,
         })
@@ -76,7 +168,7 @@ impl Blob {
 
 impl Display for Blob {
     fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
-        let string = format!("100644 {} {}\n", self.hash, self.ubicacion.display());
+        let string = format!("{} {} {}\n", self.modo, self.hash, self.ubicacion.display());
         write!(f, "{}", string)
     }
 }
@@ -129,10 +129,20 @@ impl CommitObj {
     /// Devuelve un error si el hash no es valido o si no se pudo obtener el contenido del commit.
     /// Devuelve error si no se logran llenar todos los campos del commit.
     pub fn from_hash(hash: String, logger: Arc<Logger>) -> Result<CommitObj, String> {
+        Self::from_hash_de(hash, ".gir/objects/", logger)
+    }
+
+    /// Igual que `from_hash`, pero lee el commit de `dir_objetos` en lugar del repositorio
+    /// del directorio actual.
+    pub fn from_hash_de(
+        hash: String,
+        dir_objetos: &str,
+        logger: Arc<Logger>,
+    ) -> Result<CommitObj, String> {
         if hash.len() != 40 {
             return Err("Hash invalido".to_string());
         }
-        let (_header, contenido) = cat_file::obtener_contenido_objeto(&hash)?;
+        let (_header, contenido) = cat_file::obtener_contenido_objeto_de(&hash, dir_objetos)?;
         let mut padres: Vec<String> = Vec::new();
         let mut autor_option: Option<String> = None;
         let mut mail_option: Option<String> = None;
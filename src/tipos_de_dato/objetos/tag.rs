@@ -0,0 +1,169 @@
+use crate::{
+    tipos_de_dato::{comandos::hash_object::HashObject, date::Date},
+    utils::{compresion, io},
+};
+
+/// Objeto tag anotado: apunta a otro objeto (normalmente un commit) y guarda quien lo creo,
+/// cuando y con que mensaje.
+#[derive(Clone, Debug, PartialEq)]
+pub struct TagObj {
+    /// Hash del objeto tag.
+    pub hash: String,
+    /// Hash del objeto al que apunta el tag.
+    pub objeto: String,
+    /// Tipo del objeto al que apunta el tag.
+    pub tipo_objeto: String,
+    /// Nombre del tag.
+    pub nombre: String,
+    /// Nombre de quien creo el tag.
+    pub tagger: String,
+    /// Mail de quien creo el tag.
+    pub mail: String,
+    /// Fecha de creacion del tag, guardada en formato unix.
+    pub date: Date,
+    /// Mensaje del tag.
+    pub mensaje: String,
+}
+
+impl TagObj {
+    /// Arma el contenido (sin header) de un objeto tag en formato git.
+    /// El timestamp tiene el formato `<timestamp> <offset>`, igual que en los commits.
+    pub fn formatear_contenido(
+        objeto: &str,
+        tipo_objeto: &str,
+        nombre: &str,
+        tagger: &str,
+        mail: &str,
+        timestamp: &str,
+        mensaje: &str,
+    ) -> String {
+        format!(
+            "object {}\ntype {}\ntag {}\ntagger {} {} {}\n\n{}",
+            objeto, tipo_objeto, nombre, tagger, mail, timestamp, mensaje
+        )
+    }
+
+    /// Escribe el objeto tag con el contenido dado en `{dir_objetos}` y devuelve su hash.
+    pub fn escribir(contenido: &str, dir_objetos: &str) -> Result<String, String> {
+        let contenido_total = format!("tag {}\0{}", contenido.len(), contenido);
+        let hash = HashObject::hashear_contenido_objeto(&contenido_total.as_bytes().to_vec());
+        let ruta = format!("{}{}/{}", dir_objetos, &hash[..2], &hash[2..]);
+        io::escribir_bytes(ruta, compresion::comprimir_contenido(&contenido_total)?)?;
+        Ok(hash)
+    }
+
+    /// Lee el objeto tag con el hash dado del directorio de objetos del .gir.
+    /// Devuelve error si el objeto no existe o no es un tag.
+    pub fn from_hash(hash: &str) -> Result<TagObj, String> {
+        Self::from_hash_de(hash, ".gir/objects/")
+    }
+
+    /// Lee el objeto tag con el hash dado de `dir_objetos`.
+    /// Devuelve error si el objeto no existe o no es un tag.
+    pub fn from_hash_de(hash: &str, dir_objetos: &str) -> Result<TagObj, String> {
+        let objeto = compresion::descomprimir_objeto(hash, dir_objetos)?;
+        let (header, contenido) = objeto
+            .split_once('\0')
+            .ok_or(format!("El objeto {} no tiene header", hash))?;
+        if !header.starts_with("tag ") {
+            return Err(format!("El objeto {} no es un tag", hash));
+        }
+
+        let (encabezado, mensaje) = contenido.split_once("\n\n").unwrap_or((contenido, ""));
+        let mut objeto = None;
+        let mut tipo_objeto = None;
+        let mut nombre = None;
+        let mut tagger = None;
+        for linea in encabezado.lines() {
+            let linea_splitteada = linea.split(' ').collect::<Vec<&str>>();
+            match linea_splitteada[0] {
+                "object" => objeto = linea_splitteada.get(1).map(|s| s.to_string()),
+                "type" => tipo_objeto = linea_splitteada.get(1).map(|s| s.to_string()),
+                "tag" => nombre = linea_splitteada.get(1).map(|s| s.to_string()),
+                "tagger" if linea_splitteada.len() == 5 => tagger = Some(linea_splitteada),
+                _ => {}
+            }
+        }
+        let tagger = tagger.ok_or(format!("El tag {} no tiene tagger", hash))?;
+
+        Ok(TagObj {
+            hash: hash.to_string(),
+            objeto: objeto.ok_or(format!("El tag {} no tiene objeto", hash))?,
+            tipo_objeto: tipo_objeto.ok_or(format!("El tag {} no tiene tipo", hash))?,
+            nombre: nombre.ok_or(format!("El tag {} no tiene nombre", hash))?,
+            tagger: tagger[1].to_string(),
+            mail: tagger[2].to_string(),
+            date: Date {
+                tiempo: tagger[3].to_string(),
+                offset: tagger[4].to_string(),
+            },
+            mensaje: mensaje.to_string(),
+        })
+    }
+
+    /// Devuelve true si el objeto con el hash dado, dentro de `dir_objetos`, es un tag anotado.
+    pub fn es_tag(hash: &str, dir_objetos: &str) -> bool {
+        match compresion::obtener_objeto_descomprimido(hash, dir_objetos) {
+            Ok(objeto) => objeto.starts_with(b"tag "),
+            Err(_) => false,
+        }
+    }
+
+    /// "Pela" el hash dado: si es un tag anotado sigue la cadena de tags hasta llegar a un objeto
+    /// que no sea tag (normalmente un commit) y devuelve su hash. Si no es un tag lo devuelve igual.
+    pub fn pelar(hash: &str, dir_objetos: &str) -> Result<String, String> {
+        let mut hash = hash.to_string();
+        while Self::es_tag(&hash, dir_objetos) {
+            hash = Self::from_hash_de(&hash, dir_objetos)?.objeto;
+        }
+        Ok(hash)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use serial_test::serial;
+    use std::{path::PathBuf, sync::Arc};
+
+    use crate::{
+        tipos_de_dato::logger::Logger,
+        utils::{
+            ramas,
+            testing::{addear_archivos_y_comittear, limpiar_archivo_gir},
+        },
+    };
+
+    use super::*;
+
+    #[test]
+    #[serial]
+    fn test01_se_escribe_y_se_lee_un_tag() {
+        let logger = Arc::new(Logger::new(PathBuf::from("tmp/tag_obj_test01")).unwrap());
+        limpiar_archivo_gir(logger.clone());
+        io::escribir_bytes("tmp/tag_obj_archivo", "contenido").unwrap();
+        addear_archivos_y_comittear(vec!["tmp/tag_obj_archivo".to_string()], logger);
+        let commit = ramas::obtener_hash_commit_asociado_rama_actual().unwrap();
+
+        let contenido = TagObj::formatear_contenido(
+            &commit,
+            "commit",
+            "v1.0",
+            "nombre",
+            "mail",
+            "1700000000 -0300",
+            "primera version",
+        );
+        let hash = TagObj::escribir(&contenido, ".gir/objects/").unwrap();
+        let tag = TagObj::from_hash(&hash).unwrap();
+
+        assert_eq!(tag.objeto, commit);
+        assert_eq!(tag.tipo_objeto, "commit");
+        assert_eq!(tag.nombre, "v1.0");
+        assert_eq!(tag.tagger, "nombre");
+        assert_eq!(tag.mail, "mail");
+        assert_eq!(tag.date.tiempo, "1700000000");
+        assert_eq!(tag.mensaje, "primera version");
+        assert_eq!(TagObj::pelar(&hash, ".gir/objects/").unwrap(), commit);
+        assert_eq!(TagObj::pelar(&commit, ".gir/objects/").unwrap(), commit);
+    }
+}
@@ -17,6 +17,7 @@ use crate::{
         objeto::Objeto,
         tipo_diff::TipoDiff,
     },
+    utils::index::CacheStat,
     utils::path_buf::{esta_directorio_habilitado, obtener_nombre},
     utils::{
         compresion::{comprimir_contenido_u8, descomprimir_objeto},
@@ -24,7 +25,7 @@ use crate::{
     },
 };
 
-use super::blob::Blob;
+use super::blob::{Blob, ModoBlob};
 
 #[derive(Clone, Debug)]
 
@@ -82,27 +83,45 @@ impl Tree {
     pub fn deep_changes(
         &self,
         arbol_a_comparar: &Tree,
+    ) -> Result<HashMap<String, Vec<(usize, TipoDiff)>>, String> {
+        self.deep_changes_de(arbol_a_comparar, ".gir/objects/")
+    }
+
+    /// Igual que `deep_changes`, pero el contenido de los archivos se lee de `dir_objetos`.
+    pub fn deep_changes_de(
+        &self,
+        arbol_a_comparar: &Tree,
+        dir_objetos: &str,
     ) -> Result<HashMap<String, Vec<(usize, TipoDiff)>>, String> {
         let mut deep_diffs: HashMap<String, Vec<(usize, TipoDiff)>> = HashMap::new();
 
         for objeto in &self.objetos {
             for objeto_a_comparar in arbol_a_comparar.obtener_objetos() {
                 if objeto.obtener_path() == objeto_a_comparar.obtener_path() {
-                    if objeto.obtener_hash() == objeto_a_comparar.obtener_hash() {
+                    if objeto.obtener_hash() == objeto_a_comparar.obtener_hash()
+                        || objeto.es_gitlink()
+                        || objeto_a_comparar.es_gitlink()
+                    {
                         break;
                     }
                     match objeto_a_comparar {
                         Objeto::Tree(ref tree_a_comparar) => {
                             if let Objeto::Tree(tree) = objeto {
-                                let diff_hijos = tree.deep_changes(tree_a_comparar)?;
+                                let diff_hijos =
+                                    tree.deep_changes_de(tree_a_comparar, dir_objetos)?;
                                 deep_diffs.extend(diff_hijos);
                             }
                         }
                         Objeto::Blob(blob_a_comparar) => {
                             if let Objeto::Blob(blob) = objeto {
-                                let contenido_1 = cat_file::obtener_contenido_objeto(&blob.hash)?.1;
-                                let contenido_2 =
-                                    cat_file::obtener_contenido_objeto(&blob_a_comparar.hash)?.1;
+                                let contenido_1 =
+                                    cat_file::obtener_contenido_objeto_de(&blob.hash, dir_objetos)?
+                                        .1;
+                                let contenido_2 = cat_file::obtener_contenido_objeto_de(
+                                    &blob_a_comparar.hash,
+                                    dir_objetos,
+                                )?
+                                .1;
                                 let diff = Merge::obtener_diff(
                                     contenido_1.lines().collect(),
                                     contenido_2.lines().collect(),
@@ -124,17 +143,31 @@ impl Tree {
         let objetos = self.obtener_objetos_hoja();
         for objeto in objetos {
             match objeto {
-                Objeto::Blob(blob) => {
-                    let objeto = descomprimir_objeto(&blob.hash, ".gir/objects/")?;
-                    let contenido = objeto.split('\0').collect::<Vec<&str>>()[1];
-                    io::escribir_bytes(blob.ubicacion, contenido).unwrap();
-                }
+                Objeto::Blob(blob) => Self::escribir_blob_en_directorio(&blob)?,
                 Objeto::Tree(_) => Err("Llego a un tree pero no deberia")?,
             };
         }
         Ok(())
     }
 
+    /// Escribe el blob en su ubicacion respetando su modo: los ejecutables quedan con permiso
+    /// de ejecucion, los symlinks se crean como links y los gitlinks quedan como un directorio vacio.
+    fn escribir_blob_en_directorio(blob: &Blob) -> Result<(), String> {
+        if blob.modo == ModoBlob::Gitlink {
+            return io::crear_directorio(&blob.ubicacion);
+        }
+        let objeto = descomprimir_objeto(&blob.hash, ".gir/objects/")?;
+        let contenido = objeto.split('\0').collect::<Vec<&str>>()[1];
+        if blob.modo == ModoBlob::Symlink {
+            return io::escribir_symlink(&blob.ubicacion, contenido);
+        }
+        if blob.ubicacion.is_symlink() {
+            io::rm_directorio(&blob.ubicacion)?;
+        }
+        io::escribir_bytes(&blob.ubicacion, contenido)?;
+        io::cambiar_permiso_ejecucion(&blob.ubicacion, blob.modo == ModoBlob::Ejecutable)
+    }
+
     /// Pasa un string de hexadecimal a un vector de u8.
     pub fn decode_hex(s: &str) -> Result<Vec<u8>, String> {
         match (0..s.len())
@@ -180,7 +213,8 @@ impl Tree {
             let mut line = match objeto {
                 Objeto::Blob(ref blob) => {
                     let hash = Self::decode_hex(&blob.hash)?;
-                    [b"100644 ", blob.nombre.as_bytes(), b"\0", &hash].concat()
+                    let modo = format!("{} ", blob.modo);
+                    [modo.as_bytes(), blob.nombre.as_bytes(), b"\0", &hash].concat()
                 }
                 Objeto::Tree(tree) => {
                     let nombre = if tree.directorio == PathBuf::from(".") {
@@ -198,9 +232,12 @@ impl Tree {
     }
 
     /// Devuelve un objeto Tree a partir de un directorio y un vector de directorios que se quieren.
+    /// Si se pasa un cache, no se vuelven a hashear los archivos cuyo hash esta en el cache y
+    /// cuya metadata no cambio.
     pub fn from_directorio(
         directorio: PathBuf,
         hijos_especificados: Option<&Vec<PathBuf>>,
+        mut cache: Option<&mut CacheStat>,
         logger: Arc<Logger>,
     ) -> Result<Tree, String> {
         let mut objetos: Vec<Objeto> = Vec::new();
@@ -225,8 +262,13 @@ impl Tree {
                 }
             }
 
-            let objeto = match fs::metadata(&path) {
-                Ok(_) => Objeto::from_directorio(path, hijos_especificados, logger.clone())?,
+            let objeto = match fs::symlink_metadata(&path) {
+                Ok(_) => Objeto::from_directorio(
+                    path,
+                    hijos_especificados,
+                    cache.as_deref_mut(),
+                    logger.clone(),
+                )?,
                 Err(_) => Err("Error al leer el archivo".to_string())?,
             };
             objetos.push(objeto);
@@ -256,7 +298,7 @@ impl Tree {
 
     /// Dado el contenido del arbol, devuelve un vector con los datos de cada uno de sus hijos.
     /// Cada dato es una tupla con el modo, el nombre y el hash del hijo.
-    fn obtener_datos_de_contenido(
+    pub fn obtener_datos_de_contenido(
         contenido: &str,
     ) -> Result<Vec<(String, String, String)>, String> {
         let mut contenido_parseado: Vec<(String, String, String)> = Vec::new();
@@ -299,7 +341,18 @@ impl Tree {
     /// Lee el objeto tree de la base de datos en base a un hash pasado por parametro junto con
     /// el directorio en el que se encuentra el tree y lo devuelve como un objeto Tree
     pub fn from_hash(hash: &str, directorio: PathBuf, logger: Arc<Logger>) -> Result<Tree, String> {
-        let contenido = descomprimir_objeto(hash, ".gir/objects/")?;
+        Self::from_hash_de(hash, directorio, ".gir/objects/", logger)
+    }
+
+    /// Igual que `from_hash`, pero lee el tree y sus subarboles de `dir_objetos` en lugar del
+    /// repositorio del directorio actual.
+    pub fn from_hash_de(
+        hash: &str,
+        directorio: PathBuf,
+        dir_objetos: &str,
+        logger: Arc<Logger>,
+    ) -> Result<Tree, String> {
+        let contenido = descomprimir_objeto(hash, dir_objetos)?;
         let contenido_parseado = Self::obtener_datos_de_contenido(&contenido)?;
         let mut objetos: Vec<Objeto> = Vec::new();
 
@@ -309,22 +362,23 @@ impl Tree {
                 ubicacion = nombre.clone()
             }
 
-            match modo.as_str() {
-                "100644" => {
-                    let blob = Objeto::Blob(Blob {
-                        nombre,
-                        ubicacion: PathBuf::from(ubicacion),
-                        hash: hash_hijo.to_string(),
-                        logger: logger.clone(),
-                    });
-                    objetos.push(blob);
-                }
-                "40000" => {
-                    let tree =
-                        Self::from_hash(&hash_hijo, PathBuf::from(ubicacion), logger.clone())?;
-                    objetos.push(Objeto::Tree(tree));
-                }
-                _ => {}
+            if modo == "40000" {
+                let tree = Self::from_hash_de(
+                    &hash_hijo,
+                    PathBuf::from(ubicacion),
+                    dir_objetos,
+                    logger.clone(),
+                )?;
+                objetos.push(Objeto::Tree(tree));
+            } else if let Some(modo) = ModoBlob::desde_str(&modo) {
+                let blob = Objeto::Blob(Blob {
+                    nombre,
+                    ubicacion: PathBuf::from(ubicacion),
+                    hash: hash_hijo.to_string(),
+                    modo,
+                    logger: logger.clone(),
+                });
+                objetos.push(blob);
             }
         }
 
@@ -397,6 +451,20 @@ impl Tree {
         false
     }
 
+    /// Reemplaza los objetos del arbol que estan en la ubicacion de alguno de los gitlinks por el
+    /// gitlink. Los gitlinks no se pueden armar a partir del directorio de trabajo, por lo que se
+    /// conservan los que ya estaban registrados.
+    pub fn conservar_gitlinks(&mut self, gitlinks: &[Blob]) {
+        for objeto in self.objetos.iter_mut() {
+            let path = objeto.obtener_path();
+            if let Some(gitlink) = gitlinks.iter().find(|gitlink| gitlink.ubicacion == path) {
+                *objeto = Objeto::Blob(gitlink.clone());
+            } else if let Objeto::Tree(tree) = objeto {
+                tree.conservar_gitlinks(gitlinks);
+            }
+        }
+    }
+
     /// Ordena los objetos del arbol alfabeticamente por directorio.
     pub fn ordenar_objetos_alfabeticamente(objetos: &[Objeto]) -> Vec<Objeto> {
         let mut objetos = objetos.to_owned();
@@ -428,6 +496,7 @@ impl Tree {
 
         for objeto in &self.objetos {
             match objeto {
+                Objeto::Blob(blob) if blob.modo == ModoBlob::Gitlink => {}
                 Objeto::Blob(blob) => {
                     HashObject {
                         logger: self.logger.clone(),
@@ -522,10 +591,13 @@ impl Display for Tree {
 mod tests {
     use serial_test::serial;
 
+    use crate::tipos_de_dato::comandos::checkout::Checkout;
     use crate::tipos_de_dato::logger::Logger;
+    use crate::tipos_de_dato::objetos::blob::{Blob, ModoBlob};
     use crate::tipos_de_dato::{objeto::Objeto, objetos::tree::Tree};
     use crate::utils::compresion::descomprimir_contenido_u8;
     use crate::utils::io;
+    use crate::utils::testing::{addear_archivos_y_comittear, limpiar_archivo_gir};
     use std::path::PathBuf;
     use std::sync::Arc;
 
@@ -537,7 +609,7 @@ mod tests {
 
         for objeto in objetos_ordenados {
             let line = match objeto {
-                Objeto::Blob(blob) => format!("100644 {}    {}\n", blob.nombre, blob.hash),
+                Objeto::Blob(blob) => format!("{} {}    {}\n", blob.modo, blob.nombre, blob.hash),
                 Objeto::Tree(tree) => {
                     let name = match tree.directorio.file_name() {
                         Some(name) => name,
@@ -557,7 +629,7 @@ mod tests {
     fn test01_test_obtener_hash() {
         let logger = Arc::new(Logger::new(PathBuf::from("tmp/tree_test01")).unwrap());
         let objeto =
-            Objeto::from_directorio(PathBuf::from("test_dir/objetos"), None, logger).unwrap();
+            Objeto::from_directorio(PathBuf::from("test_dir/objetos"), None, None, logger).unwrap();
 
         if let Objeto::Tree(ref tree) = objeto {
             tree.escribir_en_base().unwrap();
@@ -571,7 +643,7 @@ mod tests {
     fn test02_test_obtener_tamanio() {
         let logger = Arc::new(Logger::new(PathBuf::from("tmp/tree_test02")).unwrap());
         let objeto =
-            Objeto::from_directorio(PathBuf::from("test_dir/muchos_objetos"), None, logger)
+            Objeto::from_directorio(PathBuf::from("test_dir/muchos_objetos"), None, None, logger)
                 .unwrap();
         let tamanio = objeto.obtener_tamanio().unwrap();
         assert_eq!(tamanio, 83);
@@ -583,7 +655,7 @@ mod tests {
         let logger = Arc::new(Logger::new(PathBuf::from("tmp/tree_test03")).unwrap());
 
         let objeto =
-            Objeto::from_directorio(PathBuf::from("test_dir/objetos"), None, logger).unwrap();
+            Objeto::from_directorio(PathBuf::from("test_dir/objetos"), None, None, logger).unwrap();
 
         if let Objeto::Tree(tree) = objeto {
             let contenido = mostrar_contenido(&tree.objetos).unwrap();
@@ -601,7 +673,8 @@ mod tests {
     fn test04_test_mostrar_contenido_recursivo() {
         let logger = Arc::new(Logger::new(PathBuf::from("tmp/tree_test04")).unwrap());
 
-        let objeto = Objeto::from_directorio(PathBuf::from("test_dir/"), None, logger).unwrap();
+        let objeto =
+            Objeto::from_directorio(PathBuf::from("test_dir/"), None, None, logger).unwrap();
 
         if let Objeto::Tree(tree) = objeto {
             let contenido = mostrar_contenido(&tree.objetos).unwrap();
@@ -620,7 +693,7 @@ mod tests {
         let logger = Arc::new(Logger::new(PathBuf::from("tmp/tree_test05")).unwrap());
 
         let objeto =
-            Objeto::from_directorio(PathBuf::from("test_dir/objetos"), None, logger).unwrap();
+            Objeto::from_directorio(PathBuf::from("test_dir/objetos"), None, None, logger).unwrap();
         if let Objeto::Tree(tree) = objeto {
             tree.escribir_en_base().unwrap();
 
@@ -648,7 +721,8 @@ mod tests {
     fn test06_escribir_en_base_con_anidados() -> Result<(), String> {
         let logger = Arc::new(Logger::new(PathBuf::from("tmp/tree_test06")).unwrap());
 
-        let objeto = Objeto::from_directorio(PathBuf::from("test_dir"), None, logger).unwrap();
+        let objeto =
+            Objeto::from_directorio(PathBuf::from("test_dir"), None, None, logger).unwrap();
         if let Objeto::Tree(tree) = objeto {
             tree.escribir_en_base().unwrap();
 
@@ -678,7 +752,7 @@ mod tests {
     fn test07_contiene_hijo_por_ubicacion() {
         let logger = Arc::new(Logger::new(PathBuf::from("tmp/tree_test07")).unwrap());
 
-        let tree = Tree::from_directorio(PathBuf::from("src"), None, logger).unwrap();
+        let tree = Tree::from_directorio(PathBuf::from("src"), None, None, logger).unwrap();
 
         assert!(tree.contiene_hijo_por_ubicacion(PathBuf::from("src/utils/io.rs")))
     }
@@ -689,8 +763,74 @@ mod tests {
     fn test08_contiene_hijo_por_ubicacion_rec() {
         let logger = Arc::new(Logger::new(PathBuf::from("tmp/tree_test08")).unwrap());
 
-        let tree = Tree::from_directorio(PathBuf::from("src"), None, logger).unwrap();
+        let tree = Tree::from_directorio(PathBuf::from("src"), None, None, logger).unwrap();
 
         assert!(tree.contiene_directorio(&PathBuf::from("src/tipos_de_dato")))
     }
+
+    #[cfg(unix)]
+    #[test]
+    #[serial]
+    fn test09_ejecutables_y_symlinks_se_conservan_al_commitear_y_escribir() {
+        let logger = Arc::new(Logger::new(PathBuf::from("tmp/tree_test09")).unwrap());
+        limpiar_archivo_gir(logger.clone());
+        let _ = io::rm_directorio("tmp/tree_test09_dir");
+        io::escribir_bytes("tmp/tree_test09_dir/script.sh", "echo hola").unwrap();
+        io::cambiar_permiso_ejecucion("tmp/tree_test09_dir/script.sh", true).unwrap();
+        io::escribir_symlink("tmp/tree_test09_dir/link", "script.sh").unwrap();
+        addear_archivos_y_comittear(vec!["tmp/tree_test09_dir".to_string()], logger.clone());
+
+        let tree = Checkout::obtener_arbol_commit_actual(logger).unwrap();
+        let modos = tree
+            .obtener_objetos_hoja()
+            .iter()
+            .filter_map(|objeto| match objeto {
+                Objeto::Blob(blob) => Some((blob.nombre.clone(), blob.modo)),
+                Objeto::Tree(_) => None,
+            })
+            .collect::<Vec<(String, ModoBlob)>>();
+        assert!(modos.contains(&("script.sh".to_string(), ModoBlob::Ejecutable)));
+        assert!(modos.contains(&("link".to_string(), ModoBlob::Symlink)));
+
+        io::rm_directorio("tmp/tree_test09_dir").unwrap();
+        tree.escribir_en_directorio().unwrap();
+
+        let link = PathBuf::from("tmp/tree_test09_dir/link");
+        assert!(link.is_symlink());
+        assert_eq!(
+            std::fs::read_link(link).unwrap(),
+            PathBuf::from("script.sh")
+        );
+        assert!(io::es_ejecutable("tmp/tree_test09_dir/script.sh"));
+        io::rm_directorio("tmp/tree_test09_dir").unwrap();
+    }
+
+    #[test]
+    #[serial]
+    fn test10_los_gitlinks_se_leen_y_se_escriben_como_directorio_vacio() {
+        let logger = Arc::new(Logger::new(PathBuf::from("tmp/tree_test10")).unwrap());
+        limpiar_archivo_gir(logger.clone());
+        let _ = io::rm_directorio("tmp/tree_test10_sub");
+        let gitlink = Blob {
+            hash: "3ad74b1f5b6fd0ed5ebbcf61cdb2e3a4c1f6d9f1".to_string(),
+            modo: ModoBlob::Gitlink,
+            ubicacion: PathBuf::from("tmp/tree_test10_sub"),
+            nombre: "tree_test10_sub".to_string(),
+            logger: logger.clone(),
+        };
+        let tree = Tree {
+            directorio: PathBuf::from("."),
+            objetos: vec![Objeto::Blob(gitlink.clone())],
+            logger: logger.clone(),
+        };
+        tree.escribir_en_base().unwrap();
+
+        let leido =
+            Tree::from_hash(&tree.obtener_hash().unwrap(), PathBuf::from("tmp"), logger).unwrap();
+        assert_eq!(leido.objetos, vec![Objeto::Blob(gitlink)]);
+
+        leido.escribir_en_directorio().unwrap();
+        assert!(PathBuf::from("tmp/tree_test10_sub").is_dir());
+        io::rm_directorio("tmp/tree_test10_sub").unwrap();
+    }
 }
@@ -1,12 +1,16 @@
-use crate::tipos_de_dato::comandos::cat_file;
-use crate::tipos_de_dato::logger::Logger;
-use crate::utils::{self, io};
-use crate::utils::{compresion, objects};
-use flate2::{Decompress, FlushDecompress};
+use crate::err_packfile::ErrorPackfile;
+use crate::tipos_de_dato::indice_packfile::{EntradaIndice, IndicePackfile};
+use crate::tipos_de_dato::objetos::tree::Tree;
+use crate::utils::{compresion, gir_config, io, objects};
+use flate2::{Crc, Decompress, FlushDecompress, Status};
 use sha1::{Digest, Sha1};
-use std::path::PathBuf;
+use std::collections::{HashMap, HashSet};
+use std::fs;
+use std::io::{Read, Seek, SeekFrom};
+use std::path::{Path, PathBuf};
 use std::str;
-use std::sync::Arc;
+use std::sync::{Arc, Mutex, OnceLock};
+use std::time::SystemTime;
 
 const COMMIT: u8 = 1;
 const TREE: u8 = 2;
@@ -15,104 +19,496 @@ const TAG: u8 = 4;
 const OFS_DELTA: u8 = 6;
 const REF_DELTA: u8 = 7;
 
+const VENTANA_DELTA_DEFAULT: usize = 10;
+const PROFUNDIDAD_DELTA_DEFAULT: usize = 50;
+// objetos mas chicos que esto no se deltifican, no vale la pena
+const TAMANIO_MINIMO_DELTA: usize = 50;
+const TAMANIO_BLOQUE_DELTA: usize = 16;
+// lo maximo que se reserva de antemano para un objeto segun el tamanio que declara su entrada,
+// mas alla de eso el buffer crece a medida que se descomprime
+const RESERVA_MAXIMA_OBJETO: usize = 1 << 20;
+
 pub struct Packfile;
 
+// Indices de los packfiles guardados, por directorio de packs, con la fecha de modificacion del
+// directorio cuando se leyeron
+type CachePacks = HashMap<PathBuf, (SystemTime, Arc<Vec<PackGuardado>>)>;
+static PACKS_GUARDADOS: OnceLock<Mutex<CachePacks>> = OnceLock::new();
+
+// Packfile guardado en `objects/pack/` junto a su indice ya decodificado
+struct PackGuardado {
+    ruta: PathBuf,
+    indice: IndicePackfile,
+    // offsets de todas las entradas ordenados, para saber donde termina cada una
+    offsets: Vec<u64>,
+    // donde terminan las entradas: antes del checksum final del packfile
+    fin_entradas: u64,
+}
+
+impl PackGuardado {
+    fn cargar(ruta_indice: &Path) -> Result<PackGuardado, String> {
+        let indice = IndicePackfile::decodificar(&io::leer_bytes(ruta_indice)?)?;
+        let ruta = ruta_indice.with_extension("pack");
+        let tamanio = fs::metadata(&ruta)
+            .map_err(|e| format!("No se pudo leer el packfile {}: {}", ruta.display(), e))?
+            .len();
+        let mut offsets = indice.obtener_offsets();
+        offsets.sort();
+        Ok(PackGuardado {
+            ruta,
+            indice,
+            offsets,
+            fin_entradas: tamanio.saturating_sub(20),
+        })
+    }
+
+    // Lee del archivo solo los bytes de la entrada que empieza en el offset
+    fn leer_entrada(&self, offset: u64) -> Result<Vec<u8>, String> {
+        let fin = match self.offsets.binary_search(&offset) {
+            Ok(posicion) => self
+                .offsets
+                .get(posicion + 1)
+                .copied()
+                .unwrap_or(self.fin_entradas),
+            Err(_) => return Err(format!("No hay un objeto en el offset {}", offset)),
+        };
+        let error = |e: std::io::Error| format!("Fallo al leer {}: {}", self.ruta.display(), e);
+        let mut archivo = fs::File::open(&self.ruta).map_err(error)?;
+        archivo.seek(SeekFrom::Start(offset)).map_err(error)?;
+        let mut entrada = vec![0; fin.saturating_sub(offset) as usize];
+        archivo.read_exact(&mut entrada).map_err(error)?;
+        Ok(entrada)
+    }
+
+    // Lee el objeto de la entrada del offset, resolviendo los deltas. Las bases de los OFS_DELTA
+    // se leen del mismo packfile y las de los REF_DELTA del directorio de objetos. La cadena de
+    // OFS_DELTA se recorre de forma iterativa, como al leer el packfile entero
+    fn leer_objeto(&self, offset: u64, dir_objetos: &str) -> Result<Option<(u8, Vec<u8>)>, String> {
+        // entradas de los deltas de la cadena, con donde empiezan sus datos y su tamanio
+        let mut deltas: Vec<(Vec<u8>, usize, u32)> = Vec::new();
+        let mut actual = offset;
+        let base = loop {
+            let entrada = self.leer_entrada(actual)?;
+            let mut posicion = 0;
+            let (tipo, tamanio) = Packfile::decodificar_bytes(&entrada, &mut posicion);
+            if tipo != OFS_DELTA {
+                break Packfile::leer_objeto_del_packfile(
+                    &entrada,
+                    &mut 0,
+                    &HashMap::new(),
+                    dir_objetos,
+                )?;
+            }
+
+            let distancia = Packfile::leer_vli_be(&entrada, &mut posicion, true) as u64;
+            if distancia == 0 || distancia > actual {
+                return Err(format!(
+                    "La base del objeto en el offset {} esta fuera del packfile",
+                    actual
+                ));
+            }
+            deltas.push((entrada, posicion, tamanio));
+            actual -= distancia;
+        };
+
+        let mut objeto = base;
+        for (entrada, mut posicion, tamanio) in deltas.into_iter().rev() {
+            if objeto.is_none() {
+                break;
+            }
+            objeto = Packfile::aplicar_delta_a_base(&entrada, &mut posicion, objeto, tamanio)?;
+        }
+        Ok(objeto)
+    }
+}
+
+// Objetos ya decodificados de un packfile, indexados por hash, con su tipo y su contenido sin header
+type ObjetosDelPackfile = HashMap<String, (u8, Vec<u8>)>;
+
+// Objeto a escribir en el packfile. Si tiene base, se escribe como delta contra ella. Las entradas
+// externas son objetos que el receptor ya tiene: solo se usan como bases y no se escriben
+struct EntradaPackfile {
+    hash: String,
+    externa: bool,
+    tipo: u8,
+    contenido: Vec<u8>,
+    base: Option<usize>,
+    delta: Vec<u8>,
+    profundidad: usize,
+}
+
 impl Packfile {
-    // Devuelve el largo del objeto descomprimido junto a su tipo
-    fn obtener_largo_y_tipo_de_directorio(
-        objeto: String,
-        dir: &str,
-    ) -> Result<(u32, String), String> {
-        let log = Arc::new(Logger::new(PathBuf::from("log.txt"))?);
-        let tamanio_objeto_str =
-            cat_file::CatFile::from(&mut vec!["-s".to_string(), objeto.clone()], log.clone())?
-                .ejecutar_de(dir)?;
+    /// Dado un directorio, arma el packfile en base a los objetos del mismo y lo devuelve
+    pub fn obtener_pack_entero(dir: &str) -> Result<Vec<u8>, String> {
+        println!("Despachando packfile");
+        let objetos = objects::obtener_objetos_del_dir(&PathBuf::from(dir))?;
+        Self::obtener_pack_con_archivos(objetos, dir)
+    }
 
-        let tamanio_objeto = tamanio_objeto_str.trim().parse::<u32>().unwrap_or(0);
+    /// Dado un directorio y un vector de objetos, arma el packfile en base a los objetos del mismo y lo devuelve.
+    /// La ventana y la profundidad de la compresion delta se leen del archivo de configuracion
+    /// (`ventana_delta` y `profundidad_delta`), y si no estan se usan los valores por defecto.
+    pub fn obtener_pack_con_archivos(objetos: Vec<String>, dir: &str) -> Result<Vec<u8>, String> {
+        let (ventana, profundidad) = Self::obtener_parametros_delta();
+        Self::obtener_pack_con_archivos_y_parametros(objetos, dir, ventana, profundidad)
+    }
 
-        let tipo_objeto = cat_file::obtener_tipo_objeto_de(&objeto, dir)?;
+    /// Arma un packfile thin con los objetos pedidos: los objetos de `bases`, que el receptor ya
+    /// tiene, no se mandan pero se prueban como bases de los deltas. Los deltas contra esas bases
+    /// se escriben como REF_DELTA, con el hash de la base, para que el receptor la busque entre
+    /// sus objetos.
+    pub fn obtener_pack_thin(
+        objetos: Vec<String>,
+        bases: Vec<String>,
+        dir: &str,
+    ) -> Result<Vec<u8>, String> {
+        let (ventana, profundidad) = Self::obtener_parametros_delta();
+        Self::armar_packfile_con_bases(objetos, bases, dir, ventana, profundidad)
+    }
 
-        Ok((tamanio_objeto, tipo_objeto))
+    // La ventana y la profundidad de la compresion delta del archivo de configuracion, o los valores
+    // por defecto
+    fn obtener_parametros_delta() -> (usize, usize) {
+        let ventana = gir_config::conseguir_ventana_delta()
+            .and_then(|ventana| ventana.parse::<usize>().ok())
+            .unwrap_or(VENTANA_DELTA_DEFAULT);
+        let profundidad = gir_config::conseguir_profundidad_delta()
+            .and_then(|profundidad| profundidad.parse::<usize>().ok())
+            .unwrap_or(PROFUNDIDAD_DELTA_DEFAULT);
+        (ventana, profundidad)
     }
 
-    // Funcion que dado el hash de un objeto, lo aniade al packfile
-    fn aniadir_objeto(
-        objetos_packfile: &mut Vec<u8>,
-        objeto: String,
+    /// Arma el packfile con los objetos pedidos, comprimiendo como OFS_DELTA aquellos objetos
+    /// para los que se encuentre una base parecida.
+    ///
+    /// # Argumentos
+    /// - ventana: cantidad de objetos anteriores (del mismo tipo) contra los que se prueba armar un delta,
+    ///   con ventana 0 todos los objetos se mandan enteros.
+    /// - profundidad: largo maximo de una cadena de deltas.
+    pub fn obtener_pack_con_archivos_y_parametros(
+        objetos: Vec<String>,
         dir: &str,
-    ) -> Result<(), String> {
-        let (tamanio_objeto, tipo_objeto) =
-            Self::obtener_largo_y_tipo_de_directorio(objeto.clone(), dir)?;
-
-        // codifica el tamanio del archivo descomprimido y su tipo en un tipo variable de longitud
-        let nbyte = match tipo_objeto.as_str() {
-            "commit" => Self::codificar_bytes(COMMIT, tamanio_objeto), //1
-            "tree" => Self::codificar_bytes(TREE, tamanio_objeto),     // 2
-            "blob" => Self::codificar_bytes(BLOB, tamanio_objeto),     // 3
-            "tag" => Self::codificar_bytes(TAG, tamanio_objeto),       // 4
-            "ofs_delta" => Self::codificar_bytes(OFS_DELTA, tamanio_objeto), // 6
-            "ref_delta" => Self::codificar_bytes(REF_DELTA, tamanio_objeto), // 7
+        ventana: usize,
+        profundidad: usize,
+    ) -> Result<Vec<u8>, String> {
+        Self::armar_packfile_con_bases(objetos, Vec::new(), dir, ventana, profundidad)
+    }
+
+    fn armar_packfile_con_bases(
+        objetos: Vec<String>,
+        bases: Vec<String>,
+        dir: &str,
+        ventana: usize,
+        profundidad: usize,
+    ) -> Result<Vec<u8>, String> {
+        let cant_objetos = objetos.len() as u32;
+
+        let mut entradas = Vec::new();
+        for objeto in objetos {
+            entradas.push(Self::obtener_entrada_de_directorio(&objeto, dir)?);
+        }
+        for base in bases {
+            let mut entrada = Self::obtener_entrada_de_directorio(&base, dir)?;
+            entrada.externa = true;
+            entradas.push(entrada);
+        }
+        // Ordenando por tipo y tamanio los objetos parecidos quedan cerca dentro de la ventana
+        entradas.sort_by_key(|entrada| (entrada.tipo, entrada.contenido.len()));
+
+        Self::elegir_bases_delta(&mut entradas, ventana, profundidad);
+
+        let objetos_packfile = Self::escribir_entradas(&entradas)?;
+        Ok(Self::armar_packfile(objetos_packfile, cant_objetos))
+    }
+
+    // Lee un objeto del directorio y devuelve su entrada para el packfile con el contenido sin header
+    fn obtener_entrada_de_directorio(objeto: &str, dir: &str) -> Result<EntradaPackfile, String> {
+        let (tipo, contenido) = Self::leer_objeto_de_directorio(objeto, dir)?;
+
+        Ok(EntradaPackfile {
+            hash: objeto.to_string(),
+            externa: false,
+            tipo,
+            contenido,
+            base: None,
+            delta: Vec::new(),
+            profundidad: 0,
+        })
+    }
+
+    // Lee un objeto del directorio, suelto o dentro de un packfile, y devuelve su tipo junto a su contenido sin header
+    fn leer_objeto_de_directorio(objeto: &str, dir: &str) -> Result<(u8, Vec<u8>), String> {
+        let contenido_descomprimido = compresion::obtener_objeto_descomprimido(objeto, dir)?;
+
+        let fin_header = contenido_descomprimido
+            .iter()
+            .position(|&byte| byte == 0)
+            .ok_or(format!("El objeto {} no tiene header", objeto))?;
+        let header =
+            str::from_utf8(&contenido_descomprimido[..fin_header]).map_err(|e| e.to_string())?;
+
+        let tipo = match header.split(' ').next().unwrap_or("") {
+            "commit" => COMMIT,
+            "tree" => TREE,
+            "blob" => BLOB,
+            "tag" => TAG,
             _ => {
                 return Err("Tipo de objeto invalido".to_string());
             }
         };
-        // El objeto manda comprimido pero sin header
-        let obj = utils::compresion::obtener_contenido_comprimido_sin_header_de(&objeto, dir)?;
-        objetos_packfile.extend(nbyte);
-        objetos_packfile.extend(obj);
 
-        Ok(())
+        Ok((tipo, contenido_descomprimido[fin_header + 1..].to_vec()))
+    }
+
+    // Para cada entrada busca, entre las anteriores de la ventana, la base que genere el delta mas chico.
+    // Solo se usa el delta si ocupa menos de la mitad que el objeto entero.
+    fn elegir_bases_delta(entradas: &mut [EntradaPackfile], ventana: usize, profundidad: usize) {
+        for actual in 0..entradas.len() {
+            if entradas[actual].externa || entradas[actual].contenido.len() < TAMANIO_MINIMO_DELTA {
+                continue;
+            }
+            let mut mejor_base: Option<(usize, Vec<u8>)> = None;
+
+            for candidata in actual.saturating_sub(ventana)..actual {
+                if entradas[candidata].tipo != entradas[actual].tipo
+                    || entradas[candidata].profundidad >= profundidad
+                {
+                    continue;
+                }
+                let delta =
+                    Self::crear_delta(&entradas[candidata].contenido, &entradas[actual].contenido);
+
+                let limite = match &mejor_base {
+                    Some((_, mejor_delta)) => mejor_delta.len(),
+                    None => entradas[actual].contenido.len() / 2,
+                };
+                if delta.len() < limite {
+                    mejor_base = Some((candidata, delta));
+                }
+            }
+
+            if let Some((base, delta)) = mejor_base {
+                entradas[actual].profundidad = entradas[base].profundidad + 1;
+                entradas[actual].base = Some(base);
+                entradas[actual].delta = delta;
+            }
+        }
     }
 
-    // Funcion que dado un directorio extrae todos sus objetos y los aniade al packfile
-    fn obtener_objetos_del_dir(dir: &str) -> Result<(Vec<u8>, u32), String> {
-        // esto porque es un clone, deberia pasarle los objetos que quiero
+    // Escribe las entradas una detras de otra, salvo las externas. Las que tienen base se escriben
+    // como OFS_DELTA, apuntando al offset de su base, que siempre esta antes en el packfile, o como
+    // REF_DELTA si la base es externa
+    fn escribir_entradas(entradas: &[EntradaPackfile]) -> Result<Vec<u8>, String> {
         let mut objetos_packfile: Vec<u8> = Vec::new();
-        let objetos = objects::obtener_objetos_del_dir(&PathBuf::from(dir))?;
-        let cant_objetos = objetos.len() as u32;
-        // ---
-        for objeto in objetos {
-            Self::aniadir_objeto(&mut objetos_packfile, objeto.clone(), dir)?;
+        let mut offsets: Vec<usize> = Vec::new();
+
+        for entrada in entradas {
+            // el offset se cuenta desde el inicio del packfile, incluyendo el header de 12 bytes
+            let offset_actual = 12 + objetos_packfile.len();
+            offsets.push(offset_actual);
+            if entrada.externa {
+                continue;
+            }
+
+            match entrada.base {
+                Some(base) if entradas[base].externa => {
+                    objetos_packfile
+                        .extend(Self::codificar_bytes(REF_DELTA, entrada.delta.len() as u32));
+                    objetos_packfile.extend(Tree::decode_hex(&entradas[base].hash)?);
+                    objetos_packfile.extend(compresion::comprimir_contenido_u8(&entrada.delta)?);
+                }
+                Some(base) => {
+                    objetos_packfile
+                        .extend(Self::codificar_bytes(OFS_DELTA, entrada.delta.len() as u32));
+                    objetos_packfile
+                        .extend(Self::codificar_offset_delta(offset_actual - offsets[base]));
+                    objetos_packfile.extend(compresion::comprimir_contenido_u8(&entrada.delta)?);
+                }
+                None => {
+                    objetos_packfile.extend(Self::codificar_bytes(
+                        entrada.tipo,
+                        entrada.contenido.len() as u32,
+                    ));
+                    objetos_packfile
+                        .extend(compresion::comprimir_contenido_u8(&entrada.contenido)?);
+                }
+            }
         }
-        Ok((objetos_packfile, cant_objetos))
+        Ok(objetos_packfile)
     }
 
-    /// Dado un directorio, arma el packfile en base a los objetos del mismo y lo devuelve
-    pub fn obtener_pack_entero(dir: &str) -> Result<Vec<u8>, String> {
-        println!("Despachando packfile");
-        let (objetos_packfile, cant_objetos) = Self::obtener_objetos_del_dir(dir)?;
+    // Arma las instrucciones delta (copiar de la base / insertar) que reconstruyen el objetivo a partir de la base.
+    // Se indexan los bloques de la base y se busca cada posicion del objetivo en ese indice
+    fn crear_delta(base: &[u8], objetivo: &[u8]) -> Vec<u8> {
+        let mut delta = Self::codificar_varint_le(base.len());
+        delta.extend(Self::codificar_varint_le(objetivo.len()));
+
+        let mut indice: HashMap<&[u8], usize> = HashMap::new();
+        let mut inicio = 0;
+        while inicio + TAMANIO_BLOQUE_DELTA <= base.len() {
+            indice
+                .entry(&base[inicio..inicio + TAMANIO_BLOQUE_DELTA])
+                .or_insert(inicio);
+            inicio += TAMANIO_BLOQUE_DELTA;
+        }
 
-        Ok(Self::armar_packfile(objetos_packfile, cant_objetos))
+        let mut pendientes: Vec<u8> = Vec::new();
+        let mut posicion = 0;
+        while posicion < objetivo.len() {
+            let coincidencia = match objetivo.get(posicion..posicion + TAMANIO_BLOQUE_DELTA) {
+                Some(bloque) => indice.get(bloque).copied(),
+                None => None,
+            };
+
+            let mut inicio_base = match coincidencia {
+                Some(inicio_base) => inicio_base,
+                None => {
+                    pendientes.push(objetivo[posicion]);
+                    posicion += 1;
+                    continue;
+                }
+            };
+
+            let mut largo = TAMANIO_BLOQUE_DELTA;
+            while inicio_base + largo < base.len()
+                && posicion + largo < objetivo.len()
+                && base[inicio_base + largo] == objetivo[posicion + largo]
+            {
+                largo += 1;
+            }
+            posicion += largo;
+            // extiendo la coincidencia hacia atras con lo que estaba pendiente de insertar
+            while inicio_base > 0 && pendientes.last() == Some(&base[inicio_base - 1]) {
+                pendientes.pop();
+                inicio_base -= 1;
+                largo += 1;
+            }
+
+            Self::agregar_inserciones(&mut delta, &mut pendientes);
+            Self::agregar_copias(&mut delta, inicio_base, largo);
+        }
+        Self::agregar_inserciones(&mut delta, &mut pendientes);
+        delta
     }
 
-    /// Dado un directorio y un vector de objetos, arma el packfile en base a los objetos del mismo y lo devuelve
-    pub fn obtener_pack_con_archivos(objetos: Vec<String>, dir: &str) -> Result<Vec<u8>, String> {
-        let mut objetos_packfile: Vec<u8> = Vec::new();
+    // Agrega las instrucciones de insercion de los bytes pendientes, de a 127 bytes como maximo
+    fn agregar_inserciones(delta: &mut Vec<u8>, pendientes: &mut Vec<u8>) {
+        for tramo in pendientes.chunks(0x7f) {
+            delta.push(tramo.len() as u8);
+            delta.extend(tramo);
+        }
+        pendientes.clear();
+    }
 
-        let cant_objetos = objetos.len() as u32;
-        for objeto in objetos {
-            Self::aniadir_objeto(&mut objetos_packfile, objeto, dir)?;
+    // Agrega las instrucciones de copia desde la base, de a 0x10000 bytes como maximo.
+    // Solo se escriben los bytes no nulos del offset y del tamanio, marcandolos en el primer byte
+    fn agregar_copias(delta: &mut Vec<u8>, mut inicio: usize, mut largo: usize) {
+        while largo > 0 {
+            let tramo = largo.min(0x10000);
+            let mut instruccion: u8 = 0x80;
+            let mut argumentos: Vec<u8> = Vec::new();
+
+            for i in 0..4 {
+                let byte = ((inicio >> (8 * i)) & 0xff) as u8;
+                if byte != 0 {
+                    instruccion |= 1 << i;
+                    argumentos.push(byte);
+                }
+            }
+            // un tamanio de 0x10000 se codifica sin bytes de tamanio
+            if tramo != 0x10000 {
+                for i in 0..2 {
+                    let byte = ((tramo >> (8 * i)) & 0xff) as u8;
+                    if byte != 0 {
+                        instruccion |= 1 << (4 + i);
+                        argumentos.push(byte);
+                    }
+                }
+            }
+            delta.push(instruccion);
+            delta.extend(argumentos);
+
+            inicio += tramo;
+            largo -= tramo;
         }
-        Ok(Self::armar_packfile(objetos_packfile, cant_objetos))
     }
 
-    // Dado un vector de bytes y el offset absoluto de un objeto junto a su tamanio descomprimido, devuelve el objeto descomprimido
+    // Codifica un numero como varint en formato little endian, como los tamanios del header de un delta
+    fn codificar_varint_le(valor: usize) -> Vec<u8> {
+        let mut resultado = Vec::new();
+        let mut valor = valor;
+        loop {
+            let byte = (valor & 0x7f) as u8;
+            valor >>= 7;
+            if valor == 0 {
+                resultado.push(byte);
+                break;
+            }
+            resultado.push(byte | 0x80);
+        }
+        resultado
+    }
+
+    // Codifica el offset negativo de un OFS_DELTA, es la inversa de leer_vli_be con offset en true
+    fn codificar_offset_delta(offset: usize) -> Vec<u8> {
+        let mut valor = offset;
+        let mut resultado = vec![(valor & 0x7f) as u8];
+        valor >>= 7;
+        while valor != 0 {
+            valor -= 1;
+            resultado.push(0x80 | (valor & 0x7f) as u8);
+            valor >>= 7;
+        }
+        resultado.reverse();
+        resultado
+    }
+
+    // Dado un vector de bytes y el offset absoluto de un objeto junto a su tamanio descomprimido, devuelve el objeto descomprimido.
+    // Falla si el objeto no se descomprime exactamente al tamanio declarado. Como el tamanio viene del
+    // packfile, no se reserva todo de antemano y se corta apenas el objeto lo supera
     fn descomprimir_objeto(
         bytes: &[u8],
         offset: &mut usize,
         tamanio_objeto_descomprimido: u32,
-    ) -> Result<Vec<u8>, String> {
-        let mut objeto_descomprimido = vec![0; tamanio_objeto_descomprimido as usize];
+    ) -> Result<Vec<u8>, ErrorPackfile> {
+        let entrada = bytes.get(*offset..).ok_or(ErrorPackfile::Truncado)?;
+        let tamanio_declarado = tamanio_objeto_descomprimido as usize;
+        // un byte de mas para darse cuenta si el objeto es mas grande que lo declarado
+        let mut objeto_descomprimido =
+            Vec::with_capacity(tamanio_declarado.min(RESERVA_MAXIMA_OBJETO) + 1);
 
         let mut descompresor = Decompress::new(true);
+        loop {
+            if objeto_descomprimido.len() == objeto_descomprimido.capacity() {
+                objeto_descomprimido.reserve(objeto_descomprimido.capacity().max(1024));
+            }
+            let (leidos, escritos) = (descompresor.total_in(), descompresor.total_out());
+            let estado = descompresor
+                .decompress_vec(
+                    &entrada[leidos as usize..],
+                    &mut objeto_descomprimido,
+                    FlushDecompress::None,
+                )
+                .map_err(|e| ErrorPackfile::ObjetoCorrupto {
+                    offset: *offset,
+                    motivo: e.to_string(),
+                })?;
+
+            if estado == Status::StreamEnd || objeto_descomprimido.len() > tamanio_declarado {
+                break;
+            }
+            if descompresor.total_in() == leidos && descompresor.total_out() == escritos {
+                return Err(ErrorPackfile::Truncado);
+            }
+        }
 
-        descompresor
-            .decompress(
-                &bytes[*offset..],
-                &mut objeto_descomprimido,
-                FlushDecompress::None,
-            )
-            .map_err(|e| e.to_string())?;
+        if objeto_descomprimido.len() != tamanio_declarado {
+            return Err(ErrorPackfile::TamanioIncorrecto {
+                offset: *offset,
+                declarado: tamanio_declarado,
+                obtenido: objeto_descomprimido.len(),
+            });
+        }
 
         *offset += descompresor.total_in() as usize;
         Ok(objeto_descomprimido)
@@ -172,10 +568,11 @@ impl Packfile {
         let mut continua = false;
 
         // decodifico el primer byte que es distinto
-        let tipo = &bytes[*offset] >> 4 & 0x07; // deduzco el tipo
-        numero_decodificado = (bytes[*offset] & 0x0f) as u32; // obtengo los primeros 4 bits
+        let primer_byte = bytes.get(*offset).copied().unwrap_or(0);
+        let tipo = primer_byte >> 4 & 0x07; // deduzco el tipo
+        numero_decodificado = (primer_byte & 0x0f) as u32; // obtengo los primeros 4 bits
 
-        if bytes[*offset] & 0x80 != 0 {
+        if primer_byte & 0x80 != 0 {
             continua = true;
         }
         *offset += 1;
@@ -184,10 +581,12 @@ impl Packfile {
             if !continua {
                 break;
             }
-            if bytes[*offset] & 0x80 == 0 {
+            // si el packfile esta truncado se corta la lectura
+            let byte = bytes.get(*offset).copied().unwrap_or(0);
+            if byte & 0x80 == 0 || corrimiento >= 32 {
                 continua = false;
             }
-            numero_decodificado |= ((&bytes[*offset] & 0x7f) as u32) << corrimiento;
+            numero_decodificado |= ((byte & 0x7f) as u32).checked_shl(corrimiento).unwrap_or(0);
             corrimiento += 7;
             *offset += 1;
         }
@@ -200,20 +599,30 @@ impl Packfile {
         tamanio: u32,
         contenido_descomprimido: &mut Vec<u8>,
     ) -> Result<Vec<u8>, String> {
-        let mut header: Vec<u8> = match tipo {
-            1 => format!("{} {}\0", "commit", tamanio).as_bytes().to_vec(),
-            2 => format!("{} {}\0", "tree", tamanio).as_bytes().to_vec(),
-            3 => format!("{} {}\0", "blob", tamanio).as_bytes().to_vec(),
-            _ => {
-                return Err("Tipo de objeto invalido".to_string());
-            }
-        };
+        if !(COMMIT..=TAG).contains(&tipo) {
+            return Err("Tipo de objeto invalido".to_string());
+        }
+        let mut header = Self::obtener_header(tipo, tamanio as usize);
         header.append(contenido_descomprimido);
         Ok(header)
     }
 
+    // Devuelve el header `{tipo} {tamanio}\0` de un objeto de tipo commit, tree, blob o tag
+    fn obtener_header(tipo: u8, tamanio: usize) -> Vec<u8> {
+        let nombre_tipo = match tipo {
+            COMMIT => "commit",
+            TREE => "tree",
+            BLOB => "blob",
+            _ => "tag",
+        };
+        format!("{} {}\0", nombre_tipo, tamanio).into_bytes()
+    }
+
     // Verifica el checksum de un packfile
     pub fn verificar_checksum(packfile: &[u8]) -> bool {
+        if packfile.len() < 20 {
+            return false;
+        }
         let expected_hash = &packfile[packfile.len() - 20..];
 
         let mut hasher = Sha1::new();
@@ -224,7 +633,10 @@ impl Packfile {
     }
 
     // Lee el header del packfile y devuelve la firma, la version y el largo
-    fn leer_header_packfile(packfile: &[u8]) -> Result<(&[u8], &[u8], u32), String> {
+    fn leer_header_packfile(packfile: &[u8]) -> Result<(&[u8], &[u8], u32), ErrorPackfile> {
+        if packfile.len() < 12 {
+            return Err(ErrorPackfile::Truncado);
+        }
         let firma = &packfile[0..4];
         let version = &packfile[4..8];
         let largo = &packfile[8..12];
@@ -232,33 +644,320 @@ impl Packfile {
         Ok((firma, version, largo))
     }
 
-    /// Dado un packfile (en forma de Vec<u8>) y una ubicacion, decodifica los objetos dentro del mismo, y los escribe en la ubicacion dada
+    /// Valida el packfile al estilo de `git verify-pack`, sin escribir nada:
+    /// - que tenga la firma `PACK` y version 2 o 3
+    /// - que el checksum del final coincida con el sha1 del resto del packfile
+    /// - que cada objeto se descomprima al tamanio declarado en su header
+    /// - que se puedan resolver todos los deltas, buscando las bases de los REF_DELTA en el mismo
+    ///   packfile o en `dir_objetos`
+    /// - que no sobren bytes entre el ultimo objeto y el checksum
+    ///
+    /// Devuelve las entradas del indice del packfile.
+    pub fn verificar_packfile(
+        bytes: &[u8],
+        dir_objetos: &str,
+    ) -> Result<Vec<EntradaIndice>, ErrorPackfile> {
+        if bytes.len() < 32 {
+            return Err(ErrorPackfile::Truncado);
+        }
+        let (firma, version, _) = Self::leer_header_packfile(bytes)?;
+        if firma != "PACK".as_bytes() {
+            return Err(ErrorPackfile::FirmaInvalida);
+        }
+        let version = u32::from_be_bytes([version[0], version[1], version[2], version[3]]);
+        if version != 2 && version != 3 {
+            return Err(ErrorPackfile::VersionNoSoportada(version));
+        }
+        if !Self::verificar_checksum(bytes) {
+            return Err(ErrorPackfile::ChecksumInvalido {
+                esperado: Tree::encode_hex(&bytes[bytes.len() - 20..]),
+                calculado: format!("{:x}", Sha1::digest(&bytes[..bytes.len() - 20])),
+            });
+        }
+
+        let (_, entradas) = Self::leer_objetos_del_packfile(bytes, dir_objetos)?;
+        Ok(entradas)
+    }
+
+    /// Guarda un packfile ya verificado en `{ubicacion}pack/` como `pack-{checksum}.pack`,
+    /// junto a su indice `pack-{checksum}.idx` armado con las entradas dadas.
+    /// Si el packfile es thin, antes se le agregan las bases que tomo de `ubicacion`, como
+    /// `index-pack --fix-thin`, para que no dependa de objetos de afuera.
+    /// Un packfile sin objetos no se guarda.
+    pub fn guardar_packfile(
+        bytes: &[u8],
+        entradas: Vec<EntradaIndice>,
+        ubicacion: &str,
+    ) -> Result<(), String> {
+        Self::guardar_packfile_en(bytes, entradas, ubicacion, ubicacion)
+    }
+
+    /// Igual que `guardar_packfile`, pero las bases que le faltan a un packfile thin se toman de
+    /// `dir_objetos` y el packfile se guarda en `{ubicacion}pack/`.
+    pub fn guardar_packfile_en(
+        bytes: &[u8],
+        mut entradas: Vec<EntradaIndice>,
+        dir_objetos: &str,
+        ubicacion: &str,
+    ) -> Result<(), String> {
+        if entradas.is_empty() {
+            return Ok(());
+        }
+        let completo = Self::completar_pack_thin(bytes, &mut entradas, dir_objetos)?;
+        let bytes = completo.as_deref().unwrap_or(bytes);
+
+        let checksum_packfile = &bytes[bytes.len() - 20..];
+        let indice = IndicePackfile::new(entradas, checksum_packfile);
+        let nombre_pack = format!(
+            "{}pack/pack-{}",
+            ubicacion,
+            Tree::encode_hex(checksum_packfile)
+        );
+
+        io::escribir_bytes(nombre_pack.clone() + ".pack", bytes)?;
+        io::escribir_bytes(nombre_pack + ".idx", indice.codificar()?)?;
+        Ok(())
+    }
+
+    // Si hay REF_DELTA cuya base no esta en el packfile, devuelve el packfile con esas bases
+    // agregadas al final (leidas de `dir_objetos`) y agrega sus entradas al indice. Las entradas
+    // que ya estaban no se mueven, solo cambian la cantidad de objetos y el checksum
+    fn completar_pack_thin(
+        bytes: &[u8],
+        entradas: &mut Vec<EntradaIndice>,
+        dir_objetos: &str,
+    ) -> Result<Option<Vec<u8>>, String> {
+        let hashes: HashSet<&str> = entradas
+            .iter()
+            .map(|entrada| entrada.hash.as_str())
+            .collect();
+        let mut faltantes: Vec<String> = Vec::new();
+        for entrada in entradas.iter() {
+            if let Some(base) = Self::obtener_hash_base_ref_delta(bytes, entrada.offset as usize) {
+                if !hashes.contains(base.as_str()) && !faltantes.contains(&base) {
+                    faltantes.push(base);
+                }
+            }
+        }
+        if faltantes.is_empty() {
+            return Ok(None);
+        }
+
+        let mut packfile = bytes[..bytes.len() - 20].to_vec();
+        let cantidad = entradas.len() + faltantes.len();
+        packfile[8..12].copy_from_slice(&(cantidad as u32).to_be_bytes());
+        for base in faltantes {
+            let (tipo, contenido) = Self::leer_objeto_de_directorio(&base, dir_objetos)?;
+            let inicio = packfile.len();
+            packfile.extend(Self::codificar_bytes(tipo, contenido.len() as u32));
+            packfile.extend(compresion::comprimir_contenido_u8(&contenido)?);
+            entradas.push(Self::crear_entrada_indice(
+                &packfile,
+                base,
+                inicio,
+                packfile.len(),
+            ));
+        }
+        let checksum = Sha1::digest(&packfile);
+        packfile.extend(checksum);
+        Ok(Some(packfile))
+    }
+
+    /// Dado un packfile (en forma de Vec<u8>) y una ubicacion de objetos, lo verifica y lo guarda en `{ubicacion}pack/`.
+    /// Si el packfile no es valido no se guarda nada.
     pub fn leer_packfile_y_escribir(bytes: &[u8], ubicacion: String) -> Result<(), String> {
-        let checksum = Self::verificar_checksum(bytes);
-        match checksum {
-            true => println!("Checksum correcto"),
-            false => println!("Checksum incorrecto"),
+        let entradas = Self::verificar_packfile(bytes, &ubicacion)?;
+        Self::guardar_packfile(bytes, entradas, &ubicacion)
+    }
+
+    /// Busca el objeto en los packfiles guardados en `{dir_objetos}pack/` y lo devuelve descomprimido y con su header.
+    /// Devuelve None si ningun packfile lo contiene.
+    pub fn obtener_objeto_de_packs(
+        hash: &str,
+        dir_objetos: &str,
+    ) -> Result<Option<Vec<u8>>, String> {
+        for pack in Self::obtener_packs_guardados(dir_objetos)?.iter() {
+            let offset = match pack.indice.buscar_offset(hash) {
+                Some(offset) => offset,
+                None => continue,
+            };
+            let (tipo, mut objeto) = pack
+                .leer_objeto(offset, dir_objetos)?
+                .ok_or(format!("No se encontro la base del objeto {}", hash))?;
+
+            let objeto_con_header =
+                Self::obtener_objeto_con_header(tipo, objeto.len() as u32, &mut objeto)?;
+            return Ok(Some(objeto_con_header));
+        }
+        Ok(None)
+    }
+
+    /// Devuelve si alguno de los packfiles de `{dir_objetos}pack/` tiene el objeto, sin leerlo.
+    pub fn existe_objeto_en_packs(hash: &str, dir_objetos: &str) -> bool {
+        match Self::obtener_packs_guardados(dir_objetos) {
+            Ok(packs) => packs
+                .iter()
+                .any(|pack| pack.indice.buscar_offset(hash).is_some()),
+            Err(_) => false,
+        }
+    }
+
+    /// Devuelve los hashes de todos los objetos guardados en los packfiles de `{dir_objetos}pack/`.
+    pub fn obtener_hashes_de_packs(dir_objetos: &str) -> Result<Vec<String>, String> {
+        let mut hashes = Vec::new();
+        for pack in Self::obtener_packs_guardados(dir_objetos)?.iter() {
+            hashes.extend(pack.indice.obtener_hashes());
+        }
+        Ok(hashes)
+    }
+
+    // Devuelve los packfiles de `{dir_objetos}pack/` con sus indices ya decodificados. Los indices se
+    // guardan en memoria por directorio y solo se vuelven a leer si cambio el directorio de packs
+    // (se agrego o se borro un packfile)
+    fn obtener_packs_guardados(dir_objetos: &str) -> Result<Arc<Vec<PackGuardado>>, String> {
+        let dir_packs = PathBuf::from(dir_objetos).join("pack");
+        let modificacion = match fs::metadata(&dir_packs).and_then(|datos| datos.modified()) {
+            Ok(modificacion) => modificacion,
+            Err(_) => return Ok(Arc::new(Vec::new())),
+        };
+        // la clave es absoluta porque las rutas de objetos suelen ser relativas al directorio actual
+        let clave = fs::canonicalize(&dir_packs).map_err(|e| e.to_string())?;
+
+        let cache = PACKS_GUARDADOS.get_or_init(|| Mutex::new(HashMap::new()));
+        if let Some((modificacion_guardada, packs)) =
+            cache.lock().map_err(|e| e.to_string())?.get(&clave)
+        {
+            if *modificacion_guardada == modificacion {
+                return Ok(packs.clone());
+            }
         }
+
+        let mut packs = Vec::new();
+        for entrada in io::leer_directorio(&clave)? {
+            let ruta = entrada.map_err(|e| e.to_string())?.path();
+            if ruta.extension().and_then(|extension| extension.to_str()) != Some("idx") {
+                continue;
+            }
+            packs.push(PackGuardado::cargar(&ruta)?);
+        }
+        let packs = Arc::new(packs);
+        cache
+            .lock()
+            .map_err(|e| e.to_string())?
+            .insert(clave, (modificacion, packs.clone()));
+        Ok(packs)
+    }
+
+    // Decodifica todos los objetos del packfile, resolviendo los deltas, y los devuelve indexados por su hash
+    // junto a las entradas para el indice del packfile.
+    // Los REF_DELTA cuya base todavia no se leyo se reintentan al final, ya que la base puede estar mas adelante en el packfile
+    fn leer_objetos_del_packfile(
+        bytes: &[u8],
+        dir_objetos: &str,
+    ) -> Result<(ObjetosDelPackfile, Vec<EntradaIndice>), ErrorPackfile> {
         let (_firma, _version, largo) = Self::leer_header_packfile(bytes)?;
         let mut offset = 12;
 
+        let mut objetos = ObjetosDelPackfile::new();
+        let mut entradas = Vec::new();
+        let mut sin_base: Vec<(usize, usize)> = Vec::new();
         let mut contador: u32 = 0;
 
         while contador < largo {
-            let (tipo, mut objeto) = Self::leer_objeto_del_packfile(bytes, &mut offset)?;
+            let offset_objeto = offset;
+            match Self::leer_objeto_del_packfile(bytes, &mut offset, &objetos, dir_objetos)? {
+                Some((tipo, objeto)) => {
+                    let hash = Self::agregar_objeto_leido(&mut objetos, tipo, objeto);
+                    entradas.push(Self::crear_entrada_indice(
+                        bytes,
+                        hash,
+                        offset_objeto,
+                        offset,
+                    ));
+                }
+                None => sin_base.push((offset_objeto, offset)),
+            }
+            contador += 1;
+        }
 
-            let objeto = Self::obtener_objeto_con_header(tipo, objeto.len() as u32, &mut objeto)?;
+        let fin_objetos = bytes.len() - 20;
+        if offset > fin_objetos {
+            return Err(ErrorPackfile::Truncado);
+        }
+        if offset < fin_objetos {
+            return Err(ErrorPackfile::BytesSobrantes(fin_objetos - offset));
+        }
+
+        while !sin_base.is_empty() {
+            let mut todavia_sin_base = Vec::new();
+            for (offset_objeto, fin_objeto) in &sin_base {
+                match Self::leer_objeto_del_packfile(
+                    bytes,
+                    &mut { *offset_objeto },
+                    &objetos,
+                    dir_objetos,
+                )? {
+                    Some((tipo, objeto)) => {
+                        let hash = Self::agregar_objeto_leido(&mut objetos, tipo, objeto);
+                        entradas.push(Self::crear_entrada_indice(
+                            bytes,
+                            hash,
+                            *offset_objeto,
+                            *fin_objeto,
+                        ));
+                    }
+                    None => todavia_sin_base.push((*offset_objeto, *fin_objeto)),
+                }
+            }
+            if todavia_sin_base.len() == sin_base.len() {
+                let bases_faltantes = sin_base
+                    .iter()
+                    .filter_map(|(offset_objeto, _)| {
+                        Self::obtener_hash_base_ref_delta(bytes, *offset_objeto)
+                    })
+                    .collect();
+                return Err(ErrorPackfile::BaseFaltante(bases_faltantes));
+            }
+            sin_base = todavia_sin_base;
+        }
+        Ok((objetos, entradas))
+    }
 
-            let mut hasher = Sha1::new();
-            hasher.update(objeto.clone());
-            let _hash = hasher.finalize();
-            let hash = format!("{:x}", _hash);
+    // Arma la entrada del indice de un objeto, con el crc32 de los bytes de su entrada en el packfile
+    fn crear_entrada_indice(
+        bytes: &[u8],
+        hash: String,
+        inicio: usize,
+        fin: usize,
+    ) -> EntradaIndice {
+        let mut crc = Crc::new();
+        crc.update(&bytes[inicio..fin]);
+        EntradaIndice {
+            hash,
+            crc: crc.sum(),
+            offset: inicio as u64,
+        }
+    }
 
-            let ruta = format!("{}{}/{}", &ubicacion, &hash[..2], &hash[2..]);
-            io::escribir_bytes(ruta, compresion::comprimir_contenido_u8(&objeto)?)?;
-            contador += 1;
+    // Devuelve el hash de la base si la entrada que empieza en el offset es un REF_DELTA
+    fn obtener_hash_base_ref_delta(bytes: &[u8], mut offset: usize) -> Option<String> {
+        let (tipo, _) = Self::decodificar_bytes(bytes, &mut offset);
+        if tipo != REF_DELTA {
+            return None;
         }
-        Ok(())
+        bytes.get(offset..offset + 20).map(Tree::encode_hex)
+    }
+
+    // Calcula el hash del objeto leido, lo guarda junto a los demas objetos del packfile y lo devuelve.
+    // El tipo ya fue validado al leer el objeto
+    fn agregar_objeto_leido(objetos: &mut ObjetosDelPackfile, tipo: u8, objeto: Vec<u8>) -> String {
+        let mut hasher = Sha1::new();
+        hasher.update(Self::obtener_header(tipo, objeto.len()));
+        hasher.update(&objeto);
+        let hash = format!("{:x}", hasher.finalize());
+
+        objetos.insert(hash.clone(), (tipo, objeto));
+        hash
     }
 
     // Funcion para leer un varint de un vector de bytes en formato big endian, la forma en la que se procesa tiene que ver con su codificacion
@@ -266,7 +965,8 @@ impl Packfile {
     fn leer_vli_be(bytes: &[u8], actual_offset: &mut usize, offset: bool) -> usize {
         let mut val: usize = 0;
         loop {
-            let byt = &bytes[*actual_offset];
+            // si el packfile esta truncado se corta la lectura
+            let byt = bytes.get(*actual_offset).copied().unwrap_or(0);
             *actual_offset += 1;
             val = (val << 7) | (byt & 0x7f) as usize;
             if byt & 0x80 == 0 {
@@ -279,56 +979,134 @@ impl Packfile {
         val
     }
 
-    // Funcion para leer un varint de un vector de bytes en formato little endian
-    fn leer_objeto_del_packfile(bytes: &[u8], offset: &mut usize) -> Result<(u8, Vec<u8>), String> {
-        let offset_pre_varint = *offset;
-        let (tipo, tamanio) = Self::decodificar_bytes(bytes, offset);
-        if tipo == 6 {
-            Self::leer_ofs_delta_obj(bytes, tamanio, offset, offset_pre_varint)
-        } else {
-            let objeto_descomprimido = Self::descomprimir_objeto(bytes, offset, tamanio)?;
-            Ok((tipo, objeto_descomprimido))
+    // Lee el objeto que empieza en el offset, resolviendo los deltas. Devuelve None si es un REF_DELTA
+    // cuya base no esta ni entre los objetos ya leidos ni en el directorio de objetos. En todos los casos
+    // el offset queda al final de la entrada.
+    // La cadena de OFS_DELTA se recorre de forma iterativa hasta la base y despues se aplican los
+    // deltas desde la base, para que una cadena larga no desborde la pila
+    fn leer_objeto_del_packfile(
+        bytes: &[u8],
+        offset: &mut usize,
+        objetos_leidos: &ObjetosDelPackfile,
+        dir_objetos: &str,
+    ) -> Result<Option<(u8, Vec<u8>)>, ErrorPackfile> {
+        // donde empiezan los datos de cada delta de la cadena y su tamanio, desde el pedido
+        let mut deltas: Vec<(usize, u32)> = Vec::new();
+        let mut actual = *offset;
+        let base = loop {
+            if actual >= bytes.len() {
+                return Err(ErrorPackfile::Truncado);
+            }
+            let offset_pre_varint = actual;
+            let (tipo, tamanio) = Self::decodificar_bytes(bytes, &mut actual);
+            match tipo {
+                COMMIT | TREE | BLOB | TAG => {
+                    let objeto_descomprimido =
+                        Self::descomprimir_objeto(bytes, &mut actual, tamanio)?;
+                    break Some((tipo, objeto_descomprimido));
+                }
+                OFS_DELTA => {
+                    let distancia = Self::leer_vli_be(bytes, &mut actual, true);
+                    // la base tiene que estar antes, despues del header del packfile
+                    if distancia == 0 || distancia > offset_pre_varint.saturating_sub(12) {
+                        return Err(ErrorPackfile::DeltaInvalido {
+                            offset: offset_pre_varint,
+                            motivo: format!(
+                                "la base esta fuera del packfile (offset -{})",
+                                distancia
+                            ),
+                        });
+                    }
+                    deltas.push((actual, tamanio));
+                    actual = offset_pre_varint - distancia;
+                }
+                REF_DELTA => {
+                    let hash_base = bytes
+                        .get(actual..actual + 20)
+                        .ok_or(ErrorPackfile::Truncado)?;
+                    let hash_base = Tree::encode_hex(hash_base);
+                    deltas.push((actual + 20, tamanio));
+                    // la base se busca primero entre los objetos ya leidos del packfile y despues
+                    // en el directorio de objetos
+                    break match objetos_leidos.get(&hash_base) {
+                        Some(base) => Some(base.clone()),
+                        None => Self::leer_objeto_de_directorio(&hash_base, dir_objetos).ok(),
+                    };
+                }
+                _ => {
+                    return Err(ErrorPackfile::ObjetoCorrupto {
+                        offset: offset_pre_varint,
+                        motivo: format!("tipo de objeto {} desconocido", tipo),
+                    })
+                }
+            }
+        };
+
+        if deltas.is_empty() {
+            *offset = actual;
+            return Ok(base);
         }
+        let mut objeto = base;
+        for (indice, (mut posicion, tamanio)) in deltas.into_iter().enumerate().rev() {
+            // sin base solo hace falta consumir el delta pedido, para saber donde termina
+            if objeto.is_none() && indice > 0 {
+                continue;
+            }
+            objeto = Self::aplicar_delta_a_base(bytes, &mut posicion, objeto, tamanio)?;
+            if indice == 0 {
+                *offset = posicion;
+            }
+        }
+        Ok(objeto)
     }
 
-    // Funcion para decodificar ofs delta. Devuelve el tipo y el objeto reconstruido y descomprimido
-    fn leer_ofs_delta_obj(
+    // Aplica el delta que empieza en el offset a la base. Si no hay base igual se consume el delta,
+    // para dejar el offset al final de la entrada
+    fn aplicar_delta_a_base(
         bytes: &[u8],
-        obj_size: u32,
         actual_offset: &mut usize,
-        offset_pre_varint: usize,
-    ) -> Result<(u8, Vec<u8>), String> {
-        let offset = Self::leer_vli_be(bytes, actual_offset, true);
-
-        let base_obj_offset = offset_pre_varint - offset;
-
-        let (base_obj_type, mut base_obj_data) =
-            Self::leer_objeto_del_packfile(bytes, &mut { base_obj_offset })?;
-
-        Self::crear_delta_obj(
-            bytes,
-            actual_offset,
-            base_obj_type,
-            &mut base_obj_data,
-            obj_size,
-        )
+        base: Option<(u8, Vec<u8>)>,
+        obj_size: u32,
+    ) -> Result<Option<(u8, Vec<u8>)>, ErrorPackfile> {
+        match base {
+            Some((base_obj_type, mut base_obj_data)) => Ok(Some(Self::crear_delta_obj(
+                bytes,
+                actual_offset,
+                base_obj_type,
+                &mut base_obj_data,
+                obj_size,
+            )?)),
+            None => {
+                Self::descomprimir_objeto(bytes, actual_offset, obj_size)?;
+                Ok(None)
+            }
+        }
     }
 
-    // Funcion para procesar las instrucciones de reconstruccion de un objeto delta. Devuelve el tipo y el objeto reconstruido y descomprimido
+    // Funcion para procesar las instrucciones de reconstruccion de un objeto delta. Devuelve el tipo y el objeto reconstruido y descomprimido.
+    // Falla si el delta no corresponde a la base o si no reconstruye el tamanio que declara
     fn crear_delta_obj(
         bytes: &[u8],
         actual_offset: &mut usize,
         tipo_de_objeto_base: u8,
         data_objeto_base: &mut [u8],
         obj_size: u32,
-    ) -> Result<(u8, Vec<u8>), String> {
+    ) -> Result<(u8, Vec<u8>), ErrorPackfile> {
+        let offset_delta = *actual_offset;
+        let delta_invalido = |motivo: &str| ErrorPackfile::DeltaInvalido {
+            offset: offset_delta,
+            motivo: motivo.to_string(),
+        };
         let objeto_descomprimido = Self::descomprimir_objeto(bytes, actual_offset, obj_size)?;
 
         let mut data_descomprimida_offset: usize = 0;
-        let _tamanio_objeto_base =
+        let tamanio_objeto_base =
             Self::leer_varint_le(&objeto_descomprimido, &mut data_descomprimida_offset);
-        let _tamanio_objeto_reconstruido =
+        let tamanio_objeto_reconstruido =
             Self::leer_varint_le(&objeto_descomprimido, &mut data_descomprimida_offset);
+        if tamanio_objeto_base as usize != data_objeto_base.len() {
+            return Err(delta_invalido("el tamanio de la base no coincide"));
+        }
 
         let mut obj_data: Vec<u8> = Vec::new();
 
@@ -343,7 +1121,11 @@ impl Packfile {
                 for i in 0..7 {
                     let mascara = 1 << i;
                     if (byt & mascara) != 0 {
-                        vals.push(objeto_descomprimido[data_descomprimida_offset]);
+                        vals.push(
+                            *objeto_descomprimido
+                                .get(data_descomprimida_offset)
+                                .ok_or(delta_invalido("instruccion de copia incompleta"))?,
+                        );
                         data_descomprimida_offset += 1;
                     } else {
                         vals.push(0);
@@ -355,16 +1137,26 @@ impl Packfile {
                     nbytes = 0x10000
                 }
 
-                obj_data.extend(&data_objeto_base[inicio..inicio + nbytes]);
+                obj_data.extend(
+                    data_objeto_base
+                        .get(inicio..inicio + nbytes)
+                        .ok_or(delta_invalido("copia fuera de la base"))?,
+                );
             } else {
                 let nbytes = byt & 0x7f;
                 obj_data.extend(
-                    &objeto_descomprimido
-                        [data_descomprimida_offset..data_descomprimida_offset + nbytes as usize],
+                    objeto_descomprimido
+                        .get(data_descomprimida_offset..data_descomprimida_offset + nbytes as usize)
+                        .ok_or(delta_invalido("insercion incompleta"))?,
                 );
                 data_descomprimida_offset += nbytes as usize;
             }
         }
+        if tamanio_objeto_reconstruido as usize != obj_data.len() {
+            return Err(delta_invalido(
+                "el objeto reconstruido no tiene el tamanio declarado",
+            ));
+        }
         Ok((tipo_de_objeto_base, obj_data))
     }
 
@@ -374,8 +1166,9 @@ impl Packfile {
         let mut shift = 0;
 
         loop {
-            let byte = input[*offset];
-            result |= ((byte & 0x7F) as u32) << shift;
+            // si el delta esta truncado se corta la lectura
+            let byte = input.get(*offset).copied().unwrap_or(0);
+            result |= ((byte & 0x7F) as u32).checked_shl(shift).unwrap_or(0);
             shift += 7;
             *offset += 1;
 
@@ -390,6 +1183,7 @@ impl Packfile {
 #[cfg(test)]
 mod tests {
     use super::*;
+    use crate::utils;
     use serial_test::serial;
 
     fn leer_blob_de_packfile(packfile: &[u8], offset: &mut usize) -> (Vec<u8>, u8, u32) {
@@ -521,7 +1315,10 @@ mod tests {
 
         let packfile = result.unwrap();
         let mut offset = 12;
-        let mut objeto = Packfile::leer_objeto_del_packfile(&packfile, &mut offset).unwrap();
+        let mut objeto =
+            Packfile::leer_objeto_del_packfile(&packfile, &mut offset, &HashMap::new(), "")
+                .unwrap()
+                .unwrap();
         let objeto_con_header =
             Packfile::obtener_objeto_con_header(objeto.0, objeto.1.len() as u32, &mut objeto.1)
                 .unwrap();
@@ -542,9 +1339,429 @@ mod tests {
         let (_firma, _version, largo) = Packfile::leer_header_packfile(&packfile).unwrap();
         let mut contador = 0;
         while contador < largo {
-            let objeto = Packfile::leer_objeto_del_packfile(&packfile, &mut offset);
+            let objeto =
+                Packfile::leer_objeto_del_packfile(&packfile, &mut offset, &HashMap::new(), "");
             contador += 1;
-            assert!(objeto.is_ok());
+            assert!(objeto.unwrap().is_some());
         }
     }
+
+    // Escribe un blob como objeto suelto en el directorio y devuelve su hash
+    fn escribir_blob_suelto(dir: &str, contenido: &[u8]) -> String {
+        let mut objeto = format!("blob {}\0", contenido.len()).into_bytes();
+        objeto.extend(contenido);
+        let hash = format!("{:x}", Sha1::digest(&objeto));
+        let ruta = format!("{}{}/{}", dir, &hash[..2], &hash[2..]);
+        io::escribir_bytes(ruta, compresion::comprimir_contenido_u8(&objeto).unwrap()).unwrap();
+        hash
+    }
+
+    fn crear_versiones_de_un_archivo(dir: &str) -> Vec<String> {
+        let _ = std::fs::remove_dir_all(dir);
+        let mut contenido = String::new();
+        for i in 0..200 {
+            contenido.push_str(&format!("linea numero {} del archivo\n", i));
+        }
+        let mut hashes = vec![escribir_blob_suelto(dir, contenido.as_bytes())];
+        for i in 0..3 {
+            contenido = contenido.replace(
+                &format!("linea numero {} del", i * 50),
+                &format!("linea modificada {} del", i),
+            );
+            hashes.push(escribir_blob_suelto(dir, contenido.as_bytes()));
+        }
+        hashes
+    }
+
+    #[test]
+    #[serial]
+    fn test10_codificar_offset_delta_es_la_inversa_de_leer_vli_be() {
+        for offset in [1, 127, 128, 16511, 16512, 1 << 20] {
+            let bytes = Packfile::codificar_offset_delta(offset);
+            let mut actual_offset = 0;
+            assert_eq!(
+                Packfile::leer_vli_be(&bytes, &mut actual_offset, true),
+                offset
+            );
+            assert_eq!(actual_offset, bytes.len());
+        }
+    }
+
+    #[test]
+    #[serial]
+    fn test11_el_pack_con_deltas_se_lee_y_escribe_bien() {
+        let dir = "tmp/packfile_test11/objetos/";
+        let dir_destino = "tmp/packfile_test11/destino/";
+        let _ = std::fs::remove_dir_all(dir_destino);
+        let hashes = crear_versiones_de_un_archivo(dir);
+
+        let packfile =
+            Packfile::obtener_pack_con_archivos_y_parametros(hashes.clone(), dir, 10, 50).unwrap();
+        let pack_sin_deltas =
+            Packfile::obtener_pack_con_archivos_y_parametros(hashes.clone(), dir, 0, 50).unwrap();
+        assert!(packfile.len() < pack_sin_deltas.len());
+
+        let mut offset = 12;
+        let mut tipos = Vec::new();
+        for _ in 0..hashes.len() {
+            let (tipo, tamanio) = Packfile::decodificar_bytes(&packfile, &mut offset);
+            if tipo == OFS_DELTA {
+                Packfile::leer_vli_be(&packfile, &mut offset, true);
+            }
+            Packfile::descomprimir_objeto(&packfile, &mut offset, tamanio).unwrap();
+            tipos.push(tipo);
+        }
+        assert_eq!(tipos.iter().filter(|&&tipo| tipo == OFS_DELTA).count(), 3);
+
+        Packfile::leer_packfile_y_escribir(&packfile, dir_destino.to_string()).unwrap();
+        for hash in hashes {
+            assert_eq!(
+                compresion::obtener_objeto_descomprimido(&hash, dir).unwrap(),
+                compresion::obtener_objeto_descomprimido(&hash, dir_destino).unwrap()
+            );
+        }
+    }
+
+    #[test]
+    #[serial]
+    fn test12_la_profundidad_limita_las_cadenas_de_deltas() {
+        let dir = "tmp/packfile_test12/objetos/";
+        let hashes = crear_versiones_de_un_archivo(dir);
+
+        let mut entradas: Vec<EntradaPackfile> = hashes
+            .iter()
+            .map(|hash| Packfile::obtener_entrada_de_directorio(hash, dir).unwrap())
+            .collect();
+        Packfile::elegir_bases_delta(&mut entradas, 10, 1);
+
+        assert!(entradas.iter().all(|entrada| entrada.profundidad <= 1));
+        assert!(entradas.iter().any(|entrada| entrada.base.is_some()));
+    }
+
+    // Arma un packfile con un REF_DELTA del objetivo contra la base y, si se pide, la base entera despues del delta
+    fn armar_pack_con_ref_delta(base: &[u8], objetivo: &[u8], incluir_base: bool) -> Vec<u8> {
+        let hash_base = format!(
+            "{:x}",
+            Sha1::digest([format!("blob {}\0", base.len()).as_bytes(), base].concat())
+        );
+        let delta = Packfile::crear_delta(base, objetivo);
+
+        let mut objetos = Packfile::codificar_bytes(REF_DELTA, delta.len() as u32);
+        objetos.extend(Tree::decode_hex(&hash_base).unwrap());
+        objetos.extend(compresion::comprimir_contenido_u8(&delta).unwrap());
+        if incluir_base {
+            objetos.extend(Packfile::codificar_bytes(BLOB, base.len() as u32));
+            objetos.extend(compresion::comprimir_contenido_u8(base).unwrap());
+        }
+        Packfile::armar_packfile(objetos, if incluir_base { 2 } else { 1 })
+    }
+
+    fn contenido_base_y_objetivo() -> (String, String) {
+        let base = "contenido base del archivo que se va a deltificar\n".repeat(10);
+        let objetivo = base.clone() + "una linea nueva al final\n";
+        (base, objetivo)
+    }
+
+    #[test]
+    #[serial]
+    fn test13_ref_delta_con_la_base_en_el_mismo_packfile() {
+        let dir = "tmp/packfile_test13/";
+        let _ = std::fs::remove_dir_all(dir);
+        let (base, objetivo) = contenido_base_y_objetivo();
+        let packfile = armar_pack_con_ref_delta(base.as_bytes(), objetivo.as_bytes(), true);
+
+        Packfile::leer_packfile_y_escribir(&packfile, dir.to_string()).unwrap();
+
+        let hash_objetivo =
+            escribir_blob_suelto("tmp/packfile_test13_esperado/", objetivo.as_bytes());
+        let (tipo, contenido) = Packfile::leer_objeto_de_directorio(&hash_objetivo, dir).unwrap();
+        assert_eq!(tipo, BLOB);
+        assert_eq!(contenido, objetivo.as_bytes());
+    }
+
+    #[test]
+    #[serial]
+    fn test14_ref_delta_con_la_base_en_el_directorio_de_objetos() {
+        let dir = "tmp/packfile_test14/";
+        let _ = std::fs::remove_dir_all(dir);
+        let (base, objetivo) = contenido_base_y_objetivo();
+        escribir_blob_suelto(dir, base.as_bytes());
+        let packfile = armar_pack_con_ref_delta(base.as_bytes(), objetivo.as_bytes(), false);
+
+        Packfile::leer_packfile_y_escribir(&packfile, dir.to_string()).unwrap();
+
+        let hash_objetivo =
+            escribir_blob_suelto("tmp/packfile_test14_esperado/", objetivo.as_bytes());
+        let (_, contenido) = Packfile::leer_objeto_de_directorio(&hash_objetivo, dir).unwrap();
+        assert_eq!(contenido, objetivo.as_bytes());
+    }
+
+    #[test]
+    #[serial]
+    fn test15_ref_delta_sin_base_rechaza_el_packfile_sin_escribir_nada() {
+        let dir = "tmp/packfile_test15/";
+        let _ = std::fs::remove_dir_all(dir);
+        let (base, objetivo) = contenido_base_y_objetivo();
+        let packfile = armar_pack_con_ref_delta(base.as_bytes(), objetivo.as_bytes(), false);
+
+        assert!(Packfile::leer_packfile_y_escribir(&packfile, dir.to_string()).is_err());
+        assert!(!PathBuf::from(dir).exists());
+    }
+
+    #[test]
+    #[serial]
+    fn test16_el_packfile_recibido_se_guarda_con_su_indice() {
+        let dir = "tmp/packfile_test16/objetos/";
+        let dir_destino = "tmp/packfile_test16/destino/";
+        let _ = std::fs::remove_dir_all(dir_destino);
+        let hashes = crear_versiones_de_un_archivo(dir);
+        let packfile = Packfile::obtener_pack_con_archivos(hashes.clone(), dir).unwrap();
+
+        Packfile::leer_packfile_y_escribir(&packfile, dir_destino.to_string()).unwrap();
+
+        let nombre_pack = format!(
+            "{}pack/pack-{}",
+            dir_destino,
+            Tree::encode_hex(&packfile[packfile.len() - 20..])
+        );
+        assert_eq!(
+            io::leer_bytes(nombre_pack.clone() + ".pack").unwrap(),
+            packfile
+        );
+        assert!(PathBuf::from(nombre_pack + ".idx").exists());
+
+        let mut hashes_en_packs = Packfile::obtener_hashes_de_packs(dir_destino).unwrap();
+        let mut hashes_esperados = hashes.clone();
+        hashes_en_packs.sort();
+        hashes_esperados.sort();
+        assert_eq!(hashes_en_packs, hashes_esperados);
+
+        for hash in hashes {
+            let contenido = compresion::descomprimir_objeto(&hash, dir_destino).unwrap();
+            assert!(contenido.starts_with("blob "));
+        }
+    }
+
+    // Rearma el packfile con el checksum correcto despues de modificar sus bytes
+    fn recalcular_checksum(packfile: &[u8]) -> Vec<u8> {
+        let mut packfile = packfile[..packfile.len() - 20].to_vec();
+        let checksum = Sha1::digest(&packfile);
+        packfile.extend(checksum);
+        packfile
+    }
+
+    fn armar_pack_con_un_blob(contenido: &[u8], tamanio_declarado: u32) -> Vec<u8> {
+        let mut objetos = Packfile::codificar_bytes(BLOB, tamanio_declarado);
+        objetos.extend(compresion::comprimir_contenido_u8(contenido).unwrap());
+        Packfile::armar_packfile(objetos, 1)
+    }
+
+    #[test]
+    #[serial]
+    fn test17_verificar_packfile_valido() {
+        let packfile = armar_pack_con_un_blob(b"hola mundo\n", 11);
+
+        let entradas = Packfile::verificar_packfile(&packfile, "").unwrap();
+        assert_eq!(entradas.len(), 1);
+        assert_eq!(entradas[0].offset, 12);
+
+        let con_blob_vacio = armar_pack_con_un_blob(b"", 0);
+        assert!(Packfile::verificar_packfile(&con_blob_vacio, "").is_ok());
+    }
+
+    #[test]
+    #[serial]
+    fn test18_verificar_packfile_con_checksum_incorrecto() {
+        let mut packfile = armar_pack_con_un_blob(b"hola mundo\n", 11);
+        let ultimo = packfile.len() - 1;
+        packfile[ultimo] ^= 0xff;
+
+        assert!(matches!(
+            Packfile::verificar_packfile(&packfile, ""),
+            Err(ErrorPackfile::ChecksumInvalido { .. })
+        ));
+    }
+
+    #[test]
+    #[serial]
+    fn test19_verificar_packfile_con_firma_o_version_invalida() {
+        let packfile = armar_pack_con_un_blob(b"hola mundo\n", 11);
+
+        let mut con_otra_firma = packfile.clone();
+        con_otra_firma[..4].copy_from_slice(b"KCAP");
+        assert_eq!(
+            Packfile::verificar_packfile(&recalcular_checksum(&con_otra_firma), ""),
+            Err(ErrorPackfile::FirmaInvalida)
+        );
+
+        let mut con_otra_version = packfile;
+        con_otra_version[4..8].copy_from_slice(&5u32.to_be_bytes());
+        assert_eq!(
+            Packfile::verificar_packfile(&recalcular_checksum(&con_otra_version), ""),
+            Err(ErrorPackfile::VersionNoSoportada(5))
+        );
+    }
+
+    #[test]
+    #[serial]
+    fn test20_verificar_packfile_con_tamanio_declarado_incorrecto() {
+        // la descompresion se corta apenas el objeto supera lo declarado
+        let mas_chico = armar_pack_con_un_blob(b"hola mundo\n", 5);
+        assert!(matches!(
+            Packfile::verificar_packfile(&mas_chico, ""),
+            Err(ErrorPackfile::TamanioIncorrecto {
+                offset: 13,
+                declarado: 5,
+                obtenido
+            }) if obtenido > 5
+        ));
+
+        let mas_grande = armar_pack_con_un_blob(b"hola mundo\n", 20);
+        assert!(matches!(
+            Packfile::verificar_packfile(&mas_grande, ""),
+            Err(ErrorPackfile::TamanioIncorrecto { obtenido: 11, .. })
+        ));
+    }
+
+    #[test]
+    #[serial]
+    fn test21_verificar_packfile_truncado_o_con_bytes_sobrantes() {
+        let packfile = armar_pack_con_un_blob(b"hola mundo\n", 11);
+
+        let mut con_dos_objetos = packfile.clone();
+        con_dos_objetos[8..12].copy_from_slice(&2u32.to_be_bytes());
+        assert!(Packfile::verificar_packfile(&recalcular_checksum(&con_dos_objetos), "").is_err());
+
+        let mut sin_objetos = packfile;
+        sin_objetos[8..12].copy_from_slice(&0u32.to_be_bytes());
+        assert!(matches!(
+            Packfile::verificar_packfile(&recalcular_checksum(&sin_objetos), ""),
+            Err(ErrorPackfile::BytesSobrantes(_))
+        ));
+
+        assert_eq!(
+            Packfile::verificar_packfile(b"PACK", ""),
+            Err(ErrorPackfile::Truncado)
+        );
+    }
+
+    #[test]
+    #[serial]
+    fn test22_un_packfile_invalido_no_se_escribe() {
+        let dir = "tmp/packfile_test22/";
+        let _ = std::fs::remove_dir_all(dir);
+        let packfile = armar_pack_con_un_blob(b"hola mundo\n", 5);
+
+        assert!(Packfile::leer_packfile_y_escribir(&packfile, dir.to_string()).is_err());
+        assert!(!PathBuf::from(dir).exists());
+    }
+
+    #[test]
+    #[serial]
+    fn test23_el_pack_thin_no_manda_las_bases_y_las_referencia_por_hash() {
+        let dir = "tmp/packfile_test23/objetos/";
+        let dir_destino = "tmp/packfile_test23/destino/";
+        let _ = std::fs::remove_dir_all(dir_destino);
+        let hashes = crear_versiones_de_un_archivo(dir);
+        let (base, objetos) = hashes.split_first().unwrap();
+
+        let packfile =
+            Packfile::obtener_pack_thin(objetos.to_vec(), vec![base.clone()], dir).unwrap();
+        let (_, _, cantidad) = Packfile::leer_header_packfile(&packfile).unwrap();
+        assert_eq!(cantidad, 3);
+        let (tipo, _) = Packfile::decodificar_bytes(&packfile, &mut 12);
+        assert_eq!(tipo, REF_DELTA);
+
+        assert!(Packfile::leer_packfile_y_escribir(&packfile, dir_destino.to_string()).is_err());
+        let (_, contenido_base) = Packfile::leer_objeto_de_directorio(base, dir).unwrap();
+        let hash_base = escribir_blob_suelto(dir_destino, &contenido_base);
+        Packfile::leer_packfile_y_escribir(&packfile, dir_destino.to_string()).unwrap();
+
+        // la base se agrega al pack guardado, que ya no depende del objeto suelto
+        std::fs::remove_file(format!(
+            "{}{}/{}",
+            dir_destino,
+            &hash_base[..2],
+            &hash_base[2..]
+        ))
+        .unwrap();
+        let mut hashes_en_packs = Packfile::obtener_hashes_de_packs(dir_destino).unwrap();
+        hashes_en_packs.sort();
+        let mut hashes_esperados = hashes.clone();
+        hashes_esperados.sort();
+        assert_eq!(hashes_en_packs, hashes_esperados);
+        for hash in objetos {
+            assert_eq!(
+                compresion::obtener_objeto_descomprimido(hash, dir).unwrap(),
+                compresion::obtener_objeto_descomprimido(hash, dir_destino).unwrap()
+            );
+        }
+    }
+
+    #[test]
+    #[serial]
+    fn test24_los_objetos_de_un_pack_nuevo_se_encuentran_despues_de_leer_los_indices() {
+        let dir = "tmp/packfile_test24/objetos/";
+        let dir_destino = "tmp/packfile_test24/destino/";
+        let _ = std::fs::remove_dir_all(dir_destino);
+        let hashes = crear_versiones_de_un_archivo(dir);
+        let (ultimo, anteriores) = hashes.split_last().unwrap();
+
+        let packfile = Packfile::obtener_pack_con_archivos(anteriores.to_vec(), dir).unwrap();
+        Packfile::leer_packfile_y_escribir(&packfile, dir_destino.to_string()).unwrap();
+        assert!(anteriores
+            .iter()
+            .all(|hash| compresion::existe_objeto(hash, dir_destino)));
+        assert!(!compresion::existe_objeto(ultimo, dir_destino));
+
+        let packfile = Packfile::obtener_pack_con_archivos(hashes.clone(), dir).unwrap();
+        Packfile::leer_packfile_y_escribir(&packfile, dir_destino.to_string()).unwrap();
+        assert!(compresion::existe_objeto(ultimo, dir_destino));
+        for hash in hashes {
+            assert_eq!(
+                compresion::obtener_objeto_descomprimido(&hash, dir).unwrap(),
+                compresion::obtener_objeto_descomprimido(&hash, dir_destino).unwrap()
+            );
+        }
+    }
+
+    #[test]
+    fn test25_un_tamanio_declarado_enorme_no_se_reserva_de_antemano() {
+        let packfile = armar_pack_con_un_blob(b"hola mundo\n", u32::MAX);
+        assert!(matches!(
+            Packfile::verificar_packfile(&packfile, ""),
+            Err(ErrorPackfile::TamanioIncorrecto { obtenido: 11, .. })
+        ));
+    }
+
+    #[test]
+    fn test26_una_cadena_larga_de_ofs_delta_se_resuelve_sin_recursion() {
+        let contenido = contenido_base_y_objetivo().0;
+        let delta = Packfile::crear_delta(contenido.as_bytes(), contenido.as_bytes());
+        let delta_comprimido = compresion::comprimir_contenido_u8(&delta).unwrap();
+
+        let mut objetos = Packfile::codificar_bytes(BLOB, contenido.len() as u32);
+        objetos.extend(compresion::comprimir_contenido_u8(contenido.as_bytes()).unwrap());
+        let mut offset_anterior = 12;
+        let cantidad = 50000;
+        for _ in 1..cantidad {
+            let offset_actual = 12 + objetos.len();
+            objetos.extend(Packfile::codificar_bytes(OFS_DELTA, delta.len() as u32));
+            objetos.extend(Packfile::codificar_offset_delta(
+                offset_actual - offset_anterior,
+            ));
+            objetos.extend(&delta_comprimido);
+            offset_anterior = offset_actual;
+        }
+        let packfile = Packfile::armar_packfile(objetos, cantidad);
+
+        let mut offset = offset_anterior;
+        let (tipo, objeto) =
+            Packfile::leer_objeto_del_packfile(&packfile, &mut offset, &HashMap::new(), "")
+                .unwrap()
+                .unwrap();
+        assert_eq!(tipo, BLOB);
+        assert_eq!(objeto, contenido.as_bytes());
+        assert_eq!(offset, packfile.len() - 20);
+    }
 }
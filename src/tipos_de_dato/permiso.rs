@@ -0,0 +1,27 @@
+use std::fmt::Display;
+
+use serde::{Deserialize, Serialize};
+
+/// Permiso de una cuenta sobre un repositorio del servidor. Cada permiso incluye a los
+/// anteriores: quien puede escribir tambien puede leer y un admin puede hacer todo.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum Permiso {
+    /// Clonar, hacer fetch y consultar los pull requests.
+    Lectura,
+    /// Hacer push y crear, actualizar o mergear pull requests.
+    Escritura,
+    /// Crear repositorios nuevos y administrarlos.
+    Admin,
+}
+
+impl Display for Permiso {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        let nombre = match self {
+            Permiso::Lectura => "lectura",
+            Permiso::Escritura => "escritura",
+            Permiso::Admin => "admin",
+        };
+        write!(f, "{}", nombre)
+    }
+}
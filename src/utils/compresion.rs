@@ -1,20 +1,43 @@
-use crate::tipos_de_dato::objetos::tree::Tree;
+use crate::tipos_de_dato::{objetos::tree::Tree, packfile::Packfile};
 use flate2::{read::ZlibDecoder, write::ZlibEncoder, Compression};
 use std::io::{Read, Write};
+use std::path::Path;
 
 use super::io;
 
 /// Dado un hash y una ruta, busca el archivo de ese hash en la ruta especificada
 /// y devuelve el contenido del objeto descomprimido.
 pub fn descomprimir_objeto(hash: &str, ruta: &str) -> Result<String, String> {
-    let ruta_objeto = format!("{}{}/{}", ruta, &hash[..2], &hash[2..]);
-
-    let contenido_leido = io::leer_bytes(ruta_objeto)?;
-    let contenido_descomprimido = descomprimir_contenido_u8(&contenido_leido)?;
+    let contenido_descomprimido = obtener_objeto_descomprimido(hash, ruta)?;
     let contenido_decodificado = decodificar_contenido(contenido_descomprimido)?;
     Ok(contenido_decodificado)
 }
 
+/// Dado un hash y una ruta de objetos, devuelve el objeto descomprimido con su header.
+/// Primero se busca como objeto suelto en `{ruta}{hash[..2]}/{hash[2..]}` y si no esta,
+/// dentro de los packfiles guardados en `{ruta}pack/`.
+pub fn obtener_objeto_descomprimido(hash: &str, ruta: &str) -> Result<Vec<u8>, String> {
+    if hash.len() < 3 {
+        return Err(format!("Hash de objeto invalido: {}", hash));
+    }
+    let ruta_objeto = format!("{}{}/{}", ruta, &hash[..2], &hash[2..]);
+
+    match io::leer_bytes(&ruta_objeto) {
+        Ok(contenido_leido) => descomprimir_contenido_u8(&contenido_leido),
+        Err(error) => Packfile::obtener_objeto_de_packs(hash, ruta)?.ok_or(error),
+    }
+}
+
+/// Devuelve si el objeto esta en la ruta de objetos, ya sea suelto o dentro de un packfile.
+/// No lee el objeto: solo se fija si existe el archivo o si esta en el indice de algun packfile.
+pub fn existe_objeto(hash: &str, ruta: &str) -> bool {
+    if hash.len() < 3 {
+        return false;
+    }
+    let ruta_objeto = format!("{}{}/{}", ruta, &hash[..2], &hash[2..]);
+    Path::new(&ruta_objeto).exists() || Packfile::existe_objeto_en_packs(hash, ruta)
+}
+
 /// Descomprime el objeto indicado por el hash en la ruta .gir/objects
 pub fn descomprimir_objeto_gir(hash: &str) -> Result<String, String> {
     descomprimir_objeto(hash, ".gir/objects/")
@@ -38,7 +61,7 @@ pub fn decodificar_contenido(contenido: Vec<u8>) -> Result<String, String> {
     let tipo_objeto = header.split_whitespace().collect::<Vec<&str>>()[0];
 
     match tipo_objeto {
-        "blob" | "commit" => Ok(String::from_utf8_lossy(&contenido).to_string()),
+        "blob" | "commit" | "tag" => Ok(String::from_utf8_lossy(&contenido).to_string()),
         "tree" => decodificar_tree(&header, &contenido),
         _ => Err("Tipo de objeto invalido".to_string()),
     }
@@ -149,23 +172,14 @@ pub fn descomprimir_contenido_u8(contenido: &[u8]) -> Result<Vec<u8>, String> {
 /// Dado un hash y una ruta, busca el archivo de ese hash en la ruta especificada
 /// y devuelve el contenido del objeto comprimido, sin tener en cuenta la linea del header del objeto.
 pub fn obtener_contenido_comprimido_sin_header(hash: &str) -> Result<Vec<u8>, String> {
-    let ruta_objeto = format!(".gir/objects/{}/{}", &hash[..2], &hash[2..]);
-    let contenido_leido = io::leer_bytes(ruta_objeto)?;
-    let cont_descomprimido = descomprimir_contenido_u8(&contenido_leido).unwrap();
-    let vec: Vec<&[u8]> = cont_descomprimido.splitn(2, |&x| x == 0).collect();
-
-    let contenido = vec[1];
-    let contenido_comprimido = comprimir_contenido_u8(contenido)?;
-    Ok(contenido_comprimido)
+    obtener_contenido_comprimido_sin_header_de(hash, ".gir/objects/")
 }
 
 pub fn obtener_contenido_comprimido_sin_header_de(
     hash: &str,
     dir: &str,
 ) -> Result<Vec<u8>, String> {
-    let ruta_objeto = format!("{}{}/{}", dir, &hash[..2], &hash[2..]);
-    let contenido_leido = io::leer_bytes(ruta_objeto)?;
-    let cont_descomprimido = descomprimir_contenido_u8(&contenido_leido).unwrap();
+    let cont_descomprimido = obtener_objeto_descomprimido(hash, dir)?;
     let vec: Vec<&[u8]> = cont_descomprimido.splitn(2, |&x| x == 0).collect();
 
     let contenido = vec[1];
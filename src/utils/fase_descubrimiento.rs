@@ -31,7 +31,26 @@ pub fn fase_de_descubrimiento<T: Write + Read>(
     ),
     String,
 > {
-    let mut lineas_recibidas = comunicacion.obtener_lineas()?;
+    let lineas_recibidas = comunicacion.obtener_lineas()?;
+    interpretar_anuncio_de_referencias(lineas_recibidas)
+}
+
+/// Interpreta las lineas del anuncio de referencias del protocolo v1, ya recibidas del servidor.
+/// Devuelve lo mismo que `fase_de_descubrimiento`.
+pub fn interpretar_anuncio_de_referencias(
+    mut lineas_recibidas: Vec<String>,
+) -> Result<
+    (
+        Vec<String>,
+        Option<String>,
+        ReferenciaCommit,
+        ReferenciaCommit,
+    ),
+    String,
+> {
+    if lineas_recibidas.is_empty() {
+        return Err("El servidor no envio el anuncio de referencias".to_string());
+    }
     let primera_linea = lineas_recibidas.remove(0);
     if &primera_linea != "version 1\n" {
         let mensaje_error: Vec<&str> = primera_linea.splitn(2, ' ').collect();
@@ -60,6 +79,71 @@ pub fn fase_de_descubrimiento<T: Write + Read>(
     ))
 }
 
+/// Fase de descubrimiento del protocolo v2. El servidor ya anuncio sus capacidades (las lineas
+/// siguientes a `version 2`), asi que se le piden las referencias con el comando `ls-refs`,
+/// filtrando solo HEAD, las ramas y los tags.
+///
+/// # Resultado
+/// Lo mismo que `fase_de_descubrimiento`. Los tags anotados se devuelven sin su version pelada.
+pub fn fase_de_descubrimiento_v2<T: Write + Read>(
+    comunicacion: &mut Comunicacion<T>,
+    capacidades: Vec<String>,
+) -> Result<
+    (
+        Vec<String>,
+        Option<String>,
+        ReferenciaCommit,
+        ReferenciaCommit,
+    ),
+    String,
+> {
+    let capacidades: Vec<String> = capacidades
+        .iter()
+        .map(|capacidad| capacidad.trim().to_string())
+        .collect();
+    if !capacidades.iter().any(|capacidad| capacidad == "ls-refs") {
+        return Err("El servidor no soporta el comando ls-refs".to_string());
+    }
+
+    let argumentos = [
+        "symrefs",
+        "ref-prefix HEAD",
+        "ref-prefix refs/heads/",
+        "ref-prefix refs/tags/",
+    ]
+    .map(String::from);
+    comunicacion.enviar_comando_v2("ls-refs", &argumentos)?;
+    let lineas_recibidas = comunicacion.obtener_lineas_v2()?;
+
+    let mut commit_head_remoto = None;
+    let mut referencias = Vec::new();
+    for linea in lineas_recibidas {
+        if let Some(mensaje) = linea.strip_prefix("ERR ") {
+            return Err(format!("Error, {}", mensaje));
+        }
+        // 'hash' 'nombre'[ symref-target:'destino'][ peeled:'hash']
+        let mut partes = linea.split_whitespace();
+        let (Some(hash), Some(nombre)) = (partes.next(), partes.next()) else {
+            return Err(format!("Referencia invalida en ls-refs: {}", linea));
+        };
+        if nombre == "HEAD" {
+            commit_head_remoto = Some(hash.to_string());
+        } else {
+            referencias.push(format!("{} {}", hash, nombre));
+        }
+    }
+
+    let (commits_cabezas_y_dir_rama_asosiado, commits_y_tags_asosiados) =
+        obtener_commits_y_dir_rama_o_tag_asosiados(&referencias)?;
+
+    Ok((
+        capacidades,
+        commit_head_remoto,
+        commits_cabezas_y_dir_rama_asosiado,
+        commits_y_tags_asosiados,
+    ))
+}
+
 fn separar_capacidades(primera_linea: &str) -> Result<(String, Vec<String>), String> {
     let (contenido, capacidades) = primera_linea
         .split_once('\0')
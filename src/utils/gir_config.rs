@@ -63,10 +63,31 @@ pub fn conseguir_direccion_nombre_remoto() -> Option<String> {
     buscar_en_config_el_valor_de("remoto")
 }
 
+///extrae el certificado en formato pem en el que el cliente confia, ademas de los del sistema,
+/// para conectarse por TLS. Busca una entrada que sea 'certificado_tls=', salvo que este
+/// definida la variable de entorno GIR_CERTIFICADO_TLS, que tiene prioridad (como GIT_SSL_CAINFO)
+pub fn conseguir_certificado_tls() -> Option<String> {
+    std::env::var("GIR_CERTIFICADO_TLS")
+        .ok()
+        .or_else(|| buscar_en_config_el_valor_de("certificado_tls"))
+}
+
 pub fn conseguir_puerto_http() -> Option<String> {
     buscar_en_config_el_valor_de("puerto_http")
 }
 
+///extrae la ventana de compresion delta de los packfiles seteada en el archivo config.
+///Busca una entrada que sea 'ventana_delta='
+pub fn conseguir_ventana_delta() -> Option<String> {
+    buscar_en_config_el_valor_de("ventana_delta")
+}
+
+///extrae la profundidad maxima de las cadenas de deltas de los packfiles seteada en el archivo config.
+///Busca una entrada que sea 'profundidad_delta='
+pub fn conseguir_profundidad_delta() -> Option<String> {
+    buscar_en_config_el_valor_de("profundidad_delta")
+}
+
 ///extrae el repositorio seteada en el archivo config.
 ///Busca una entrada que sea 'repositorio='
 pub fn conseguir_direccion_nombre_repositorio() -> Option<String> {
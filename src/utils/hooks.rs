@@ -0,0 +1,169 @@
+use std::io::Write;
+use std::os::unix::fs::PermissionsExt;
+use std::path::Path;
+use std::process::{Command, Stdio};
+use std::thread;
+
+/// Directorio de los hooks del repositorio local.
+const DIR_HOOKS_LOCAL: &str = ".gir/hooks";
+/// Comienzo del error que devuelve un comando cuando lo corta un hook.
+pub const PREFIJO_ERROR_HOOK: &str = "El hook ";
+
+/// Lo que devolvio un hook al ejecutarse.
+pub struct SalidaHook {
+    /// Si el hook termino con codigo de salida 0.
+    pub exito: bool,
+    /// Lo que el hook escribio en stdout.
+    pub salida: String,
+    /// Lo que el hook escribio en stderr.
+    pub errores: String,
+}
+
+/// Ejecuta el hook `nombre` que esta en `dir_hooks`, con los argumentos dados, escribiendo
+/// `entrada` en su stdin y usando `directorio` como directorio de trabajo.
+/// Si el hook no existe o no es ejecutable no se ejecuta nada y se devuelve None, igual que si
+/// el hook no estuviera configurado.
+pub fn ejecutar_hook(
+    dir_hooks: &Path,
+    nombre: &str,
+    argumentos: &[&str],
+    entrada: &str,
+    directorio: &Path,
+) -> Result<Option<SalidaHook>, String> {
+    let Ok(hook) = dir_hooks.join(nombre).canonicalize() else {
+        return Ok(None);
+    };
+    let es_ejecutable = hook
+        .metadata()
+        .is_ok_and(|metadata| metadata.is_file() && metadata.permissions().mode() & 0o111 != 0);
+    if !es_ejecutable {
+        return Ok(None);
+    }
+
+    let mut proceso = Command::new(&hook)
+        .args(argumentos)
+        .current_dir(directorio)
+        .stdin(Stdio::piped())
+        .stdout(Stdio::piped())
+        .stderr(Stdio::piped())
+        .spawn()
+        .map_err(|e| format!("No se pudo ejecutar el hook {}: {}", nombre, e))?;
+    // la entrada se escribe desde otro thread mientras se leen stdout y stderr, porque si el
+    // hook escribe mucho antes de leer su entrada se bloquearian los dos
+    let escritor = proceso.stdin.take().map(|mut stdin| {
+        let entrada = entrada.to_string();
+        // si el hook termina sin leer la entrada la escritura falla, pero no es un error
+        thread::spawn(move || {
+            let _ = stdin.write_all(entrada.as_bytes());
+        })
+    });
+    let resultado = proceso
+        .wait_with_output()
+        .map_err(|e| format!("Fallo la ejecucion del hook {}: {}", nombre, e))?;
+    if let Some(escritor) = escritor {
+        let _ = escritor.join();
+    }
+
+    Ok(Some(SalidaHook {
+        exito: resultado.status.success(),
+        salida: String::from_utf8_lossy(&resultado.stdout).to_string(),
+        errores: String::from_utf8_lossy(&resultado.stderr).to_string(),
+    }))
+}
+
+/// Ejecuta el hook `nombre` del repositorio local (de `.gir/hooks/`) desde la raiz del
+/// repositorio. Si el hook termina con error devuelve un error con lo que escribio (en stdout y
+/// stderr), para que el comando que lo ejecuto se corte y el usuario vea el motivo.
+pub fn ejecutar_hook_local(nombre: &str, argumentos: &[&str], entrada: &str) -> Result<(), String> {
+    match ejecutar_hook(
+        Path::new(DIR_HOOKS_LOCAL),
+        nombre,
+        argumentos,
+        entrada,
+        Path::new("."),
+    )? {
+        Some(salida) if !salida.exito => {
+            let mut error = format!("{}{} fallo", PREFIJO_ERROR_HOOK, nombre);
+            let escrito = salida.salida + &salida.errores;
+            if !escrito.trim().is_empty() {
+                error.push('\n');
+                error.push_str(escrito.trim_end());
+            }
+            Err(error)
+        }
+        _ => Ok(()),
+    }
+}
+
+/// Ejecuta un hook del repositorio local que corre cuando el comando ya termino (como
+/// post-checkout o post-merge). Como ya no puede cortar el comando, si falla no devuelve un error
+/// sino la advertencia para mostrar junto al resultado.
+pub fn ejecutar_hook_local_posterior(nombre: &str, argumentos: &[&str]) -> Option<String> {
+    ejecutar_hook_local(nombre, argumentos, "").err()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::utils::{io, testing::escribir_hook};
+
+    #[test]
+    fn test01_el_hook_recibe_argumentos_y_entrada_y_se_devuelve_su_salida() {
+        let dir_hooks = "tmp/hooks_test01";
+        let _ = io::rm_directorio(dir_hooks);
+        escribir_hook(dir_hooks, "hook", "#!/bin/sh\necho \"$1\"\ncat\nexit 1\n");
+
+        let salida = ejecutar_hook(
+            Path::new(dir_hooks),
+            "hook",
+            &["argumento"],
+            "entrada\n",
+            Path::new("."),
+        )
+        .unwrap()
+        .unwrap();
+
+        assert!(!salida.exito);
+        assert_eq!(salida.salida, "argumento\nentrada\n");
+    }
+
+    #[test]
+    fn test02_un_hook_que_no_existe_o_no_es_ejecutable_no_se_ejecuta() {
+        let dir_hooks = "tmp/hooks_test02";
+        let _ = io::rm_directorio(dir_hooks);
+        io::escribir_bytes(format!("{}/no-ejecutable", dir_hooks), "#!/bin/sh\n").unwrap();
+
+        let no_ejecutable = ejecutar_hook(
+            Path::new(dir_hooks),
+            "no-ejecutable",
+            &[],
+            "",
+            Path::new("."),
+        );
+        let inexistente =
+            ejecutar_hook(Path::new(dir_hooks), "inexistente", &[], "", Path::new("."));
+
+        assert!(no_ejecutable.unwrap().is_none());
+        assert!(inexistente.unwrap().is_none());
+    }
+
+    #[test]
+    fn test03_se_lee_la_salida_del_hook_mientras_se_escribe_su_entrada() {
+        let dir_hooks = "tmp/hooks_test03";
+        let _ = io::rm_directorio(dir_hooks);
+        escribir_hook(
+            dir_hooks,
+            "hook",
+            "#!/bin/sh\nhead -c 200000 /dev/zero\necho error >&2\ncat > /dev/null\n",
+        );
+        let entrada = "0".repeat(200000);
+
+        let salida = ejecutar_hook(Path::new(dir_hooks), "hook", &[], &entrada, Path::new("."))
+            .unwrap()
+            .unwrap();
+
+        assert!(salida.exito);
+        assert_eq!(salida.salida.len(), 200000);
+        assert_eq!(salida.errores, "error\n");
+    }
+}
@@ -1,24 +1,592 @@
 use std::{
-    collections::HashSet,
-    fs::{self, OpenOptions},
-    io::BufRead,
+    collections::{HashMap, HashSet},
+    fmt::Display,
+    fs,
     path::{Path, PathBuf},
     sync::Arc,
+    time::{SystemTime, UNIX_EPOCH},
 };
 
+use sha1::{Digest, Sha1};
+
 use crate::tipos_de_dato::{
-    comando::Ejecutar, comandos::hash_object::HashObject, logger::Logger, objeto::Objeto,
+    comando::Ejecutar,
+    comandos::hash_object::HashObject,
+    logger::Logger,
+    objeto::Objeto,
+    objetos::{
+        blob::{Blob, ModoBlob},
+        tree::Tree,
+    },
 };
 
-use super::{io, path_buf::obtener_directorio_raiz};
+use super::{compresion::existe_objeto, io, path_buf::obtener_directorio_raiz};
 
 const PATH_INDEX: &str = "./.gir/index";
+const FIRMA_INDEX: &[u8] = b"DIRC";
+const VERSION_INDEX: u32 = 2;
+/// Largo del header (firma, version y cantidad de entradas) y del checksum del index.
+const LARGO_HEADER: usize = 12;
+const LARGO_CHECKSUM: usize = 20;
+/// Extension propia en la que se guardan las entradas de los archivos sacados con `rm`.
+/// Como empieza con mayuscula, git la considera opcional y la ignora al leer el index.
+const FIRMA_EXTENSION_ELIMINADOS: &[u8] = b"GIRD";
+/// Extension propia con el cache de stat de los archivos del directorio de trabajo que no
+/// estan en el index (ver `CacheStat`).
+const FIRMA_EXTENSION_CACHE: &[u8] = b"GIRC";
+/// Stages con los que se guardan las versiones de los archivos que quedaron con conflictos
+/// luego de un merge: la del ancestro comun, la de la rama actual y la de la rama que se mergea.
+const STAGE_BASE: u16 = 1;
+const STAGE_NUESTRA: u16 = 2;
+const STAGE_SUYA: u16 = 3;
 
 #[derive(Debug, Clone)]
 pub struct ObjetoIndex {
     pub merge: bool,
     pub objeto: Objeto,
     pub es_eliminado: bool,
+    /// Metadata del archivo al momento de hashearlo. Si no se conoce queda en su valor por defecto.
+    pub metadata: MetadataIndex,
+    /// Versiones del archivo si quedo con conflictos en un merge. Si es None y el objeto tiene
+    /// conflictos, se guarda el blob del objeto como la version de la rama actual.
+    pub conflicto: Option<VersionesConflicto>,
+}
+
+/// Hashes de las versiones de un archivo con conflictos, que se guardan en el index en los
+/// stages 1 (base), 2 (nuestra) y 3 (suya), como en git. Si el archivo no existe en alguna de
+/// las versiones, esa queda en None.
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct VersionesConflicto {
+    pub base: Option<String>,
+    pub nuestra: Option<String>,
+    pub suya: Option<String>,
+}
+
+impl Display for ObjetoIndex {
+    /// Muestra el objeto index con el formato `[simbolo eliminado] [merge] [modo] [hash] [path]`.
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        let simbolo_eliminado = if self.es_eliminado { "-" } else { "+" };
+        let merge = if self.merge { "1" } else { "0" };
+        match self.objeto {
+            Objeto::Blob(ref blob) => write!(f, "{simbolo_eliminado} {merge} {blob}"),
+            Objeto::Tree(ref tree) => write!(f, "{simbolo_eliminado} {merge} {tree}"),
+        }
+    }
+}
+
+/// Datos del archivo en el directorio de trabajo que se guardan en cada entrada del index.
+/// Los tiempos estan en segundos y nanosegundos desde la epoch, truncados a 32 bits como en git.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct MetadataIndex {
+    pub ctime_seg: u32,
+    pub ctime_nseg: u32,
+    pub mtime_seg: u32,
+    pub mtime_nseg: u32,
+    pub dev: u32,
+    pub ino: u32,
+    pub uid: u32,
+    pub gid: u32,
+    pub tamanio: u32,
+}
+
+impl MetadataIndex {
+    /// Lee la metadata del archivo sin seguir links simbolicos.
+    /// Si el archivo no existe devuelve la metadata vacia, que obliga a volver a hashearlo.
+    pub fn de_archivo(ubicacion: &Path) -> MetadataIndex {
+        let metadata = match fs::symlink_metadata(ubicacion) {
+            Ok(metadata) => metadata,
+            Err(_) => return MetadataIndex::default(),
+        };
+        #[cfg(unix)]
+        {
+            use std::os::unix::fs::MetadataExt;
+            MetadataIndex {
+                ctime_seg: metadata.ctime() as u32,
+                ctime_nseg: metadata.ctime_nsec() as u32,
+                mtime_seg: metadata.mtime() as u32,
+                mtime_nseg: metadata.mtime_nsec() as u32,
+                dev: metadata.dev() as u32,
+                ino: metadata.ino() as u32,
+                uid: metadata.uid(),
+                gid: metadata.gid(),
+                tamanio: metadata.len() as u32,
+            }
+        }
+        #[cfg(not(unix))]
+        {
+            let mtime = metadata
+                .modified()
+                .ok()
+                .and_then(|tiempo| tiempo.duration_since(std::time::UNIX_EPOCH).ok())
+                .unwrap_or_default();
+            MetadataIndex {
+                mtime_seg: mtime.as_secs() as u32,
+                mtime_nseg: mtime.subsec_nanos(),
+                ctime_seg: mtime.as_secs() as u32,
+                ctime_nseg: mtime.subsec_nanos(),
+                tamanio: metadata.len() as u32,
+                ..MetadataIndex::default()
+            }
+        }
+    }
+
+    /// Devuelve true si el archivo sigue teniendo esta metadata, o sea si no hace falta volver
+    /// a hashearlo. Una metadata con tamanio 0 nunca coincide: asi se marcan las entradas racy
+    /// al escribir el index, igual que en git, y los archivos vacios son baratos de hashear.
+    pub fn coincide_con(&self, actual: &MetadataIndex) -> bool {
+        self.tamanio != 0 && self == actual
+    }
+
+    /// Devuelve true si el archivo se modifico en el mismo instante o despues que el index
+    /// (racy timestamp). En ese caso pudo haber cambiado sin que cambie su metadata, por lo que
+    /// hay que volver a hashearlo aunque coincida.
+    pub fn es_racy(&self, mtime_index: (u32, u32)) -> bool {
+        (self.mtime_seg, self.mtime_nseg) >= mtime_index
+    }
+}
+
+/// Entrada del index tal como se guarda en el archivo, siguiendo el formato DIRC version 2 de git.
+struct EntradaIndex {
+    metadata: MetadataIndex,
+    modo: u32,
+    hash: String,
+    stage: u16,
+    path: String,
+}
+
+impl EntradaIndex {
+    /// Arma las entradas correspondientes a un objeto index: una sola si no tiene conflictos, o
+    /// una por cada version si los tiene. Solo los blobs pueden estar en el index.
+    fn from_objeto_index(
+        objeto_index: &ObjetoIndex,
+        metadata: MetadataIndex,
+    ) -> Result<Vec<EntradaIndex>, String> {
+        let blob = match objeto_index.objeto {
+            Objeto::Blob(ref blob) => blob,
+            Objeto::Tree(_) => Err("No se puede escribir un arbol en el index".to_string())?,
+        };
+        if !objeto_index.merge {
+            return Ok(vec![Self::nueva(
+                &blob.ubicacion,
+                blob.modo,
+                &blob.hash,
+                metadata,
+                0,
+            )?]);
+        }
+
+        let versiones = match &objeto_index.conflicto {
+            Some(conflicto) => vec![
+                (STAGE_BASE, &conflicto.base),
+                (STAGE_NUESTRA, &conflicto.nuestra),
+                (STAGE_SUYA, &conflicto.suya),
+            ],
+            None => Vec::new(),
+        };
+        let mut entradas = Vec::new();
+        for (stage, hash) in versiones {
+            if let Some(hash) = hash {
+                entradas.push(Self::nueva(
+                    &blob.ubicacion,
+                    blob.modo,
+                    hash,
+                    metadata,
+                    stage,
+                )?);
+            }
+        }
+        if entradas.is_empty() {
+            entradas.push(Self::nueva(
+                &blob.ubicacion,
+                blob.modo,
+                &blob.hash,
+                metadata,
+                STAGE_NUESTRA,
+            )?);
+        }
+        Ok(entradas)
+    }
+
+    /// Arma la entrada de un archivo con el modo, el hash, la metadata y el stage dados.
+    fn nueva(
+        ubicacion: &Path,
+        modo: ModoBlob,
+        hash: &str,
+        metadata: MetadataIndex,
+        stage: u16,
+    ) -> Result<EntradaIndex, String> {
+        let modo = u32::from_str_radix(modo.como_str(), 8)
+            .map_err(|_| format!("Modo invalido {}", modo))?;
+        Ok(EntradaIndex {
+            metadata,
+            modo,
+            hash: hash.to_string(),
+            stage,
+            path: ubicacion.to_string_lossy().to_string(),
+        })
+    }
+
+    /// Convierte la entrada en un objeto index.
+    fn a_objeto_index(
+        &self,
+        es_eliminado: bool,
+        logger: Arc<Logger>,
+    ) -> Result<ObjetoIndex, String> {
+        Ok(ObjetoIndex {
+            merge: self.stage != 0,
+            es_eliminado,
+            objeto: Objeto::Blob(self.a_blob(logger)?),
+            metadata: self.metadata,
+            conflicto: None,
+        })
+    }
+
+    /// Agrega el hash de la entrada a la version de su stage.
+    fn agregar_a_conflicto(&self, conflicto: &mut VersionesConflicto) {
+        let version = match self.stage {
+            STAGE_BASE => &mut conflicto.base,
+            STAGE_NUESTRA => &mut conflicto.nuestra,
+            _ => &mut conflicto.suya,
+        };
+        *version = Some(self.hash.clone());
+    }
+
+    /// Convierte la entrada en el blob que representa.
+    fn a_blob(&self, logger: Arc<Logger>) -> Result<Blob, String> {
+        let modo = ModoBlob::desde_str(&format!("{:o}", self.modo))
+            .ok_or(format!("Modo no soportado en el index: {:o}", self.modo))?;
+        let ubicacion = PathBuf::from(&self.path);
+        let nombre = match self.path.rsplit('/').next() {
+            Some(nombre) => nombre.to_string(),
+            None => Err("Error al leer el nombre")?,
+        };
+        Ok(Blob {
+            hash: self.hash.clone(),
+            modo,
+            ubicacion,
+            nombre,
+            logger,
+        })
+    }
+
+    /// Devuelve los bytes de la entrada: los datos de stat, el modo, el hash, los flags
+    /// (stage y largo del nombre) y el path, completado con nulls hasta un multiplo de 8.
+    fn serializar(&self) -> Result<Vec<u8>, String> {
+        let metadata = &self.metadata;
+        let mut bytes = Vec::new();
+        for campo in [
+            metadata.ctime_seg,
+            metadata.ctime_nseg,
+            metadata.mtime_seg,
+            metadata.mtime_nseg,
+            metadata.dev,
+            metadata.ino,
+            self.modo,
+            metadata.uid,
+            metadata.gid,
+            metadata.tamanio,
+        ] {
+            bytes.extend(campo.to_be_bytes());
+        }
+        let hash = Tree::decode_hex(&self.hash)?;
+        if hash.len() != 20 {
+            return Err(format!("Hash invalido en el index: {}", self.hash));
+        }
+        bytes.extend(hash);
+        let largo_nombre = self.path.len().min(0xFFF) as u16;
+        bytes.extend(((self.stage << 12) | largo_nombre).to_be_bytes());
+        bytes.extend(self.path.as_bytes());
+        let largo_con_padding = (bytes.len() + 8) & !7;
+        bytes.resize(largo_con_padding, 0);
+        Ok(bytes)
+    }
+
+    /// Lee la entrada que empieza en `offset` y avanza el offset hasta la siguiente.
+    fn deserializar(bytes: &[u8], offset: &mut usize) -> Result<EntradaIndex, String> {
+        let inicio = *offset;
+        let leer_u32 = |posicion: usize| -> Result<u32, String> {
+            let campo = bytes
+                .get(inicio + posicion..inicio + posicion + 4)
+                .ok_or("El index esta truncado")?;
+            Ok(u32::from_be_bytes([campo[0], campo[1], campo[2], campo[3]]))
+        };
+        let metadata = MetadataIndex {
+            ctime_seg: leer_u32(0)?,
+            ctime_nseg: leer_u32(4)?,
+            mtime_seg: leer_u32(8)?,
+            mtime_nseg: leer_u32(12)?,
+            dev: leer_u32(16)?,
+            ino: leer_u32(20)?,
+            uid: leer_u32(28)?,
+            gid: leer_u32(32)?,
+            tamanio: leer_u32(36)?,
+        };
+        let modo = leer_u32(24)?;
+        let hash = bytes
+            .get(inicio + 40..inicio + 60)
+            .ok_or("El index esta truncado")?;
+        let flags = bytes
+            .get(inicio + 60..inicio + 62)
+            .ok_or("El index esta truncado")?;
+        let flags = u16::from_be_bytes([flags[0], flags[1]]);
+
+        let inicio_nombre = inicio + 62;
+        let largo_nombre = match (flags & 0xFFF) as usize {
+            0xFFF => bytes[inicio_nombre..]
+                .iter()
+                .position(|byte| *byte == 0)
+                .ok_or("El index esta truncado")?,
+            largo => largo,
+        };
+        let path = bytes
+            .get(inicio_nombre..inicio_nombre + largo_nombre)
+            .ok_or("El index esta truncado")?;
+
+        *offset = inicio + ((62 + largo_nombre + 8) & !7);
+        Ok(EntradaIndex {
+            metadata,
+            modo,
+            hash: Tree::encode_hex(hash),
+            stage: (flags >> 12) & 0x3,
+            path: String::from_utf8_lossy(path).to_string(),
+        })
+    }
+}
+
+/// Contenido de un archivo index binario: las entradas comunes, las de los archivos eliminados
+/// y las del cache de stat.
+#[derive(Default)]
+struct ArchivoIndex {
+    entradas: Vec<EntradaIndex>,
+    eliminadas: Vec<EntradaIndex>,
+    cache: Vec<EntradaIndex>,
+}
+
+impl ArchivoIndex {
+    /// Lee el archivo index si existe y esta en formato binario.
+    /// En cualquier otro caso devuelve un index vacio.
+    fn leer_si_existe() -> ArchivoIndex {
+        match fs::read(PATH_INDEX) {
+            Ok(bytes) if bytes.starts_with(FIRMA_INDEX) => {
+                Self::deserializar(&bytes).unwrap_or_default()
+            }
+            _ => ArchivoIndex::default(),
+        }
+    }
+
+    /// Lee un index en formato DIRC version 2, verificando antes su checksum.
+    fn deserializar(bytes: &[u8]) -> Result<ArchivoIndex, String> {
+        if bytes.len() < LARGO_HEADER + LARGO_CHECKSUM {
+            return Err("El index esta truncado".to_string());
+        }
+        let (contenido, checksum) = bytes.split_at(bytes.len() - LARGO_CHECKSUM);
+        if Sha1::digest(contenido).as_slice() != checksum {
+            return Err("El checksum del index es invalido".to_string());
+        }
+        let version = u32::from_be_bytes([contenido[4], contenido[5], contenido[6], contenido[7]]);
+        if version != VERSION_INDEX {
+            return Err(format!("Version de index {} no soportada", version));
+        }
+        let cantidad =
+            u32::from_be_bytes([contenido[8], contenido[9], contenido[10], contenido[11]]);
+
+        let mut archivo = ArchivoIndex::default();
+        let mut offset = LARGO_HEADER;
+        for _ in 0..cantidad {
+            archivo
+                .entradas
+                .push(EntradaIndex::deserializar(contenido, &mut offset)?);
+        }
+
+        while offset + 8 <= contenido.len() {
+            let firma = &contenido[offset..offset + 4];
+            let largo = u32::from_be_bytes([
+                contenido[offset + 4],
+                contenido[offset + 5],
+                contenido[offset + 6],
+                contenido[offset + 7],
+            ]) as usize;
+            let datos = contenido
+                .get(offset + 8..offset + 8 + largo)
+                .ok_or("El index esta truncado")?;
+            let destino = if firma == FIRMA_EXTENSION_ELIMINADOS {
+                Some(&mut archivo.eliminadas)
+            } else if firma == FIRMA_EXTENSION_CACHE {
+                Some(&mut archivo.cache)
+            } else if firma[0].is_ascii_uppercase() {
+                None
+            } else {
+                return Err(format!(
+                    "Extension del index no soportada: {}",
+                    String::from_utf8_lossy(firma)
+                ));
+            };
+            if let Some(destino) = destino {
+                let mut offset_datos = 0;
+                while offset_datos < datos.len() {
+                    destino.push(EntradaIndex::deserializar(datos, &mut offset_datos)?);
+                }
+            }
+            offset += 8 + largo;
+        }
+        Ok(archivo)
+    }
+
+    /// Escribe el archivo index en formato DIRC version 2.
+    /// Las entradas comunes van ordenadas por path y stage, las eliminadas y el cache van en
+    /// extensiones y al final se agrega el sha1 de todo el contenido.
+    /// Las entradas modificadas en el mismo segundo en que se escribe el index son racy: se les
+    /// pone tamanio 0 para que la proxima vez se vuelvan a hashear.
+    fn escribir(mut self) -> Result<(), String> {
+        let ahora = SystemTime::now()
+            .duration_since(UNIX_EPOCH)
+            .map(|tiempo| tiempo.as_secs() as u32)
+            .unwrap_or(0);
+        for entrada in self.entradas.iter_mut().chain(self.cache.iter_mut()) {
+            if entrada.metadata.mtime_seg >= ahora {
+                entrada.metadata.tamanio = 0;
+            }
+        }
+        self.entradas
+            .sort_by(|a, b| (a.path.as_bytes(), a.stage).cmp(&(b.path.as_bytes(), b.stage)));
+        self.eliminadas
+            .sort_by(|a, b| a.path.as_bytes().cmp(b.path.as_bytes()));
+        self.cache
+            .sort_by(|a, b| a.path.as_bytes().cmp(b.path.as_bytes()));
+
+        let mut contenido = Vec::new();
+        contenido.extend(FIRMA_INDEX);
+        contenido.extend(VERSION_INDEX.to_be_bytes());
+        contenido.extend((self.entradas.len() as u32).to_be_bytes());
+        for entrada in &self.entradas {
+            contenido.extend(entrada.serializar()?);
+        }
+        for (firma, entradas) in [
+            (FIRMA_EXTENSION_ELIMINADOS, &self.eliminadas),
+            (FIRMA_EXTENSION_CACHE, &self.cache),
+        ] {
+            if entradas.is_empty() {
+                continue;
+            }
+            let mut datos = Vec::new();
+            for entrada in entradas {
+                datos.extend(entrada.serializar()?);
+            }
+            contenido.extend(firma);
+            contenido.extend((datos.len() as u32).to_be_bytes());
+            contenido.extend(datos);
+        }
+        let checksum = Sha1::digest(&contenido);
+        contenido.extend(checksum.as_slice());
+
+        io::escribir_bytes(PATH_INDEX, contenido)
+    }
+}
+
+/// Devuelve el mtime del archivo index, o el maximo posible si no se puede leer, de forma que
+/// todas las entradas se consideren racy.
+fn obtener_mtime_index() -> (u32, u32) {
+    fs::metadata(PATH_INDEX)
+        .and_then(|metadata| metadata.modified())
+        .ok()
+        .and_then(|tiempo| tiempo.duration_since(UNIX_EPOCH).ok())
+        .map(|tiempo| (tiempo.as_secs() as u32, tiempo.subsec_nanos()))
+        .unwrap_or((u32::MAX, u32::MAX))
+}
+
+/// Cache con el hash de los archivos del directorio de trabajo junto con la metadata que tenian
+/// al hashearlos. Mientras la metadata de un archivo no cambie no hace falta volver a hashearlo.
+/// Se arma con las entradas del index y con la extension de cache, que guarda los archivos que
+/// no estan en el index (por ejemplo los ya commiteados).
+pub struct CacheStat {
+    entradas: HashMap<PathBuf, (ModoBlob, MetadataIndex, String)>,
+    mtime_index: (u32, u32),
+    modificado: bool,
+}
+
+impl CacheStat {
+    /// Lee el cache del archivo index. Si no existe o no se puede leer, devuelve un cache vacio.
+    pub fn leer() -> CacheStat {
+        let archivo = ArchivoIndex::leer_si_existe();
+        let mut entradas = HashMap::new();
+        let validas = archivo
+            .cache
+            .iter()
+            .chain(archivo.entradas.iter().filter(|entrada| entrada.stage == 0));
+        for entrada in validas {
+            let modo = match ModoBlob::desde_str(&format!("{:o}", entrada.modo)) {
+                Some(modo) => modo,
+                None => continue,
+            };
+            entradas.insert(
+                PathBuf::from(&entrada.path),
+                (modo, entrada.metadata, entrada.hash.clone()),
+            );
+        }
+        CacheStat {
+            entradas,
+            mtime_index: obtener_mtime_index(),
+            modificado: false,
+        }
+    }
+
+    /// Devuelve el hash guardado para el archivo si su modo y su metadata no cambiaron desde
+    /// que se hasheo y no es racy.
+    pub fn obtener_hash(
+        &self,
+        ubicacion: &Path,
+        modo: ModoBlob,
+        metadata: &MetadataIndex,
+    ) -> Option<String> {
+        let (modo_guardado, metadata_guardada, hash) = self.entradas.get(ubicacion)?;
+        if *modo_guardado != modo
+            || !metadata_guardada.coincide_con(metadata)
+            || metadata_guardada.es_racy(self.mtime_index)
+        {
+            return None;
+        }
+        Some(hash.clone())
+    }
+
+    /// Guarda en el cache el hash de un archivo junto con la metadata que tenia antes de hashearlo.
+    /// Si el cache ya tenia exactamente esa entrada (por ejemplo un archivo racy que se volvio a
+    /// hashear), no se marca como modificado.
+    pub fn registrar(
+        &mut self,
+        ubicacion: PathBuf,
+        modo: ModoBlob,
+        metadata: MetadataIndex,
+        hash: String,
+    ) {
+        let entrada = (modo, metadata, hash);
+        if self.entradas.get(&ubicacion) == Some(&entrada) {
+            return;
+        }
+        self.entradas.insert(ubicacion, entrada);
+        self.modificado = true;
+    }
+
+    /// Escribe el cache en el archivo index, sin tocar sus entradas.
+    /// Solo escribe si se registro algun hash nuevo y si el repositorio existe.
+    pub fn guardar(&self) -> Result<(), String> {
+        if !self.modificado || !Path::new(".gir").is_dir() {
+            return Ok(());
+        }
+        let mut archivo = match fs::read(PATH_INDEX) {
+            Ok(bytes) if bytes.starts_with(FIRMA_INDEX) => ArchivoIndex::deserializar(&bytes)?,
+            Ok(bytes) if !bytes.is_empty() => return Ok(()),
+            _ => ArchivoIndex::default(),
+        };
+        let mut cache = Vec::new();
+        for (ubicacion, (modo, metadata, hash)) in &self.entradas {
+            if fs::symlink_metadata(ubicacion).is_err() {
+                continue;
+            }
+            cache.push(EntradaIndex::nueva(ubicacion, *modo, hash, *metadata, 0)?);
+        }
+        archivo.cache = cache;
+        archivo.escribir()
+    }
 }
 
 pub fn crear_index() {
@@ -31,39 +599,147 @@ pub fn crear_index() {
 //Devuelve true si el index esta vacio y false en caso contrario.
 //Si falla se presupone que es porque no existe y por lo tanto esta vacio
 pub fn esta_vacio_el_index() -> Result<bool, String> {
-    Ok(io::esta_vacio(PATH_INDEX))
+    let contenido = match fs::read(PATH_INDEX) {
+        Ok(contenido) => contenido,
+        Err(_) => return Ok(true),
+    };
+    if !contenido.starts_with(FIRMA_INDEX) {
+        return Ok(contenido.is_empty());
+    }
+    let archivo = ArchivoIndex::deserializar(&contenido)?;
+    Ok(archivo.entradas.is_empty() && archivo.eliminadas.is_empty())
 }
 
 /// Lee el archivo index y devuelve un vector de objetos index.
 /// Por cada entrie que lee crea su respectivo objeto index.
 /// Si el archivo index no existe, devuelve un vector vacio.
+/// Si el index esta en el formato de texto anterior, lo migra al formato binario.
 pub fn leer_index(logger: Arc<Logger>) -> Result<Vec<ObjetoIndex>, String> {
     if !PathBuf::from(PATH_INDEX).exists() {
         return Ok(Vec::new());
     }
-    let file = match OpenOptions::new().read(true).open(PATH_INDEX) {
-        Ok(file) => file,
-        Err(_) => return Err("No se pudo abrir el archivo index".to_string()),
+    let contenido = io::leer_bytes(PATH_INDEX)?;
+    if contenido.is_empty() {
+        return Ok(Vec::new());
+    }
+    if !contenido.starts_with(FIRMA_INDEX) {
+        let objetos = leer_index_de_texto(&String::from_utf8_lossy(&contenido), logger.clone())?;
+        armar_archivo_index(&objetos, ArchivoIndex::default().cache)?.escribir()?;
+        logger.log("Se migro el index del formato de texto al formato binario");
+        return Ok(objetos);
+    }
+
+    let archivo = ArchivoIndex::deserializar(&contenido)?;
+    let mut objetos: Vec<ObjetoIndex> = Vec::new();
+    for entrada in &archivo.entradas {
+        if entrada.stage == 0 {
+            objetos.push(entrada.a_objeto_index(false, logger.clone())?);
+            continue;
+        }
+        // las entradas estan ordenadas por path y stage, asi que las versiones de un archivo
+        // con conflictos quedan juntas y se arma un solo objeto con todas
+        let es_mismo_conflicto = objetos.last().is_some_and(|ultimo| {
+            ultimo.conflicto.is_some() && ultimo.objeto.obtener_path() == Path::new(&entrada.path)
+        });
+        if !es_mismo_conflicto {
+            let mut objeto_index = entrada.a_objeto_index(false, logger.clone())?;
+            objeto_index.conflicto = Some(VersionesConflicto::default());
+            objetos.push(objeto_index);
+        }
+        if let Some(objeto_index) = objetos.last_mut() {
+            // el objeto del archivo es la version de la rama actual, si la tiene
+            if entrada.stage == STAGE_NUESTRA {
+                objeto_index.objeto = Objeto::Blob(entrada.a_blob(logger.clone())?);
+            }
+            if let Some(conflicto) = objeto_index.conflicto.as_mut() {
+                entrada.agregar_a_conflicto(conflicto);
+            }
+        }
+    }
+    for entrada in &archivo.eliminadas {
+        objetos.push(entrada.a_objeto_index(true, logger.clone())?);
+    }
+    Ok(objetos)
+}
+
+/// Devuelve los hashes de los blobs del index del repositorio ubicado en `dir_repositorio`, con
+/// todas las versiones de los archivos con conflictos. No incluye los gitlinks ni los archivos
+/// eliminados. Si el index no existe devuelve un vector vacio.
+pub fn obtener_hashes_del_index_de(dir_repositorio: &Path) -> Result<Vec<String>, String> {
+    let contenido = match fs::read(dir_repositorio.join(".gir/index")) {
+        Ok(contenido) => contenido,
+        Err(_) => return Ok(Vec::new()),
     };
+    let gitlink = ModoBlob::Gitlink.como_str();
+    if contenido.starts_with(FIRMA_INDEX) {
+        let archivo = ArchivoIndex::deserializar(&contenido)?;
+        return Ok(archivo
+            .entradas
+            .into_iter()
+            .filter(|entrada| format!("{:o}", entrada.modo) != gitlink)
+            .map(|entrada| entrada.hash)
+            .collect());
+    }
 
+    // formato de texto anterior: `[simbolo eliminado] [merge] [modo] [hash] [path]`
+    let mut hashes = Vec::new();
+    for linea in String::from_utf8_lossy(&contenido).lines() {
+        let (Some(metadata), Some(objeto)) = (linea.get(..4), linea.get(4..)) else {
+            continue;
+        };
+        let mut campos = objeto.split_whitespace();
+        if metadata.get(..2).map(str::trim) == Some("-") || campos.next() == Some(gitlink) {
+            continue;
+        }
+        hashes.extend(campos.next().map(str::to_string));
+    }
+    Ok(hashes)
+}
+
+/// Lee un index en el formato de texto que se usaba antes del binario, donde cada linea es
+/// `[simbolo eliminado] [merge] [modo] [hash] [path]`.
+fn leer_index_de_texto(contenido: &str, logger: Arc<Logger>) -> Result<Vec<ObjetoIndex>, String> {
     let mut objetos: Vec<ObjetoIndex> = Vec::new();
 
-    for line in std::io::BufReader::new(file).lines() {
-        if let Ok(line) = line.as_ref() {
-            let (metadata, line) = line.split_at(4);
-            let (simbolo_eliminado, merge) = metadata.split_at(2);
-            let objeto = Objeto::from_index(line, logger.clone())?;
-            let objeto_index = ObjetoIndex {
-                merge: merge.trim() == "1",
-                es_eliminado: simbolo_eliminado.trim() == "-",
-                objeto,
-            };
-            objetos.push(objeto_index);
+    for line in contenido.lines() {
+        if line.len() < 4 {
+            continue;
         }
+        let (metadata, line) = line.split_at(4);
+        let (simbolo_eliminado, merge) = metadata.split_at(2);
+        let objeto = Objeto::from_index(line, logger.clone())?;
+        let objeto_index = ObjetoIndex {
+            merge: merge.trim() == "1",
+            es_eliminado: simbolo_eliminado.trim() == "-",
+            objeto,
+            metadata: MetadataIndex::default(),
+            conflicto: None,
+        };
+        objetos.push(objeto_index);
     }
     Ok(objetos)
 }
 
+/// Arma el contenido del archivo index a partir de los objetos index y del cache de stat.
+fn armar_archivo_index(
+    objetos_index: &[ObjetoIndex],
+    cache: Vec<EntradaIndex>,
+) -> Result<ArchivoIndex, String> {
+    let mut archivo = ArchivoIndex {
+        cache,
+        ..ArchivoIndex::default()
+    };
+    for objeto_index in objetos_index {
+        let entradas = EntradaIndex::from_objeto_index(objeto_index, objeto_index.metadata)?;
+        if objeto_index.es_eliminado {
+            archivo.eliminadas.extend(entradas);
+        } else {
+            archivo.entradas.extend(entradas);
+        }
+    }
+    Ok(archivo)
+}
+
 /// Devuelve un vector de objetos raiz a partir de un vector de objetos index.
 /// Si un objeto index esta marcado como eliminado, no se agrega al vector de objetos raiz.
 /// Cabe recalcar que estos objetos index quedan ordenados por su path.
@@ -74,6 +750,7 @@ pub fn generar_objetos_raiz(
     let mut objetos_raiz: Vec<Objeto> = Vec::new();
     let mut directorios_raiz: HashSet<PathBuf> = HashSet::new();
     let mut directorios_a_tener_en_cuenta: Vec<PathBuf> = Vec::new();
+    let mut gitlinks: Vec<Blob> = Vec::new();
 
     for objeto_index in objetos_index {
         if objeto_index.es_eliminado {
@@ -82,6 +759,9 @@ pub fn generar_objetos_raiz(
 
         match objeto_index.objeto {
             Objeto::Blob(ref blob) => {
+                if blob.modo == ModoBlob::Gitlink {
+                    gitlinks.push(blob.clone());
+                }
                 directorios_a_tener_en_cuenta.push(blob.ubicacion.clone());
                 let padre = obtener_directorio_raiz(&blob.ubicacion)?;
                 directorios_raiz.insert(PathBuf::from(padre));
@@ -91,6 +771,12 @@ pub fn generar_objetos_raiz(
                     continue;
                 }
                 directorios_a_tener_en_cuenta.extend(tree.obtener_paths_hijos());
+                gitlinks.extend(tree.obtener_objetos().into_iter().filter_map(
+                    |objeto| match objeto {
+                        Objeto::Blob(blob) if blob.modo == ModoBlob::Gitlink => Some(blob),
+                        _ => None,
+                    },
+                ));
                 let padre = obtener_directorio_raiz(&tree.directorio)?;
                 directorios_raiz.insert(PathBuf::from(padre));
             }
@@ -98,11 +784,22 @@ pub fn generar_objetos_raiz(
     }
 
     for directorio in directorios_raiz {
-        let objeto_conteniendo_al_blob = Objeto::from_directorio(
+        if let Some(gitlink) = gitlinks
+            .iter()
+            .find(|gitlink| gitlink.ubicacion == directorio)
+        {
+            objetos_raiz.push(Objeto::Blob(gitlink.clone()));
+            continue;
+        }
+        let mut objeto_conteniendo_al_blob = Objeto::from_directorio(
             directorio.clone(),
             Some(&directorios_a_tener_en_cuenta),
+            None,
             logger.clone(),
         )?;
+        if let Objeto::Tree(ref mut tree) = objeto_conteniendo_al_blob {
+            tree.conservar_gitlinks(&gitlinks);
+        }
 
         objetos_raiz.push(objeto_conteniendo_al_blob);
     }
@@ -114,54 +811,69 @@ pub fn generar_objetos_raiz(
     Ok(objetos_raiz)
 }
 
-/// Escribe los objetos index en el archivo index.
-/// Los escribe siguiendo el siguiente formato:
-/// [simbolo eliminado] [merge] [modo] [hash] [path]
+/// Escribe los objetos index en el archivo index, en el formato binario DIRC version 2 de git.
+/// Antes de escribir cada blob lo guarda en la base de objetos, salvo que su metadata no haya
+/// cambiado desde que se guardo. Si el archivo sigue teniendo el contenido del blob y no cambio
+/// mientras se hasheaba se guarda su metadata, para poder detectar cambios sin volver a
+/// hashearlo. Los archivos con conflictos se guardan sin metadata, porque sus versiones no son
+/// el contenido del archivo.
+/// El cache de stat que hubiera en el index se conserva.
 /// Si el archivo index no existe, lo crea.
 pub fn escribir_index(
     logger: Arc<Logger>,
-    objetos_index: &mut Vec<ObjetoIndex>,
+    objetos_index: &mut [ObjetoIndex],
 ) -> Result<(), String> {
-    let mut buffer = String::new();
+    let mtime_index = obtener_mtime_index();
 
     objetos_index.sort_by_key(|objeto_index| objeto_index.objeto.obtener_path());
 
-    for objeto_index in objetos_index {
-        let line = match objeto_index.objeto {
-            Objeto::Blob(ref blob) => {
-                if !objeto_index.es_eliminado {
-                    HashObject {
-                        logger: logger.clone(),
-                        escribir: true,
-                        ubicacion_archivo: blob.ubicacion.clone(),
-                    }
-                    .ejecutar()?;
-                }
-                let simbolo_eliminado = if objeto_index.es_eliminado { "-" } else { "+" };
-                let merge = if objeto_index.merge { "1" } else { "0" };
-                format!("{simbolo_eliminado} {merge} {blob}")
-            }
+    for objeto_index in objetos_index.iter_mut() {
+        let blob = match objeto_index.objeto {
+            Objeto::Blob(ref blob) => blob,
             Objeto::Tree(_) => Err("No se puede escribir un arbol en el index".to_string())?,
         };
-
-        buffer.push_str(&line);
+        if objeto_index.es_eliminado || blob.modo == ModoBlob::Gitlink {
+            continue;
+        }
+        let metadata_actual = MetadataIndex::de_archivo(&blob.ubicacion);
+        if !objeto_index.merge
+            && objeto_index.metadata.coincide_con(&metadata_actual)
+            && !objeto_index.metadata.es_racy(mtime_index)
+            && existe_objeto(&blob.hash, ".gir/objects/")
+        {
+            continue;
+        }
+        let hash = HashObject {
+            logger: logger.clone(),
+            escribir: true,
+            ubicacion_archivo: blob.ubicacion.clone(),
+        }
+        .ejecutar()?;
+        let describe_al_blob = !objeto_index.merge
+            && hash == blob.hash
+            && MetadataIndex::de_archivo(&blob.ubicacion) == metadata_actual;
+        objeto_index.metadata = if describe_al_blob {
+            metadata_actual
+        } else {
+            MetadataIndex::default()
+        };
     }
 
-    io::escribir_bytes(PATH_INDEX, buffer)?;
-    Ok(())
+    let cache = ArchivoIndex::leer_si_existe().cache;
+    armar_archivo_index(objetos_index, cache)?.escribir()
 }
 
-/// Limpia el contenido del archivo index.
+/// Limpia el contenido del archivo index, conservando el cache de stat.
 pub fn limpiar_archivo_index() -> Result<(), String> {
-    let _ = match OpenOptions::new()
-        .write(true)
-        .truncate(true)
-        .open("./.gir/index")
-    {
-        Ok(archivo) => archivo,
-        Err(_) => return Err("No se pudo abrir el archivo index".to_string()),
-    };
-    Ok(())
+    if !Path::new(PATH_INDEX).exists() {
+        return Err("No se pudo abrir el archivo index".to_string());
+    }
+    let cache = ArchivoIndex::leer_si_existe().cache;
+    ArchivoIndex {
+        cache,
+        ..ArchivoIndex::default()
+    }
+    .escribir()
 }
 
 pub fn hay_archivos_con_conflictos(logger: Arc<Logger>) -> bool {
@@ -176,3 +888,293 @@ pub fn hay_archivos_con_conflictos(logger: Arc<Logger>) -> bool {
     }
     false
 }
+
+#[cfg(test)]
+mod tests {
+    use serial_test::serial;
+    use std::{path::PathBuf, sync::Arc};
+
+    use crate::{
+        tipos_de_dato::{comando::Ejecutar, comandos::add::Add, logger::Logger},
+        utils::testing::limpiar_archivo_gir,
+    };
+
+    use super::*;
+
+    #[test]
+    #[serial]
+    fn test01_el_index_se_escribe_en_formato_dirc_y_se_vuelve_a_leer() {
+        let logger = Arc::new(Logger::new(PathBuf::from("tmp/index_test01")).unwrap());
+        limpiar_archivo_gir(logger.clone());
+        io::escribir_bytes("tmp/index_test01_a", "contenido a").unwrap();
+        io::escribir_bytes("tmp/index_test01_b", "contenido b").unwrap();
+        Add::from(
+            vec![
+                "tmp/index_test01_a".to_string(),
+                "tmp/index_test01_b".to_string(),
+            ],
+            logger.clone(),
+        )
+        .unwrap()
+        .ejecutar()
+        .unwrap();
+
+        let mut objetos_index = leer_index(logger.clone()).unwrap();
+        objetos_index[0].merge = true;
+        objetos_index[1].es_eliminado = true;
+        escribir_index(logger.clone(), &mut objetos_index).unwrap();
+
+        let contenido = io::leer_bytes(PATH_INDEX).unwrap();
+        assert_eq!(&contenido[..4], b"DIRC");
+        assert_eq!(&contenido[4..8], &2u32.to_be_bytes());
+        assert_eq!(&contenido[8..12], &1u32.to_be_bytes());
+        let (datos, checksum) = contenido.split_at(contenido.len() - 20);
+        assert_eq!(Sha1::digest(datos).as_slice(), checksum);
+
+        let leidos = leer_index(logger).unwrap();
+        assert_eq!(leidos.len(), 2);
+        assert!(leidos[0].merge && !leidos[0].es_eliminado);
+        assert!(!leidos[1].merge && leidos[1].es_eliminado);
+        assert_eq!(leidos[0].objeto, objetos_index[0].objeto);
+        assert_eq!(leidos[1].objeto, objetos_index[1].objeto);
+    }
+
+    #[test]
+    #[serial]
+    fn test02_un_index_de_texto_se_migra_al_formato_binario() {
+        let logger = Arc::new(Logger::new(PathBuf::from("tmp/index_test02")).unwrap());
+        limpiar_archivo_gir(logger.clone());
+        io::escribir_bytes(
+            PATH_INDEX,
+            "+ 0 100644 678e12dc5c03a7cf6e9f64e688868962ab5d8b65 test_file.txt\n- 0 100755 2b824e648965b94c6c6b3dd0702feb91f699ed62 script.sh\n",
+        )
+        .unwrap();
+
+        let objetos_index = leer_index(logger.clone()).unwrap();
+        assert_eq!(objetos_index.len(), 2);
+        assert!(io::leer_bytes(PATH_INDEX).unwrap().starts_with(b"DIRC"));
+
+        let texto = leer_index(logger)
+            .unwrap()
+            .iter()
+            .map(|objeto_index| objeto_index.to_string())
+            .collect::<String>();
+        assert_eq!(
+            texto,
+            "+ 0 100644 678e12dc5c03a7cf6e9f64e688868962ab5d8b65 test_file.txt\n- 0 100755 2b824e648965b94c6c6b3dd0702feb91f699ed62 script.sh\n"
+        );
+    }
+
+    #[test]
+    #[serial]
+    fn test03_un_index_con_checksum_invalido_da_error() {
+        let logger = Arc::new(Logger::new(PathBuf::from("tmp/index_test03")).unwrap());
+        limpiar_archivo_gir(logger.clone());
+        io::escribir_bytes("tmp/index_test03", "contenido").unwrap();
+        Add::from(vec!["tmp/index_test03".to_string()], logger.clone())
+            .unwrap()
+            .ejecutar()
+            .unwrap();
+
+        let mut contenido = io::leer_bytes(PATH_INDEX).unwrap();
+        let ultimo = contenido.len() - 1;
+        contenido[ultimo] ^= 0xFF;
+        io::escribir_bytes(PATH_INDEX, contenido).unwrap();
+
+        assert!(leer_index(logger).is_err());
+    }
+
+    #[test]
+    #[serial]
+    fn test04_un_index_sin_entradas_esta_vacio() {
+        let logger = Arc::new(Logger::new(PathBuf::from("tmp/index_test04")).unwrap());
+        limpiar_archivo_gir(logger.clone());
+        escribir_index(logger, &mut Vec::new()).unwrap();
+
+        assert!(esta_vacio_el_index().unwrap());
+    }
+
+    const HASH_TEST: &str = "8c536261d19b1ceb2fa6998c5e6c779215a093e2";
+
+    fn atrasar_mtime(ubicacion: &str) {
+        fs::File::options()
+            .write(true)
+            .open(ubicacion)
+            .unwrap()
+            .set_modified(SystemTime::now() - std::time::Duration::from_secs(10))
+            .unwrap();
+    }
+
+    #[test]
+    #[serial]
+    fn test05_el_cache_de_stat_se_guarda_y_evita_rehashear() {
+        let logger = Arc::new(Logger::new(PathBuf::from("tmp/index_test05")).unwrap());
+        limpiar_archivo_gir(logger.clone());
+        io::escribir_bytes("tmp/index_test05_archivo", "contenido").unwrap();
+        atrasar_mtime("tmp/index_test05_archivo");
+        let ubicacion = PathBuf::from("tmp/index_test05_archivo");
+        let metadata = MetadataIndex::de_archivo(&ubicacion);
+
+        let mut cache = CacheStat::leer();
+        cache.registrar(
+            ubicacion.clone(),
+            ModoBlob::Normal,
+            metadata,
+            HASH_TEST.to_string(),
+        );
+        cache.guardar().unwrap();
+
+        let cache = CacheStat::leer();
+        assert_eq!(
+            cache.obtener_hash(&ubicacion, ModoBlob::Normal, &metadata),
+            Some(HASH_TEST.to_string())
+        );
+        assert_eq!(
+            cache.obtener_hash(&ubicacion, ModoBlob::Ejecutable, &metadata),
+            None
+        );
+        assert!(esta_vacio_el_index().unwrap());
+    }
+
+    #[test]
+    #[serial]
+    fn test06_un_archivo_modificado_al_escribir_el_index_es_racy_y_se_rehashea() {
+        let logger = Arc::new(Logger::new(PathBuf::from("tmp/index_test06")).unwrap());
+        limpiar_archivo_gir(logger.clone());
+        io::escribir_bytes("tmp/index_test06_archivo", "contenido").unwrap();
+        let ubicacion = PathBuf::from("tmp/index_test06_archivo");
+        let metadata = MetadataIndex::de_archivo(&ubicacion);
+
+        let mut cache = CacheStat::leer();
+        cache.registrar(
+            ubicacion.clone(),
+            ModoBlob::Normal,
+            metadata,
+            HASH_TEST.to_string(),
+        );
+        cache.guardar().unwrap();
+
+        let cache = CacheStat::leer();
+        assert_eq!(
+            cache.obtener_hash(&ubicacion, ModoBlob::Normal, &metadata),
+            None
+        );
+    }
+
+    #[test]
+    #[serial]
+    fn test07_limpiar_el_index_conserva_el_cache_de_stat() {
+        let logger = Arc::new(Logger::new(PathBuf::from("tmp/index_test07")).unwrap());
+        limpiar_archivo_gir(logger.clone());
+        io::escribir_bytes("tmp/index_test07_archivo", "contenido").unwrap();
+        atrasar_mtime("tmp/index_test07_archivo");
+        Add::from(vec!["tmp/index_test07_archivo".to_string()], logger.clone())
+            .unwrap()
+            .ejecutar()
+            .unwrap();
+        let ubicacion = PathBuf::from("tmp/index_test07_archivo");
+        let metadata = MetadataIndex::de_archivo(&ubicacion);
+        let hash = leer_index(logger).unwrap()[0].objeto.obtener_hash();
+
+        limpiar_archivo_index().unwrap();
+
+        assert!(esta_vacio_el_index().unwrap());
+        assert_eq!(
+            CacheStat::leer().obtener_hash(&ubicacion, ModoBlob::Normal, &metadata),
+            Some(hash)
+        );
+    }
+
+    #[test]
+    #[serial]
+    fn test08_un_archivo_con_conflictos_guarda_sus_tres_versiones() {
+        let logger = Arc::new(Logger::new(PathBuf::from("tmp/index_test08")).unwrap());
+        limpiar_archivo_gir(logger.clone());
+        io::escribir_bytes("tmp/index_test08", "contenido").unwrap();
+        Add::from(vec!["tmp/index_test08".to_string()], logger.clone())
+            .unwrap()
+            .ejecutar()
+            .unwrap();
+        let versiones = VersionesConflicto {
+            base: Some("1".repeat(40)),
+            nuestra: Some("2".repeat(40)),
+            suya: Some("3".repeat(40)),
+        };
+
+        let mut objetos_index = leer_index(logger.clone()).unwrap();
+        objetos_index[0].merge = true;
+        objetos_index[0].conflicto = Some(versiones.clone());
+        escribir_index(logger.clone(), &mut objetos_index).unwrap();
+
+        let contenido = io::leer_bytes(PATH_INDEX).unwrap();
+        let archivo = ArchivoIndex::deserializar(&contenido).unwrap();
+        let stages: Vec<u16> = archivo
+            .entradas
+            .iter()
+            .map(|entrada| entrada.stage)
+            .collect();
+        assert_eq!(stages, vec![1, 2, 3]);
+
+        let leidos = leer_index(logger).unwrap();
+        assert_eq!(leidos.len(), 1);
+        assert!(leidos[0].merge);
+        assert_eq!(leidos[0].conflicto, Some(versiones));
+        assert_eq!(leidos[0].objeto.obtener_hash(), "2".repeat(40));
+    }
+
+    #[test]
+    #[serial]
+    fn test09_registrar_una_entrada_que_no_cambio_no_modifica_el_cache() {
+        let logger = Arc::new(Logger::new(PathBuf::from("tmp/index_test09")).unwrap());
+        limpiar_archivo_gir(logger.clone());
+        io::escribir_bytes("tmp/index_test09_archivo", "contenido").unwrap();
+        atrasar_mtime("tmp/index_test09_archivo");
+        let ubicacion = PathBuf::from("tmp/index_test09_archivo");
+        let metadata = MetadataIndex::de_archivo(&ubicacion);
+
+        let mut cache = CacheStat::leer();
+        cache.registrar(
+            ubicacion.clone(),
+            ModoBlob::Normal,
+            metadata,
+            HASH_TEST.to_string(),
+        );
+        assert!(cache.modificado);
+        cache.guardar().unwrap();
+
+        let mut cache = CacheStat::leer();
+        cache.registrar(ubicacion, ModoBlob::Normal, metadata, HASH_TEST.to_string());
+        assert!(!cache.modificado);
+    }
+
+    #[test]
+    #[serial]
+    fn test10_las_versiones_de_un_conflicto_se_guardan_sin_la_metadata_del_archivo() {
+        let logger = Arc::new(Logger::new(PathBuf::from("tmp/index_test10")).unwrap());
+        limpiar_archivo_gir(logger.clone());
+        io::escribir_bytes("tmp/index_test10", "contenido").unwrap();
+        atrasar_mtime("tmp/index_test10");
+        Add::from(vec!["tmp/index_test10".to_string()], logger.clone())
+            .unwrap()
+            .ejecutar()
+            .unwrap();
+        let hash = HashObject::hashear_contenido_objeto(&b"blob 9\0contenido".to_vec());
+
+        let mut objetos_index = leer_index(logger.clone()).unwrap();
+        objetos_index[0].merge = true;
+        objetos_index[0].conflicto = Some(VersionesConflicto {
+            base: Some("1".repeat(40)),
+            nuestra: Some(hash),
+            suya: Some("3".repeat(40)),
+        });
+        escribir_index(logger, &mut objetos_index).unwrap();
+
+        let contenido = io::leer_bytes(PATH_INDEX).unwrap();
+        let archivo = ArchivoIndex::deserializar(&contenido).unwrap();
+        assert_eq!(archivo.entradas.len(), 3);
+        assert!(archivo
+            .entradas
+            .iter()
+            .all(|entrada| entrada.metadata == MetadataIndex::default()));
+    }
+}
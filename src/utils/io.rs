@@ -128,6 +128,72 @@ where
     }
 }
 
+/// Devuelve true si el archivo tiene algun bit de ejecucion prendido.
+/// En sistemas que no son unix siempre devuelve false.
+pub fn es_ejecutable<P: AsRef<Path>>(archivo: P) -> bool {
+    #[cfg(unix)]
+    {
+        use std::os::unix::fs::PermissionsExt;
+        match fs::symlink_metadata(archivo) {
+            Ok(metadata) => metadata.permissions().mode() & 0o111 != 0,
+            Err(_) => false,
+        }
+    }
+    #[cfg(not(unix))]
+    {
+        let _ = archivo;
+        false
+    }
+}
+
+/// Cambia los permisos del archivo para que sea ejecutable (o deje de serlo).
+/// En sistemas que no son unix no hace nada.
+pub fn cambiar_permiso_ejecucion<P: AsRef<Path>>(
+    archivo: P,
+    ejecutable: bool,
+) -> Result<(), String> {
+    #[cfg(unix)]
+    {
+        use std::os::unix::fs::PermissionsExt;
+        let modo = if ejecutable { 0o755 } else { 0o644 };
+        fs::set_permissions(&archivo, fs::Permissions::from_mode(modo)).map_err(|e| {
+            format!(
+                "No se pudieron cambiar los permisos de {}. {}",
+                archivo.as_ref().display(),
+                e
+            )
+        })
+    }
+    #[cfg(not(unix))]
+    {
+        let _ = (archivo, ejecutable);
+        Ok(())
+    }
+}
+
+/// Crea un link simbolico en `link` que apunta a `destino`, pisando lo que hubiera en `link`.
+/// En sistemas que no son unix escribe un archivo comun con el destino como contenido.
+pub fn escribir_symlink<P: AsRef<Path>>(link: P, destino: &str) -> Result<(), String> {
+    si_no_existe_directorio_de_archivo_crearlo(&link)?;
+    if fs::symlink_metadata(&link).is_ok() {
+        rm_directorio(&link)?;
+    }
+    #[cfg(unix)]
+    {
+        std::os::unix::fs::symlink(destino, &link).map_err(|e| {
+            format!(
+                "No se pudo crear el link simbolico {}. {}",
+                link.as_ref().display(),
+                e
+            )
+        })
+    }
+    #[cfg(not(unix))]
+    {
+        escribir_bytes(link, destino)
+    }
+}
+
 pub fn si_no_existe_directorio_de_archivo_crearlo<P>(dir_archivo: &P) -> Result<(), String>
 where
     P: AsRef<Path>,
@@ -147,7 +213,7 @@ pub fn rm_directorio<P>(directorio: P) -> Result<(), String>
 where
     P: AsRef<Path>,
 {
-    let metadata = fs::metadata(&directorio).map_err(|e| {
+    let metadata = fs::symlink_metadata(&directorio).map_err(|e| {
         format!(
             "No se pudo obtener la metadata del directorio {}. {}",
             directorio.as_ref().display(),
@@ -155,7 +221,7 @@ where
         )
     })?;
 
-    if metadata.is_file() {
+    if metadata.is_file() || metadata.file_type().is_symlink() {
         return match fs::remove_file(&directorio) {
             Ok(_) => Ok(()),
             Err(e) => Err(format!(
@@ -1,12 +1,17 @@
+use std::collections::HashSet;
 use std::fs;
 use std::{io, path::PathBuf};
 
 use crate::err_comunicacion::ErrorDeComunicacion;
+use crate::tipos_de_dato::objetos::tag::TagObj;
+use crate::tipos_de_dato::objetos::tree::Tree;
+use crate::tipos_de_dato::packfile::Packfile;
 
-use super::path_buf;
-use super::{io as gir_io, strings};
+use super::io as gir_io;
+use super::{compresion, path_buf};
 
-///Devuelve todos los objetos dentro de objetcs (sus hash)
+///Devuelve todos los objetos dentro de objetcs (sus hash), tanto los sueltos como los guardados
+/// en los packfiles de objects/pack
 pub fn obtener_objetos_del_dir(dir: &PathBuf) -> Result<Vec<String>, String> {
     let dir_abierto = gir_io::leer_directorio(dir)?;
 
@@ -28,6 +33,13 @@ pub fn obtener_objetos_del_dir(dir: &PathBuf) -> Result<Vec<String>, String> {
             }
         }
     }
+
+    let dir_objetos = format!("{}/", dir.display());
+    for objeto in Packfile::obtener_hashes_de_packs(&dir_objetos)? {
+        if !objetos.contains(&objeto) {
+            objetos.push(objeto);
+        }
+    }
     Ok(objetos)
 }
 
@@ -38,9 +50,10 @@ pub fn obtener_objetos_del_dir(dir: &PathBuf) -> Result<Vec<String>, String> {
 /// - recive: jk/
 /// - devuleve: jksfsfsffafasfas...fdfdf, kjsfsfaftyhththht, jkiodf235453535355fs, ...
 ///
+/// Una carpeta vacia (por ejemplo despues de borrar sus objetos) no tiene objetos asociados.
+///
 /// ## Error
 /// -Si no existe dir
-/// -Si no tiene conti8dio
 fn obtener_objetos_con_nombre_carpeta(dir: PathBuf) -> Result<Vec<String>, String> {
     let directorio = gir_io::leer_directorio(&dir)?;
 
@@ -60,31 +73,9 @@ fn obtener_objetos_con_nombre_carpeta(dir: PathBuf) -> Result<Vec<String>, Strin
         }
     }
 
-    if objetos.is_empty() {
-        return Err(format!(
-            "Error el directorio {} no tiene cotenido",
-            nombre_directorio
-        ));
-    }
-
     Ok(objetos)
 }
 
-// dado un vector con nombres de archivos de vuelve aquellos que no estan en el directorio
-pub fn obtener_archivos_faltantes(nombres_archivos: Vec<String>, dir: &str) -> Vec<String> {
-    // DESHARDCODEAR EL NOMBRE DEL DIRECTORIO (.gir)
-    let objetcts_contained =
-        obtener_objetos_del_dir(&PathBuf::from(dir.to_string() + "objects/")).unwrap();
-    let mut archivos_faltantes: Vec<String> = Vec::new();
-    for nombre in &objetcts_contained {
-        if nombres_archivos.contains(nombre) {
-        } else {
-            archivos_faltantes.push(nombre.clone());
-        }
-    }
-    archivos_faltantes
-}
-
 // dado un directorio devuelve el nombre del archivo contenido (solo caso de objectos de git)
 pub fn obtener_objetos(dir: PathBuf) -> Result<String, ErrorDeComunicacion> {
     let mut directorio = fs::read_dir(dir.clone())?;
@@ -104,20 +95,203 @@ pub fn obtener_objetos(dir: PathBuf) -> Result<String, ErrorDeComunicacion> {
     )))
 }
 
-// aca depende de si esta multi_ack y esas cosas, esta es para cuando no hay multi_ack ni multi_ack_mode
-pub fn obtener_objetos_en_comun(nombres_archivos: Vec<String>, dir: &str) -> Vec<String> {
-    let mut ack = Vec::new();
-    for nombre in nombres_archivos {
-        let dir_archivo = format!("{}{}/{}", dir, &nombre[..2], &nombre[2..]);
-        if PathBuf::from(dir_archivo.clone()).exists() {
-            ack.push(strings::obtener_linea_con_largo_hex(
-                ("ACK ".to_string() + &nombre + "\n").as_str(),
-            ));
-            break;
+/// Datos de un commit que hacen falta para recorrer la historia.
+pub struct DatosCommit {
+    pub tree: String,
+    pub padres: Vec<String>,
+    /// Fecha del committer, en segundos desde epoch.
+    pub fecha: i64,
+}
+
+/// Lee el commit dado de `dir_objetos` y devuelve su tree, sus padres y la fecha del committer.
+/// Si el objeto no existe o no es un commit devuelve error.
+pub fn leer_commit(hash: &str, dir_objetos: &str) -> Result<DatosCommit, String> {
+    let objeto = compresion::descomprimir_objeto(hash, dir_objetos)?;
+    let (header, contenido) = objeto
+        .split_once('\0')
+        .ok_or(format!("El objeto {} no tiene header", hash))?;
+    if !header.starts_with("commit ") {
+        return Err(format!("El objeto {} no es un commit", hash));
+    }
+
+    let mut tree = None;
+    let mut padres = Vec::new();
+    let mut fecha = 0;
+    for linea in contenido.lines().take_while(|linea| !linea.is_empty()) {
+        let partes: Vec<&str> = linea.split(' ').collect();
+        match partes[0] {
+            "tree" => tree = partes.get(1).map(|hash| hash.to_string()),
+            "parent" => padres.extend(partes.get(1).map(|hash| hash.to_string())),
+            // la fecha es el anteultimo campo, antes del offset
+            "committer" if partes.len() >= 3 => {
+                fecha = partes[partes.len() - 2].parse().unwrap_or(0);
+            }
+            _ => {}
+        }
+    }
+    let tree = tree.ok_or(format!("El commit {} no tiene tree", hash))?;
+    Ok(DatosCommit {
+        tree,
+        padres,
+        fecha,
+    })
+}
+
+/// Devuelve el tree y todos los trees y blobs que contiene, salvo los que esten en `excluidos`.
+/// Si un tree esta excluido tampoco se recorre su contenido. Los gitlinks (submodulos) no son
+/// objetos del repositorio, asi que no se incluyen.
+pub fn obtener_objetos_de_tree(
+    hash_tree: &str,
+    dir_objetos: &str,
+    excluidos: &HashSet<String>,
+) -> Result<Vec<String>, String> {
+    if excluidos.contains(hash_tree) {
+        return Ok(Vec::new());
+    }
+    let mut objetos = vec![hash_tree.to_string()];
+    let contenido = compresion::descomprimir_objeto(hash_tree, dir_objetos)?;
+    for (modo, _, hash) in Tree::obtener_datos_de_contenido(&contenido)? {
+        match modo.as_str() {
+            "40000" => objetos.extend(obtener_objetos_de_tree(&hash, dir_objetos, excluidos)?),
+            "160000" => {}
+            _ if excluidos.contains(&hash) => {}
+            _ => objetos.push(hash),
+        }
+    }
+    Ok(objetos)
+}
+
+/// Devuelve los commits dados junto con todos los objetos de sus trees. Los objetos que no son
+/// commits se devuelven tal cual y los que no existen se ignoran.
+pub fn obtener_objetos_de_commits(
+    commits: &[String],
+    dir_objetos: &str,
+) -> Result<HashSet<String>, String> {
+    let mut objetos = HashSet::new();
+    for hash in commits {
+        match leer_commit(hash, dir_objetos) {
+            Ok(commit) => {
+                let objetos_tree = obtener_objetos_de_tree(&commit.tree, dir_objetos, &objetos)?;
+                objetos.extend(objetos_tree);
+            }
+            Err(_) if !compresion::existe_objeto(hash, dir_objetos) => continue,
+            Err(_) => {}
+        }
+        objetos.insert(hash.clone());
+    }
+    Ok(objetos)
+}
+
+/// Devuelve los objetos alcanzables desde `desde` que no son alcanzables desde `excluyendo`. Se
+/// recorren los commits desde `desde` sin pasar por los ancestros de `excluyendo` y se agregan sus
+/// trees y blobs, salvo los que ya estan en los commits excluidos del borde (los de `excluyendo` y
+/// los padres excluidos de los commits recorridos). Los tags anotados de `desde` tambien se
+/// incluyen. De los commits en `commits_shallow` no se siguen los padres, porque no estan en el
+/// repositorio.
+pub fn obtener_objetos_alcanzables(
+    desde: &[String],
+    excluyendo: &[String],
+    dir_objetos: &str,
+    commits_shallow: &HashSet<String>,
+) -> Result<HashSet<String>, String> {
+    let commits_excluidos = obtener_commits_alcanzables(excluyendo, dir_objetos);
+    let mut objetos = HashSet::new();
+    let mut pendientes = Vec::new();
+    for hash in desde {
+        // un tag anotado se manda junto con la historia del commit al que apunta
+        if TagObj::es_tag(hash, dir_objetos) {
+            objetos.insert(hash.clone());
+        }
+        pendientes.push(TagObj::pelar(hash, dir_objetos)?);
+    }
+
+    let mut borde: Vec<String> = excluyendo.to_vec();
+    let mut commits_incluidos = Vec::new();
+    let mut visitados = HashSet::new();
+    while let Some(hash) = pendientes.pop() {
+        if commits_excluidos.contains(&hash) {
+            borde.push(hash);
+            continue;
+        }
+        if !visitados.insert(hash.clone()) {
+            continue;
+        }
+        let commit = leer_commit(&hash, dir_objetos)?;
+        if !commits_shallow.contains(&hash) {
+            pendientes.extend(commit.padres);
+        }
+        commits_incluidos.push((hash, commit.tree));
+    }
+
+    // los trees y blobs ya vistos (incluidos los excluidos) no se vuelven a recorrer en los
+    // commits siguientes
+    let mut vistos = obtener_objetos_de_commits(&borde, dir_objetos)?;
+    for (hash, tree) in commits_incluidos {
+        let objetos_tree = obtener_objetos_de_tree(&tree, dir_objetos, &vistos)?;
+        vistos.extend(objetos_tree.iter().cloned());
+        objetos.extend(objetos_tree);
+        objetos.insert(hash);
+    }
+    Ok(objetos)
+}
+
+/// Recorre la historia de `descendiente` buscando el commit `ancestro`. Si falta algun commit de
+/// la historia devuelve false, porque no se puede asegurar que lo sea.
+pub fn es_ancestro(ancestro: &str, descendiente: &str, dir_objetos: &str) -> Result<bool, String> {
+    es_ancestro_en(ancestro, descendiente, &[dir_objetos])
+}
+
+/// Igual que `es_ancestro`, pero los commits pueden estar en cualquiera de los directorios de
+/// objetos dados, como cuando un push todavia no paso los objetos recibidos al repositorio.
+pub fn es_ancestro_en(
+    ancestro: &str,
+    descendiente: &str,
+    dirs_objetos: &[&str],
+) -> Result<bool, String> {
+    let mut commits_a_revisar = vec![descendiente.to_string()];
+    let mut revisados = HashSet::new();
+    while let Some(commit) = commits_a_revisar.pop() {
+        if commit == ancestro {
+            return Ok(true);
+        }
+        if !revisados.insert(commit.clone()) {
+            continue;
         }
+        let Some(dir_objetos) = dirs_objetos
+            .iter()
+            .find(|dir_objetos| compresion::existe_objeto(&commit, dir_objetos))
+        else {
+            return Ok(false);
+        };
+        commits_a_revisar.extend(leer_commit(&commit, dir_objetos)?.padres);
     }
-    if ack.is_empty() {
-        ack.push(strings::obtener_linea_con_largo_hex("NAK\n"));
+    Ok(false)
+}
+
+// Devuelve los commits dados y todos sus ancestros. Los objetos que no existen o no son commits
+// se ignoran, asi los haves desconocidos no impiden armar el packfile.
+fn obtener_commits_alcanzables(commits: &[String], dir_objetos: &str) -> HashSet<String> {
+    let mut alcanzables = HashSet::new();
+    agregar_commits_alcanzables(commits, dir_objetos, &mut alcanzables);
+    alcanzables
+}
+
+/// Agrega a `alcanzables` los commits dados y todos sus ancestros. La historia de los commits
+/// que ya estan en `alcanzables` no se vuelve a recorrer, asi que se puede ir completando de a
+/// poco. Los objetos que no existen o no son commits se ignoran.
+pub fn agregar_commits_alcanzables(
+    commits: &[String],
+    dir_objetos: &str,
+    alcanzables: &mut HashSet<String>,
+) {
+    let mut pendientes = commits.to_vec();
+    while let Some(hash) = pendientes.pop() {
+        if alcanzables.contains(&hash) {
+            continue;
+        }
+        if let Ok(commit) = leer_commit(&hash, dir_objetos) {
+            alcanzables.insert(hash);
+            pendientes.extend(commit.padres);
+        }
     }
-    ack
 }
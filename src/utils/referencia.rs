@@ -3,6 +3,8 @@ use std::{
     path::{Path, PathBuf},
 };
 
+use crate::tipos_de_dato::objetos::tag::TagObj;
+
 use super::{io, strings};
 
 pub fn obtener_refs_con_largo_hex(
@@ -21,6 +23,9 @@ pub fn obtener_refs_con_largo_hex(
 
                 let referencia = obtener_referencia(&mut path, dir)?;
                 refs.push(strings::obtener_linea_con_largo_hex(&referencia));
+                if let Some(pelada) = obtener_referencia_pelada(&referencia, dir)? {
+                    refs.push(strings::obtener_linea_con_largo_hex(&pelada));
+                }
             }
             Err(error) => {
                 eprintln!("Error leyendo directorio: {}", error);
@@ -30,6 +35,20 @@ pub fn obtener_refs_con_largo_hex(
     Ok(())
 }
 
+// Si la referencia (`<hash> <nombre>`) apunta a un tag anotado devuelve la linea `<commit> <nombre>^{}`
+// con el commit al que apunta el tag, como se anuncia en la fase de descubrimiento.
+fn obtener_referencia_pelada(referencia: &str, dir: &str) -> Result<Option<String>, String> {
+    let (hash, nombre) = referencia
+        .split_once(' ')
+        .ok_or(format!("Referencia invalida: {}", referencia))?;
+    let dir_objetos = format!("{}/", PathBuf::from(dir).join("objects").display());
+    if !TagObj::es_tag(hash, &dir_objetos) {
+        return Ok(None);
+    }
+    let commit = TagObj::pelar(hash, &dir_objetos)?;
+    Ok(Some(format!("{} {}^{{}}", commit, nombre)))
+}
+
 pub fn obtener_ref_head(path: PathBuf) -> Result<String, String> {
     if !path.exists() {
         return Err("No existe HEAD".to_string());
@@ -84,3 +103,35 @@ fn obtener_referencia(path: &mut Path, prefijo: &str) -> Result<String, String>
     );
     Ok(referencia)
 }
+
+/// Recorre recursivamente el directorio de refs y devuelve cada referencia junto al hash al que apunta.
+/// El nombre de cada referencia es su ruta sin el prefijo, por ejemplo `refs/heads/master`.
+/// Las referencias vacias (ramas sin commits) se ignoran.
+pub fn obtener_refs_y_hashes(
+    refs_path: &Path,
+    prefijo: &Path,
+) -> Result<Vec<(String, String)>, String> {
+    let mut refs = Vec::new();
+    if !refs_path.exists() {
+        return Ok(refs);
+    }
+
+    for entrada in io::leer_directorio(&refs_path)? {
+        let ruta = entrada.map_err(|e| e.to_string())?.path();
+        if io::es_dir(&ruta) {
+            refs.extend(obtener_refs_y_hashes(&ruta, prefijo)?);
+            continue;
+        }
+        let hash = io::leer_a_string(&ruta)?.trim().to_string();
+        if hash.is_empty() {
+            continue;
+        }
+        let nombre = ruta
+            .strip_prefix(prefijo)
+            .map_err(|e| e.to_string())?
+            .to_string_lossy()
+            .to_string();
+        refs.push((nombre, hash));
+    }
+    Ok(refs)
+}
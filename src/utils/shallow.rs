@@ -0,0 +1,62 @@
+use std::collections::HashSet;
+use std::path::Path;
+
+use chrono::NaiveDate;
+
+use super::io;
+
+/// Archivo con los commits que son borde de la historia local: estan en el repositorio pero sus
+/// padres no, porque se clono o se hizo fetch con una profundidad.
+const UBICACION_SHALLOW: &str = "./.gir/shallow";
+
+///Devuelve los commits borde de la historia local. Si el repositorio no es shallow no hay ninguno
+pub fn obtener_commits_shallow() -> HashSet<String> {
+    obtener_commits_shallow_de(Path::new("."))
+}
+
+///Devuelve los commits borde de la historia del repositorio ubicado en `dir_repositorio`
+pub fn obtener_commits_shallow_de(dir_repositorio: &Path) -> HashSet<String> {
+    io::leer_a_string(dir_repositorio.join(UBICACION_SHALLOW))
+        .unwrap_or_default()
+        .lines()
+        .filter(|linea| !linea.is_empty())
+        .map(String::from)
+        .collect()
+}
+
+///Agrega los nuevos commits borde y saca los que dejaron de serlo. Si no queda ninguno se borra
+/// el archivo shallow, porque el repositorio ya tiene la historia completa
+pub fn actualizar_commits_shallow(
+    shallows: &[String],
+    unshallows: &[String],
+) -> Result<(), String> {
+    let mut commits = obtener_commits_shallow();
+    commits.extend(shallows.iter().cloned());
+    commits.retain(|commit| !unshallows.contains(commit));
+
+    if commits.is_empty() {
+        if Path::new(UBICACION_SHALLOW).exists() {
+            io::rm_directorio(UBICACION_SHALLOW)?;
+        }
+        return Ok(());
+    }
+    let mut commits: Vec<String> = commits.into_iter().collect();
+    commits.sort();
+    io::escribir_bytes(UBICACION_SHALLOW, commits.join("\n") + "\n")
+}
+
+///Convierte la fecha de `--shallow-since` a segundos desde epoch. Acepta un timestamp o una
+/// fecha con el formato 'AAAA-MM-DD'
+pub fn parsear_fecha(fecha: &str) -> Result<i64, String> {
+    if let Ok(timestamp) = fecha.parse::<i64>() {
+        return Ok(timestamp);
+    }
+    NaiveDate::parse_from_str(fecha, "%Y-%m-%d")
+        .ok()
+        .and_then(|fecha| fecha.and_hms_opt(0, 0, 0))
+        .map(|fecha| fecha.and_utc().timestamp())
+        .ok_or(format!(
+            "Fecha invalida: {}. Use un timestamp o el formato AAAA-MM-DD",
+            fecha
+        ))
+}
@@ -7,19 +7,84 @@ pub fn eliminar_prefijos(lineas: &Vec<String>) -> Vec<String> {
     lineas_sin_prefijo
 }
 
-///Obtiene de la url el ip puerto y el repositorio
+/// Prefijo de las urls de remotos que se acceden por smart http en lugar del protocolo de gir.
+pub const PREFIJO_URL_HTTP: &str = "http://";
+pub const PREFIJO_URL_HTTPS: &str = "https://";
+/// Prefijo de las urls del servidor gir cuando atiende por TLS. Sin prefijo la conexion no se
+/// cifra.
+pub const PREFIJO_URL_GIR_TLS: &str = "girs://";
+
+///Obtiene de la url el ip puerto y el repositorio. Si la url empieza con `http://`, `https://` o
+/// `girs://` se ignora el prefijo
 ///
 /// ## Ejemplo
-/// - recibe: ip:puerto/repositorio/
+/// - recibe: ip:puerto/repositorio/ o http://ip:puerto/repositorio/
 /// - devuelve: (ip:puerto, /respositorio/)
 pub fn obtener_ip_puerto_y_repositorio(url: &str) -> Result<(String, String), String> {
-    let (ip_puerto_str, repositorio) = url
+    let sin_prefijo = [PREFIJO_URL_HTTP, PREFIJO_URL_HTTPS, PREFIJO_URL_GIR_TLS]
+        .iter()
+        .find_map(|prefijo| url.strip_prefix(prefijo))
+        .unwrap_or(url);
+    let (ip_puerto_str, repositorio) = sin_prefijo
         .split_once('/')
         .ok_or_else(|| format!("Fallo en obtener el ip:puerto y repo de {}", url))?;
 
     Ok((ip_puerto_str.to_string(), "/".to_string() + repositorio))
 }
 
+/// Separa las credenciales (`usuario:contrasenia@`) del comienzo del ip:puerto de una url.
+///
+/// ## Ejemplo
+/// - recibe: usuario:contrasenia@ip:puerto
+/// - devuelve: (Some(usuario:contrasenia), ip:puerto)
+pub fn separar_credenciales(ip_puerto: &str) -> (Option<String>, String) {
+    match ip_puerto.rsplit_once('@') {
+        Some((credenciales, ip_puerto)) => (Some(credenciales.to_string()), ip_puerto.to_string()),
+        None => (None, ip_puerto.to_string()),
+    }
+}
+
+const ALFABETO_BASE64: &[u8; 64] =
+    b"ABCDEFGHIJKLMNOPQRSTUVWXYZabcdefghijklmnopqrstuvwxyz0123456789+/";
+
+/// Codifica los bytes en base64, con relleno `=`.
+pub fn codificar_base64(bytes: &[u8]) -> String {
+    let mut codificado = String::new();
+    for grupo in bytes.chunks(3) {
+        let valor = grupo.iter().enumerate().fold(0u32, |valor, (i, byte)| {
+            valor | (*byte as u32) << (16 - 8 * i)
+        });
+        for i in 0..4 {
+            if i <= grupo.len() {
+                let indice = (valor >> (18 - 6 * i)) & 0x3f;
+                codificado.push(ALFABETO_BASE64[indice as usize] as char);
+            } else {
+                codificado.push('=');
+            }
+        }
+    }
+    codificado
+}
+
+/// Decodifica un texto en base64 con o sin relleno. Devuelve None si no es base64 valido.
+pub fn decodificar_base64(codificado: &str) -> Option<Vec<u8>> {
+    let codificado = codificado.trim_end_matches('=');
+    let mut bytes = Vec::new();
+    let mut valor = 0u32;
+    let mut bits = 0;
+    for caracter in codificado.bytes() {
+        let indice = ALFABETO_BASE64.iter().position(|c| *c == caracter)?;
+        valor = (valor << 6) | indice as u32;
+        bits += 6;
+        if bits >= 8 {
+            bits -= 8;
+            bytes.push((valor >> bits) as u8);
+            valor &= (1 << bits) - 1;
+        }
+    }
+    Some(bytes)
+}
+
 //le calcula en hexa el largo a una linea
 pub fn calcular_largo_hex(line: &str) -> String {
     let largo = line.len() + 4; // el + 4 es por los 4 bytes que indican el largo
@@ -1,6 +1,8 @@
 use std::{
     io::{Read, Write},
+    os::unix::fs::PermissionsExt,
     path::PathBuf,
+    process::Command,
     sync::Arc,
 };
 
@@ -10,7 +12,7 @@ use crate::tipos_de_dato::{
     logger::Logger,
 };
 
-use super::io;
+use super::{index, io};
 
 pub struct MockTcpStream {
     pub lectura_data: Vec<u8>,
@@ -75,12 +77,30 @@ pub fn escribir_rama_local(rama: &str, logger: Arc<Logger>) {
         .unwrap();
 }
 
+/// Escribe un hook ejecutable con el script dado en `dir_hooks`.
+pub fn escribir_hook(dir_hooks: &str, nombre: &str, script: &str) {
+    let ruta = format!("{}/{}", dir_hooks.trim_end_matches('/'), nombre);
+    io::escribir_bytes(&ruta, script).unwrap();
+    std::fs::set_permissions(&ruta, std::fs::Permissions::from_mode(0o755)).unwrap();
+}
+
 pub fn eliminar_archivo_gir() {
     if PathBuf::from("./.gir").exists() {
         io::rm_directorio("./.gir").unwrap();
     }
 }
 
+/// Devuelve el contenido del index con una linea por entrada, en el formato
+/// `[simbolo eliminado] [merge] [modo] [hash] [path]`.
+pub fn leer_index_como_texto() -> String {
+    let logger = Arc::new(Logger::new(PathBuf::from("tmp/leer_index_como_texto")).unwrap());
+    index::leer_index(logger)
+        .unwrap()
+        .iter()
+        .map(|objeto_index| objeto_index.to_string())
+        .collect()
+}
+
 pub fn addear_archivos_y_comittear(args: Vec<String>, logger: Arc<Logger>) {
     let mut add = Add::from(args, logger.clone()).unwrap();
     add.ejecutar().unwrap();
@@ -148,3 +168,21 @@ pub fn crear_repo_para_pr(logger: Arc<Logger>) {
 
     push.ejecutar().unwrap();
 }
+
+/// Genera en `dir` un certificado autofirmado para `localhost` (`certificado.pem`) y su clave
+/// privada en PKCS#8 (`clave.pem`), con el cli de openssl.
+pub fn generar_certificado_autofirmado(dir: &str) {
+    io::crear_directorio(dir).unwrap();
+    let estado = Command::new("openssl")
+        .args([
+            "req", "-x509", "-newkey", "rsa:2048", "-nodes", "-days", "1",
+        ])
+        .args(["-subj", "/CN=localhost"])
+        .args(["-addext", "subjectAltName=DNS:localhost,IP:127.0.0.1"])
+        .args(["-keyout", &format!("{}/clave.pem", dir)])
+        .args(["-out", &format!("{}/certificado.pem", dir)])
+        .output()
+        .expect("Hace falta openssl para generar el certificado de prueba")
+        .status;
+    assert!(estado.success());
+}